@@ -101,7 +101,12 @@ CREATE TABLE IF NOT EXISTS entries (
     -- GraphQL metadata
     graphql_operation_type TEXT,
     graphql_operation_name TEXT,
-    graphql_top_level_fields TEXT
+    graphql_top_level_fields TEXT,
+    graphql_query_hash TEXT,
+    graphql_variables_hash TEXT REFERENCES blobs(hash),
+
+    -- Replay provenance (set by `harlite replay --record`)
+    replay_of_entry_id INTEGER
 );
 
 -- Indexes
@@ -118,6 +123,8 @@ CREATE INDEX IF NOT EXISTS idx_entries_parent_request_id ON entries(parent_reque
 CREATE INDEX IF NOT EXISTS idx_entries_redirect_url ON entries(redirect_url);
 CREATE INDEX IF NOT EXISTS idx_entries_graphql_type ON entries(graphql_operation_type);
 CREATE INDEX IF NOT EXISTS idx_entries_graphql_name ON entries(graphql_operation_name);
+CREATE INDEX IF NOT EXISTS idx_entries_graphql_query_hash ON entries(graphql_query_hash);
+CREATE INDEX IF NOT EXISTS idx_entries_replay_of ON entries(replay_of_entry_id);
 
 -- GraphQL top-level fields
 CREATE TABLE IF NOT EXISTS graphql_fields (
@@ -128,6 +135,41 @@ CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_fields_entry_field ON graphql_fiel
 CREATE INDEX IF NOT EXISTS idx_graphql_fields_field ON graphql_fields(field);
 CREATE INDEX IF NOT EXISTS idx_graphql_fields_entry ON graphql_fields(entry_id);
 
+-- GraphQL nested field paths (dotted, e.g. viewer.repositories.name)
+CREATE TABLE IF NOT EXISTS graphql_field_paths (
+    entry_id INTEGER REFERENCES entries(id),
+    path TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_field_paths_entry_path ON graphql_field_paths(entry_id, path);
+CREATE INDEX IF NOT EXISTS idx_graphql_field_paths_path ON graphql_field_paths(path);
+
+-- Requests that `harlite serve` could not match (recorded with --record-misses)
+CREATE TABLE IF NOT EXISTS serve_misses (
+    id INTEGER PRIMARY KEY,
+    recorded_at TEXT NOT NULL,
+    method TEXT,
+    url TEXT,
+    host TEXT,
+    path TEXT,
+    query_string TEXT,
+    http_version TEXT,
+    request_headers TEXT,
+    request_body_hash TEXT REFERENCES blobs(hash),
+    request_body_size INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_serve_misses_url ON serve_misses(url);
+
+-- Redaction runs applied to this database (written by `harlite redact`)
+CREATE TABLE IF NOT EXISTS redactions (
+    id INTEGER PRIMARY KEY,
+    applied_at TEXT NOT NULL,
+    command TEXT NOT NULL,
+    policy_name TEXT,
+    policy_version INTEGER,
+    policy_hash TEXT,
+    values_redacted INTEGER NOT NULL
+);
+
 -- Full-text search over response bodies (text-only, deduped by blob hash)
 CREATE VIRTUAL TABLE IF NOT EXISTS response_body_fts
 USING fts5(hash UNINDEXED, body, tokenize = 'unicode61');
@@ -4,16 +4,20 @@
 //! Treat the contents of this module as SemVer-stable.
 
 pub use crate::commands::{
-    run_analyze, run_diff, run_export, run_export_data, run_fts_rebuild, run_import, run_imports,
-    run_info, run_merge, run_openapi, run_pii, run_prune, run_query, run_redact, run_report,
-    run_schema, run_search, run_stats, run_waterfall, AnalyzeOptions, DataExportFormat,
+    run_analyze, run_codegen, run_conform, run_diff, run_export, run_export_collection,
+    run_export_data, run_export_loadtest, run_fts_rebuild, run_import, run_imports, run_info,
+    run_merge, run_openapi, run_pii, run_prune, run_query, run_redact, run_report, run_schema,
+    run_search, run_stats, run_types, run_waterfall, AnalyzeOptions, CodegenFormat, CodegenOptions,
+    CollectionFormat, CollectionOptions, ConformFormat, ConformOptions, DataExportFormat,
     DedupStrategy, DiffOptions, EntryFilterOptions, ExportDataOptions, ExportOptions, FtsTokenizer,
-    ImportOptions, InfoOptions, NameMatchMode, OpenApiOptions, OutputFormat, PiiOptions,
-    QueryOptions, RedactOptions, ReportOptions, StatsOptions, WaterfallFormat, WaterfallGroupBy,
-    WaterfallOptions,
+    ImportOptions, InfoOptions, LoadTestFormat, LoadTestOptions, NameMatchMode, OpenApiOptions,
+    OutputFormat, PiiOptions, QueryOptions, RedactOptions, ReportOptions, StatsOptions,
+    TypesFormat, TypesOptions, WaterfallFormat, WaterfallGroupBy, WaterfallOptions,
 };
 #[cfg(feature = "cdp")]
 pub use crate::commands::{run_cdp, CdpOptions};
+#[cfg(feature = "graphql")]
+pub use crate::commands::{run_graphql, GraphqlFormat, GraphqlOptions};
 #[cfg(feature = "otel")]
 pub use crate::commands::{run_otel, OtelExportFormat, OtelExportOptions};
 #[cfg(feature = "repl")]
@@ -3,11 +3,14 @@ use std::path::PathBuf;
 
 use crate::commands;
 use crate::commands::{
-    DataExportFormat, DedupStrategy, NameMatchMode, OutputFormat, WaterfallFormat,
+    CodegenFormat, CollectionFormat, ConformFormat, DataExportFormat, DedupStrategy, LoadTestFormat,
+    NameMatchMode, OutputFormat, TypesFormat, WaterfallFormat,
     WaterfallGroupBy,
 };
+#[cfg(feature = "graphql")]
+use crate::commands::GraphqlFormat;
 #[cfg(feature = "otel")]
-use crate::commands::OtelExportFormat;
+use crate::commands::{OtelExportFormat, OtelSignal};
 #[cfg(feature = "serve")]
 use crate::commands::MatchMode;
 use crate::db::ExtractBodiesKind;
@@ -17,6 +20,15 @@ use crate::db::ExtractBodiesKind;
 #[command(about = "Import HAR files into SQLite. Query your web traffic with SQL.")]
 #[command(version)]
 pub struct Cli {
+    /// Read the database encryption key from FILE (requires the `encryption` feature;
+    /// defaults to $HARLITE_DB_KEY)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub db_key_file: Option<PathBuf>,
+
+    /// Prompt for the database encryption key
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue, conflicts_with = "db_key_file")]
+    pub db_key_prompt: Option<bool>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -109,6 +121,18 @@ pub enum Commands {
         #[arg(long)]
         to: Option<String>,
 
+        /// Apply `redact` rules (defaults plus [redact] config) before storing entries
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact: Option<bool>,
+
+        /// Redaction policy file to apply before storing entries (implies --redact)
+        #[arg(long, value_name = "FILE")]
+        redact_policy: Option<PathBuf>,
+
+        /// Replace PII and secrets found by the `pii` matchers before storing entries
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact_pii: Option<bool>,
+
         /// Enable plugin by name (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
         plugin: Option<Vec<String>>,
@@ -156,6 +180,18 @@ pub enum Commands {
         /// Stop capture after N seconds (omit to capture until Ctrl+C)
         #[arg(long, value_name = "SECONDS")]
         duration: Option<u64>,
+
+        /// Apply `redact` rules (defaults plus [redact] config) before storing entries
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact: Option<bool>,
+
+        /// Redaction policy file to apply before storing entries (implies --redact)
+        #[arg(long, value_name = "FILE")]
+        redact_policy: Option<PathBuf>,
+
+        /// Replace PII and secrets found by the `pii` matchers before storing entries
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact_pii: Option<bool>,
     },
 
     /// Watch a directory for new HAR files and auto-import
@@ -269,6 +305,18 @@ pub enum Commands {
         #[arg(long)]
         to: Option<String>,
 
+        /// Apply `redact` rules (defaults plus [redact] config) before storing entries
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact: Option<bool>,
+
+        /// Redaction policy file to apply before storing entries (implies --redact)
+        #[arg(long, value_name = "FILE")]
+        redact_policy: Option<PathBuf>,
+
+        /// Replace PII and secrets found by the `pii` matchers before storing entries
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact_pii: Option<bool>,
+
         /// Enable plugin by name (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
         plugin: Option<Vec<String>>,
@@ -557,10 +605,14 @@ pub enum Commands {
         /// Database file to export
         database: PathBuf,
 
-        /// Output format (json, otlp-http, otlp-grpc)
+        /// Output format (json, otlp-http, otlp-grpc, prometheus)
         #[arg(short, long, value_enum, default_value_t = OtelExportFormat::Json)]
         format: OtelExportFormat,
 
+        /// What to export: request spans, or duration/count/byte metrics
+        #[arg(long, value_enum, default_value_t = OtelSignal::Traces)]
+        signal: OtelSignal,
+
         /// Output file for JSON (default: stdout). Use '-' for stdout.
         #[arg(short, long)]
         output: Option<PathBuf>,
@@ -581,14 +633,22 @@ pub enum Commands {
         #[arg(long, action = clap::ArgAction::SetTrue)]
         no_phases: bool,
 
-        /// Deterministic sampling rate (0.0 - 1.0)
+        /// Do not group entries under a root span per page load
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_page_spans: bool,
+
+        /// Deterministic sampling rate for traces (0.0 - 1.0); metrics count every request
         #[arg(long, default_value_t = 1.0)]
         sample_rate: f64,
 
-        /// Maximum number of root spans to export
+        /// Maximum number of requests to export as spans (metrics count every request)
         #[arg(long)]
         max_spans: Option<usize>,
 
+        /// Metrics: treat a path position as a route parameter once it takes this many distinct values (default: 20, 0 disables)
+        #[arg(long, value_name = "N")]
+        path_param_threshold: Option<usize>,
+
         /// Exact URL match (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
         url: Option<Vec<String>>,
@@ -654,31 +714,31 @@ pub enum Commands {
         max_response_size: Option<String>,
     },
 
-    /// Generate an OpenAPI schema from captured traffic
-    #[command(name = "openapi")]
-    OpenApi {
-        /// Database file to inspect
+    /// Export captured requests as a Postman or Insomnia collection
+    ExportCollection {
+        /// Database file to export
         database: PathBuf,
 
-        /// Output file (default: <database>-openapi.json). Use '-' for stdout.
+        /// Output file (default: <database>-<format>.json). Use '-' for stdout.
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// OpenAPI title
-        #[arg(long)]
-        title: Option<String>,
+        /// Collection format
+        #[arg(short, long, value_enum, default_value_t = CollectionFormat::Postman)]
+        format: CollectionFormat,
 
-        /// OpenAPI version
+        /// Collection name (default: database file stem)
         #[arg(long)]
-        version: Option<String>,
+        name: Option<String>,
 
-        /// Sample up to N request/response bodies per operation (opt-in)
-        #[arg(long)]
-        sample_bodies: Option<usize>,
+        /// Also write the host/auth variables as an environment file. Recorded credentials
+        /// (auth headers, cookies) are only written here; the collection leaves them empty
+        #[arg(long, value_name = "FILE")]
+        environment_output: Option<PathBuf>,
 
-        /// Maximum body size to sample (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
-        #[arg(long)]
-        sample_body_max_size: Option<String>,
+        /// Example responses to keep per request (distinct status codes)
+        #[arg(long, default_value_t = 3)]
+        max_examples: usize,
 
         /// Allow reading external blob paths from the database
         #[arg(long, action = clap::ArgAction::SetTrue)]
@@ -728,11 +788,11 @@ pub enum Commands {
         #[arg(long, action = clap::ArgAction::Append)]
         source_contains: Option<Vec<String>>,
 
-        /// Only export entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        /// Only include entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
         #[arg(long)]
         from: Option<String>,
 
-        /// Only export entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        /// Only include entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
         #[arg(long)]
         to: Option<String>,
 
@@ -753,30 +813,78 @@ pub enum Commands {
         max_response_size: Option<String>,
     },
 
-    /// Export request waterfall timing data
-    Waterfall {
-        /// Database file to inspect
+    /// Export a k6 or Locust load-test script that replays captured requests in order
+    ExportLoadtest {
+        /// Database file to export
         database: PathBuf,
 
-        /// Output file (default: stdout). Use '-' for stdout.
+        /// Output file (default: <database>-k6.js or <database>-locustfile.py). Use '-' for stdout.
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Output format
-        #[arg(short, long, value_enum)]
-        format: Option<WaterfallFormat>,
+        /// Script format
+        #[arg(short, long, value_enum, default_value_t = LoadTestFormat::K6)]
+        format: LoadTestFormat,
 
-        /// Group requests by page, top-level navigation, or none
-        #[arg(long, value_enum)]
-        group_by: Option<WaterfallGroupBy>,
+        /// Keep static asset requests (scripts, stylesheets, images, fonts)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        include_static: Option<bool>,
+
+        /// Cap recorded pauses between requests at this many seconds (0 disables think times)
+        #[arg(long, value_name = "SECS", default_value_t = 10.0)]
+        max_think_time: f64,
+
+        /// Drop sensitive headers and cookies (the `redact` default header patterns)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        drop_sensitive: Option<bool>,
+
+        /// Allow reading external blob paths from the database
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        allow_external_paths: Option<bool>,
+
+        /// Root directory for external blob paths (defaults to database directory)
+        #[arg(long, value_name = "DIR")]
+        external_path_root: Option<PathBuf>,
+
+        /// Exact URL match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url: Option<Vec<String>>,
+
+        /// URL substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_contains: Option<Vec<String>>,
+
+        /// URL regex match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_regex: Option<Vec<String>>,
 
         /// Hostname filter (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
         host: Option<Vec<String>>,
 
-        /// Page id or title substring filter (repeatable)
+        /// HTTP method filter (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
-        page: Option<Vec<String>>,
+        method: Option<Vec<String>>,
+
+        /// HTTP status filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        status: Option<Vec<i32>>,
+
+        /// Response MIME type substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        mime: Option<Vec<String>>,
+
+        /// URL extension filter (repeatable, comma-separated allowed; e.g. 'js,css,json')
+        #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
+        ext: Option<Vec<String>>,
+
+        /// Filter by import source filename (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source: Option<Vec<String>>,
+
+        /// Filter by import source filename substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source_contains: Option<Vec<String>>,
 
         /// Only include entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
         #[arg(long)]
@@ -786,47 +894,64 @@ pub enum Commands {
         #[arg(long)]
         to: Option<String>,
 
-        /// Width of the ASCII timeline (text format only)
+        /// Minimum request body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
         #[arg(long)]
-        width: Option<usize>,
+        min_request_size: Option<String>,
+
+        /// Maximum request body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_request_size: Option<String>,
+
+        /// Minimum response body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_response_size: Option<String>,
+
+        /// Maximum response body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_response_size: Option<String>,
     },
 
-    /// Generate a self-contained HTML report (waterfall, slowest requests, errors)
-    Report {
-        /// Input SQLite database or HAR file
-        input: PathBuf,
+    /// Generate an OpenAPI schema from captured traffic
+    #[command(name = "openapi")]
+    OpenApi {
+        /// Database file to inspect
+        database: PathBuf,
 
-        /// Output HTML file (default: <input>.html). Use '-' for stdout.
+        /// Output file (default: <database>-openapi.json). Use '-' for stdout.
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Report title
+        /// OpenAPI title
         #[arg(long)]
         title: Option<String>,
 
-        /// Top N rows for slow/error tables
+        /// OpenAPI version
         #[arg(long)]
-        top: Option<usize>,
+        version: Option<String>,
 
-        /// Threshold for slow requests by total time (ms)
+        /// Sample up to N request/response bodies per operation (opt-in)
         #[arg(long)]
-        slow_total_ms: Option<f64>,
+        sample_bodies: Option<usize>,
 
-        /// Threshold for slow requests by TTFB (ms)
+        /// Maximum body size to sample (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
         #[arg(long)]
-        slow_ttfb_ms: Option<f64>,
+        sample_body_max_size: Option<String>,
 
-        /// Max number of entries rendered in the waterfall (stats still computed over all filtered entries)
-        #[arg(long)]
-        waterfall_limit: Option<usize>,
+        /// Keep literal URL paths instead of templating IDs as path parameters
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        literal_paths: Option<bool>,
 
-        /// Group waterfall requests by page, navigation, or none
-        #[arg(long, value_enum)]
-        group_by: Option<WaterfallGroupBy>,
+        /// Treat a path position as a parameter once it takes this many distinct values (default: 20, 0 disables)
+        #[arg(long, value_name = "N")]
+        path_param_threshold: Option<usize>,
 
-        /// Page id or title substring filter (repeatable; DB uses pages table, HAR uses pageref/page title)
-        #[arg(long, action = clap::ArgAction::Append)]
-        page: Option<Vec<String>>,
+        /// Allow reading external blob paths from the database
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        allow_external_paths: Option<bool>,
+
+        /// Root directory for external blob paths (defaults to database directory)
+        #[arg(long, value_name = "DIR")]
+        external_path_root: Option<PathBuf>,
 
         /// Exact URL match (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
@@ -860,19 +985,19 @@ pub enum Commands {
         #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
         ext: Option<Vec<String>>,
 
-        /// Filter by import source filename (repeatable; DB only)
+        /// Filter by import source filename (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
         source: Option<Vec<String>>,
 
-        /// Filter by import source filename substring match (repeatable; DB only)
+        /// Filter by import source filename substring match (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
         source_contains: Option<Vec<String>>,
 
-        /// Only include entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        /// Only export entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
         #[arg(long)]
         from: Option<String>,
 
-        /// Only include entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        /// Only export entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
         #[arg(long)]
         to: Option<String>,
 
@@ -893,71 +1018,626 @@ pub enum Commands {
         max_response_size: Option<String>,
     },
 
-    /// Redact sensitive headers/cookies in a harlite SQLite database
-    Redact {
-        /// Output database file (default: modify in-place)
+    /// Check captured traffic against an OpenAPI spec and report drift
+    Conform {
+        /// Database file to check
+        database: PathBuf,
+
+        /// OpenAPI 3.x spec (YAML or JSON)
+        #[arg(long, value_name = "FILE")]
+        spec: PathBuf,
+
+        /// Output file (default: stdout). Use '-' for stdout.
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Overwrite output database if it exists
-        #[arg(long, action = clap::ArgAction::SetTrue)]
-        force: Option<bool>,
+        /// Report format
+        #[arg(short, long, value_enum, default_value_t = ConformFormat::Table)]
+        format: ConformFormat,
 
-        /// Only report what would be redacted (no writes)
+        /// Request header to accept even if the spec does not document it (repeatable)
+        #[arg(long, value_name = "NAME", action = clap::ArgAction::Append)]
+        ignore_header: Option<Vec<String>>,
+
+        /// Exit with an error when any entry violates the spec
         #[arg(long, action = clap::ArgAction::SetTrue)]
-        dry_run: Option<bool>,
+        fail_on_violation: Option<bool>,
 
-        /// Disable default redaction patterns
+        /// Allow reading external blob paths from the database
         #[arg(long, action = clap::ArgAction::SetTrue)]
-        no_defaults: Option<bool>,
+        allow_external_paths: Option<bool>,
 
-        /// Header name pattern to redact (repeatable)
-        #[arg(long, action = clap::ArgAction::Append)]
-        header: Option<Vec<String>>,
+        /// Root directory for external blob paths (defaults to database directory)
+        #[arg(long, value_name = "DIR")]
+        external_path_root: Option<PathBuf>,
 
-        /// Cookie name pattern to redact (repeatable)
+        /// Exact URL match (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
-        cookie: Option<Vec<String>>,
+        url: Option<Vec<String>>,
 
-        /// Query parameter name pattern to redact (repeatable)
+        /// URL substring match (repeatable)
         #[arg(long, action = clap::ArgAction::Append)]
-        query_param: Option<Vec<String>>,
+        url_contains: Option<Vec<String>>,
 
-        /// Regex pattern to redact from stored bodies (repeatable)
-        #[arg(long = "body-regex", action = clap::ArgAction::Append)]
-        body_regex: Option<Vec<String>>,
+        /// URL regex match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_regex: Option<Vec<String>>,
 
-        /// Pattern matching mode for names
-        #[arg(long = "match", value_enum)]
-        match_mode: Option<NameMatchMode>,
+        /// Hostname filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        host: Option<Vec<String>>,
 
-        /// Replacement token to write for redacted values
-        #[arg(long)]
-        token: Option<String>,
+        /// HTTP method filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        method: Option<Vec<String>>,
 
-        /// Database file to redact (default: the only *.db in the current directory)
-        database: Option<PathBuf>,
-    },
+        /// HTTP status filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        status: Option<Vec<i32>>,
 
-    /// Scan for PII in URLs and bodies
-    Pii {
-        /// Output format
-        #[arg(short, long, value_enum)]
-        format: Option<OutputFormat>,
+        /// Response MIME type substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        mime: Option<Vec<String>>,
 
-        /// Automatically redact findings (write changes)
-        #[arg(long, action = clap::ArgAction::SetTrue)]
-        redact: Option<bool>,
+        /// URL extension filter (repeatable, comma-separated allowed; e.g. 'js,css,json')
+        #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
+        ext: Option<Vec<String>>,
 
-        /// Output database file (default: modify in-place when --redact)
-        #[arg(short, long)]
-        output: Option<PathBuf>,
+        /// Filter by import source filename (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source: Option<Vec<String>>,
 
-        /// Overwrite output database if it exists
-        #[arg(long, action = clap::ArgAction::SetTrue)]
-        force: Option<bool>,
+        /// Filter by import source filename substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source_contains: Option<Vec<String>>,
 
-        /// Only report what would be redacted (no writes)
+        /// Only export entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only export entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Minimum request body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_request_size: Option<String>,
+
+        /// Maximum request body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_request_size: Option<String>,
+
+        /// Minimum response body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_response_size: Option<String>,
+
+        /// Maximum response body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_response_size: Option<String>,
+    },
+
+    /// Generate JSON Schema, TypeScript or Rust types from captured JSON bodies
+    Types {
+        /// Database file to inspect
+        database: PathBuf,
+
+        /// Output file (default: stdout). Use '-' for stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = TypesFormat::Typescript)]
+        format: TypesFormat,
+
+        /// Merge up to N request/response bodies per endpoint
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        sample_bodies: usize,
+
+        /// Maximum body size to sample (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        sample_body_max_size: Option<String>,
+
+        /// Keep literal URL paths instead of templating IDs as path parameters
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        literal_paths: Option<bool>,
+
+        /// Treat a path position as a parameter once it takes this many distinct values (default: 20, 0 disables)
+        #[arg(long, value_name = "N")]
+        path_param_threshold: Option<usize>,
+
+        /// Allow reading external blob paths from the database
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        allow_external_paths: Option<bool>,
+
+        /// Root directory for external blob paths (defaults to database directory)
+        #[arg(long, value_name = "DIR")]
+        external_path_root: Option<PathBuf>,
+
+        /// Exact URL match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url: Option<Vec<String>>,
+
+        /// URL substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_contains: Option<Vec<String>>,
+
+        /// URL regex match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_regex: Option<Vec<String>>,
+
+        /// Hostname filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        host: Option<Vec<String>>,
+
+        /// HTTP method filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        method: Option<Vec<String>>,
+
+        /// HTTP status filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        status: Option<Vec<i32>>,
+
+        /// Response MIME type substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        mime: Option<Vec<String>>,
+
+        /// URL extension filter (repeatable, comma-separated allowed; e.g. 'js,css,json')
+        #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
+        ext: Option<Vec<String>>,
+
+        /// Filter by import source filename (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source: Option<Vec<String>>,
+
+        /// Filter by import source filename substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source_contains: Option<Vec<String>>,
+
+        /// Only export entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only export entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Minimum request body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_request_size: Option<String>,
+
+        /// Maximum request body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_request_size: Option<String>,
+
+        /// Minimum response body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_response_size: Option<String>,
+
+        /// Maximum response body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_response_size: Option<String>,
+    },
+    /// Report per-operation GraphQL latency and errors, and infer an SDL schema
+    #[cfg(feature = "graphql")]
+    #[command(name = "graphql")]
+    Graphql {
+        /// Database file to inspect
+        database: PathBuf,
+
+        /// Output file (default: stdout). Use '-' for stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format (table, json, sdl)
+        #[arg(short, long, value_enum, default_value_t = GraphqlFormat::Table)]
+        format: GraphqlFormat,
+
+        /// Allow reading external blob paths from the database
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        allow_external_paths: Option<bool>,
+
+        /// Root directory for external blob paths (defaults to database directory)
+        #[arg(long, value_name = "DIR")]
+        external_path_root: Option<PathBuf>,
+
+        /// Exact URL match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url: Option<Vec<String>>,
+
+        /// URL substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_contains: Option<Vec<String>>,
+
+        /// URL regex match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_regex: Option<Vec<String>>,
+
+        /// Hostname filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        host: Option<Vec<String>>,
+
+        /// HTTP method filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        method: Option<Vec<String>>,
+
+        /// HTTP status filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        status: Option<Vec<i32>>,
+
+        /// Response MIME type substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        mime: Option<Vec<String>>,
+
+        /// URL extension filter (repeatable, comma-separated allowed; e.g. 'js,css,json')
+        #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
+        ext: Option<Vec<String>>,
+
+        /// Filter by import source filename (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source: Option<Vec<String>>,
+
+        /// Filter by import source filename substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source_contains: Option<Vec<String>>,
+
+        /// Only export entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only export entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Minimum request body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_request_size: Option<String>,
+
+        /// Maximum request body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_request_size: Option<String>,
+
+        /// Minimum response body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_response_size: Option<String>,
+
+        /// Maximum response body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_response_size: Option<String>,
+    },
+
+
+    /// Generate curl/HTTPie commands or client code that re-sends captured requests
+    Codegen {
+        /// Database file to inspect
+        database: PathBuf,
+
+        /// Output file (default: stdout). Use '-' for stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = CodegenFormat::Curl)]
+        format: CodegenFormat,
+
+        /// Drop sensitive headers and cookies (the `redact` default header patterns)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        drop_sensitive: Option<bool>,
+
+        /// Allow reading external blob paths from the database
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        allow_external_paths: Option<bool>,
+
+        /// Root directory for external blob paths (defaults to database directory)
+        #[arg(long, value_name = "DIR")]
+        external_path_root: Option<PathBuf>,
+
+        /// Exact URL match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url: Option<Vec<String>>,
+
+        /// URL substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_contains: Option<Vec<String>>,
+
+        /// URL regex match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_regex: Option<Vec<String>>,
+
+        /// Hostname filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        host: Option<Vec<String>>,
+
+        /// HTTP method filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        method: Option<Vec<String>>,
+
+        /// HTTP status filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        status: Option<Vec<i32>>,
+
+        /// Response MIME type substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        mime: Option<Vec<String>>,
+
+        /// URL extension filter (repeatable, comma-separated allowed; e.g. 'js,css,json')
+        #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
+        ext: Option<Vec<String>>,
+
+        /// Filter by import source filename (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source: Option<Vec<String>>,
+
+        /// Filter by import source filename substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source_contains: Option<Vec<String>>,
+
+        /// Only include entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only include entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Minimum request body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_request_size: Option<String>,
+
+        /// Maximum request body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_request_size: Option<String>,
+
+        /// Minimum response body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_response_size: Option<String>,
+
+        /// Maximum response body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_response_size: Option<String>,
+    },
+
+    /// Export request waterfall timing data
+    Waterfall {
+        /// Database file to inspect
+        database: PathBuf,
+
+        /// Output file (default: stdout). Use '-' for stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: Option<WaterfallFormat>,
+
+        /// Group requests by page, top-level navigation, or none
+        #[arg(long, value_enum)]
+        group_by: Option<WaterfallGroupBy>,
+
+        /// Hostname filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        host: Option<Vec<String>>,
+
+        /// Page id or title substring filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        page: Option<Vec<String>>,
+
+        /// Only include entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only include entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Width of the ASCII timeline (text format only)
+        #[arg(long)]
+        width: Option<usize>,
+    },
+
+    /// Generate a self-contained HTML report (waterfall, slowest requests, errors)
+    Report {
+        /// Input SQLite database or HAR file
+        input: PathBuf,
+
+        /// Output HTML file (default: <input>.html). Use '-' for stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Report title
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Top N rows for slow/error tables
+        #[arg(long)]
+        top: Option<usize>,
+
+        /// Threshold for slow requests by total time (ms)
+        #[arg(long)]
+        slow_total_ms: Option<f64>,
+
+        /// Threshold for slow requests by TTFB (ms)
+        #[arg(long)]
+        slow_ttfb_ms: Option<f64>,
+
+        /// Max number of entries rendered in the waterfall (stats still computed over all filtered entries)
+        #[arg(long)]
+        waterfall_limit: Option<usize>,
+
+        /// Group waterfall requests by page, navigation, or none
+        #[arg(long, value_enum)]
+        group_by: Option<WaterfallGroupBy>,
+
+        /// Page id or title substring filter (repeatable; DB uses pages table, HAR uses pageref/page title)
+        #[arg(long, action = clap::ArgAction::Append)]
+        page: Option<Vec<String>>,
+
+        /// Exact URL match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url: Option<Vec<String>>,
+
+        /// URL substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_contains: Option<Vec<String>>,
+
+        /// URL regex match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        url_regex: Option<Vec<String>>,
+
+        /// Hostname filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        host: Option<Vec<String>>,
+
+        /// HTTP method filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        method: Option<Vec<String>>,
+
+        /// HTTP status filter (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        status: Option<Vec<i32>>,
+
+        /// Response MIME type substring match (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        mime: Option<Vec<String>>,
+
+        /// URL extension filter (repeatable, comma-separated allowed; e.g. 'js,css,json')
+        #[arg(long, value_delimiter = ',', action = clap::ArgAction::Append)]
+        ext: Option<Vec<String>>,
+
+        /// Filter by import source filename (repeatable; DB only)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source: Option<Vec<String>>,
+
+        /// Filter by import source filename substring match (repeatable; DB only)
+        #[arg(long, action = clap::ArgAction::Append)]
+        source_contains: Option<Vec<String>>,
+
+        /// Only include entries on/after this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only include entries on/before this timestamp (RFC3339) or date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Minimum request body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_request_size: Option<String>,
+
+        /// Maximum request body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_request_size: Option<String>,
+
+        /// Minimum response body size (e.g., '1KB', '1.5MB', '1M', '100k', '500B')
+        #[arg(long)]
+        min_response_size: Option<String>,
+
+        /// Maximum response body size (e.g., '100KB', '1.5MB', '1M', '100k', 'unlimited')
+        #[arg(long)]
+        max_response_size: Option<String>,
+    },
+
+    /// Redact sensitive headers/cookies in a harlite SQLite database
+    Redact {
+        /// Output database file (default: modify in-place)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite output database if it exists
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        force: Option<bool>,
+
+        /// Only report what would be redacted (no writes)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        dry_run: Option<bool>,
+
+        /// Disable default redaction patterns
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_defaults: Option<bool>,
+
+        /// Header name pattern to redact (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        header: Option<Vec<String>>,
+
+        /// Cookie name pattern to redact (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        cookie: Option<Vec<String>>,
+
+        /// Query parameter name pattern to redact (repeatable)
+        #[arg(long, action = clap::ArgAction::Append)]
+        query_param: Option<Vec<String>>,
+
+        /// Regex pattern to redact from stored bodies (repeatable)
+        #[arg(long = "body-regex", action = clap::ArgAction::Append)]
+        body_regex: Option<Vec<String>>,
+
+        /// JSONPath of a body value to redact, e.g. '$.user.password' (repeatable)
+        #[arg(long = "body-path", action = clap::ArgAction::Append)]
+        body_path: Option<Vec<String>>,
+
+        /// JSON key, form field or multipart part name to redact in bodies (repeatable)
+        #[arg(long = "body-field", action = clap::ArgAction::Append)]
+        body_field: Option<Vec<String>>,
+
+        /// Pattern matching mode for names
+        #[arg(long = "match", value_enum)]
+        match_mode: Option<NameMatchMode>,
+
+        /// Replacement token to write for redacted values
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Replace values with deterministic keyed pseudonyms instead of --token
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        pseudonymize: Option<bool>,
+
+        /// File holding the pseudonymization key (default: $HARLITE_PSEUDONYM_KEY)
+        #[arg(long, value_name = "FILE")]
+        pseudonym_key_file: Option<PathBuf>,
+
+        /// Versioned TOML policy file with header, cookie, param and body rules
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = [
+                "no_defaults",
+                "header",
+                "cookie",
+                "query_param",
+                "body_regex",
+                "body_path",
+                "body_field",
+                "match_mode",
+            ]
+        )]
+        policy: Option<PathBuf>,
+
+        /// Re-scan the database and fail if any rule still matches (no writes)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        verify: Option<bool>,
+
+        /// Database file to redact (default: the only *.db in the current directory)
+        database: Option<PathBuf>,
+    },
+
+    /// Scan for PII and secrets in URLs, headers, cookies and bodies
+    Pii {
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Automatically redact findings (write changes)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        redact: Option<bool>,
+
+        /// Output database file (default: modify in-place when --redact)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite output database if it exists
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        force: Option<bool>,
+
+        /// Only report what would be redacted (no writes)
         #[arg(long, action = clap::ArgAction::SetTrue)]
         dry_run: Option<bool>,
 
@@ -981,6 +1661,26 @@ pub enum Commands {
         #[arg(long, action = clap::ArgAction::SetTrue)]
         no_credit_card: Option<bool>,
 
+        /// Disable JWT detection
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_jwt: Option<bool>,
+
+        /// Disable AWS, GCP, Stripe and GitHub key detection
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_api_keys: Option<bool>,
+
+        /// Disable PEM private key detection
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_private_keys: Option<bool>,
+
+        /// Also detect high-entropy tokens (off by default; they include IDs and hashes)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        entropy: Option<bool>,
+
+        /// Minimum Shannon entropy in bits per character for --entropy (default: 4.2)
+        #[arg(long, value_name = "BITS")]
+        min_entropy: Option<f64>,
+
         /// Regex pattern to detect emails (repeatable)
         #[arg(long = "email-regex", action = clap::ArgAction::Append)]
         email_regex: Option<Vec<String>>,
@@ -1001,6 +1701,14 @@ pub enum Commands {
         #[arg(long)]
         token: Option<String>,
 
+        /// Replace values with deterministic keyed pseudonyms instead of --token
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        pseudonymize: Option<bool>,
+
+        /// File holding the pseudonymization key (default: $HARLITE_PSEUDONYM_KEY)
+        #[arg(long, value_name = "FILE")]
+        pseudonym_key_file: Option<PathBuf>,
+
         /// Database file to scan (default: the only *.db in the current directory)
         database: Option<PathBuf>,
     },
@@ -1099,6 +1807,55 @@ pub enum Commands {
         /// Override header by URL regex (repeatable, format: '<regex>:<name>=<value>' or '<name>=<value>')
         #[arg(long, action = clap::ArgAction::Append)]
         override_header: Option<Vec<String>>,
+
+        /// Compare replayed response bodies with the recorded ones (JSON, text or binary diff)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        diff_body: Option<bool>,
+
+        /// JSONPath to skip when diffing JSON response bodies (repeatable, e.g. '$.meta.requestId', '$..timestamp')
+        #[arg(long, value_name = "PATH", action = clap::ArgAction::Append)]
+        ignore_json_path: Option<Vec<String>>,
+
+        /// Store the replay run as a new import in this database (created if missing)
+        #[arg(long, value_name = "DB")]
+        record: Option<PathBuf>,
+
+        /// Extract a value from each response for later requests (repeatable,
+        /// format: '<name>=json:<path>', '<name>=regex:<pattern>', '<name>=header:<name>' or '<name>=cookie:<name>')
+        #[arg(long, value_name = "RULE", action = clap::ArgAction::Append)]
+        extract: Option<Vec<String>>,
+
+        /// Keep cookies set by replayed responses and send them on later requests
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        cookie_jar: Option<bool>,
+
+        /// Load-test mode: loop over the selected entries and report latency/error statistics
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        load: Option<bool>,
+
+        /// Load test duration in seconds (default: 60)
+        #[arg(long, value_name = "SECS", requires = "load")]
+        duration: Option<u64>,
+
+        /// Ramp the request rate (or virtual-user starts) up over this many seconds
+        #[arg(long, value_name = "SECS", requires = "load")]
+        ramp_up: Option<u64>,
+
+        /// Number of virtual users issuing requests concurrently (default: --concurrency)
+        #[arg(long, value_name = "N", requires = "load")]
+        vus: Option<usize>,
+
+        /// Target requests per second across all virtual users
+        #[arg(long, value_name = "RPS", requires = "load")]
+        rps: Option<f64>,
+
+        /// Send each request at its recorded offset from the first one (by startedDateTime)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        preserve_timing: Option<bool>,
+
+        /// Playback speed for --preserve-timing (e.g. 2 = twice as fast, 0.5 = half speed)
+        #[arg(long, value_name = "FACTOR", requires = "preserve_timing")]
+        speed: Option<f64>,
     },
 
     /// Serve recorded responses as a mock API server
@@ -1134,6 +1891,22 @@ pub enum Commands {
         /// TLS private key (PEM)
         #[arg(long)]
         tls_key: Option<PathBuf>,
+
+        /// Enable the unauthenticated /__harlite/ admin endpoints (routes, stats, unmatched, reset, reload)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        admin: Option<bool>,
+
+        /// Keep at most this many unmatched requests for the admin API (oldest are dropped first; default: 1000); --misses-har and --record-misses still get every miss
+        #[arg(long, value_name = "N")]
+        max_unmatched: Option<usize>,
+
+        /// On graceful shutdown (Ctrl+C), write unmatched requests to this HAR file; nothing is written if the process is killed
+        #[arg(long, value_name = "FILE")]
+        misses_har: Option<PathBuf>,
+
+        /// On graceful shutdown (Ctrl+C), store unmatched requests in the database's serve_misses table; nothing is stored if the process is killed
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        record_misses: Option<bool>,
     },
 
     /// Merge multiple harlite databases into one
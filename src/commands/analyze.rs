@@ -3,12 +3,12 @@ use std::path::PathBuf;
 
 use chrono::SecondsFormat;
 use chrono::{DateTime, NaiveDate, Utc};
-use rusqlite::Connection;
 use serde::Serialize;
 use url::Url;
 
-use crate::db::{ensure_schema_upgrades, load_entries, EntryQuery, EntryRow};
+use crate::db::{ensure_schema_upgrades, load_entries, open_database, EntryQuery, EntryRow};
 use crate::error::{HarliteError, Result};
+use super::util::percentile;
 
 pub struct AnalyzeOptions {
     pub json: bool,
@@ -126,7 +126,7 @@ struct AnalyzeOutput {
 }
 
 pub fn run_analyze(database: PathBuf, options: &AnalyzeOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     ensure_schema_upgrades(&conn)?;
 
     let from_started_at = match options.from.as_deref() {
@@ -374,18 +374,6 @@ fn build_stats(values: &[f64]) -> Option<TimeStats> {
     })
 }
 
-fn percentile(sorted: &[f64], pct: f64) -> f64 {
-    if sorted.is_empty() {
-        return 0.0;
-    }
-    if sorted.len() == 1 {
-        return sorted[0];
-    }
-    let rank = (pct / 100.0) * (sorted.len() as f64 - 1.0);
-    let idx = rank.round() as usize;
-    sorted[idx.min(sorted.len() - 1)]
-}
-
 fn build_slow_entry(
     row: &EntryRow,
     total_ms: Option<f64>,
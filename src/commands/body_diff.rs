@@ -0,0 +1,408 @@
+use serde_json::Value;
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+
+use super::json_path::{format_path, JsonPath, PathSegment};
+
+/// Line diffs above this many normalized lines per side fall back to a count comparison.
+const MAX_TEXT_DIFF_LINES: usize = 5000;
+/// Maximum number of individual changes kept per body.
+const MAX_REPORTED_CHANGES: usize = 20;
+const MAX_CHANGE_LEN: usize = 120;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyKind {
+    Json,
+    Text,
+    Binary,
+}
+
+impl BodyKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BodyKind::Json => "json",
+            BodyKind::Text => "text",
+            BodyKind::Binary => "binary",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BodyDiff {
+    pub kind: BodyKind,
+    pub matches: bool,
+    pub summary: String,
+    pub changes: Vec<String>,
+}
+
+/// Compare a recorded response body with a replayed one.
+///
+/// JSON bodies get a structural diff (object key order is ignored and `ignore` paths are
+/// skipped), textual bodies a line diff after whitespace normalization, and anything else
+/// is compared by blake3 hash.
+pub fn diff_bodies(
+    original: &[u8],
+    original_mime: Option<&str>,
+    replay: &[u8],
+    replay_mime: Option<&str>,
+    ignore: &[JsonPath],
+) -> BodyDiff {
+    let looks_json = is_json_mime(original_mime) || is_json_mime(replay_mime);
+    if looks_json || (looks_like_json(original) && looks_like_json(replay)) {
+        if let (Ok(left), Ok(right)) = (
+            serde_json::from_slice::<Value>(original),
+            serde_json::from_slice::<Value>(replay),
+        ) {
+            return diff_json(&left, &right, ignore);
+        }
+    }
+
+    let textual = is_text_mime(original_mime) || is_text_mime(replay_mime);
+    match (std::str::from_utf8(original), std::str::from_utf8(replay)) {
+        (Ok(left), Ok(right))
+            if textual || (!is_binary_mime(original_mime) && !is_binary_mime(replay_mime)) =>
+        {
+            diff_text(left, right)
+        }
+        _ => diff_binary(original, replay),
+    }
+}
+
+pub fn diff_json(left: &Value, right: &Value, ignore: &[JsonPath]) -> BodyDiff {
+    let mut acc = JsonDiffAcc::default();
+    let mut path = Vec::new();
+    compare_json(left, right, &mut path, ignore, &mut acc);
+
+    let matches = acc.changed + acc.added + acc.removed == 0;
+    let summary = if matches {
+        "identical".to_string()
+    } else {
+        format!(
+            "{} changed, {} added, {} removed",
+            acc.changed, acc.added, acc.removed
+        )
+    };
+    BodyDiff {
+        kind: BodyKind::Json,
+        matches,
+        summary,
+        changes: acc.changes,
+    }
+}
+
+#[derive(Default)]
+struct JsonDiffAcc {
+    changed: usize,
+    added: usize,
+    removed: usize,
+    changes: Vec<String>,
+}
+
+impl JsonDiffAcc {
+    fn push(&mut self, change: String) {
+        if self.changes.len() < MAX_REPORTED_CHANGES {
+            self.changes.push(truncate_change(&change));
+        }
+    }
+}
+
+fn compare_json(
+    left: &Value,
+    right: &Value,
+    path: &mut Vec<PathSegment>,
+    ignore: &[JsonPath],
+    acc: &mut JsonDiffAcc,
+) {
+    if ignore.iter().any(|p| p.matches(path)) {
+        return;
+    }
+
+    match (left, right) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, left_child) in a {
+                path.push(PathSegment::Key(key.clone()));
+                match b.get(key) {
+                    Some(right_child) => compare_json(left_child, right_child, path, ignore, acc),
+                    None if !ignore.iter().any(|p| p.matches(path)) => {
+                        acc.removed += 1;
+                        acc.push(format!("- {}", format_path(path)));
+                    }
+                    None => {}
+                }
+                path.pop();
+            }
+            for key in b.keys().filter(|key| !a.contains_key(*key)) {
+                path.push(PathSegment::Key(key.clone()));
+                if !ignore.iter().any(|p| p.matches(path)) {
+                    acc.added += 1;
+                    acc.push(format!("+ {}", format_path(path)));
+                }
+                path.pop();
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for idx in 0..a.len().max(b.len()) {
+                path.push(PathSegment::Index(idx));
+                match (a.get(idx), b.get(idx)) {
+                    (Some(l), Some(r)) => compare_json(l, r, path, ignore, acc),
+                    (Some(_), None) if !ignore.iter().any(|p| p.matches(path)) => {
+                        acc.removed += 1;
+                        acc.push(format!("- {}", format_path(path)));
+                    }
+                    (None, Some(_)) if !ignore.iter().any(|p| p.matches(path)) => {
+                        acc.added += 1;
+                        acc.push(format!("+ {}", format_path(path)));
+                    }
+                    _ => {}
+                }
+                path.pop();
+            }
+        }
+        (a, b) if a == b => {}
+        (a, b) => {
+            acc.changed += 1;
+            acc.push(format!(
+                "~ {}: {} -> {}",
+                format_path(path),
+                scalar_preview(a),
+                scalar_preview(b)
+            ));
+        }
+    }
+}
+
+fn scalar_preview(value: &Value) -> String {
+    match value {
+        Value::Object(_) => "{...}".to_string(),
+        Value::Array(_) => "[...]".to_string(),
+        other => other.to_string(),
+    }
+}
+
+pub fn diff_text(left: &str, right: &str) -> BodyDiff {
+    let left = normalize_text(left);
+    let right = normalize_text(right);
+
+    if left == right {
+        return BodyDiff {
+            kind: BodyKind::Text,
+            matches: true,
+            summary: "identical".to_string(),
+            changes: Vec::new(),
+        };
+    }
+
+    if left.len() > MAX_TEXT_DIFF_LINES || right.len() > MAX_TEXT_DIFF_LINES {
+        return BodyDiff {
+            kind: BodyKind::Text,
+            matches: false,
+            summary: format!(
+                "differs ({} -> {} lines, too large for a line diff)",
+                left.len(),
+                right.len()
+            ),
+            changes: Vec::new(),
+        };
+    }
+
+    let ops = line_diff(&left, &right);
+    let mut added = 0usize;
+    let mut removed = 0usize;
+    let mut changes = Vec::new();
+    for op in ops {
+        let line = match op {
+            LineOp::Added(line) => {
+                added += 1;
+                format!("+ {line}")
+            }
+            LineOp::Removed(line) => {
+                removed += 1;
+                format!("- {line}")
+            }
+        };
+        if changes.len() < MAX_REPORTED_CHANGES {
+            changes.push(truncate_change(&line));
+        }
+    }
+
+    BodyDiff {
+        kind: BodyKind::Text,
+        matches: false,
+        summary: format!("{added} lines added, {removed} lines removed"),
+        changes,
+    }
+}
+
+/// Collapse whitespace runs inside lines and drop blank lines, so reformatting alone
+/// does not count as a change.
+fn normalize_text(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+enum LineOp<'a> {
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+/// Myers line diff; memory stays linear in the input size.
+fn line_diff<'a>(left: &'a [String], right: &'a [String]) -> Vec<LineOp<'a>> {
+    let mut ops = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, left, right) {
+        let (tag, removed, added) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        ops.extend(left[removed].iter().map(|line| LineOp::Removed(line)));
+        ops.extend(right[added].iter().map(|line| LineOp::Added(line)));
+    }
+    ops
+}
+
+pub fn diff_binary(left: &[u8], right: &[u8]) -> BodyDiff {
+    let left_hash = blake3::hash(left);
+    let right_hash = blake3::hash(right);
+    let matches = left_hash == right_hash;
+    let summary = if matches {
+        "identical".to_string()
+    } else {
+        format!(
+            "hash {} -> {}",
+            &left_hash.to_hex()[..12],
+            &right_hash.to_hex()[..12]
+        )
+    };
+    BodyDiff {
+        kind: BodyKind::Binary,
+        matches,
+        summary,
+        changes: Vec::new(),
+    }
+}
+
+fn media_type(mime: Option<&str>) -> Option<String> {
+    mime.map(|m| m.split(';').next().unwrap_or(m).trim().to_ascii_lowercase())
+        .filter(|m| !m.is_empty())
+}
+
+fn is_json_mime(mime: Option<&str>) -> bool {
+    media_type(mime)
+        .is_some_and(|m| m == "application/json" || m.ends_with("+json") || m.ends_with("/json"))
+}
+
+fn is_text_mime(mime: Option<&str>) -> bool {
+    media_type(mime).is_some_and(|m| {
+        m.starts_with("text/")
+            || m.ends_with("+xml")
+            || m.ends_with("/xml")
+            || m.contains("javascript")
+            || m == "application/x-www-form-urlencoded"
+    })
+}
+
+fn is_binary_mime(mime: Option<&str>) -> bool {
+    media_type(mime).is_some_and(|m| {
+        m.starts_with("image/")
+            || m.starts_with("audio/")
+            || m.starts_with("video/")
+            || m.starts_with("font/")
+            || m == "application/octet-stream"
+            || m == "application/pdf"
+            || m.contains("zip")
+            || m.contains("protobuf")
+    })
+}
+
+fn looks_like_json(body: &[u8]) -> bool {
+    matches!(
+        body.iter().find(|b| !b.is_ascii_whitespace()),
+        Some(b'{') | Some(b'[')
+    )
+}
+
+fn truncate_change(change: &str) -> String {
+    if change.chars().count() <= MAX_CHANGE_LEN {
+        return change.to_string();
+    }
+    let mut out: String = change.chars().take(MAX_CHANGE_LEN - 3).collect();
+    out.push_str("...");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_diff_reports_paths_and_honours_ignores() {
+        let original =
+            br#"{"id": 1, "user": {"name": "a", "token": "x"}, "items": [1, 2], "ts": 10}"#;
+        let replay = br#"{"ts": 99, "items": [1, 3, 4], "user": {"name": "b", "token": "y"}, "id": 1, "new": true}"#;
+        let ignore = vec![
+            JsonPath::parse("$.ts").unwrap(),
+            JsonPath::parse("$..token").unwrap(),
+        ];
+
+        let diff = diff_bodies(original, Some("application/json"), replay, None, &ignore);
+        assert_eq!(diff.kind, BodyKind::Json);
+        assert!(!diff.matches);
+        assert_eq!(diff.summary, "2 changed, 2 added, 0 removed");
+        assert!(diff
+            .changes
+            .contains(&"~ $.user.name: \"a\" -> \"b\"".to_string()));
+        assert!(diff.changes.contains(&"~ $.items[1]: 2 -> 3".to_string()));
+        assert!(diff.changes.contains(&"+ $.items[2]".to_string()));
+        assert!(diff.changes.contains(&"+ $.new".to_string()));
+    }
+
+    #[test]
+    fn json_diff_ignores_key_order_and_wildcards() {
+        let original = br#"{"rows": [{"id": 1, "at": "x"}, {"id": 2, "at": "y"}]}"#;
+        let replay = br#"{"rows": [{"at": "z", "id": 1}, {"at": "w", "id": 2}]}"#;
+        let ignore = vec![JsonPath::parse("$.rows[*].at").unwrap()];
+
+        let diff = diff_bodies(original, None, replay, None, &ignore);
+        assert_eq!(diff.kind, BodyKind::Json);
+        assert!(diff.matches, "{:?}", diff.changes);
+    }
+
+    #[test]
+    fn text_diff_normalizes_whitespace() {
+        let original = b"<html>\n  <body>\n    <p>Hello   world</p>\n\n  </body>\n</html>\n";
+        let same = b"<html>\n<body>\n<p>Hello world</p>\n</body>\n</html>";
+        let changed = b"<html>\n<body>\n<p>Goodbye world</p>\n</body>\n</html>";
+
+        let diff = diff_bodies(original, Some("text/html"), same, Some("text/html"), &[]);
+        assert_eq!(diff.kind, BodyKind::Text);
+        assert!(diff.matches);
+
+        let diff = diff_bodies(original, Some("text/html"), changed, Some("text/html"), &[]);
+        assert!(!diff.matches);
+        assert_eq!(diff.summary, "1 lines added, 1 lines removed");
+        assert_eq!(
+            diff.changes,
+            vec![
+                "- <p>Hello world</p>".to_string(),
+                "+ <p>Goodbye world</p>".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_bodies_compare_by_hash() {
+        let diff = diff_bodies(
+            &[0, 159, 146, 150],
+            Some("image/png"),
+            &[0, 159, 146, 150],
+            None,
+            &[],
+        );
+        assert_eq!(diff.kind, BodyKind::Binary);
+        assert!(diff.matches);
+
+        let diff = diff_bodies(&[0, 159, 146, 150], Some("image/png"), &[1, 2], None, &[]);
+        assert!(!diff.matches);
+        assert!(diff.summary.starts_with("hash "));
+    }
+}
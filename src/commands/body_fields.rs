@@ -0,0 +1,663 @@
+//! Structure-aware rewriting of JSON, form-urlencoded, multipart and GraphQL bodies.
+//!
+//! Values are visited with a JSONPath-style location (`$.user.password`, `$.token`
+//! for a form field, `$.meta.id` inside a JSON multipart part named `meta`,
+//! `$.password` for a GraphQL argument literal) and the body is rewritten only when
+//! something changed. JSON and GraphQL values are spliced in place, so key order and
+//! layout stay as recorded.
+
+use serde_json::Value;
+
+use super::json_path::PathSegment;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum BodyFormat {
+    Json,
+    Form,
+    Multipart(String),
+    Graphql,
+}
+
+/// Pick a structured format for a body, or `None` when it should be treated as text.
+pub(super) fn detect_body_format(mime: Option<&str>, text: &str) -> Option<BodyFormat> {
+    let media_type = mime
+        .map(|m| m.split(';').next().unwrap_or(m).trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if media_type == "application/x-www-form-urlencoded" {
+        return Some(BodyFormat::Form);
+    }
+    if media_type == "application/graphql" {
+        return Some(BodyFormat::Graphql);
+    }
+    if media_type.starts_with("multipart/") {
+        let boundary = mime
+            .and_then(boundary_param)
+            .or_else(|| sniff_boundary(text))?;
+        return Some(BodyFormat::Multipart(boundary));
+    }
+
+    let trimmed = text.trim_start();
+    let looks_json = media_type.contains("json") || trimmed.starts_with(['{', '[']);
+    if looks_json && serde_json::from_str::<Value>(text).is_ok() {
+        return Some(BodyFormat::Json);
+    }
+    None
+}
+
+/// Rewrite values inside a structured body.
+///
+/// `rewrite` is called pre-order for every JSON node (and once per form field or
+/// multipart part); returning `Some` replaces that node without descending into it.
+pub(super) fn rewrite_body_values(
+    text: &str,
+    format: &BodyFormat,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+) -> Option<String> {
+    match format {
+        BodyFormat::Json => rewrite_json_text(text, Vec::new(), rewrite),
+        BodyFormat::Form => rewrite_form(text, rewrite),
+        BodyFormat::Multipart(boundary) => rewrite_multipart(text, boundary, rewrite),
+        BodyFormat::Graphql => rewrite_graphql(text, Vec::new(), rewrite),
+    }
+}
+
+/// Rewrite values in a JSON document by splicing new values over the old ones, so
+/// key order, indentation and untouched values stay byte-for-byte as recorded.
+fn rewrite_json_text(
+    text: &str,
+    mut path: Vec<PathSegment>,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+) -> Option<String> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    let mut edits = Vec::new();
+    rewrite_json(&value, &mut path, rewrite, &mut edits);
+    if edits.is_empty() {
+        return None;
+    }
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    find_json_spans(text.as_bytes(), &mut pos, &mut path, &edits, &mut spans)?;
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, end, new_value) in spans {
+        out.push_str(&text[copied..start]);
+        out.push_str(&serde_json::to_string(new_value).ok()?);
+        copied = end;
+    }
+    out.push_str(&text[copied..]);
+    Some(out)
+}
+
+/// Collect `(path, new value)` for every node `rewrite` changes.
+fn rewrite_json(
+    value: &Value,
+    path: &mut Vec<PathSegment>,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+    edits: &mut Vec<(Vec<PathSegment>, Value)>,
+) {
+    // Like a JSON multipart part, the GraphQL document in a `query` field is visited
+    // through its argument literals rather than as one string.
+    if let Value::String(text) = value {
+        if is_graphql_document_at(path, text) {
+            if let Some(out) = rewrite_graphql(text, path.clone(), rewrite) {
+                edits.push((path.clone(), Value::String(out)));
+            }
+            return;
+        }
+    }
+    if let Some(new_value) = rewrite(path, value) {
+        if *value != new_value {
+            edits.push((path.clone(), new_value));
+        }
+        return;
+    }
+
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                path.push(PathSegment::Key(key.clone()));
+                rewrite_json(child, path, rewrite, edits);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (idx, child) in items.iter().enumerate() {
+                path.push(PathSegment::Index(idx));
+                rewrite_json(child, path, rewrite, edits);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Walk the JSON value at `pos` and record the byte span of every node with an edit.
+fn find_json_spans<'a>(
+    bytes: &[u8],
+    pos: &mut usize,
+    path: &mut Vec<PathSegment>,
+    edits: &'a [(Vec<PathSegment>, Value)],
+    spans: &mut Vec<(usize, usize, &'a Value)>,
+) -> Option<()> {
+    skip_json_whitespace(bytes, pos);
+    let start = *pos;
+    if let Some((_, new_value)) = edits.iter().find(|(at, _)| at == path) {
+        skip_json_value(bytes, pos)?;
+        spans.push((start, *pos, new_value));
+        return Some(());
+    }
+
+    match bytes.get(*pos)? {
+        b'{' => {
+            *pos += 1;
+            loop {
+                skip_json_whitespace(bytes, pos);
+                if bytes.get(*pos) == Some(&b'}') {
+                    *pos += 1;
+                    return Some(());
+                }
+                let key_start = *pos;
+                skip_json_string(bytes, pos)?;
+                let key: String = serde_json::from_slice(&bytes[key_start..*pos]).ok()?;
+                skip_json_whitespace(bytes, pos);
+                if bytes.get(*pos) != Some(&b':') {
+                    return None;
+                }
+                *pos += 1;
+                path.push(PathSegment::Key(key));
+                find_json_spans(bytes, pos, path, edits, spans)?;
+                path.pop();
+                skip_json_whitespace(bytes, pos);
+                match bytes.get(*pos)? {
+                    b',' => *pos += 1,
+                    b'}' => {
+                        *pos += 1;
+                        return Some(());
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        b'[' => {
+            *pos += 1;
+            let mut idx = 0;
+            loop {
+                skip_json_whitespace(bytes, pos);
+                if bytes.get(*pos) == Some(&b']') {
+                    *pos += 1;
+                    return Some(());
+                }
+                path.push(PathSegment::Index(idx));
+                find_json_spans(bytes, pos, path, edits, spans)?;
+                path.pop();
+                idx += 1;
+                skip_json_whitespace(bytes, pos);
+                match bytes.get(*pos)? {
+                    b',' => *pos += 1,
+                    b']' => {
+                        *pos += 1;
+                        return Some(());
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        _ => skip_json_value(bytes, pos),
+    }
+}
+
+fn skip_json_whitespace(bytes: &[u8], pos: &mut usize) {
+    while bytes.get(*pos).is_some_and(u8::is_ascii_whitespace) {
+        *pos += 1;
+    }
+}
+
+fn skip_json_string(bytes: &[u8], pos: &mut usize) -> Option<()> {
+    if bytes.get(*pos) != Some(&b'"') {
+        return None;
+    }
+    *pos += 1;
+    loop {
+        match bytes.get(*pos)? {
+            b'\\' => *pos += 2,
+            b'"' => {
+                *pos += 1;
+                return Some(());
+            }
+            _ => *pos += 1,
+        }
+    }
+}
+
+fn skip_json_value(bytes: &[u8], pos: &mut usize) -> Option<()> {
+    skip_json_whitespace(bytes, pos);
+    match bytes.get(*pos)? {
+        b'"' => skip_json_string(bytes, pos),
+        b'{' | b'[' => {
+            let mut depth = 0usize;
+            loop {
+                match bytes.get(*pos)? {
+                    b'"' => {
+                        skip_json_string(bytes, pos)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            *pos += 1;
+                            return Some(());
+                        }
+                    }
+                    _ => {}
+                }
+                *pos += 1;
+            }
+        }
+        _ => {
+            while bytes
+                .get(*pos)
+                .is_some_and(|b| !matches!(b, b',' | b']' | b'}') && !b.is_ascii_whitespace())
+            {
+                *pos += 1;
+            }
+            Some(())
+        }
+    }
+}
+
+fn rewrite_form(
+    text: &str,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+) -> Option<String> {
+    let mut changed = false;
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (name, value) in url::form_urlencoded::parse(text.trim().as_bytes()) {
+        let path = [PathSegment::Key(name.to_string())];
+        let current = Value::String(value.to_string());
+        let new_value = match rewrite(&path, &current) {
+            Some(new_value) if new_value != current => {
+                changed = true;
+                value_text(&new_value)
+            }
+            _ => value.to_string(),
+        };
+        serializer.append_pair(&name, &new_value);
+    }
+    changed.then(|| serializer.finish())
+}
+
+fn rewrite_multipart(
+    text: &str,
+    boundary: &str,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+) -> Option<String> {
+    let delimiter = format!("--{boundary}");
+    let mut segments: Vec<String> = text.split(delimiter.as_str()).map(str::to_string).collect();
+    if segments.len() < 2 {
+        return None;
+    }
+
+    let mut changed = false;
+    // The first segment is the preamble and a segment starting with "--" follows the
+    // closing delimiter.
+    for segment in segments.iter_mut().skip(1) {
+        if segment.starts_with("--") {
+            break;
+        }
+        if let Some(new_segment) = rewrite_multipart_part(segment, rewrite) {
+            *segment = new_segment;
+            changed = true;
+        }
+    }
+
+    changed.then(|| segments.join(delimiter.as_str()))
+}
+
+fn rewrite_multipart_part(
+    segment: &str,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+) -> Option<String> {
+    let (header_end, separator) = match (segment.find("\r\n\r\n"), segment.find("\n\n")) {
+        (Some(crlf), Some(lf)) if lf < crlf => (lf, "\n\n"),
+        (Some(crlf), _) => (crlf, "\r\n\r\n"),
+        (None, Some(lf)) => (lf, "\n\n"),
+        (None, None) => return None,
+    };
+    let headers = &segment[..header_end];
+    let rest = &segment[header_end + separator.len()..];
+    let (body, line_end) = if let Some(body) = rest.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = rest.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (rest, "")
+    };
+
+    let name = part_header(headers, "content-disposition")
+        .and_then(|value| header_param(value, "name"))?;
+    let is_json = part_header(headers, "content-type")
+        .is_some_and(|value| value.to_ascii_lowercase().contains("json"));
+
+    let new_body = if is_json && serde_json::from_str::<Value>(body).is_ok() {
+        rewrite_json_text(body, vec![PathSegment::Key(name)], rewrite)?
+    } else {
+        let path = [PathSegment::Key(name)];
+        let current = Value::String(body.to_string());
+        match rewrite(&path, &current) {
+            Some(new_value) if new_value != current => value_text(&new_value),
+            _ => return None,
+        }
+    };
+
+    Some(format!("{headers}{separator}{new_body}{line_end}"))
+}
+
+/// Rewrite string and number literals inside a GraphQL document.
+///
+/// A literal is reported under the argument or input field it is bound to
+/// (`login(password: "x")` and `input: {password: "x"}` both give `$.password`,
+/// or `$.query.password` inside a JSON payload; list items use the name of the
+/// list). Replacements are written back as string literals so the document stays
+/// parseable.
+fn rewrite_graphql(
+    text: &str,
+    base: Vec<PathSegment>,
+    rewrite: &mut dyn FnMut(&[PathSegment], &Value) -> Option<Value>,
+) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut copied = 0;
+    let mut pending_key: Option<String> = None;
+    let mut lists: Vec<Option<String>> = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let literal = match c {
+            b'"' => {
+                let end = graphql_string_end(text, i)?;
+                i = end;
+                graphql_string_value(&text[start..end]).map(Value::String)
+            }
+            b'-' | b'0'..=b'9' => {
+                while i < bytes.len()
+                    && matches!(bytes[i], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    i += 1;
+                }
+                serde_json::from_str::<Value>(&text[start..i])
+                    .ok()
+                    .filter(Value::is_number)
+            }
+            b'#' => {
+                i = text[i..].find('\n').map_or(bytes.len(), |off| i + off);
+                continue;
+            }
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' => {
+                while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                let rest = text[i..].trim_start_matches(|ch: char| ch.is_whitespace() || ch == ',');
+                pending_key = rest.starts_with(':').then(|| text[start..i].to_string());
+                continue;
+            }
+            b'[' => {
+                lists.push(pending_key.take());
+                i += 1;
+                continue;
+            }
+            b']' => {
+                lists.pop();
+                i += 1;
+                continue;
+            }
+            b'{' | b'(' => {
+                lists.push(None);
+                pending_key = None;
+                i += 1;
+                continue;
+            }
+            b'}' | b')' => {
+                lists.pop();
+                i += 1;
+                continue;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let key = pending_key
+            .take()
+            .or_else(|| lists.last().cloned().flatten());
+        let (Some(key), Some(current)) = (key, literal) else {
+            continue;
+        };
+        let mut path = base.clone();
+        path.push(PathSegment::Key(key));
+        if let Some(new_value) = rewrite(&path, &current) {
+            if new_value != current {
+                out.push_str(&text[copied..start]);
+                out.push_str(&serde_json::to_string(&value_text(&new_value)).ok()?);
+                copied = i;
+                changed = true;
+            }
+        }
+    }
+
+    if !changed {
+        return None;
+    }
+    out.push_str(&text[copied..]);
+    Some(out)
+}
+
+/// Whether a JSON string holds a GraphQL document sent as the `query` of a request.
+fn is_graphql_document_at(path: &[PathSegment], text: &str) -> bool {
+    if path.last() != Some(&PathSegment::Key("query".to_string())) {
+        return false;
+    }
+    let trimmed = text.trim_start();
+    let keyword = trimmed
+        .split(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+        .next()
+        .unwrap_or_default();
+    (trimmed.starts_with('{')
+        || matches!(keyword, "query" | "mutation" | "subscription" | "fragment"))
+        && trimmed.contains('{')
+}
+
+/// Byte offset just past the string (or block string) literal starting at `start`.
+fn graphql_string_end(text: &str, start: usize) -> Option<usize> {
+    if text[start..].starts_with("\"\"\"") {
+        let body = start + 3;
+        let mut search = body;
+        loop {
+            let off = text[search..].find("\"\"\"")?;
+            if !text[..search + off].ends_with('\\') {
+                return Some(search + off + 3);
+            }
+            search += off + 3;
+        }
+    }
+    let bytes = text.as_bytes();
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn graphql_string_value(literal: &str) -> Option<String> {
+    match literal.strip_prefix("\"\"\"") {
+        Some(body) => Some(body.strip_suffix("\"\"\"")?.replace("\\\"\"\"", "\"\"\"")),
+        None => serde_json::from_str(literal).ok(),
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn part_header<'a>(headers: &'a str, name: &str) -> Option<&'a str> {
+    headers.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, raw) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        Some(raw.trim().trim_matches('"').to_string())
+    })
+}
+
+fn boundary_param(mime: &str) -> Option<String> {
+    header_param(mime, "boundary").filter(|b| !b.is_empty())
+}
+
+fn sniff_boundary(text: &str) -> Option<String> {
+    let first = text.lines().next()?.trim_end();
+    first
+        .strip_prefix("--")
+        .filter(|b| !b.is_empty() && !b.contains(char::is_whitespace))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::json_path::JsonPath;
+
+    fn redact_path(pattern: &str) -> impl FnMut(&[PathSegment], &Value) -> Option<Value> {
+        let path = JsonPath::parse(pattern).expect("path");
+        move |at, _| {
+            path.matches(at)
+                .then(|| Value::String("REDACTED".to_string()))
+        }
+    }
+
+    #[test]
+    fn rewrites_json_paths_and_keeps_valid_json() {
+        let text = r#"{"user":{"name":"a","password":"p\"w"},"items":[{"token":"x"}]}"#;
+        let format = detect_body_format(Some("application/json"), text).expect("json");
+        let out = rewrite_body_values(text, &format, &mut redact_path("$.user.password"))
+            .expect("changed");
+        let value: Value = serde_json::from_str(&out).expect("valid json");
+        assert_eq!(value["user"]["password"], "REDACTED");
+        assert_eq!(value["user"]["name"], "a");
+
+        let out =
+            rewrite_body_values(text, &format, &mut redact_path("$..token")).expect("changed");
+        let value: Value = serde_json::from_str(&out).expect("valid json");
+        assert_eq!(value["items"][0]["token"], "REDACTED");
+
+        assert!(rewrite_body_values(text, &format, &mut redact_path("$.missing")).is_none());
+    }
+
+    #[test]
+    fn json_rewrites_keep_key_order_and_layout() {
+        let text = "{\n    \"zeta\": 1,\n    \"password\": \"a \\\"b\\\"\",\n    \"alpha\": [ {\"password\" : 2} ]\n}";
+        let format = detect_body_format(Some("application/json"), text).expect("json");
+        let out =
+            rewrite_body_values(text, &format, &mut redact_path("$..password")).expect("changed");
+        assert_eq!(
+            out,
+            "{\n    \"zeta\": 1,\n    \"password\": \"REDACTED\",\n    \"alpha\": [ {\"password\" : \"REDACTED\"} ]\n}"
+        );
+    }
+
+    #[test]
+    fn rewrites_form_fields() {
+        let text = "user=alice&access_token=abc%20def&next=%2Fhome";
+        let format =
+            detect_body_format(Some("application/x-www-form-urlencoded"), text).expect("form");
+        let out = rewrite_body_values(text, &format, &mut redact_path("$.access_token"))
+            .expect("changed");
+        assert_eq!(out, "user=alice&access_token=REDACTED&next=%2Fhome");
+    }
+
+    #[test]
+    fn rewrites_multipart_fields_and_json_parts() {
+        let text = "--XyZ\r\nContent-Disposition: form-data; name=\"password\"\r\n\r\nhunter2\r\n\
+--XyZ\r\nContent-Disposition: form-data; name=\"meta\"\r\nContent-Type: application/json\r\n\r\n{\"secret\":\"s\",\"id\":1}\r\n\
+--XyZ--\r\n";
+        let format =
+            detect_body_format(Some("multipart/form-data; boundary=XyZ"), text).expect("multipart");
+        assert_eq!(format, BodyFormat::Multipart("XyZ".to_string()));
+
+        let out =
+            rewrite_body_values(text, &format, &mut redact_path("$.password")).expect("changed");
+        assert!(out.contains("name=\"password\"\r\n\r\nREDACTED\r\n--XyZ"));
+        assert!(out.ends_with("--XyZ--\r\n"));
+
+        let out =
+            rewrite_body_values(text, &format, &mut redact_path("$.meta.secret")).expect("changed");
+        assert!(out.contains(r#"{"secret":"REDACTED","id":1}"#));
+        assert!(out.contains("hunter2"));
+    }
+
+    #[test]
+    fn rewrites_graphql_argument_literals() {
+        let text = r#"mutation { login(email: "a@b.c", password: "p\"w", pin: 1234, tags: ["x"], input: {password: "s"}) { token } }"#;
+        let format = detect_body_format(Some("application/graphql"), text).expect("graphql");
+        assert_eq!(format, BodyFormat::Graphql);
+
+        let out =
+            rewrite_body_values(text, &format, &mut redact_path("$.password")).expect("changed");
+        assert_eq!(
+            out,
+            r#"mutation { login(email: "a@b.c", password: "REDACTED", pin: 1234, tags: ["x"], input: {password: "REDACTED"}) { token } }"#
+        );
+
+        let mut seen = Vec::new();
+        rewrite_body_values(text, &format, &mut |path, value| {
+            seen.push((path.to_vec(), value.clone()));
+            None
+        });
+        assert!(seen.contains(&(vec![PathSegment::Key("pin".to_string())], Value::from(1234))));
+        assert!(seen.contains(&(vec![PathSegment::Key("tags".to_string())], Value::from("x"))));
+        assert_eq!(seen.len(), 5);
+
+        let payload =
+            serde_json::json!({ "query": text, "variables": { "password": "v" } }).to_string();
+        let format = detect_body_format(Some("application/json"), &payload).expect("json");
+        let out = rewrite_body_values(&payload, &format, &mut redact_path("$..password"))
+            .expect("changed");
+        let value: Value = serde_json::from_str(&out).expect("valid json");
+        assert_eq!(value["variables"]["password"], "REDACTED");
+        assert!(value["query"]
+            .as_str()
+            .unwrap()
+            .contains(r#"password: "REDACTED", pin: 1234"#));
+    }
+
+    #[test]
+    fn plain_text_is_not_structured() {
+        assert!(detect_body_format(Some("text/plain"), "hello").is_none());
+        assert!(detect_body_format(None, "{not json").is_none());
+        assert_eq!(
+            detect_body_format(None, r#"{"a":1}"#),
+            Some(BodyFormat::Json)
+        );
+    }
+}
@@ -16,8 +16,8 @@ use tungstenite::{connect, Message, WebSocket};
 use url::Url;
 
 use crate::db::{
-    create_import_with_status, create_schema, insert_entry, update_import_count, BlobStats,
-    EntryRelations, ImportStats, InsertEntryOptions,
+    create_import_with_status, create_schema, insert_entry, open_database, update_import_count,
+    BlobStats, EntryRelations, ImportStats, InsertEntryOptions,
 };
 use crate::error::{HarliteError, Result};
 use crate::har::{
@@ -25,6 +25,9 @@ use crate::har::{
     Response, Timings,
 };
 
+use super::inline_redact::EntryRedactor;
+use super::{PiiOptions, RedactOptions};
+
 /// Options for capturing traffic from Chrome via CDP.
 pub struct CdpOptions {
     pub host: String,
@@ -36,6 +39,10 @@ pub struct CdpOptions {
     pub max_body_size: Option<usize>,
     pub text_only: bool,
     pub duration_secs: Option<u64>,
+    /// Apply `redact` rules to captured entries before writing them.
+    pub redact: Option<RedactOptions>,
+    /// Replace values found by the `pii` matchers before writing entries.
+    pub redact_pii: Option<PiiOptions>,
 }
 
 #[derive(Deserialize)]
@@ -162,6 +169,7 @@ pub fn run_cdp(options: &CdpOptions) -> Result<()> {
             "CDP capture requires --har and/or --output to be set".to_string(),
         ));
     }
+    let redactor = EntryRedactor::new(options.redact.as_ref(), options.redact_pii.as_ref())?;
 
     let base_url = format!("http://{}:{}", options.host, options.port);
     let version = fetch_version(&base_url)?;
@@ -233,7 +241,12 @@ pub fn run_cdp(options: &CdpOptions) -> Result<()> {
 
     println!("Captured {} entries", state.entries.len());
 
-    let har = build_har(&version, state.entries);
+    let mut har = build_har(&version, state.entries);
+    if let Some(redactor) = &redactor {
+        for entry in &mut har.log.entries {
+            redactor.redact_entry(entry)?;
+        }
+    }
 
     if let Some(path) = &options.output_har {
         let file = std::fs::File::create(path)?;
@@ -245,6 +258,16 @@ pub fn run_cdp(options: &CdpOptions) -> Result<()> {
         import_entries(db_path, &har, options)?;
     }
 
+    if let Some(redactor) = &redactor {
+        match &options.output_db {
+            Some(db_path) => {
+                let conn = open_database(db_path)?;
+                redactor.finish(Some(&conn), "cdp")?;
+            }
+            None => redactor.finish(None, "cdp")?,
+        }
+    }
+
     Ok(())
 }
 
@@ -761,7 +784,7 @@ fn build_har(version: &VersionInfo, entries: Vec<Entry>) -> Har {
 }
 
 fn import_entries(path: &PathBuf, har: &Har, options: &CdpOptions) -> Result<()> {
-    let conn = rusqlite::Connection::open(path)?;
+    let conn = open_database(path)?;
     create_schema(&conn)?;
     conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
 
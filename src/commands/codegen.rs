@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use rusqlite::Connection;
+
+use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, open_database, EntryRow};
+use crate::error::{HarliteError, Result};
+
+use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::redact::is_default_sensitive_header;
+use super::util::{load_external_blob_content, open_output};
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum CodegenFormat {
+    Curl,
+    Httpie,
+    Python,
+    Fetch,
+    Reqwest,
+}
+
+pub struct CodegenOptions {
+    pub output: Option<PathBuf>,
+    pub format: CodegenFormat,
+    pub drop_sensitive: bool,
+    pub allow_external_paths: bool,
+    pub external_path_root: Option<PathBuf>,
+    pub filters: EntryFilterOptions,
+}
+
+/// A captured request reduced to what a client needs to send it again.
+pub(super) struct CodegenRequest {
+    pub(super) id: i64,
+    pub(super) method: String,
+    pub(super) url: String,
+    pub(super) headers: Vec<(String, String)>,
+    pub(super) body: Option<Vec<u8>>,
+}
+
+pub fn run_codegen(database: PathBuf, options: &CodegenOptions) -> Result<()> {
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let entries = load_entries_with_filters(&conn, &options.filters)?;
+
+    let external_root = if options.allow_external_paths {
+        let root = options
+            .external_path_root
+            .clone()
+            .or_else(|| database.parent().map(|p| p.to_path_buf()))
+            .ok_or_else(|| {
+                HarliteError::InvalidArgs(
+                    "Cannot resolve external path root; pass --external-path-root".to_string(),
+                )
+            })?;
+        Some(root.canonicalize()?)
+    } else {
+        None
+    };
+
+    let bodies = load_request_bodies(&conn, &entries, external_root.as_deref())?;
+
+    let requests: Vec<CodegenRequest> = entries
+        .iter()
+        .filter_map(|entry| build_request(entry, &bodies, options.drop_sensitive))
+        .collect();
+
+    let rendered = match options.format {
+        CodegenFormat::Curl => render_curl(&requests),
+        CodegenFormat::Httpie => render_httpie(&requests),
+        CodegenFormat::Python => render_python(&requests),
+        CodegenFormat::Fetch => render_fetch(&requests),
+        CodegenFormat::Reqwest => render_reqwest(&requests),
+    };
+
+    let output_path = options.output.clone().unwrap_or_else(|| PathBuf::from("-"));
+    let mut writer = open_output(&output_path)?;
+    writer.write_all(rendered.as_bytes())?;
+    writer.flush()?;
+
+    if output_path != Path::new("-") {
+        println!(
+            "Generated {} request(s) to {}",
+            requests.len(),
+            output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Request bodies of `entries`, keyed by blob hash.
+pub(super) fn load_request_bodies(
+    conn: &Connection,
+    entries: &[EntryRow],
+    external_root: Option<&Path>,
+) -> Result<HashMap<String, Vec<u8>>> {
+    let hashes: Vec<String> = entries
+        .iter()
+        .filter_map(|e| e.request_body_hash.clone())
+        .collect();
+    let mut bodies: HashMap<String, Vec<u8>> = HashMap::new();
+    for blob in load_blobs_by_hashes(conn, &hashes)? {
+        let blob = load_external_blob_content(blob, external_root)?;
+        bodies.insert(blob.hash, blob.content);
+    }
+    Ok(bodies)
+}
+
+pub(super) fn build_request(
+    entry: &EntryRow,
+    bodies: &HashMap<String, Vec<u8>>,
+    drop_sensitive: bool,
+) -> Option<CodegenRequest> {
+    let url = entry.url.clone()?;
+    let method = entry
+        .method
+        .as_deref()
+        .unwrap_or("GET")
+        .to_ascii_uppercase();
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    if let Some(json) = entry.request_headers.as_deref() {
+        if let Ok(serde_json::Value::Object(map)) = serde_json::from_str(json) {
+            for (name, value) in map {
+                let Some(value) = value.as_str() else {
+                    continue;
+                };
+                // Pseudo-headers and transport details are produced by the client itself.
+                if name.starts_with(':')
+                    || matches!(name.as_str(), "host" | "content-length" | "connection")
+                {
+                    continue;
+                }
+                headers.push((name, value.to_string()));
+            }
+        }
+    }
+
+    if !headers.iter().any(|(name, _)| name == "cookie") {
+        if let Some(cookie) = cookie_header(entry.request_cookies.as_deref()) {
+            headers.push(("cookie".to_string(), cookie));
+        }
+    }
+    if drop_sensitive {
+        headers.retain(|(name, _)| !is_default_sensitive_header(name));
+    }
+    headers.sort();
+
+    let body = entry
+        .request_body_hash
+        .as_ref()
+        .and_then(|hash| bodies.get(hash))
+        .filter(|content| !content.is_empty())
+        .cloned();
+
+    Some(CodegenRequest {
+        id: entry.id,
+        method,
+        url,
+        headers,
+        body,
+    })
+}
+
+fn cookie_header(cookies_json: Option<&str>) -> Option<String> {
+    let cookies: Vec<serde_json::Value> = serde_json::from_str(cookies_json?).ok()?;
+    let pairs: Vec<String> = cookies
+        .iter()
+        .filter_map(|c| {
+            let name = c.get("name")?.as_str()?;
+            let value = c.get("value").and_then(|v| v.as_str()).unwrap_or_default();
+            Some(format!("{name}={value}"))
+        })
+        .collect();
+    if pairs.is_empty() {
+        None
+    } else {
+        Some(pairs.join("; "))
+    }
+}
+
+pub(super) enum Body<'a> {
+    Text(&'a str),
+    Binary(&'a [u8]),
+}
+
+pub(super) fn body_of(request: &CodegenRequest) -> Option<Body<'_>> {
+    let bytes = request.body.as_deref()?;
+    Some(match std::str::from_utf8(bytes) {
+        Ok(text) => Body::Text(text),
+        Err(_) => Body::Binary(bytes),
+    })
+}
+
+pub(super) fn base64(bytes: &[u8]) -> String {
+    general_purpose::STANDARD.encode(bytes)
+}
+
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// JSON string literals are also valid Python and JavaScript string literals.
+pub(super) fn js_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn render_curl(requests: &[CodegenRequest]) -> String {
+    let mut out = String::new();
+    for request in requests {
+        out.push_str(&format!(
+            "# {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        let mut parts = vec![format!("curl {}", sh_quote(&request.url))];
+        match request.method.as_str() {
+            "GET" => {}
+            "HEAD" => parts.push("--head".to_string()),
+            method => parts.push(format!("-X {method}")),
+        }
+        for (name, value) in &request.headers {
+            parts.push(format!("-H {}", sh_quote(&format!("{name}: {value}"))));
+        }
+        match body_of(request) {
+            Some(Body::Text(text)) => parts.push(format!("--data-raw {}", sh_quote(text))),
+            Some(Body::Binary(bytes)) => parts.push(format!(
+                "--data-binary @<(printf '%s' {} | base64 -d)",
+                sh_quote(&base64(bytes))
+            )),
+            None => {}
+        }
+        out.push_str(&parts.join(" \\\n  "));
+        out.push_str("\n\n");
+    }
+    out
+}
+
+fn render_httpie(requests: &[CodegenRequest]) -> String {
+    let mut out = String::new();
+    for request in requests {
+        out.push_str(&format!(
+            "# {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        let mut parts = vec![format!(
+            "http {} {}",
+            request.method,
+            sh_quote(&request.url)
+        )];
+        for (name, value) in &request.headers {
+            parts.push(sh_quote(&format!("{name}:{value}")));
+        }
+        match body_of(request) {
+            Some(Body::Text(text)) => parts.push(format!("--raw {}", sh_quote(text))),
+            Some(Body::Binary(bytes)) => parts.push(format!(
+                "< <(printf '%s' {} | base64 -d)",
+                sh_quote(&base64(bytes))
+            )),
+            None => {}
+        }
+        out.push_str(&parts.join(" \\\n  "));
+        out.push_str("\n\n");
+    }
+    out
+}
+
+fn render_python(requests: &[CodegenRequest]) -> String {
+    let needs_base64 = requests
+        .iter()
+        .any(|r| matches!(body_of(r), Some(Body::Binary(_))));
+    let mut out = String::new();
+    if needs_base64 {
+        out.push_str("import base64\n");
+    }
+    out.push_str("import requests\n");
+
+    for request in requests {
+        out.push_str(&format!(
+            "\n# {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        out.push_str("response = requests.request(\n");
+        out.push_str(&format!("    {},\n", js_string(&request.method)));
+        out.push_str(&format!("    {},\n", js_string(&request.url)));
+        if !request.headers.is_empty() {
+            out.push_str("    headers={\n");
+            for (name, value) in &request.headers {
+                out.push_str(&format!(
+                    "        {}: {},\n",
+                    js_string(name),
+                    js_string(value)
+                ));
+            }
+            out.push_str("    },\n");
+        }
+        match body_of(request) {
+            Some(Body::Text(text)) => out.push_str(&format!(
+                "    data={}.encode(\"utf-8\"),\n",
+                js_string(text)
+            )),
+            Some(Body::Binary(bytes)) => out.push_str(&format!(
+                "    data=base64.b64decode({}),\n",
+                js_string(&base64(bytes))
+            )),
+            None => {}
+        }
+        out.push_str(")\n");
+        out.push_str("print(response.status_code, len(response.content))\n");
+    }
+    out
+}
+
+fn render_fetch(requests: &[CodegenRequest]) -> String {
+    let mut out = String::new();
+    for request in requests {
+        out.push_str(&format!(
+            "// {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        out.push_str("{\n");
+        out.push_str(&format!(
+            "  const response = await fetch({}, {{\n",
+            js_string(&request.url)
+        ));
+        out.push_str(&format!("    method: {},\n", js_string(&request.method)));
+        if !request.headers.is_empty() {
+            out.push_str("    headers: {\n");
+            for (name, value) in &request.headers {
+                out.push_str(&format!(
+                    "      {}: {},\n",
+                    js_string(name),
+                    js_string(value)
+                ));
+            }
+            out.push_str("    },\n");
+        }
+        match body_of(request) {
+            Some(Body::Text(text)) => out.push_str(&format!("    body: {},\n", js_string(text))),
+            Some(Body::Binary(bytes)) => out.push_str(&format!(
+                "    body: Uint8Array.from(atob({}), (c) => c.charCodeAt(0)),\n",
+                js_string(&base64(bytes))
+            )),
+            None => {}
+        }
+        out.push_str("  });\n");
+        out.push_str(
+            "  console.log(response.status, (await response.arrayBuffer()).byteLength);\n",
+        );
+        out.push_str("}\n\n");
+    }
+    out
+}
+
+fn render_reqwest(requests: &[CodegenRequest]) -> String {
+    let mut out = String::new();
+    out.push_str("fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    out.push_str("    let client = reqwest::blocking::Client::new();\n");
+
+    for request in requests {
+        out.push_str(&format!(
+            "\n    // {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        let method = match request.method.as_str() {
+            "GET" | "POST" | "PUT" | "DELETE" | "HEAD" | "OPTIONS" | "CONNECT" | "PATCH"
+            | "TRACE" => format!("reqwest::Method::{}", request.method),
+            other => format!("reqwest::Method::from_bytes({:?}.as_bytes())?", other),
+        };
+        out.push_str("    let response = client\n");
+        out.push_str(&format!("        .request({method}, {:?})\n", request.url));
+        for (name, value) in &request.headers {
+            out.push_str(&format!("        .header({name:?}, {value:?})\n"));
+        }
+        match body_of(request) {
+            Some(Body::Text(text)) => out.push_str(&format!("        .body({text:?})\n")),
+            Some(Body::Binary(bytes)) => out.push_str(&format!(
+                "        .body(b\"{}\".to_vec())\n",
+                bytes.escape_ascii()
+            )),
+            None => {}
+        }
+        out.push_str("        .send()?;\n");
+        out.push_str("    println!(\"{} {}\", response.status(), response.bytes()?.len());\n");
+    }
+
+    out.push_str("\n    Ok(())\n}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, body: Option<&[u8]>) -> CodegenRequest {
+        CodegenRequest {
+            id: 7,
+            method: method.to_string(),
+            url: "https://api.example.com/items?q=it's".to_string(),
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: body.map(|b| b.to_vec()),
+        }
+    }
+
+    #[test]
+    fn curl_and_httpie_quote_shell_arguments() {
+        let requests = vec![
+            request("POST", Some(br#"{"name":"O'Brien"}"#)),
+            request("GET", None),
+        ];
+        let curl = render_curl(&requests);
+        assert!(curl.contains("curl 'https://api.example.com/items?q=it'\\''s' \\\n  -X POST"));
+        assert!(curl.contains("-H 'content-type: application/json'"));
+        assert!(curl.contains(r#"--data-raw '{"name":"O'\''Brien"}'"#));
+        assert!(!curl.contains("-X GET"));
+
+        let httpie = render_httpie(&requests);
+        assert!(httpie.contains("http POST 'https://api.example.com/items?q=it'\\''s'"));
+        assert!(httpie.contains("'content-type:application/json'"));
+        assert!(httpie.contains("--raw '"));
+    }
+
+    #[test]
+    fn binary_bodies_are_embedded_losslessly() {
+        let requests = vec![request("PUT", Some(&[0xff, 0x00, b'"']))];
+        assert!(render_curl(&requests).contains("printf '%s' '/wAi' | base64 -d"));
+        let python = render_python(&requests);
+        assert!(python.starts_with("import base64\nimport requests\n"));
+        assert!(python.contains("data=base64.b64decode(\"/wAi\")"));
+        assert!(render_fetch(&requests).contains("atob(\"/wAi\")"));
+        assert!(render_reqwest(&requests).contains(r#".body(b"\xff\x00\"".to_vec())"#));
+    }
+
+    #[test]
+    fn python_fetch_and_reqwest_render_headers_and_text_bodies() {
+        let requests = vec![request("PURGE", Some("naïve \"x\"".as_bytes()))];
+        let python = render_python(&requests);
+        assert!(python.contains("    \"content-type\": \"application/json\",\n"));
+        assert!(python.contains(r#"data="naïve \"x\"".encode("utf-8"),"#));
+
+        let fetch = render_fetch(&requests);
+        assert!(fetch.contains(r#"method: "PURGE","#));
+        assert!(fetch.contains(r#"body: "naïve \"x\"","#));
+
+        let rust = render_reqwest(&requests);
+        assert!(rust.contains(r#".request(reqwest::Method::from_bytes("PURGE".as_bytes())?, "#));
+        assert!(rust.contains(r#".header("content-type", "application/json")"#));
+        assert!(rust.contains(r#".body("naïve \"x\"")"#));
+    }
+
+    #[test]
+    fn cookie_header_is_built_from_recorded_cookies() {
+        let json = r#"[{"name":"sid","value":"abc"},{"name":"theme","value":"dark"}]"#;
+        assert_eq!(
+            cookie_header(Some(json)).as_deref(),
+            Some("sid=abc; theme=dark")
+        );
+        assert_eq!(cookie_header(Some("[]")), None);
+    }
+}
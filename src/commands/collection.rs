@@ -0,0 +1,758 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, open_database, EntryRow};
+use crate::error::{HarliteError, Result};
+
+use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::redact::is_default_sensitive_header;
+use super::util::{load_external_blob_content, open_output};
+
+const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Recorded auth headers that become per-host variables instead of literal values.
+const AUTH_HEADERS: [&str; 7] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "api-key",
+    "x-auth-token",
+    "x-access-token",
+];
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum CollectionFormat {
+    Postman,
+    Insomnia,
+}
+
+impl CollectionFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            CollectionFormat::Postman => "postman",
+            CollectionFormat::Insomnia => "insomnia",
+        }
+    }
+}
+
+pub struct CollectionOptions {
+    pub output: Option<PathBuf>,
+    pub format: CollectionFormat,
+    pub name: Option<String>,
+    pub environment_output: Option<PathBuf>,
+    pub max_examples: usize,
+    pub allow_external_paths: bool,
+    pub external_path_root: Option<PathBuf>,
+    pub filters: EntryFilterOptions,
+}
+
+/// One distinct request (method + URL) with the responses recorded for it.
+struct CollectionRequest {
+    method: String,
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    body_mime: Option<String>,
+    examples: Vec<ExampleResponse>,
+}
+
+struct ExampleResponse {
+    status: i32,
+    status_text: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    mime: Option<String>,
+}
+
+/// Requests grouped into folders: host -> route -> requests.
+type Folders = BTreeMap<String, BTreeMap<String, Vec<CollectionRequest>>>;
+
+/// Host and credential variables. Credentials are secrets: their recorded values are only
+/// written to the `--environment-output` file and left empty in the collection itself.
+#[derive(Default)]
+struct Variables {
+    values: BTreeMap<String, String>,
+    secrets: BTreeSet<String>,
+}
+
+impl Variables {
+    /// Values safe to embed in the collection.
+    fn shared(&self) -> BTreeMap<String, String> {
+        self.values
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.secrets.contains(key) {
+                    String::new()
+                } else {
+                    value.clone()
+                };
+                (key.clone(), value)
+            })
+            .collect()
+    }
+}
+
+pub fn run_export_collection(database: PathBuf, options: &CollectionOptions) -> Result<()> {
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let entries = load_entries_with_filters(&conn, &options.filters)?;
+
+    let external_root = if options.allow_external_paths {
+        let root = options
+            .external_path_root
+            .clone()
+            .or_else(|| database.parent().map(|p| p.to_path_buf()))
+            .ok_or_else(|| {
+                HarliteError::InvalidArgs(
+                    "Cannot resolve external path root; pass --external-path-root".to_string(),
+                )
+            })?;
+        Some(root.canonicalize()?)
+    } else {
+        None
+    };
+
+    let hashes: Vec<String> = entries
+        .iter()
+        .flat_map(|e| [e.request_body_hash.clone(), e.response_body_hash.clone()])
+        .flatten()
+        .collect();
+    let mut blobs: HashMap<String, Vec<u8>> = HashMap::new();
+    for blob in load_blobs_by_hashes(&conn, &hashes)? {
+        let blob = load_external_blob_content(blob, external_root.as_deref())?;
+        blobs.insert(blob.hash, blob.content);
+    }
+
+    let stem = database
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("harlite")
+        .to_string();
+    let name = options.name.clone().unwrap_or_else(|| stem.clone());
+
+    let mut variables = Variables::default();
+    let folders = group_entries(&entries, &blobs, options.max_examples, &mut variables);
+
+    let shared = variables.shared();
+    let document = match options.format {
+        CollectionFormat::Postman => postman_collection(&name, &folders, &shared),
+        CollectionFormat::Insomnia => insomnia_export(&name, &folders, &shared),
+    };
+
+    let output_path = match &options.output {
+        Some(p) => p.clone(),
+        None => PathBuf::from(format!("{stem}-{}.json", options.format.as_str())),
+    };
+    let mut writer = open_output(&output_path)?;
+    serde_json::to_writer_pretty(&mut writer, &document)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+
+    if let Some(env_path) = options.environment_output.as_ref() {
+        let environment = match options.format {
+            CollectionFormat::Postman => postman_environment(&name, &variables),
+            CollectionFormat::Insomnia => json!(variables.values),
+        };
+        let mut writer = open_output(env_path)?;
+        serde_json::to_writer_pretty(&mut writer, &environment)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+
+    if output_path != Path::new("-") {
+        let requests: usize = folders
+            .values()
+            .flat_map(|routes| routes.values())
+            .map(|requests| requests.len())
+            .sum();
+        println!(
+            "Exported {} request(s) in {} host folder(s) to {}",
+            requests,
+            folders.len(),
+            output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn group_entries(
+    entries: &[EntryRow],
+    blobs: &HashMap<String, Vec<u8>>,
+    max_examples: usize,
+    variables: &mut Variables,
+) -> Folders {
+    let mut folders: Folders = BTreeMap::new();
+    let mut positions: HashMap<(String, String), (String, String, usize)> = HashMap::new();
+
+    for entry in entries {
+        let Some(url) = entry.url.as_deref().and_then(|u| Url::parse(u).ok()) else {
+            continue;
+        };
+        let Some(host) = url.host_str().map(|h| h.to_string()) else {
+            continue;
+        };
+        let method = entry
+            .method
+            .as_deref()
+            .unwrap_or("GET")
+            .to_ascii_uppercase();
+        let host_var = host_variable(&host);
+        variables
+            .values
+            .entry(format!("{host_var}_base_url"))
+            .or_insert_with(|| base_url(&url));
+
+        let headers: Vec<(String, String)> = header_list(entry.request_headers.as_deref())
+            .into_iter()
+            .filter(|(name, _)| {
+                !name.starts_with(':')
+                    && !matches!(name.as_str(), "host" | "content-length" | "connection")
+            })
+            .map(|(name, value)| {
+                if AUTH_HEADERS.contains(&name.as_str()) {
+                    let var = format!("{host_var}_{}", name.replace('-', "_"));
+                    // Keep the latest recorded credential as the variable's value.
+                    variables.values.insert(var.clone(), value);
+                    variables.secrets.insert(var.clone());
+                    (name, format!("{{{{{var}}}}}"))
+                } else {
+                    (name, value)
+                }
+            })
+            .collect();
+
+        let key = (method.clone(), url.to_string());
+        let example = example_response(entry, blobs);
+        if let Some((host, route, idx)) = positions.get(&key) {
+            let request = &mut folders
+                .get_mut(host)
+                .expect("host folder")
+                .get_mut(route)
+                .expect("route folder")[*idx];
+            if let Some(example) = example {
+                if request.examples.len() < max_examples
+                    && !request.examples.iter().any(|e| e.status == example.status)
+                {
+                    request.examples.push(example);
+                }
+            }
+            continue;
+        }
+
+        let route = route_for_path(url.path());
+        let requests = folders
+            .entry(host.clone())
+            .or_default()
+            .entry(route.clone())
+            .or_default();
+        positions.insert(key, (host, route, requests.len()));
+        requests.push(CollectionRequest {
+            method,
+            body: entry
+                .request_body_hash
+                .as_ref()
+                .and_then(|hash| blobs.get(hash))
+                .and_then(|bytes| String::from_utf8(bytes.clone()).ok())
+                .filter(|text| !text.is_empty()),
+            body_mime: header_value(&headers, "content-type"),
+            url,
+            headers,
+            examples: example.filter(|_| max_examples > 0).into_iter().collect(),
+        });
+    }
+
+    folders
+}
+
+fn example_response(entry: &EntryRow, blobs: &HashMap<String, Vec<u8>>) -> Option<ExampleResponse> {
+    let status = entry.status?;
+    Some(ExampleResponse {
+        status,
+        status_text: entry.status_text.clone().unwrap_or_default(),
+        // Recorded responses can set session cookies or tokens; keep those out of examples.
+        headers: header_list(entry.response_headers.as_deref())
+            .into_iter()
+            .filter(|(name, _)| !is_default_sensitive_header(name))
+            .collect(),
+        body: entry
+            .response_body_hash
+            .as_ref()
+            .and_then(|hash| blobs.get(hash))
+            .and_then(|bytes| String::from_utf8(bytes.clone()).ok()),
+        mime: entry.response_mime_type.clone(),
+    })
+}
+
+fn header_list(json: Option<&str>) -> Vec<(String, String)> {
+    let Some(Ok(Value::Object(map))) = json.map(serde_json::from_str::<Value>) else {
+        return Vec::new();
+    };
+    map.into_iter()
+        .filter_map(|(name, value)| Some((name, value.as_str()?.to_string())))
+        .collect()
+}
+
+fn header_value(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.clone())
+}
+
+fn base_url(url: &Url) -> String {
+    let mut base = format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default());
+    if let Some(port) = url.port() {
+        base.push_str(&format!(":{port}"));
+    }
+    base
+}
+
+fn host_variable(host: &str) -> String {
+    host.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Folder name for a path: ids (numbers, UUIDs, long hex strings) collapse to `:id`.
+fn route_for_path(path: &str) -> String {
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|segment| {
+            if is_id_segment(segment) {
+                ":id"
+            } else {
+                segment
+            }
+        })
+        .collect();
+    format!("/{}", segments.join("/"))
+}
+
+fn is_id_segment(segment: &str) -> bool {
+    if segment.chars().all(|c| c.is_ascii_digit()) {
+        return true;
+    }
+    let hex: String = segment.chars().filter(|c| *c != '-').collect();
+    let is_hex = hex.chars().all(|c| c.is_ascii_hexdigit());
+    let is_uuid = segment.len() == 36 && segment.matches('-').count() == 4 && is_hex;
+    is_uuid || (hex.len() >= 16 && is_hex && hex.chars().any(|c| c.is_ascii_digit()))
+}
+
+/// URL with the scheme and host replaced by the host's base URL variable.
+fn templated_url(url: &Url, var: &str) -> String {
+    let mut out = format!("{var}{}", url.path());
+    if let Some(query) = url.query() {
+        out.push('?');
+        out.push_str(query);
+    }
+    out
+}
+
+fn body_language(mime: Option<&str>) -> &'static str {
+    let mime = mime.unwrap_or_default().to_ascii_lowercase();
+    if mime.contains("json") {
+        "json"
+    } else if mime.contains("xml") {
+        "xml"
+    } else if mime.contains("html") {
+        "html"
+    } else if mime.contains("javascript") {
+        "javascript"
+    } else {
+        "text"
+    }
+}
+
+fn request_name(request: &CollectionRequest) -> String {
+    let mut name = format!("{} {}", request.method, request.url.path());
+    if let Some(query) = request.url.query() {
+        name.push('?');
+        name.push_str(query);
+    }
+    name
+}
+
+fn postman_collection(
+    name: &str,
+    folders: &Folders,
+    variables: &BTreeMap<String, String>,
+) -> Value {
+    let items: Vec<Value> = folders
+        .iter()
+        .map(|(host, routes)| {
+            let route_items: Vec<Value> = routes
+                .iter()
+                .map(|(route, requests)| {
+                    json!({
+                        "name": route,
+                        "item": requests.iter().map(|r| postman_item(host, r)).collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+            json!({ "name": host, "item": route_items })
+        })
+        .collect();
+
+    json!({
+        "info": {
+            "name": name,
+            "description": "Exported by harlite from captured traffic",
+            "schema": POSTMAN_SCHEMA,
+        },
+        "item": items,
+        "variable": variables
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn postman_item(host: &str, request: &CollectionRequest) -> Value {
+    let var = format!("{{{{{}_base_url}}}}", host_variable(host));
+    let mut url = json!({
+        "raw": templated_url(&request.url, &var),
+        "host": [var],
+        "path": request
+            .url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+            .unwrap_or_default(),
+    });
+    let query: Vec<Value> = request
+        .url
+        .query_pairs()
+        .map(|(key, value)| json!({ "key": key, "value": value }))
+        .collect();
+    if !query.is_empty() {
+        url["query"] = json!(query);
+    }
+
+    let mut postman_request = json!({
+        "method": request.method,
+        "header": request
+            .headers
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect::<Vec<_>>(),
+        "url": url,
+    });
+    if let Some(body) = request.body.as_ref() {
+        postman_request["body"] = json!({
+            "mode": "raw",
+            "raw": body,
+            "options": { "raw": { "language": body_language(request.body_mime.as_deref()) } },
+        });
+    }
+
+    let responses: Vec<Value> = request
+        .examples
+        .iter()
+        .map(|example| {
+            json!({
+                "name": format!("{} {}", example.status, example.status_text).trim().to_string(),
+                "originalRequest": postman_request.clone(),
+                "status": example.status_text,
+                "code": example.status,
+                "_postman_previewlanguage": body_language(example.mime.as_deref()),
+                "header": example
+                    .headers
+                    .iter()
+                    .map(|(key, value)| json!({ "key": key, "value": value }))
+                    .collect::<Vec<_>>(),
+                "body": example.body.clone().unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    json!({
+        "name": request_name(request),
+        "request": postman_request,
+        "response": responses,
+    })
+}
+
+fn postman_environment(name: &str, variables: &Variables) -> Value {
+    json!({
+        "name": name,
+        "values": variables
+            .values
+            .iter()
+            .map(|(key, value)| {
+                let kind = if variables.secrets.contains(key) {
+                    "secret"
+                } else {
+                    "default"
+                };
+                json!({ "key": key, "value": value, "type": kind, "enabled": true })
+            })
+            .collect::<Vec<_>>(),
+        "_postman_variable_scope": "environment",
+    })
+}
+
+fn insomnia_export(name: &str, folders: &Folders, variables: &BTreeMap<String, String>) -> Value {
+    let workspace_id = "wrk_harlite";
+    let mut resources = vec![
+        json!({
+            "_id": workspace_id,
+            "_type": "workspace",
+            "parentId": null,
+            "name": name,
+            "description": "Exported by harlite from captured traffic",
+            "scope": "collection",
+        }),
+        json!({
+            "_id": "env_harlite_base",
+            "_type": "environment",
+            "parentId": workspace_id,
+            "name": "Base Environment",
+            "data": variables,
+        }),
+    ];
+
+    let mut next_id = 0usize;
+    let mut id = |prefix: &str| {
+        next_id += 1;
+        format!("{prefix}_harlite_{next_id}")
+    };
+
+    for (host, routes) in folders {
+        let host_id = id("fld");
+        resources.push(json!({
+            "_id": host_id,
+            "_type": "request_group",
+            "parentId": workspace_id,
+            "name": host,
+        }));
+        let var = format!("{{{{ _.{}_base_url }}}}", host_variable(host));
+
+        for (route, requests) in routes {
+            let route_id = id("fld");
+            resources.push(json!({
+                "_id": route_id,
+                "_type": "request_group",
+                "parentId": host_id,
+                "name": route,
+            }));
+
+            for request in requests {
+                let mut body = json!({});
+                if let Some(text) = request.body.as_ref() {
+                    body = json!({
+                        "mimeType": request.body_mime.clone().unwrap_or_else(|| "text/plain".to_string()),
+                        "text": text,
+                    });
+                }
+                resources.push(json!({
+                    "_id": id("req"),
+                    "_type": "request",
+                    "parentId": route_id,
+                    "name": request_name(request),
+                    "method": request.method,
+                    "url": templated_url(&request.url, &var),
+                    "headers": request
+                        .headers
+                        .iter()
+                        .map(|(name, value)| json!({ "name": name, "value": insomnia_template(value) }))
+                        .collect::<Vec<_>>(),
+                    "body": body,
+                    "parameters": [],
+                    "description": insomnia_examples(&request.examples),
+                }));
+            }
+        }
+    }
+
+    json!({
+        "_type": "export",
+        "__export_format": 4,
+        "__export_date": chrono::Utc::now().to_rfc3339(),
+        "__export_source": format!("harlite:{}", env!("CARGO_PKG_VERSION")),
+        "resources": resources,
+    })
+}
+
+/// Rewrite `{{var}}` placeholders into Insomnia's `{{ _.var }}` syntax.
+fn insomnia_template(value: &str) -> String {
+    match value.strip_prefix("{{").and_then(|v| v.strip_suffix("}}")) {
+        Some(var) => format!("{{{{ _.{var} }}}}"),
+        None => value.to_string(),
+    }
+}
+
+/// Insomnia exports have no example responses, so they go into the request description.
+fn insomnia_examples(examples: &[ExampleResponse]) -> String {
+    let mut out = String::new();
+    for example in examples {
+        out.push_str(&format!(
+            "### Example response: {} {}\n",
+            example.status, example.status_text
+        ));
+        if let Some(body) = example.body.as_ref().filter(|b| !b.is_empty()) {
+            out.push_str(&format!(
+                "\n```{}\n{}\n```\n",
+                body_language(example.mime.as_deref()),
+                body
+            ));
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, method: &str, url: &str, status: i32) -> EntryRow {
+        EntryRow {
+            id,
+            method: Some(method.to_string()),
+            url: Some(url.to_string()),
+            status: Some(status),
+            status_text: Some("OK".to_string()),
+            request_headers: Some(
+                r#"{"authorization":"Bearer abc","accept":"application/json","host":"x"}"#
+                    .to_string(),
+            ),
+            response_body_hash: Some(format!("resp{id}")),
+            response_mime_type: Some("application/json".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn grouped(entries: &[EntryRow]) -> (Folders, Variables) {
+        let blobs: HashMap<String, Vec<u8>> = entries
+            .iter()
+            .map(|e| {
+                (
+                    format!("resp{}", e.id),
+                    format!("{{\"id\":{}}}", e.id).into_bytes(),
+                )
+            })
+            .collect();
+        let mut variables = Variables::default();
+        let folders = group_entries(entries, &blobs, 3, &mut variables);
+        (folders, variables)
+    }
+
+    #[test]
+    fn groups_by_host_and_route_and_collects_examples() {
+        let entries = vec![
+            entry(1, "GET", "https://api.example.com/users/42", 200),
+            entry(2, "GET", "https://api.example.com/users/42", 404),
+            entry(3, "GET", "https://api.example.com/users/42", 200),
+            entry(4, "GET", "https://api.example.com/users/7?full=1", 200),
+            entry(5, "POST", "https://cdn.example.com:8443/upload", 201),
+        ];
+        let (folders, variables) = grouped(&entries);
+
+        assert_eq!(
+            folders.keys().collect::<Vec<_>>(),
+            vec!["api.example.com", "cdn.example.com"]
+        );
+        let users = &folders["api.example.com"]["/users/:id"];
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].examples.len(), 2);
+        assert!(users[0].headers.iter().all(|(name, _)| name != "host"));
+        assert_eq!(
+            header_value(&users[0].headers, "authorization").as_deref(),
+            Some("{{api_example_com_authorization}}")
+        );
+
+        assert_eq!(
+            variables
+                .values
+                .get("cdn_example_com_base_url")
+                .map(|s| s.as_str()),
+            Some("https://cdn.example.com:8443")
+        );
+        assert_eq!(
+            variables
+                .values
+                .get("api_example_com_authorization")
+                .map(|s| s.as_str()),
+            Some("Bearer abc")
+        );
+        assert!(variables.secrets.contains("api_example_com_authorization"));
+        assert!(!variables.secrets.contains("api_example_com_base_url"));
+    }
+
+    #[test]
+    fn postman_and_insomnia_documents_use_variables() {
+        let entries = vec![entry(1, "GET", "https://api.example.com/users/42?x=1", 200)];
+        let (folders, variables) = grouped(&entries);
+        let shared = variables.shared();
+
+        let postman = postman_collection("demo", &folders, &shared);
+        assert_eq!(postman["info"]["schema"], POSTMAN_SCHEMA);
+        let item = &postman["item"][0]["item"][0]["item"][0];
+        assert_eq!(
+            item["request"]["url"]["raw"],
+            "{{api_example_com_base_url}}/users/42?x=1"
+        );
+        assert_eq!(item["request"]["url"]["query"][0]["key"], "x");
+        assert_eq!(item["response"][0]["code"], 200);
+        assert_eq!(item["response"][0]["body"], "{\"id\":1}");
+        assert!(postman["variable"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|v| v["key"] == "api_example_com_authorization" && v["value"] == ""));
+        assert!(!postman.to_string().contains("Bearer abc"));
+
+        let insomnia = insomnia_export("demo", &folders, &shared);
+        assert!(!insomnia.to_string().contains("Bearer abc"));
+        let resources = insomnia["resources"].as_array().unwrap();
+        let request = resources.iter().find(|r| r["_type"] == "request").unwrap();
+        assert_eq!(
+            request["url"],
+            "{{ _.api_example_com_base_url }}/users/42?x=1"
+        );
+        assert!(request["headers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|h| h["value"] == "{{ _.api_example_com_authorization }}"));
+        assert!(request["description"]
+            .as_str()
+            .unwrap()
+            .contains("Example response: 200 OK"));
+        assert_eq!(
+            resources
+                .iter()
+                .filter(|r| r["_type"] == "request_group")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn route_collapses_id_segments() {
+        assert_eq!(route_for_path("/users/42/orders"), "/users/:id/orders");
+        assert_eq!(
+            route_for_path("/items/3f2b8c1e-9a4d-4e2f-8b1a-0c9d8e7f6a5b"),
+            "/items/:id"
+        );
+        assert_eq!(
+            route_for_path("/assets/5f4dcc3b5aa765d61d8327deb882cf99"),
+            "/assets/:id"
+        );
+        assert_eq!(route_for_path("/api/v2/feed"), "/api/v2/feed");
+        assert_eq!(route_for_path("/"), "/");
+    }
+}
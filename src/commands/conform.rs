@@ -0,0 +1,1414 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate};
+use regex::Regex;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, open_database, EntryRow};
+use crate::error::{HarliteError, Result};
+
+use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::util::{load_external_blob_content, open_output};
+
+/// Schema errors reported per body before the rest are summarised.
+const MAX_BODY_ERRORS: usize = 10;
+/// `allOf`/`anyOf`/`oneOf` levels applied to one value before the schema is treated as cyclic.
+const MAX_COMPOSITION_DEPTH: usize = 32;
+
+/// Request headers every client sends; they are never reported as unexpected.
+const STANDARD_HEADERS: &[&str] = &[
+    "accept",
+    "accept-encoding",
+    "accept-language",
+    "authorization",
+    "cache-control",
+    "connection",
+    "content-length",
+    "content-type",
+    "cookie",
+    "dnt",
+    "host",
+    "if-match",
+    "if-modified-since",
+    "if-none-match",
+    "keep-alive",
+    "origin",
+    "pragma",
+    "priority",
+    "proxy-connection",
+    "range",
+    "referer",
+    "te",
+    "upgrade-insecure-requests",
+    "user-agent",
+];
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ConformFormat {
+    Table,
+    Json,
+    Junit,
+}
+
+pub struct ConformOptions {
+    pub spec: PathBuf,
+    pub output: Option<PathBuf>,
+    pub format: ConformFormat,
+    pub ignore_headers: Vec<String>,
+    pub fail_on_violation: bool,
+    pub allow_external_paths: bool,
+    pub external_path_root: Option<PathBuf>,
+    pub filters: EntryFilterOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ViolationKind {
+    UndocumentedEndpoint,
+    UndocumentedStatus,
+    MissingParameter,
+    InvalidParameter,
+    RequestBody,
+    ResponseBody,
+    UnexpectedHeader,
+}
+
+impl ViolationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ViolationKind::UndocumentedEndpoint => "undocumented-endpoint",
+            ViolationKind::UndocumentedStatus => "undocumented-status",
+            ViolationKind::MissingParameter => "missing-parameter",
+            ViolationKind::InvalidParameter => "invalid-parameter",
+            ViolationKind::RequestBody => "request-body",
+            ViolationKind::ResponseBody => "response-body",
+            ViolationKind::UnexpectedHeader => "unexpected-header",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Violation {
+    kind: ViolationKind,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EntryResult {
+    entry_id: i64,
+    method: String,
+    url: String,
+    status: Option<i32>,
+    /// Matched spec path, e.g. `GET /users/{id}`.
+    operation: Option<String>,
+    violations: Vec<Violation>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConformReport {
+    spec: String,
+    entries_checked: usize,
+    entries_failed: usize,
+    violations: BTreeMap<ViolationKind, usize>,
+    results: Vec<EntryResult>,
+}
+
+pub fn run_conform(database: PathBuf, options: &ConformOptions) -> Result<()> {
+    let spec = load_spec(&options.spec)?;
+    let spec = Spec::new(&spec)?;
+
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let external_root = if options.allow_external_paths {
+        let root = options
+            .external_path_root
+            .clone()
+            .or_else(|| database.parent().map(|p| p.to_path_buf()))
+            .ok_or_else(|| {
+                HarliteError::InvalidArgs(
+                    "Cannot resolve external path root; pass --external-path-root".to_string(),
+                )
+            })?;
+        Some(root.canonicalize()?)
+    } else {
+        None
+    };
+
+    let ignore_headers: Vec<String> = options
+        .ignore_headers
+        .iter()
+        .map(|h| h.to_ascii_lowercase())
+        .collect();
+
+    let entries = load_entries_with_filters(&conn, &options.filters)?;
+    let mut results: Vec<EntryResult> = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let Some(url) = entry.url.as_deref().and_then(|u| Url::parse(u).ok()) else {
+            continue;
+        };
+        let bodies = EntryBodies {
+            conn: &conn,
+            entry,
+            external_root: external_root.as_deref(),
+        };
+        results.push(check_entry(&spec, entry, &url, &bodies, &ignore_headers)?);
+    }
+
+    let mut violations: BTreeMap<ViolationKind, usize> = BTreeMap::new();
+    for violation in results.iter().flat_map(|r| &r.violations) {
+        *violations.entry(violation.kind).or_default() += 1;
+    }
+    let report = ConformReport {
+        spec: options.spec.display().to_string(),
+        entries_checked: results.len(),
+        entries_failed: results.iter().filter(|r| !r.violations.is_empty()).count(),
+        violations,
+        results,
+    };
+
+    let output = options.output.clone().unwrap_or_else(|| PathBuf::from("-"));
+    let mut writer = open_output(&output)?;
+    match options.format {
+        ConformFormat::Table => write_table(&mut writer, &report)?,
+        ConformFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &report)?;
+            writer.write_all(b"\n")?;
+        }
+        ConformFormat::Junit => write_junit(&mut writer, &report)?,
+    }
+    writer.flush()?;
+
+    if options.fail_on_violation && report.entries_failed > 0 {
+        return Err(HarliteError::InvalidArgs(format!(
+            "{} of {} entries do not conform to {}",
+            report.entries_failed,
+            report.entries_checked,
+            options.spec.display()
+        )));
+    }
+    Ok(())
+}
+
+fn load_spec(path: &Path) -> Result<Value> {
+    let text = std::fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    if is_json || text.trim_start().starts_with('{') {
+        return Ok(serde_json::from_str(&text)?);
+    }
+    serde_norway::from_str(&text).map_err(|e| {
+        HarliteError::InvalidArgs(format!("Failed to parse spec {}: {e}", path.display()))
+    })
+}
+
+/// A spec path template compiled for matching concrete request paths.
+struct SpecPath<'a> {
+    template: String,
+    pattern: Regex,
+    params: Vec<String>,
+    literal_len: usize,
+    item: &'a Value,
+}
+
+struct Spec<'a> {
+    root: &'a Value,
+    validator: Validator<'a>,
+    base_paths: Vec<String>,
+    paths: Vec<SpecPath<'a>>,
+}
+
+impl<'a> Spec<'a> {
+    fn new(root: &'a Value) -> Result<Self> {
+        let Some(paths) = root.get("paths").and_then(|p| p.as_object()) else {
+            return Err(HarliteError::InvalidArgs(
+                "Spec has no 'paths' object".to_string(),
+            ));
+        };
+
+        let mut compiled = Vec::with_capacity(paths.len());
+        for (template, item) in paths {
+            let mut pattern = String::from("^");
+            let mut params = Vec::new();
+            let mut literal_len = 0;
+            let mut rest = template.as_str();
+            while let Some(open) = rest.find('{') {
+                let Some(close) = rest[open..].find('}') else {
+                    break;
+                };
+                pattern.push_str(&regex::escape(&rest[..open]));
+                literal_len += open;
+                params.push(rest[open + 1..open + close].to_string());
+                pattern.push_str("([^/]+)");
+                rest = &rest[open + close + 1..];
+            }
+            pattern.push_str(&regex::escape(rest));
+            literal_len += rest.len();
+            pattern.push('$');
+            compiled.push(SpecPath {
+                template: template.clone(),
+                pattern: Regex::new(&pattern)?,
+                params,
+                literal_len,
+                item: resolve(root, item),
+            });
+        }
+        // Prefer concrete paths over templated ones (`/users/me` before `/users/{id}`).
+        compiled.sort_by(|a, b| {
+            a.params
+                .len()
+                .cmp(&b.params.len())
+                .then(b.literal_len.cmp(&a.literal_len))
+        });
+
+        let mut base_paths: Vec<String> = root
+            .get("servers")
+            .and_then(|s| s.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|server| server.get("url").and_then(|u| u.as_str()))
+            .filter_map(server_base_path)
+            .collect();
+        base_paths.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        base_paths.push(String::new());
+
+        Ok(Self {
+            root,
+            validator: Validator::new(root),
+            base_paths,
+            paths: compiled,
+        })
+    }
+
+    fn find(&self, path: &str) -> Option<(&SpecPath<'a>, HashMap<String, String>)> {
+        for base in &self.base_paths {
+            let Some(rest) = path.strip_prefix(base.as_str()) else {
+                continue;
+            };
+            let rest = if rest.is_empty() { "/" } else { rest };
+            for spec_path in &self.paths {
+                if let Some(captures) = spec_path.pattern.captures(rest) {
+                    let values = spec_path
+                        .params
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(idx, name)| {
+                            let value = captures.get(idx + 1)?.as_str();
+                            Some((name.clone(), percent_decode(value)))
+                        })
+                        .collect();
+                    return Some((spec_path, values));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Path component of a server URL; relative server URLs are used as-is.
+fn server_base_path(url: &str) -> Option<String> {
+    let path = match Url::parse(url) {
+        Ok(parsed) => parsed.path().to_string(),
+        Err(_) if url.starts_with('/') => url.to_string(),
+        Err(_) => return None,
+    };
+    Some(path.trim_end_matches('/').to_string())
+}
+
+fn percent_decode(value: &str) -> String {
+    url::form_urlencoded::parse(format!("v={}", value.replace('+', "%2B")).as_bytes())
+        .next()
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Follow local `$ref`s (`#/components/...`) until a concrete object is reached.
+fn resolve<'a>(root: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..32 {
+        let Some(reference) = value.get("$ref").and_then(|r| r.as_str()) else {
+            return value;
+        };
+        let Some(target) = reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        else {
+            return value;
+        };
+        value = target;
+    }
+    value
+}
+
+/// Lazily loads an entry's bodies so entries without schema checks never touch blobs.
+struct EntryBodies<'a> {
+    conn: &'a Connection,
+    entry: &'a EntryRow,
+    external_root: Option<&'a Path>,
+}
+
+impl EntryBodies<'_> {
+    fn request(&self) -> Result<Option<Vec<u8>>> {
+        self.load(self.entry.request_body_hash.as_deref())
+    }
+
+    fn response(&self) -> Result<Option<Vec<u8>>> {
+        self.load(self.entry.response_body_hash.as_deref())
+    }
+
+    fn load(&self, hash: Option<&str>) -> Result<Option<Vec<u8>>> {
+        let Some(hash) = hash else {
+            return Ok(None);
+        };
+        let blobs = load_blobs_by_hashes(self.conn, &[hash.to_string()])?;
+        let Some(blob) = blobs.into_iter().next() else {
+            return Ok(None);
+        };
+        let blob = load_external_blob_content(blob, self.external_root)?;
+        Ok(Some(blob.content).filter(|c| !c.is_empty()))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+fn check_entry(
+    spec: &Spec,
+    entry: &EntryRow,
+    url: &Url,
+    bodies: &EntryBodies,
+    ignore_headers: &[String],
+) -> Result<EntryResult> {
+    let method = entry
+        .method
+        .as_deref()
+        .unwrap_or("GET")
+        .to_ascii_lowercase();
+    let result = |operation: Option<String>, violations: Vec<Violation>| EntryResult {
+        entry_id: entry.id,
+        method: method.to_ascii_uppercase(),
+        url: url.to_string(),
+        status: entry.status,
+        operation,
+        violations,
+    };
+    let mut violations: Vec<Violation> = Vec::new();
+    let mut violation = |kind: ViolationKind, message: String| {
+        violations.push(Violation { kind, message });
+    };
+
+    let Some((spec_path, path_values)) = spec.find(url.path()) else {
+        violation(
+            ViolationKind::UndocumentedEndpoint,
+            format!("No path in the spec matches {}", url.path()),
+        );
+        return Ok(result(None, violations));
+    };
+    let Some(operation) = spec_path.item.get(&method).map(|op| resolve(spec.root, op)) else {
+        violation(
+            ViolationKind::UndocumentedEndpoint,
+            format!(
+                "{} is documented, but not for {}",
+                spec_path.template,
+                method.to_ascii_uppercase()
+            ),
+        );
+        return Ok(result(Some(spec_path.template.clone()), violations));
+    };
+    let operation_name = format!("{} {}", method.to_ascii_uppercase(), spec_path.template);
+
+    let request_headers = headers_map(entry.request_headers.as_deref());
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    let cookies = cookie_names(entry, &request_headers);
+
+    // Operation-level parameters override path-level ones with the same name and location.
+    let mut parameters: BTreeMap<(String, String), &Value> = BTreeMap::new();
+    for source in [spec_path.item, operation] {
+        for param in source
+            .get("parameters")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let param = resolve(spec.root, param);
+            let (Some(name), Some(location)) = (
+                param.get("name").and_then(|n| n.as_str()),
+                param.get("in").and_then(|n| n.as_str()),
+            ) else {
+                continue;
+            };
+            let name = if location == "header" {
+                name.to_ascii_lowercase()
+            } else {
+                name.to_string()
+            };
+            parameters.insert((location.to_string(), name), param);
+        }
+    }
+
+    for ((location, name), param) in &parameters {
+        let values: Vec<String> = match location.as_str() {
+            "path" => path_values.get(name).cloned().into_iter().collect(),
+            "query" => query
+                .iter()
+                .filter(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+                .collect(),
+            "header" => request_headers.get(name).cloned().into_iter().collect(),
+            "cookie" => {
+                if cookies.iter().any(|c| c == name) {
+                    vec![String::new()]
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => continue,
+        };
+        let required = location == "path"
+            || param
+                .get("required")
+                .and_then(|r| r.as_bool())
+                .unwrap_or(false);
+        if values.is_empty() {
+            if required {
+                violation(
+                    ViolationKind::MissingParameter,
+                    format!("Missing required {location} parameter '{name}'"),
+                );
+            }
+            continue;
+        }
+        let Some(schema) = param.get("schema") else {
+            continue;
+        };
+        if location == "cookie" {
+            continue;
+        }
+        let schema = resolve(spec.root, schema);
+        let value = if schema.get("type").and_then(|t| t.as_str()) == Some("array") {
+            Value::Array(
+                values
+                    .iter()
+                    .flat_map(|v| v.split(','))
+                    .map(|v| coerce_scalar(spec.root, schema.get("items"), v))
+                    .collect(),
+            )
+        } else {
+            coerce_scalar(spec.root, Some(schema), &values[0])
+        };
+        let mut errors = Vec::new();
+        spec.validator
+            .validate(schema, &value, "", Direction::Request, &mut errors);
+        if let Some(error) = errors.first() {
+            violation(
+                ViolationKind::InvalidParameter,
+                format!("Invalid {location} parameter '{name}': {error}"),
+            );
+        }
+    }
+
+    let security_headers = security_header_names(spec.root, operation);
+    for name in request_headers.keys() {
+        if name.starts_with(':')
+            || name.starts_with("sec-")
+            || STANDARD_HEADERS.contains(&name.as_str())
+            || ignore_headers.contains(name)
+            || security_headers.contains(name)
+            || parameters.contains_key(&("header".to_string(), name.clone()))
+        {
+            continue;
+        }
+        violation(
+            ViolationKind::UnexpectedHeader,
+            format!("Request header '{name}' is not documented"),
+        );
+    }
+
+    let request_body = operation
+        .get("requestBody")
+        .map(|body| resolve(spec.root, body));
+    let request_bytes = bodies.request()?;
+    match (request_body, request_bytes) {
+        (Some(body), None) => {
+            let required = body
+                .get("required")
+                .and_then(|r| r.as_bool())
+                .unwrap_or(false);
+            if required && entry.request_body_size.unwrap_or(0) <= 0 {
+                violation(
+                    ViolationKind::RequestBody,
+                    "Missing required request body".to_string(),
+                );
+            }
+        }
+        (Some(body), Some(bytes)) => {
+            let mime = request_headers
+                .get("content-type")
+                .and_then(|v| normalize_mime(v));
+            for message in check_body(
+                &spec.validator,
+                body,
+                mime.as_deref(),
+                &bytes,
+                Direction::Request,
+            ) {
+                violation(ViolationKind::RequestBody, message);
+            }
+        }
+        (None, Some(_)) if !matches!(method.as_str(), "get" | "head" | "options") => {
+            violation(
+                ViolationKind::RequestBody,
+                "Request has a body but the operation documents none".to_string(),
+            );
+        }
+        _ => {}
+    }
+
+    let status_key = entry.status.map(|s| s.to_string());
+    let responses = operation.get("responses").and_then(|r| r.as_object());
+    let response = status_key.as_deref().and_then(|status| {
+        let responses = responses?;
+        let range = format!("{}XX", &status[..1]);
+        responses
+            .get(status)
+            .or_else(|| {
+                responses
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(&range))
+                    .map(|(_, v)| v)
+            })
+            .or_else(|| responses.get("default"))
+    });
+    match response {
+        None => {
+            if let Some(status) = status_key {
+                violation(
+                    ViolationKind::UndocumentedStatus,
+                    format!("Status {status} is not documented"),
+                );
+            }
+        }
+        Some(response) => {
+            let response = resolve(spec.root, response);
+            if response.get("content").is_some() {
+                if let Some(bytes) = bodies.response()? {
+                    let mime = entry
+                        .response_mime_type
+                        .as_deref()
+                        .and_then(normalize_mime)
+                        .or_else(|| {
+                            headers_map(entry.response_headers.as_deref())
+                                .get("content-type")
+                                .and_then(|v| normalize_mime(v))
+                        });
+                    for message in check_body(
+                        &spec.validator,
+                        response,
+                        mime.as_deref(),
+                        &bytes,
+                        Direction::Response,
+                    ) {
+                        violation(ViolationKind::ResponseBody, message);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(result(Some(operation_name), violations))
+}
+
+/// Check a body against the media type the spec documents for it.
+fn check_body(
+    validator: &Validator,
+    body: &Value,
+    mime: Option<&str>,
+    bytes: &[u8],
+    direction: Direction,
+) -> Vec<String> {
+    let Some(content) = body.get("content").and_then(|c| c.as_object()) else {
+        return Vec::new();
+    };
+    let media = match mime {
+        Some(mime) => content.get(mime).or_else(|| {
+            let wildcard = format!("{}/*", mime.split('/').next().unwrap_or_default());
+            content.get(&wildcard).or_else(|| content.get("*/*"))
+        }),
+        None => content.values().next(),
+    };
+    let Some(media) = media else {
+        let documented: Vec<&str> = content.keys().map(|k| k.as_str()).collect();
+        return vec![format!(
+            "Content type {} is not documented (expected {})",
+            mime.unwrap_or("(none)"),
+            documented.join(", ")
+        )];
+    };
+    let is_json = mime.map(|m| m.contains("json")).unwrap_or(true);
+    let Some(schema) = media.get("schema").filter(|_| is_json) else {
+        return Vec::new();
+    };
+    let value: Value = match serde_json::from_slice(bytes) {
+        Ok(value) => value,
+        Err(err) => return vec![format!("Body is not valid JSON: {err}")],
+    };
+    let mut errors = Vec::new();
+    validator.validate(schema, &value, "", direction, &mut errors);
+    if errors.len() > MAX_BODY_ERRORS {
+        let extra = errors.len() - MAX_BODY_ERRORS;
+        errors.truncate(MAX_BODY_ERRORS);
+        errors.push(format!("... and {extra} more schema errors"));
+    }
+    errors
+}
+
+/// Interpret a query, path or header value according to the declared scalar type.
+fn coerce_scalar(root: &Value, schema: Option<&Value>, raw: &str) -> Value {
+    let schema_type = schema
+        .map(|s| resolve(root, s))
+        .and_then(|s| s.get("type"))
+        .and_then(|t| t.as_str());
+    match schema_type {
+        Some("integer") | Some("number") => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .ok()
+            .or_else(|| {
+                raw.parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+            })
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+        Some("boolean") => match raw {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::String(raw.to_string()),
+        },
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+/// Validates values against the schemas of one spec, resolving `$ref`s against its root and
+/// compiling each `pattern` once.
+struct Validator<'a> {
+    root: &'a Value,
+    patterns: RefCell<HashMap<String, Option<Regex>>>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(root: &'a Value) -> Self {
+        Self {
+            root,
+            patterns: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Validate `value` against an OpenAPI (3.0 or 3.1) schema, collecting readable errors.
+    fn validate(
+        &self,
+        schema: &Value,
+        value: &Value,
+        at: &str,
+        direction: Direction,
+        errors: &mut Vec<String>,
+    ) {
+        self.check(schema, value, at, direction, 0, errors);
+    }
+
+    /// `depth` counts the composition keywords applied to this value; descending into array
+    /// items or properties resets it, so only schemas that loop without consuming the value
+    /// (`A: allOf: [$ref: A]`) hit the limit.
+    fn check(
+        &self,
+        schema: &Value,
+        value: &Value,
+        at: &str,
+        direction: Direction,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        let root = self.root;
+        let schema = resolve(root, schema);
+        let Some(obj) = schema.as_object() else {
+            return;
+        };
+        let location = if at.is_empty() { "body" } else { at };
+        if depth > MAX_COMPOSITION_DEPTH {
+            errors.push(format!(
+                "{location}: schema composition nests deeper than {MAX_COMPOSITION_DEPTH} levels (cyclic $ref?)"
+            ));
+            return;
+        }
+
+        if value.is_null() && obj.get("nullable").and_then(|n| n.as_bool()) == Some(true) {
+            return;
+        }
+
+        if let Some(all_of) = obj.get("allOf").and_then(|a| a.as_array()) {
+            for sub in all_of {
+                self.check(sub, value, at, direction, depth + 1, errors);
+            }
+        }
+        for (keyword, exactly_one) in [("anyOf", false), ("oneOf", true)] {
+            let Some(options) = obj.get(keyword).and_then(|a| a.as_array()) else {
+                continue;
+            };
+            let matches = options
+                .iter()
+                .filter(|sub| {
+                    let mut sub_errors = Vec::new();
+                    self.check(sub, value, at, direction, depth + 1, &mut sub_errors);
+                    sub_errors.is_empty()
+                })
+                .count();
+            if matches == 0 || (exactly_one && matches > 1 && !has_discriminator(obj)) {
+                errors.push(format!(
+                    "{location}: matches {matches} of the {keyword} schemas"
+                ));
+            }
+        }
+
+        if let Some(types) = obj.get("type") {
+            let allowed: Vec<&str> = match types {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(list) => list.iter().filter_map(|t| t.as_str()).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.is_empty() && !allowed.iter().any(|t| type_matches(t, value)) {
+                errors.push(format!(
+                    "{location}: expected {}, got {}",
+                    allowed.join(" or "),
+                    json_type(value)
+                ));
+                return;
+            }
+        }
+
+        if let Some(options) = obj.get("enum").and_then(|e| e.as_array()) {
+            if !options.contains(value) {
+                errors.push(format!("{location}: {value} is not one of the enum values"));
+            }
+        }
+        if let Some(constant) = obj.get("const") {
+            if constant != value {
+                errors.push(format!("{location}: expected constant {constant}"));
+            }
+        }
+
+        match value {
+            Value::String(s) => {
+                let len = s.chars().count() as u64;
+                if let Some(min) = obj.get("minLength").and_then(|m| m.as_u64()) {
+                    if len < min {
+                        errors.push(format!("{location}: shorter than {min} characters"));
+                    }
+                }
+                if let Some(max) = obj.get("maxLength").and_then(|m| m.as_u64()) {
+                    if len > max {
+                        errors.push(format!("{location}: longer than {max} characters"));
+                    }
+                }
+                if let Some(pattern) = obj.get("pattern").and_then(|p| p.as_str()) {
+                    if self.pattern_matches(pattern, s) == Some(false) {
+                        errors.push(format!("{location}: does not match pattern {pattern}"));
+                    }
+                }
+                if let Some(format) = obj.get("format").and_then(|f| f.as_str()) {
+                    if !format_matches(format, s) {
+                        errors.push(format!("{location}: {s:?} is not a valid {format}"));
+                    }
+                }
+            }
+            Value::Number(n) => {
+                let Some(n) = n.as_f64() else {
+                    return;
+                };
+                check_bounds(obj, n, location, errors);
+            }
+            Value::Array(items) => {
+                if let Some(min) = obj.get("minItems").and_then(|m| m.as_u64()) {
+                    if (items.len() as u64) < min {
+                        errors.push(format!("{location}: fewer than {min} items"));
+                    }
+                }
+                if let Some(max) = obj.get("maxItems").and_then(|m| m.as_u64()) {
+                    if items.len() as u64 > max {
+                        errors.push(format!("{location}: more than {max} items"));
+                    }
+                }
+                if let Some(item_schema) = obj.get("items") {
+                    for (idx, item) in items.iter().enumerate() {
+                        self.check(
+                            item_schema,
+                            item,
+                            &format!("{at}[{idx}]"),
+                            direction,
+                            0,
+                            errors,
+                        );
+                    }
+                }
+            }
+            Value::Object(map) => {
+                let properties = obj.get("properties").and_then(|p| p.as_object());
+                for name in obj
+                    .get("required")
+                    .and_then(|r| r.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|n| n.as_str())
+                {
+                    if map.contains_key(name) {
+                        continue;
+                    }
+                    // readOnly fields are not sent by clients; writeOnly fields are not returned.
+                    let skipped_flag = match direction {
+                        Direction::Request => "readOnly",
+                        Direction::Response => "writeOnly",
+                    };
+                    let skipped = properties
+                        .and_then(|p| p.get(name))
+                        .map(|p| resolve(root, p))
+                        .and_then(|p| p.get(skipped_flag))
+                        .and_then(|f| f.as_bool())
+                        .unwrap_or(false);
+                    if !skipped {
+                        errors.push(format!("{location}: missing required property '{name}'"));
+                    }
+                }
+                let additional = obj.get("additionalProperties");
+                for (key, child) in map {
+                    let child_at = format!("{at}.{key}");
+                    match properties.and_then(|p| p.get(key)) {
+                        Some(child_schema) => {
+                            self.check(child_schema, child, &child_at, direction, 0, errors)
+                        }
+                        None => match additional {
+                            Some(Value::Bool(false)) => {
+                                errors.push(format!("{location}: unexpected property '{key}'"));
+                            }
+                            Some(extra @ Value::Object(_)) => {
+                                self.check(extra, child, &child_at, direction, 0, errors)
+                            }
+                            _ => {}
+                        },
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `text` matches `pattern`; `None` when the pattern is not a valid regex.
+    fn pattern_matches(&self, pattern: &str, text: &str) -> Option<bool> {
+        let mut patterns = self.patterns.borrow_mut();
+        let compiled = patterns
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).ok());
+        compiled.as_ref().map(|re| re.is_match(text))
+    }
+}
+
+fn has_discriminator(schema: &serde_json::Map<String, Value>) -> bool {
+    schema.contains_key("discriminator")
+}
+
+fn check_bounds(
+    schema: &serde_json::Map<String, Value>,
+    n: f64,
+    location: &str,
+    errors: &mut Vec<String>,
+) {
+    // OpenAPI 3.0 uses boolean exclusive flags; 3.1 (JSON Schema) uses numeric bounds.
+    let flag = |key: &str| schema.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+        if n < min || (flag("exclusiveMinimum") && n <= min) {
+            errors.push(format!("{location}: {n} is below the minimum {min}"));
+        }
+    }
+    if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+        if n > max || (flag("exclusiveMaximum") && n >= max) {
+            errors.push(format!("{location}: {n} is above the maximum {max}"));
+        }
+    }
+    if let Some(min) = schema.get("exclusiveMinimum").and_then(|m| m.as_f64()) {
+        if n <= min {
+            errors.push(format!("{location}: {n} is not above {min}"));
+        }
+    }
+    if let Some(max) = schema.get("exclusiveMaximum").and_then(|m| m.as_f64()) {
+        if n >= max {
+            errors.push(format!("{location}: {n} is not below {max}"));
+        }
+    }
+}
+
+fn type_matches(schema_type: &str, value: &Value) -> bool {
+    match schema_type {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => match value {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        _ => true,
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Assert the common string formats; unknown formats are annotations only.
+fn format_matches(format: &str, value: &str) -> bool {
+    match format {
+        "date-time" => DateTime::parse_from_rfc3339(value).is_ok(),
+        "date" => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        "uuid" => {
+            let groups: Vec<&str> = value.split('-').collect();
+            groups.len() == 5
+                && groups
+                    .iter()
+                    .zip([8, 4, 4, 4, 12])
+                    .all(|(g, len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+        }
+        "email" => value
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.')),
+        "uri" => Url::parse(value).is_ok(),
+        _ => true,
+    }
+}
+
+/// Header names consumed by the operation's (or the spec's default) apiKey security schemes.
+fn security_header_names(root: &Value, operation: &Value) -> Vec<String> {
+    let requirements = operation
+        .get("security")
+        .or_else(|| root.get("security"))
+        .and_then(|s| s.as_array());
+    let schemes = root
+        .pointer("/components/securitySchemes")
+        .and_then(|s| s.as_object());
+    let (Some(requirements), Some(schemes)) = (requirements, schemes) else {
+        return Vec::new();
+    };
+    requirements
+        .iter()
+        .filter_map(|req| req.as_object())
+        .flat_map(|req| req.keys())
+        .filter_map(|name| schemes.get(name))
+        .map(|scheme| resolve(root, scheme))
+        .filter(|scheme| {
+            scheme.get("type").and_then(|t| t.as_str()) == Some("apiKey")
+                && scheme.get("in").and_then(|t| t.as_str()) == Some("header")
+        })
+        .filter_map(|scheme| scheme.get("name").and_then(|n| n.as_str()))
+        .map(|name| name.to_ascii_lowercase())
+        .collect()
+}
+
+fn cookie_names(entry: &EntryRow, headers: &HashMap<String, String>) -> Vec<String> {
+    let mut names: Vec<String> = entry
+        .request_cookies
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<Value>(json).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|c| {
+            c.get("name")
+                .and_then(|n| n.as_str())
+                .map(|n| n.to_string())
+        })
+        .collect();
+    if let Some(header) = headers.get("cookie") {
+        names.extend(
+            header
+                .split(';')
+                .filter_map(|pair| pair.split_once('=').map(|(n, _)| n.trim().to_string())),
+        );
+    }
+    names
+}
+
+fn headers_map(json: Option<&str>) -> HashMap<String, String> {
+    let Some(json) = json else {
+        return HashMap::new();
+    };
+    let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(json) else {
+        return HashMap::new();
+    };
+    obj.iter()
+        .filter_map(|(k, v)| v.as_str().map(|s| (k.to_ascii_lowercase(), s.to_string())))
+        .collect()
+}
+
+fn normalize_mime(value: &str) -> Option<String> {
+    let trimmed = value.split(';').next()?.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_ascii_lowercase())
+    }
+}
+
+fn write_table(out: &mut dyn Write, report: &ConformReport) -> Result<()> {
+    let columns = ["entry", "method", "operation", "status", "kind", "message"];
+    let rows: Vec<[String; 6]> = report
+        .results
+        .iter()
+        .flat_map(|result| {
+            result.violations.iter().map(move |v| {
+                [
+                    result.entry_id.to_string(),
+                    result.method.clone(),
+                    result
+                        .operation
+                        .clone()
+                        .unwrap_or_else(|| result.url.clone()),
+                    result.status.map(|s| s.to_string()).unwrap_or_default(),
+                    v.kind.as_str().to_string(),
+                    v.message.clone(),
+                ]
+            })
+        })
+        .collect();
+
+    if !rows.is_empty() {
+        let mut widths: Vec<usize> = columns.iter().map(|c| c.len()).collect();
+        for row in &rows {
+            for (i, value) in row.iter().enumerate() {
+                widths[i] = widths[i].max(value.chars().count()).min(80);
+            }
+        }
+        let line = |fields: &[&str]| -> String {
+            fields
+                .iter()
+                .zip(&widths)
+                .map(|(field, width)| {
+                    let field: String = field.chars().take(*width).collect();
+                    format!("{field:<width$}")
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        };
+        writeln!(out, "{}", line(&columns))?;
+        let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        writeln!(out, "{}", sep.join("-+-"))?;
+        for row in &rows {
+            let fields: Vec<&str> = row.iter().map(|s| s.as_str()).collect();
+            writeln!(out, "{}", line(&fields))?;
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(
+        out,
+        "Checked {} entries against {}: {} conform, {} with violations",
+        report.entries_checked,
+        report.spec,
+        report.entries_checked - report.entries_failed,
+        report.entries_failed
+    )?;
+    for (kind, count) in &report.violations {
+        writeln!(out, "  {}: {count}", kind.as_str())?;
+    }
+    Ok(())
+}
+
+/// One test case per entry, grouped by operation, so CI dashboards show drift per endpoint.
+fn write_junit(out: &mut dyn Write, report: &ConformReport) -> Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites name="harlite conform" tests="{}" failures="{}">"#,
+        report.entries_checked, report.entries_failed
+    )?;
+    writeln!(
+        out,
+        r#"  <testsuite name="{}" tests="{}" failures="{}">"#,
+        xml_escape(&report.spec),
+        report.entries_checked,
+        report.entries_failed
+    )?;
+    for result in &report.results {
+        let classname = result
+            .operation
+            .clone()
+            .unwrap_or_else(|| "undocumented".to_string());
+        let name = format!(
+            "#{} {} {} -> {}",
+            result.entry_id,
+            result.method,
+            result.url,
+            result
+                .status
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        );
+        if result.violations.is_empty() {
+            writeln!(
+                out,
+                r#"    <testcase classname="{}" name="{}"/>"#,
+                xml_escape(&classname),
+                xml_escape(&name)
+            )?;
+            continue;
+        }
+        writeln!(
+            out,
+            r#"    <testcase classname="{}" name="{}">"#,
+            xml_escape(&classname),
+            xml_escape(&name)
+        )?;
+        let details: Vec<String> = result
+            .violations
+            .iter()
+            .map(|v| format!("[{}] {}", v.kind.as_str(), v.message))
+            .collect();
+        writeln!(
+            out,
+            r#"      <failure type="{}" message="{}">{}</failure>"#,
+            result.violations[0].kind.as_str(),
+            xml_escape(&result.violations[0].message),
+            xml_escape(&details.join("\n"))
+        )?;
+        writeln!(out, "    </testcase>")?;
+    }
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")?;
+    Ok(())
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\t' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "servers": [{ "url": "https://api.example.com/v1" }],
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                    ],
+                    "get": {
+                        "parameters": [
+                            { "name": "expand", "in": "query", "required": true, "schema": { "type": "boolean" } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": { "application/json": { "schema": { "$ref": "#/components/schemas/User" } } }
+                            }
+                        }
+                    }
+                },
+                "/users/me": { "get": { "responses": { "2XX": { "description": "OK" } } } }
+            },
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "required": ["id", "email"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "id": { "type": "integer", "readOnly": true },
+                            "email": { "type": "string", "format": "email" },
+                            "role": { "type": "string", "enum": ["admin", "member"] },
+                            "nickname": { "type": "string", "nullable": true }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn spec_matches_server_base_and_prefers_literal_paths() {
+        let root = spec();
+        let spec = Spec::new(&root).unwrap();
+        let (path, values) = spec.find("/v1/users/42").unwrap();
+        assert_eq!(path.template, "/users/{id}");
+        assert_eq!(values.get("id").map(|s| s.as_str()), Some("42"));
+        assert_eq!(spec.find("/v1/users/me").unwrap().0.template, "/users/me");
+        assert!(spec.find("/v1/orders").is_none());
+    }
+
+    #[test]
+    fn validate_reports_schema_violations() {
+        let root = spec();
+        let schema = json!({ "$ref": "#/components/schemas/User" });
+        let mut errors = Vec::new();
+        let body = json!({ "id": 1, "email": "a@example.com", "nickname": null });
+        Validator::new(&root).validate(&schema, &body, "", Direction::Response, &mut errors);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let body = json!({ "id": "1", "email": "nope", "role": "owner", "extra": 1 });
+        Validator::new(&root).validate(&schema, &body, "", Direction::Response, &mut errors);
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors
+            .iter()
+            .any(|e| e.contains(".id: expected integer, got string")));
+        assert!(errors.iter().any(|e| e.contains("not a valid email")));
+        assert!(errors.iter().any(|e| e.contains("enum")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("unexpected property 'extra'")));
+
+        // readOnly properties are not required in requests.
+        let mut errors = Vec::new();
+        let body = json!({ "email": "a@example.com" });
+        Validator::new(&root).validate(&schema, &body, "", Direction::Request, &mut errors);
+        assert!(errors.is_empty(), "{errors:?}");
+        Validator::new(&root).validate(&schema, &body, "", Direction::Response, &mut errors);
+        assert_eq!(
+            errors,
+            vec!["body: missing required property 'id'".to_string()]
+        );
+    }
+
+    #[test]
+    fn validate_stops_on_cyclic_composition_and_caches_patterns() {
+        let root = json!({
+            "components": {
+                "schemas": {
+                    "Loop": { "allOf": [{ "$ref": "#/components/schemas/Loop" }] },
+                    "Node": {
+                        "type": "object",
+                        "properties": {
+                            "code": { "type": "string", "pattern": "^[A-Z]{3}$" },
+                            "child": { "$ref": "#/components/schemas/Node" }
+                        }
+                    }
+                }
+            }
+        });
+        let validator = Validator::new(&root);
+
+        let mut errors = Vec::new();
+        let schema = json!({ "$ref": "#/components/schemas/Loop" });
+        validator.validate(&schema, &json!({}), "", Direction::Response, &mut errors);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].contains("cyclic $ref"));
+
+        // Recursive schemas that descend into the value are fine at any depth.
+        let mut body = json!({ "code": "abc" });
+        for _ in 0..50 {
+            body = json!({ "code": "ABC", "child": body });
+        }
+        let mut errors = Vec::new();
+        let schema = json!({ "$ref": "#/components/schemas/Node" });
+        validator.validate(&schema, &body, "", Direction::Response, &mut errors);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].contains("does not match pattern"));
+        assert_eq!(validator.patterns.borrow().len(), 1);
+    }
+
+    #[test]
+    fn junit_output_escapes_and_counts_failures() {
+        let report = ConformReport {
+            spec: "api.yaml".to_string(),
+            entries_checked: 2,
+            entries_failed: 1,
+            violations: BTreeMap::from([(ViolationKind::UndocumentedStatus, 1)]),
+            results: vec![
+                EntryResult {
+                    entry_id: 1,
+                    method: "GET".to_string(),
+                    url: "https://api.example.com/v1/users/1?a=1&b=2".to_string(),
+                    status: Some(500),
+                    operation: Some("GET /users/{id}".to_string()),
+                    violations: vec![Violation {
+                        kind: ViolationKind::UndocumentedStatus,
+                        message: "Status 500 is not documented".to_string(),
+                    }],
+                },
+                EntryResult {
+                    entry_id: 2,
+                    method: "GET".to_string(),
+                    url: "https://api.example.com/v1/users/me".to_string(),
+                    status: Some(200),
+                    operation: Some("GET /users/me".to_string()),
+                    violations: Vec::new(),
+                },
+            ],
+        };
+        let mut out = Vec::new();
+        write_junit(&mut out, &report).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains(r#"<testsuites name="harlite conform" tests="2" failures="1">"#));
+        assert!(xml.contains("?a=1&amp;b=2"));
+        assert!(xml.contains(r#"<failure type="undocumented-status""#));
+        assert!(xml.contains(r##"<testcase classname="GET /users/me" name="#2 GET"##));
+    }
+
+    #[test]
+    fn load_spec_reads_yaml_and_json() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let yaml = dir.path().join("api.yaml");
+        std::fs::write(
+            &yaml,
+            "openapi: 3.0.3\npaths:\n  /users/{id}:\n    get:\n      responses:\n        '200':\n          description: ok\n",
+        )
+        .unwrap();
+        let spec = load_spec(&yaml).unwrap();
+        assert_eq!(spec["openapi"], "3.0.3");
+        assert!(spec["paths"]["/users/{id}"]["get"]["responses"]["200"].is_object());
+
+        let json_path = dir.path().join("api.json");
+        std::fs::write(&json_path, r#"{"openapi":"3.1.0"}"#).unwrap();
+        assert_eq!(load_spec(&json_path).unwrap()["openapi"], "3.1.0");
+
+        std::fs::write(&yaml, "paths: [unclosed").unwrap();
+        assert!(load_spec(&yaml).is_err());
+    }
+}
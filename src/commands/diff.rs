@@ -4,11 +4,11 @@ use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 
 use regex::Regex;
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::OpenFlags;
 use serde::Serialize;
 use url::Url;
 
-use crate::db::{load_entries, EntryQuery, EntryRow};
+use crate::db::{load_entries, open_database_with_flags, EntryQuery, EntryRow};
 use crate::error::{HarliteError, Result};
 use crate::har::{parse_har_file, Entry as HarEntry, Header};
 
@@ -167,7 +167,7 @@ fn load_entries_from_db(
     options: &DiffOptions,
     filters: &Filters,
 ) -> Result<Vec<EntrySnapshot>> {
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         path,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
@@ -1,13 +1,13 @@
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::PathBuf;
 
 use chrono::Utc;
-use rusqlite::Connection;
 use url::Url;
 
-use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, load_pages_for_imports, BlobRow};
+use crate::db::{
+    ensure_schema_upgrades, load_blobs_by_hashes, load_pages_for_imports, open_database, BlobRow,
+};
 use crate::error::{HarliteError, Result};
 use crate::har::{
     Content, Cookie, Creator, Entry, Extensions, Har, Header, Log, Page, PageTimings, PostData,
@@ -15,6 +15,7 @@ use crate::har::{
 };
 use crate::plugins::{PluginContext, PluginSet};
 use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::util::{load_external_blob_content, open_output};
 
 /// Options for exporting a harlite database back to a HAR file.
 pub struct ExportOptions {
@@ -166,34 +167,6 @@ fn body_text_and_encoding(content: &[u8]) -> (Option<String>, Option<String>) {
     }
 }
 
-fn load_external_blob_content(mut blob: BlobRow, external_root: Option<&Path>) -> Result<BlobRow> {
-    if !blob.content.is_empty() || blob.size <= 0 {
-        return Ok(blob);
-    }
-    let Some(path) = &blob.external_path else {
-        return Ok(blob);
-    };
-    let Some(root) = external_root else {
-        return Ok(blob);
-    };
-
-    let candidate = PathBuf::from(path);
-    let candidate = if candidate.is_absolute() {
-        candidate
-    } else {
-        root.join(candidate)
-    };
-    let resolved = match candidate.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return Ok(blob),
-    };
-    if !resolved.starts_with(root) {
-        return Ok(blob);
-    }
-    blob.content = std::fs::read(resolved)?;
-    Ok(blob)
-}
-
 fn page_export_id(import_id: i64, page_id: &str, multi_import: bool) -> String {
     if multi_import {
         format!("{import_id}:{page_id}")
@@ -202,16 +175,9 @@ fn page_export_id(import_id: i64, page_id: &str, multi_import: bool) -> String {
     }
 }
 
-fn open_output(path: &Path) -> Result<Box<dyn Write>> {
-    if path == Path::new("-") {
-        return Ok(Box::new(io::stdout().lock()));
-    }
-    Ok(Box::new(BufWriter::new(File::create(path)?)))
-}
-
 /// Export a harlite SQLite database back to a HAR file.
 pub fn run_export(database: PathBuf, options: &ExportOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     ensure_schema_upgrades(&conn)?;
     let external_root = if options.allow_external_paths {
         let root = options
@@ -1,14 +1,13 @@
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use clap::ValueEnum;
-use rusqlite::Connection;
 
-use crate::db::{ensure_schema_upgrades, EntryRow};
+use crate::db::{ensure_schema_upgrades, open_database, EntryRow};
 use crate::error::{HarliteError, Result};
 
 use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::util::open_output;
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 #[clap(rename_all = "kebab-case")]
@@ -35,7 +34,7 @@ pub struct ExportDataOptions {
 }
 
 pub fn run_export_data(database: PathBuf, options: &ExportDataOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     ensure_schema_upgrades(&conn)?;
 
     let entries = load_entries_with_filters(&conn, &options.filters)?;
@@ -80,13 +79,6 @@ pub fn run_export_data(database: PathBuf, options: &ExportDataOptions) -> Result
     Ok(())
 }
 
-fn open_output(path: &Path) -> Result<Box<dyn Write>> {
-    if path == Path::new("-") {
-        return Ok(Box::new(io::stdout().lock()));
-    }
-    Ok(Box::new(BufWriter::new(File::create(path)?)))
-}
-
 const ENTRY_COLUMNS: &[&str] = &[
     "import_id",
     "page_id",
@@ -1,9 +1,8 @@
 use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
-use rusqlite::Connection;
-
-use crate::db::{create_schema, load_blobs_by_hashes, BlobRow};
+use super::util::load_external_blob_content;
+use crate::db::{create_schema, load_blobs_by_hashes, open_database};
 use crate::error::Result;
 
 #[derive(Clone, Copy, Debug, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
@@ -40,33 +39,6 @@ fn is_text_mime_type(mime: Option<&str>) -> bool {
     }
 }
 
-fn load_external_blob_content(mut blob: BlobRow, external_root: Option<&Path>) -> Result<BlobRow> {
-    if !blob.content.is_empty() || blob.size <= 0 {
-        return Ok(blob);
-    }
-    let Some(path) = &blob.external_path else {
-        return Ok(blob);
-    };
-    let Some(root) = external_root else {
-        return Ok(blob);
-    };
-    let candidate = PathBuf::from(path);
-    let candidate = if candidate.is_absolute() {
-        candidate
-    } else {
-        root.join(candidate)
-    };
-    let resolved = match candidate.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return Ok(blob),
-    };
-    if !resolved.starts_with(root) {
-        return Ok(blob);
-    }
-    blob.content = std::fs::read(resolved)?;
-    Ok(blob)
-}
-
 pub fn run_fts_rebuild(
     database: PathBuf,
     tokenizer: FtsTokenizer,
@@ -74,7 +46,7 @@ pub fn run_fts_rebuild(
     allow_external_paths: bool,
     external_path_root: Option<PathBuf>,
 ) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     create_schema(&conn)?;
     let external_root = if allow_external_paths {
         let root = external_path_root
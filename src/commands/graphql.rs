@@ -0,0 +1,1101 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use graphql_parser::query::{
+    parse_query, Definition, Document, OperationDefinition, Selection, SelectionSet, Type,
+    TypeCondition, Value as GqlValue, VariableDefinition,
+};
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, open_database, EntryRow};
+use crate::error::{HarliteError, Result};
+
+use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::util::{load_external_blob_content, open_output, percentile};
+
+/// Label used for operations sent without an `operationName` or a named operation.
+const ANONYMOUS: &str = "(anonymous)";
+/// List elements walked per response array when inferring the schema.
+const MAX_LIST_SAMPLES: usize = 20;
+/// Deepest selection nesting followed when inferring the schema.
+const MAX_SCHEMA_DEPTH: usize = 16;
+const BUILTIN_SCALARS: &[&str] = &["Boolean", "Float", "ID", "Int", "String"];
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum GraphqlFormat {
+    Table,
+    Json,
+    Sdl,
+}
+
+pub struct GraphqlOptions {
+    pub output: Option<PathBuf>,
+    pub format: GraphqlFormat,
+    pub allow_external_paths: bool,
+    pub external_path_root: Option<PathBuf>,
+    pub filters: EntryFilterOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct LatencyStats {
+    mean_ms: f64,
+    p50_ms: f64,
+    p95_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct OperationStats {
+    operation_type: Option<String>,
+    operation_name: String,
+    requests: usize,
+    /// Distinct normalized query documents sent under this operation.
+    documents: usize,
+    http_errors: usize,
+    /// Responses carrying a non-empty `errors` array, whatever their HTTP status.
+    graphql_errors: usize,
+    errors: usize,
+    error_rate: f64,
+    latency: Option<LatencyStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct DocumentSummary {
+    hash: String,
+    operation_type: Option<String>,
+    operation_name: String,
+    requests: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphqlReport {
+    requests: usize,
+    operations: Vec<OperationStats>,
+    documents: Vec<DocumentSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+}
+
+#[derive(Default)]
+struct OperationAccumulator {
+    requests: usize,
+    documents: BTreeSet<String>,
+    http_errors: usize,
+    graphql_errors: usize,
+    errors: usize,
+    times: Vec<f64>,
+}
+
+struct DocumentAccumulator {
+    operation_type: Option<String>,
+    operation_name: String,
+    requests: usize,
+    query: Option<String>,
+}
+
+pub fn run_graphql(database: PathBuf, options: &GraphqlOptions) -> Result<()> {
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let external_root = if options.allow_external_paths {
+        let root = options
+            .external_path_root
+            .clone()
+            .or_else(|| database.parent().map(|p| p.to_path_buf()))
+            .ok_or_else(|| {
+                HarliteError::InvalidArgs(
+                    "Cannot resolve external path root; pass --external-path-root".to_string(),
+                )
+            })?;
+        Some(root.canonicalize()?)
+    } else {
+        None
+    };
+
+    let entries: Vec<EntryRow> = load_entries_with_filters(&conn, &options.filters)?
+        .into_iter()
+        .filter(is_graphql_entry)
+        .collect();
+    if entries.is_empty() {
+        return Err(HarliteError::InvalidArgs(
+            "No GraphQL requests found in database".to_string(),
+        ));
+    }
+
+    let mut operations: BTreeMap<(String, Option<String>), OperationAccumulator> = BTreeMap::new();
+    let mut documents: BTreeMap<String, DocumentAccumulator> = BTreeMap::new();
+    let mut schema = SchemaBuilder::default();
+
+    for entry in &entries {
+        let name = entry
+            .graphql_operation_name
+            .clone()
+            .unwrap_or_else(|| ANONYMOUS.to_string());
+        let response = load_json(
+            &conn,
+            entry.response_body_hash.as_ref(),
+            external_root.as_deref(),
+        )?;
+        let has_graphql_errors = response.as_ref().is_some_and(has_errors);
+        let http_error = entry.status.is_some_and(|s| s >= 400);
+
+        let op = operations
+            .entry((name.clone(), entry.graphql_operation_type.clone()))
+            .or_default();
+        op.requests += 1;
+        if http_error {
+            op.http_errors += 1;
+        }
+        if has_graphql_errors {
+            op.graphql_errors += 1;
+        }
+        if http_error || has_graphql_errors {
+            op.errors += 1;
+        }
+        if let Some(ms) = entry.time_ms.filter(|ms| *ms >= 0.0) {
+            op.times.push(ms);
+        }
+
+        let query = load_query(&conn, entry, external_root.as_deref())?;
+        if let Some(hash) = entry.graphql_query_hash.clone() {
+            op.documents.insert(hash.clone());
+            let doc = documents
+                .entry(hash)
+                .or_insert_with(|| DocumentAccumulator {
+                    operation_type: entry.graphql_operation_type.clone(),
+                    operation_name: name.clone(),
+                    requests: 0,
+                    query: None,
+                });
+            doc.requests += 1;
+            if doc.query.is_none() {
+                doc.query = query.clone();
+            }
+        }
+
+        if let Some(query) = query.as_deref() {
+            let variables = load_variables(&conn, entry, external_root.as_deref())?;
+            let data = response.as_ref().and_then(response_data);
+            schema.observe(
+                query,
+                entry.graphql_operation_name.as_deref(),
+                variables.as_ref(),
+                data,
+            );
+        }
+    }
+
+    let operations: Vec<OperationStats> = operations
+        .into_iter()
+        .map(|((name, operation_type), mut acc)| {
+            acc.times
+                .sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            let latency = (!acc.times.is_empty()).then(|| LatencyStats {
+                mean_ms: acc.times.iter().sum::<f64>() / acc.times.len() as f64,
+                p50_ms: percentile(&acc.times, 50.0),
+                p95_ms: percentile(&acc.times, 95.0),
+                max_ms: acc.times.last().copied().unwrap_or(0.0),
+            });
+            OperationStats {
+                operation_type,
+                operation_name: name,
+                requests: acc.requests,
+                documents: acc.documents.len(),
+                http_errors: acc.http_errors,
+                graphql_errors: acc.graphql_errors,
+                errors: acc.errors,
+                error_rate: acc.errors as f64 / acc.requests as f64,
+                latency,
+            }
+        })
+        .collect();
+
+    let documents: Vec<DocumentSummary> = documents
+        .into_iter()
+        .map(|(hash, doc)| DocumentSummary {
+            hash,
+            operation_type: doc.operation_type,
+            operation_name: doc.operation_name,
+            requests: doc.requests,
+            query: doc.query,
+        })
+        .collect();
+
+    let sdl = schema.render();
+    let report = GraphqlReport {
+        requests: entries.len(),
+        operations,
+        documents,
+        schema: (!sdl.is_empty()).then_some(sdl),
+    };
+
+    let output = options.output.clone().unwrap_or_else(|| PathBuf::from("-"));
+    let mut writer = open_output(&output)?;
+    match options.format {
+        GraphqlFormat::Table => write_table(&mut writer, &report)?,
+        GraphqlFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &report)?;
+            writer.write_all(b"\n")?;
+        }
+        GraphqlFormat::Sdl => {
+            let Some(sdl) = report.schema.as_deref() else {
+                return Err(HarliteError::InvalidArgs(
+                    "No parseable GraphQL queries found; import with --bodies to infer a schema"
+                        .to_string(),
+                ));
+            };
+            writer.write_all(sdl.as_bytes())?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn is_graphql_entry(entry: &EntryRow) -> bool {
+    entry.graphql_operation_type.is_some()
+        || entry.graphql_operation_name.is_some()
+        || entry.graphql_query_hash.is_some()
+}
+
+fn has_errors(response: &Value) -> bool {
+    match response {
+        Value::Array(items) => items.iter().any(has_errors),
+        Value::Object(map) => map
+            .get("errors")
+            .and_then(Value::as_array)
+            .is_some_and(|errors| !errors.is_empty()),
+        _ => false,
+    }
+}
+
+fn response_data(response: &Value) -> Option<&Value> {
+    match response {
+        Value::Array(items) => items.first().and_then(response_data),
+        _ => response.get("data").filter(|d| d.is_object()),
+    }
+}
+
+fn load_blob(
+    conn: &Connection,
+    hash: Option<&String>,
+    external_root: Option<&Path>,
+) -> Result<Option<Vec<u8>>> {
+    let Some(hash) = hash else {
+        return Ok(None);
+    };
+    let Some(blob) = load_blobs_by_hashes(conn, std::slice::from_ref(hash))?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+    let blob = load_external_blob_content(blob, external_root)?;
+    Ok((!blob.content.is_empty()).then_some(blob.content))
+}
+
+fn load_json(
+    conn: &Connection,
+    hash: Option<&String>,
+    external_root: Option<&Path>,
+) -> Result<Option<Value>> {
+    Ok(load_blob(conn, hash, external_root)?.and_then(|b| serde_json::from_slice(&b).ok()))
+}
+
+/// The query document, preferring the normalized copy stored at import time and falling back
+/// to the request body or URL for databases imported before it was recorded.
+fn load_query(
+    conn: &Connection,
+    entry: &EntryRow,
+    external_root: Option<&Path>,
+) -> Result<Option<String>> {
+    if let Some(bytes) = load_blob(conn, entry.graphql_query_hash.as_ref(), external_root)? {
+        return Ok(Some(String::from_utf8_lossy(&bytes).into_owned()));
+    }
+    if let Some(bytes) = load_blob(conn, entry.request_body_hash.as_ref(), external_root)? {
+        let text = String::from_utf8_lossy(&bytes);
+        match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Array(items)) => {
+                if let Some(query) = items
+                    .first()
+                    .and_then(|v| v.get("query"))
+                    .and_then(Value::as_str)
+                {
+                    return Ok(Some(query.to_string()));
+                }
+            }
+            Ok(value) => {
+                if let Some(query) = value.get("query").and_then(Value::as_str) {
+                    return Ok(Some(query.to_string()));
+                }
+            }
+            Err(_) => {
+                if parse_query::<String>(&text).is_ok() {
+                    return Ok(Some(text.into_owned()));
+                }
+            }
+        }
+    }
+    let query = entry
+        .url
+        .as_deref()
+        .and_then(|u| Url::parse(u).ok())
+        .and_then(|u| {
+            u.query_pairs()
+                .find(|(k, _)| k == "query")
+                .map(|(_, v)| v.into_owned())
+        });
+    Ok(query)
+}
+
+fn load_variables(
+    conn: &Connection,
+    entry: &EntryRow,
+    external_root: Option<&Path>,
+) -> Result<Option<Value>> {
+    if entry.graphql_variables_hash.is_some() {
+        return load_json(conn, entry.graphql_variables_hash.as_ref(), external_root);
+    }
+    let body = load_json(conn, entry.request_body_hash.as_ref(), external_root)?;
+    let body = match body {
+        Some(Value::Array(mut items)) if !items.is_empty() => Some(items.swap_remove(0)),
+        other => other,
+    };
+    Ok(body
+        .and_then(|b| b.get("variables").cloned())
+        .filter(|v| !v.is_null()))
+}
+
+/// Field or argument type observed in traffic.
+#[derive(Debug, Clone, PartialEq)]
+enum TypeRef {
+    Unknown,
+    Named(String),
+    List(Box<TypeRef>),
+}
+
+impl TypeRef {
+    fn merge(self, other: TypeRef) -> TypeRef {
+        match (self, other) {
+            (TypeRef::Unknown, other) | (other, TypeRef::Unknown) => other,
+            (TypeRef::List(a), TypeRef::List(b)) => TypeRef::List(Box::new(a.merge(*b))),
+            (TypeRef::List(a), other) | (other, TypeRef::List(a)) => {
+                TypeRef::List(Box::new(a.merge(other)))
+            }
+            (TypeRef::Named(a), TypeRef::Named(b)) => {
+                if a == b {
+                    TypeRef::Named(a)
+                } else if is_number(&a) && is_number(&b) {
+                    TypeRef::Named("Float".to_string())
+                } else if !is_builtin(&a) {
+                    // Object types win over scalars seen for the same field without data.
+                    TypeRef::Named(a)
+                } else if !is_builtin(&b) {
+                    TypeRef::Named(b)
+                } else {
+                    TypeRef::Named("String".to_string())
+                }
+            }
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            TypeRef::Unknown => "String".to_string(),
+            TypeRef::Named(name) => name.clone(),
+            TypeRef::List(inner) => format!("[{}]", inner.render()),
+        }
+    }
+}
+
+fn is_number(name: &str) -> bool {
+    name == "Int" || name == "Float"
+}
+
+fn is_builtin(name: &str) -> bool {
+    BUILTIN_SCALARS.contains(&name)
+}
+
+#[derive(Debug, Default)]
+struct FieldDef {
+    ty: Option<TypeRef>,
+    /// Argument name -> (rendered type, declared by a variable definition).
+    args: BTreeMap<String, (String, bool)>,
+}
+
+#[derive(Debug, Default)]
+struct SchemaBuilder {
+    objects: BTreeMap<String, BTreeMap<String, FieldDef>>,
+    unions: BTreeMap<String, BTreeSet<String>>,
+    inputs: BTreeMap<String, BTreeMap<String, TypeRef>>,
+    enums: BTreeMap<String, BTreeSet<String>>,
+    scalars: BTreeSet<String>,
+}
+
+struct Walk<'d, 'v> {
+    fragments: HashMap<&'d str, (&'d str, &'d SelectionSet<'d, String>)>,
+    variable_defs: &'d [VariableDefinition<'d, String>],
+    variables: Option<&'v Value>,
+}
+
+impl SchemaBuilder {
+    /// Fold one request's selection set (and, when available, its response `data`) into the schema.
+    fn observe(
+        &mut self,
+        query: &str,
+        operation_name: Option<&str>,
+        variables: Option<&Value>,
+        data: Option<&Value>,
+    ) {
+        let Ok(document) = parse_query::<String>(query) else {
+            return;
+        };
+        let Some(operation) = select_operation(&document, operation_name) else {
+            return;
+        };
+        let (root, selection_set, variable_defs): (&str, _, &[VariableDefinition<'_, String>]) =
+            match operation {
+                OperationDefinition::Query(q) => {
+                    ("Query", &q.selection_set, &q.variable_definitions)
+                }
+                OperationDefinition::Mutation(m) => {
+                    ("Mutation", &m.selection_set, &m.variable_definitions)
+                }
+                OperationDefinition::Subscription(s) => {
+                    ("Subscription", &s.selection_set, &s.variable_definitions)
+                }
+                OperationDefinition::SelectionSet(set) => ("Query", set, &[]),
+            };
+
+        let fragments = document
+            .definitions
+            .iter()
+            .filter_map(|def| match def {
+                Definition::Fragment(fragment) => {
+                    let TypeCondition::On(on) = &fragment.type_condition;
+                    Some((
+                        fragment.name.as_str(),
+                        (on.as_str(), &fragment.selection_set),
+                    ))
+                }
+                Definition::Operation(_) => None,
+            })
+            .collect();
+        let walk = Walk {
+            fragments,
+            variable_defs,
+            variables,
+        };
+
+        for def in variable_defs {
+            let value = variables.and_then(|v| v.get(&def.name));
+            self.observe_declared_type(&def.var_type, value);
+        }
+
+        self.walk(&walk, root, selection_set, data, 0, &mut Vec::new());
+    }
+
+    fn walk<'d>(
+        &mut self,
+        ctx: &Walk<'d, '_>,
+        type_name: &str,
+        selection_set: &'d SelectionSet<'d, String>,
+        value: Option<&Value>,
+        depth: usize,
+        active_fragments: &mut Vec<&'d str>,
+    ) {
+        if depth >= MAX_SCHEMA_DEPTH {
+            return;
+        }
+        self.objects.entry(type_name.to_string()).or_default();
+
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => {
+                    if field.name.starts_with("__") {
+                        continue;
+                    }
+                    let key = field.alias.as_ref().unwrap_or(&field.name);
+                    let sub = value.and_then(|v| v.get(key));
+
+                    let mut args = Vec::new();
+                    for (name, arg) in &field.arguments {
+                        args.push((name.clone(), self.argument_type(ctx, name, arg)));
+                    }
+
+                    let ty = if field.selection_set.items.is_empty() {
+                        sub.map(|v| scalar_type(&field.name, v))
+                            .unwrap_or(TypeRef::Unknown)
+                    } else {
+                        let samples: Vec<&Value> = match sub {
+                            Some(Value::Array(items)) => {
+                                items.iter().take(MAX_LIST_SAMPLES).collect()
+                            }
+                            Some(Value::Null) | None => Vec::new(),
+                            Some(other) => vec![other],
+                        };
+                        let typenames: BTreeSet<String> = samples
+                            .iter()
+                            .filter_map(|v| typename(v))
+                            .map(str::to_string)
+                            .collect();
+                        let object = if typenames.len() > 1 {
+                            // Several concrete types behind one field: a union (or interface).
+                            let union = pascal_case(&singularize(&field.name));
+                            self.unions
+                                .entry(union.clone())
+                                .or_default()
+                                .extend(typenames);
+                            union
+                        } else {
+                            object_type_name(&field.name, sub)
+                        };
+                        if samples.is_empty() {
+                            self.walk(
+                                ctx,
+                                &object,
+                                &field.selection_set,
+                                None,
+                                depth + 1,
+                                active_fragments,
+                            );
+                        }
+                        for sample in samples {
+                            let name = typename(sample).unwrap_or(&object).to_string();
+                            self.walk(
+                                ctx,
+                                &name,
+                                &field.selection_set,
+                                Some(sample),
+                                depth + 1,
+                                active_fragments,
+                            );
+                        }
+                        let named = TypeRef::Named(object);
+                        if matches!(sub, Some(Value::Array(_))) {
+                            TypeRef::List(Box::new(named))
+                        } else {
+                            named
+                        }
+                    };
+
+                    let def = self
+                        .objects
+                        .entry(type_name.to_string())
+                        .or_default()
+                        .entry(field.name.clone())
+                        .or_default();
+                    def.ty = Some(match def.ty.take() {
+                        Some(existing) => existing.merge(ty),
+                        None => ty,
+                    });
+                    for (name, (rendered, declared)) in args {
+                        match def.args.get(&name) {
+                            Some((_, true)) => {}
+                            Some(_) if !declared => {}
+                            _ => {
+                                def.args.insert(name, (rendered, declared));
+                            }
+                        }
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let target = match &fragment.type_condition {
+                        Some(TypeCondition::On(on)) => on.as_str(),
+                        None => type_name,
+                    };
+                    let value = value.filter(|v| typename(v).is_none_or(|t| t == target));
+                    self.walk(
+                        ctx,
+                        target,
+                        &fragment.selection_set,
+                        value,
+                        depth,
+                        active_fragments,
+                    );
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name.as_str();
+                    if active_fragments.contains(&name) {
+                        continue;
+                    }
+                    let Some(&(on, fragment)) = ctx.fragments.get(name) else {
+                        continue;
+                    };
+                    let value = value.filter(|v| typename(v).is_none_or(|t| t == on));
+                    active_fragments.push(name);
+                    self.walk(ctx, on, fragment, value, depth, active_fragments);
+                    active_fragments.pop();
+                }
+            }
+        }
+    }
+
+    fn argument_type(
+        &mut self,
+        ctx: &Walk<'_, '_>,
+        name: &str,
+        arg: &GqlValue<'_, String>,
+    ) -> (String, bool) {
+        match arg {
+            GqlValue::Variable(var) => {
+                match ctx.variable_defs.iter().find(|def| &def.name == var) {
+                    Some(def) => (def.var_type.to_string(), true),
+                    None => {
+                        let value = ctx.variables.and_then(|v| v.get(var));
+                        let ty = value
+                            .map(|v| self.input_type(name, v))
+                            .unwrap_or(TypeRef::Unknown);
+                        (ty.render(), false)
+                    }
+                }
+            }
+            GqlValue::Enum(value) => {
+                let enum_name = pascal_case(name);
+                self.enums
+                    .entry(enum_name.clone())
+                    .or_default()
+                    .insert(value.clone());
+                (enum_name, false)
+            }
+            other => {
+                let json = literal_to_json(other, ctx.variables);
+                (self.input_type(name, &json).render(), false)
+            }
+        }
+    }
+
+    /// Record the named type behind a variable definition: builtins need nothing, objects
+    /// become input types, anything else is declared as a custom scalar.
+    fn observe_declared_type(&mut self, ty: &Type<'_, String>, value: Option<&Value>) {
+        match ty {
+            Type::NonNullType(inner) => self.observe_declared_type(inner, value),
+            Type::ListType(inner) => match value {
+                Some(Value::Array(items)) if !items.is_empty() => {
+                    for item in items.iter().take(MAX_LIST_SAMPLES) {
+                        self.observe_declared_type(inner, Some(item));
+                    }
+                }
+                _ => self.observe_declared_type(inner, None),
+            },
+            Type::NamedType(name) => {
+                if is_builtin(name) {
+                    return;
+                }
+                match value {
+                    Some(Value::Object(map)) => {
+                        self.scalars.remove(name);
+                        self.observe_input(name, map);
+                    }
+                    _ => {
+                        if !self.inputs.contains_key(name) && !self.enums.contains_key(name) {
+                            self.scalars.insert(name.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn observe_input(&mut self, name: &str, map: &serde_json::Map<String, Value>) {
+        let mut fields = Vec::new();
+        for (key, value) in map {
+            fields.push((key.clone(), self.input_type(key, value)));
+        }
+        let input = self.inputs.entry(name.to_string()).or_default();
+        for (key, ty) in fields {
+            let merged = match input.remove(&key) {
+                Some(existing) => existing.merge(ty),
+                None => ty,
+            };
+            input.insert(key, merged);
+        }
+    }
+
+    fn input_type(&mut self, name: &str, value: &Value) -> TypeRef {
+        match value {
+            Value::Object(map) => {
+                let input = format!("{}Input", pascal_case(&singularize(name)));
+                self.observe_input(&input, map);
+                TypeRef::Named(input)
+            }
+            Value::Array(items) => {
+                TypeRef::List(Box::new(items.iter().take(MAX_LIST_SAMPLES).fold(
+                    TypeRef::Unknown,
+                    |acc, item| {
+                        let ty = self.input_type(name, item);
+                        acc.merge(ty)
+                    },
+                )))
+            }
+            other => scalar_type(name, other),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let roots = ["Query", "Mutation", "Subscription"];
+        let ordered = roots
+            .iter()
+            .filter_map(|root| self.objects.get_key_value(*root))
+            .chain(
+                self.objects
+                    .iter()
+                    .filter(|(name, _)| !roots.contains(&name.as_str())),
+            )
+            .filter(|(name, _)| !self.unions.contains_key(*name));
+        for (name, fields) in ordered {
+            if fields.is_empty() {
+                out.push_str(&format!("type {name}\n\n"));
+                continue;
+            }
+            out.push_str(&format!("type {name} {{\n"));
+            for (field, def) in fields {
+                let args = if def.args.is_empty() {
+                    String::new()
+                } else {
+                    let args: Vec<String> = def
+                        .args
+                        .iter()
+                        .map(|(arg, (ty, _))| format!("{arg}: {ty}"))
+                        .collect();
+                    format!("({})", args.join(", "))
+                };
+                let ty = def.ty.as_ref().unwrap_or(&TypeRef::Unknown).render();
+                out.push_str(&format!("  {field}{args}: {ty}\n"));
+            }
+            out.push_str("}\n\n");
+        }
+        for (name, members) in &self.unions {
+            let members: Vec<&str> = members.iter().map(String::as_str).collect();
+            out.push_str(&format!("union {name} = {}\n\n", members.join(" | ")));
+        }
+        for (name, fields) in &self.inputs {
+            out.push_str(&format!("input {name} {{\n"));
+            for (field, ty) in fields {
+                out.push_str(&format!("  {field}: {}\n", ty.render()));
+            }
+            out.push_str("}\n\n");
+        }
+        for (name, values) in &self.enums {
+            out.push_str(&format!("enum {name} {{\n"));
+            for value in values {
+                out.push_str(&format!("  {value}\n"));
+            }
+            out.push_str("}\n\n");
+        }
+        for name in &self.scalars {
+            out.push_str(&format!("scalar {name}\n\n"));
+        }
+        let trimmed = out.trim_end();
+        if trimmed.is_empty() {
+            String::new()
+        } else {
+            format!("{trimmed}\n")
+        }
+    }
+}
+
+fn select_operation<'a>(
+    document: &'a Document<'a, String>,
+    operation_name: Option<&str>,
+) -> Option<&'a OperationDefinition<'a, String>> {
+    let operations: Vec<&OperationDefinition<'_, String>> = document
+        .definitions
+        .iter()
+        .filter_map(|def| match def {
+            Definition::Operation(op) => Some(op),
+            Definition::Fragment(_) => None,
+        })
+        .collect();
+    let name_of = |op: &OperationDefinition<'_, String>| match op {
+        OperationDefinition::Query(q) => q.name.clone(),
+        OperationDefinition::Mutation(m) => m.name.clone(),
+        OperationDefinition::Subscription(s) => s.name.clone(),
+        OperationDefinition::SelectionSet(_) => None,
+    };
+    operation_name
+        .and_then(|name| {
+            operations
+                .iter()
+                .find(|op| name_of(op).as_deref() == Some(name))
+        })
+        .or_else(|| operations.first())
+        .copied()
+}
+
+fn typename(value: &Value) -> Option<&str> {
+    value.get("__typename").and_then(Value::as_str)
+}
+
+/// Name for the object type behind a field: the response `__typename` when selected,
+/// otherwise the field name in PascalCase (singular for lists).
+fn object_type_name(field: &str, value: Option<&Value>) -> String {
+    let first = match value {
+        Some(Value::Array(items)) => items.iter().find(|v| v.is_object()),
+        other => other,
+    };
+    if let Some(name) = first.and_then(typename) {
+        return name.to_string();
+    }
+    pascal_case(&singularize(field))
+}
+
+fn scalar_type(field: &str, value: &Value) -> TypeRef {
+    match value {
+        Value::Null | Value::Object(_) => TypeRef::Unknown,
+        Value::Bool(_) => TypeRef::Named("Boolean".to_string()),
+        Value::Number(n) => {
+            if field == "id" {
+                TypeRef::Named("ID".to_string())
+            } else if n.is_i64() || n.is_u64() {
+                TypeRef::Named("Int".to_string())
+            } else {
+                TypeRef::Named("Float".to_string())
+            }
+        }
+        Value::String(_) => {
+            if field == "id" {
+                TypeRef::Named("ID".to_string())
+            } else {
+                TypeRef::Named("String".to_string())
+            }
+        }
+        Value::Array(items) => TypeRef::List(Box::new(
+            items
+                .iter()
+                .take(MAX_LIST_SAMPLES)
+                .fold(TypeRef::Unknown, |acc, item| {
+                    acc.merge(scalar_type(field, item))
+                }),
+        )),
+    }
+}
+
+fn literal_to_json(value: &GqlValue<'_, String>, variables: Option<&Value>) -> Value {
+    match value {
+        GqlValue::Variable(name) => variables
+            .and_then(|v| v.get(name))
+            .cloned()
+            .unwrap_or(Value::Null),
+        GqlValue::Int(n) => n.as_i64().map(Value::from).unwrap_or(Value::Null),
+        GqlValue::Float(f) => serde_json::Number::from_f64(*f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        GqlValue::String(s) | GqlValue::Enum(s) => Value::String(s.clone()),
+        GqlValue::Boolean(b) => Value::Bool(*b),
+        GqlValue::Null => Value::Null,
+        GqlValue::List(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| literal_to_json(item, variables))
+                .collect(),
+        ),
+        GqlValue::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), literal_to_json(v, variables)))
+                .collect(),
+        ),
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut upper = true;
+    for ch in name.chars() {
+        if ch == '_' || ch == '-' || ch == ' ' {
+            upper = true;
+            continue;
+        }
+        if upper {
+            out.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    if out.is_empty() {
+        "Object".to_string()
+    } else {
+        out
+    }
+}
+
+fn singularize(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        if !stem.is_empty() {
+            return format!("{stem}y");
+        }
+    }
+    if name.ends_with("ss") || name.ends_with("us") {
+        return name.to_string();
+    }
+    match name.strip_suffix('s') {
+        Some(stem) if !stem.is_empty() => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+fn write_table(out: &mut dyn Write, report: &GraphqlReport) -> Result<()> {
+    let columns = [
+        "operation",
+        "type",
+        "requests",
+        "documents",
+        "errors",
+        "error rate",
+        "mean ms",
+        "p50 ms",
+        "p95 ms",
+        "max ms",
+    ];
+    let ms = |v: Option<f64>| {
+        v.map(|v| format!("{v:.1}"))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let rows: Vec<[String; 10]> = report
+        .operations
+        .iter()
+        .map(|op| {
+            [
+                op.operation_name.clone(),
+                op.operation_type.clone().unwrap_or_else(|| "-".to_string()),
+                op.requests.to_string(),
+                op.documents.to_string(),
+                op.errors.to_string(),
+                format!("{:.1}%", op.error_rate * 100.0),
+                ms(op.latency.as_ref().map(|l| l.mean_ms)),
+                ms(op.latency.as_ref().map(|l| l.p50_ms)),
+                ms(op.latency.as_ref().map(|l| l.p95_ms)),
+                ms(op.latency.as_ref().map(|l| l.max_ms)),
+            ]
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.len()).collect();
+    for row in &rows {
+        for (i, value) in row.iter().enumerate() {
+            widths[i] = widths[i].max(value.chars().count()).min(60);
+        }
+    }
+    let line = |fields: &[&str]| -> String {
+        fields
+            .iter()
+            .zip(&widths)
+            .map(|(field, width)| {
+                let field: String = field.chars().take(*width).collect();
+                format!("{field:<width$}")
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    writeln!(out, "{}", line(&columns))?;
+    let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    writeln!(out, "{}", sep.join("-+-"))?;
+    for row in &rows {
+        let fields: Vec<&str> = row.iter().map(|s| s.as_str()).collect();
+        writeln!(out, "{}", line(&fields))?;
+    }
+    writeln!(out)?;
+
+    let graphql_errors: usize = report.operations.iter().map(|op| op.graphql_errors).sum();
+    writeln!(
+        out,
+        "{} GraphQL requests, {} unique operations, {} distinct documents, {} responses with GraphQL errors",
+        report.requests,
+        report.operations.len(),
+        report.documents.len(),
+        graphql_errors
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn infers_sdl_from_selection_sets_and_response_data() {
+        let mut schema = SchemaBuilder::default();
+        schema.observe(
+            "query GetUser($id: ID!, $filter: RepoFilter) { user(id: $id) { __typename id name repositories(first: 2, filter: $filter, orderBy: STARS) { name stars } } }",
+            Some("GetUser"),
+            Some(&json!({"id": "u1", "filter": {"language": "rust", "archived": false}})),
+            Some(&json!({
+                "user": {
+                    "__typename": "Account",
+                    "id": "u1",
+                    "name": "Ann",
+                    "repositories": [{"name": "harlite", "stars": 12}, {"name": "x", "stars": 1.5}]
+                }
+            })),
+        );
+        schema.observe(
+            "mutation { rename(name: \"Bea\") { ok } }",
+            None,
+            None,
+            None,
+        );
+
+        let sdl = schema.render();
+        assert!(
+            sdl.starts_with("type Query {\n  user(id: ID!): Account\n}\n"),
+            "{sdl}"
+        );
+        assert!(
+            sdl.contains("type Mutation {\n  rename(name: String): Rename\n}"),
+            "{sdl}"
+        );
+        assert!(sdl.contains("type Account {\n  id: ID\n  name: String\n  repositories(filter: RepoFilter, first: Int, orderBy: OrderBy): [Repository]\n}"), "{sdl}");
+        assert!(
+            sdl.contains("type Repository {\n  name: String\n  stars: Float\n}"),
+            "{sdl}"
+        );
+        assert!(
+            sdl.contains("input RepoFilter {\n  archived: Boolean\n  language: String\n}"),
+            "{sdl}"
+        );
+        assert!(sdl.contains("enum OrderBy {\n  STARS\n}"), "{sdl}");
+        assert!(sdl.contains("type Rename {\n  ok: String\n}"), "{sdl}");
+    }
+
+    #[test]
+    fn follows_fragments_and_type_conditions() {
+        let mut schema = SchemaBuilder::default();
+        schema.observe(
+            "query Search { search { __typename ...UserBits ... on Repo { stars } } } fragment UserBits on User { login }",
+            None,
+            None,
+            Some(&json!({
+                "search": [
+                    {"__typename": "User", "login": "ann"},
+                    {"__typename": "Repo", "stars": 3}
+                ]
+            })),
+        );
+        let sdl = schema.render();
+        assert!(sdl.contains("type User {\n  login: String\n}"), "{sdl}");
+        assert!(sdl.contains("type Repo {\n  stars: Int\n}"), "{sdl}");
+        assert!(sdl.contains("  search: [Search]\n"), "{sdl}");
+        assert!(sdl.contains("union Search = Repo | User"), "{sdl}");
+    }
+
+    #[test]
+    fn detects_errors_in_successful_responses() {
+        assert!(has_errors(
+            &json!({"data": null, "errors": [{"message": "boom"}]})
+        ));
+        assert!(!has_errors(&json!({"data": {"ok": true}, "errors": []})));
+        assert!(has_errors(
+            &json!([{"data": {}}, {"errors": [{"message": "x"}]}])
+        ));
+    }
+}
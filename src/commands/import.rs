@@ -12,14 +12,17 @@ use url::Url;
 
 use crate::db::{
     create_import_with_status, create_schema, entry_content_hash, entry_hash_from_fields,
-    insert_entry_with_hash, insert_page, update_import_metadata, EntryHashFields, EntryRelations,
-    ExtractBodiesKind, ImportStats, InsertEntryOptions,
+    insert_entry_with_hash, insert_page, open_database, update_import_metadata, EntryHashFields,
+    EntryRelations, ExtractBodiesKind, ImportStats, InsertEntryOptions,
 };
 use crate::error::{HarliteError, Result};
 use crate::har::{parse_har_file, parse_har_file_async, Entry, Extensions};
 use crate::plugins::{PluginContext, PluginSet};
 use serde_json::Value;
 
+use super::inline_redact::EntryRedactor;
+use super::{PiiOptions, RedactOptions};
+
 /// Options for importing HAR files.
 #[derive(Clone)]
 pub struct ImportOptions {
@@ -44,6 +47,10 @@ pub struct ImportOptions {
     pub from: Option<String>,
     pub to: Option<String>,
     pub plugins: PluginSet,
+    /// Apply `redact` rules to each entry before it is stored.
+    pub redact: Option<RedactOptions>,
+    /// Replace values found by the `pii` matchers before entries are stored.
+    pub redact_pii: Option<PiiOptions>,
 }
 
 impl Default for ImportOptions {
@@ -70,6 +77,8 @@ impl Default for ImportOptions {
             from: None,
             to: None,
             plugins: PluginSet::default(),
+            redact: None,
+            redact_pii: None,
         }
     }
 }
@@ -133,6 +142,13 @@ impl ImportRunConfig {
     }
 }
 
+/// Per-entry transforms applied before insert: plugins first, then inline redaction.
+#[derive(Clone)]
+struct EntryHooks {
+    plugins: PluginSet,
+    redactor: Option<Arc<EntryRedactor>>,
+}
+
 struct ResumeImport {
     import_id: i64,
     entries_imported: usize,
@@ -164,7 +180,7 @@ pub fn run_import(files: &[PathBuf], options: &ImportOptions) -> Result<ImportSt
         }
     };
 
-    let mut conn = Connection::open(&output_path)?;
+    let mut conn = open_database(&output_path)?;
     setup_connection(&conn)?;
 
     let extract_dir = if let Some(dir) = &options.extract_bodies_dir {
@@ -185,6 +201,11 @@ pub fn run_import(files: &[PathBuf], options: &ImportOptions) -> Result<ImportSt
         extract_bodies_shard_depth: options.extract_bodies_shard_depth,
     };
     let filters = build_import_filters(options)?;
+    let hooks = EntryHooks {
+        plugins: options.plugins.clone(),
+        redactor: EntryRedactor::new(options.redact.as_ref(), options.redact_pii.as_ref())?
+            .map(Arc::new),
+    };
 
     let jobs = resolve_jobs(files.len(), options.jobs);
     let run_config = ImportRunConfig {
@@ -208,7 +229,7 @@ pub fn run_import(files: &[PathBuf], options: &ImportOptions) -> Result<ImportSt
                 &entry_options,
                 &filters,
                 &run_config,
-                &options.plugins,
+                &hooks,
                 &output_path,
             )?;
             stats.add_assign(file_stats);
@@ -222,7 +243,7 @@ pub fn run_import(files: &[PathBuf], options: &ImportOptions) -> Result<ImportSt
             &entry_options,
             &filters,
             &run_config,
-            &options.plugins,
+            &hooks,
             jobs,
         )?
     };
@@ -246,6 +267,11 @@ pub fn run_import(files: &[PathBuf], options: &ImportOptions) -> Result<ImportSt
         );
     }
 
+    if let Some(redactor) = &hooks.redactor {
+        let conn = open_database(&output_path)?;
+        redactor.finish(Some(&conn), "import")?;
+    }
+
     Ok(total_stats)
 }
 
@@ -255,7 +281,7 @@ fn import_single_file(
     options: &InsertEntryOptions,
     filters: &ImportFilters,
     run_config: &ImportRunConfig,
-    plugins: &PluginSet,
+    hooks: &EntryHooks,
     output_path: &Path,
 ) -> Result<ImportStats> {
     let file_name = path
@@ -349,10 +375,10 @@ fn import_single_file(
             continue;
         }
         let owned_entry: Entry;
-        let entry_ref = if plugins.is_empty() {
+        let entry_ref = if hooks.plugins.is_empty() {
             entry
         } else {
-            match plugins.apply_import_entry(entry, &context)? {
+            match hooks.plugins.apply_import_entry(entry, &context)? {
                 Some(next) => {
                     owned_entry = next;
                     &owned_entry
@@ -363,6 +389,16 @@ fn import_single_file(
                 }
             }
         };
+        let redacted_entry: Entry;
+        let entry_ref = match hooks.redactor.as_deref() {
+            Some(redactor) => {
+                let mut next = entry_ref.clone();
+                redactor.redact_entry(&mut next)?;
+                redacted_entry = next;
+                &redacted_entry
+            }
+            None => entry_ref,
+        };
 
         let entry_hash = entry_content_hash(entry_ref);
         if run_config.incremental && entry_hash_exists(&tx, &entry_hash)? {
@@ -389,6 +425,7 @@ fn import_single_file(
             initiator_line: initiator.initiator_line,
             initiator_column: initiator.initiator_column,
             redirect_url: redirect_url.clone(),
+            replay_of_entry_id: None,
         };
 
         let entry_result = insert_entry_with_hash(
@@ -495,7 +532,7 @@ fn import_parallel(
     entry_options: &InsertEntryOptions,
     filters: &ImportFilters,
     run_config: &ImportRunConfig,
-    plugins: &PluginSet,
+    hooks: &EntryHooks,
     jobs: usize,
 ) -> Result<ImportStats> {
     let queue = Arc::new(Mutex::new(files.iter().cloned().collect::<VecDeque<_>>()));
@@ -507,9 +544,9 @@ fn import_parallel(
         let entry_options = entry_options.clone();
         let filters = filters.clone();
         let run_config = *run_config;
-        let plugins = plugins.clone();
+        let hooks = hooks.clone();
         handles.push(thread::spawn(move || -> Result<ImportStats> {
-            let mut conn = Connection::open(&output_path)?;
+            let mut conn = open_database(&output_path)?;
             setup_connection(&conn)?;
             let mut stats = ImportStats::default();
             loop {
@@ -526,7 +563,7 @@ fn import_parallel(
                     &entry_options,
                     &filters,
                     &run_config,
-                    &plugins,
+                    &hooks,
                     &output_path,
                 )?;
                 stats.add_assign(file_stats);
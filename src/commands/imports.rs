@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::OpenFlags;
 
+use crate::db::open_database_with_flags;
 use crate::error::Result;
 
 #[derive(Debug)]
@@ -18,7 +19,7 @@ struct ImportRow {
 
 /// List import metadata for a harlite database.
 pub fn run_imports(database: PathBuf) -> Result<()> {
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         &database,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use rusqlite::Connection;
 
+use crate::db::open_database;
 use crate::commands::util::parse_cert_expiry;
 use crate::error::Result;
 use chrono::{Duration, Utc};
@@ -12,7 +13,7 @@ pub struct InfoOptions {
 
 /// Show summary information for a harlite database.
 pub fn run_info(database: PathBuf, options: &InfoOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
 
     let import_count: i64 = conn.query_row("SELECT COUNT(*) FROM imports", [], |row| row.get(0))?;
 
@@ -0,0 +1,394 @@
+//! Redaction applied to HAR entries before they are written.
+//!
+//! `import`, `watch` and `cdp` run the `redact` rules and/or the `pii` matchers on
+//! each entry before `insert_entry`, so original values never reach blobs, extracted
+//! body files or free pages.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::Connection;
+
+use crate::db::{decompress_body, ensure_schema_upgrades, DEFAULT_MAX_DECOMPRESSED_BYTES};
+use crate::error::Result;
+use crate::har::{Entry, Header};
+
+use super::pii::{build_matchers, PiiMatchers, PiiOptions, PiiRedactionReport};
+use super::pseudonym::Replacement;
+use super::redact::{
+    print_breakdown, record_redaction, RedactOptions, RedactPlan, RedactionReport, RuleSet,
+};
+
+/// Decoded text of a request or response body, rewritten by the redaction stages.
+pub(super) struct BodyText {
+    pub(super) text: String,
+    pub(super) mime_type: Option<String>,
+    changed: bool,
+}
+
+impl BodyText {
+    fn new(text: String, mime_type: Option<String>) -> Self {
+        Self {
+            text,
+            mime_type,
+            changed: false,
+        }
+    }
+
+    pub(super) fn replace(&mut self, text: String) {
+        self.text = text;
+        self.changed = true;
+    }
+}
+
+/// Text bodies of one entry, decoded once and shared by both redaction stages.
+pub(super) struct EntryBodies {
+    pub(super) request: Option<BodyText>,
+    pub(super) response: Option<BodyText>,
+    /// The response text was decompressed using `Content-Encoding`.
+    response_decompressed: bool,
+}
+
+#[derive(Default)]
+struct InlineReport {
+    entries_scanned: u64,
+    entries_changed: u64,
+    redact: RedactionReport,
+    pii: PiiRedactionReport,
+}
+
+/// Applies `redact` rules and `pii` matchers to entries before they are stored.
+pub(super) struct EntryRedactor {
+    redact: Option<RedactPlan>,
+    rule_sets: Mutex<HashMap<Vec<usize>, Arc<RuleSet>>>,
+    pii: Option<(PiiMatchers, Replacement)>,
+    report: Mutex<InlineReport>,
+}
+
+impl EntryRedactor {
+    /// Build a redactor, or `None` when neither stage is enabled.
+    pub(super) fn new(
+        redact: Option<&RedactOptions>,
+        pii: Option<&PiiOptions>,
+    ) -> Result<Option<Self>> {
+        if redact.is_none() && pii.is_none() {
+            return Ok(None);
+        }
+        let redact = redact.map(RedactPlan::new).transpose()?;
+        let pii = match pii {
+            Some(options) => {
+                let matchers = build_matchers(options)?;
+                let replacement = Replacement::new(
+                    &options.token,
+                    options.pseudonymize,
+                    options.pseudonym_key_file.as_deref(),
+                )?;
+                Some((matchers, replacement))
+            }
+            None => None,
+        };
+        Ok(Some(Self {
+            redact,
+            rule_sets: Mutex::new(HashMap::new()),
+            pii,
+            report: Mutex::new(InlineReport::default()),
+        }))
+    }
+
+    /// Redact an entry in place.
+    pub(super) fn redact_entry(&self, entry: &mut Entry) -> Result<()> {
+        let mut bodies = decode_bodies(entry);
+        let mut redact_report = RedactionReport::default();
+        let mut pii_report = PiiRedactionReport::default();
+
+        if let Some(plan) = &self.redact {
+            let host = url::Url::parse(&entry.request.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string));
+            let applicable = plan.applicable_rules(host.as_deref());
+            if !applicable.is_empty() {
+                let rule_set = self.rule_set(plan, applicable)?;
+                rule_set.redact_entry(entry, &mut bodies, &plan.replacement, &mut redact_report);
+            }
+        }
+        if let Some((matchers, replacement)) = &self.pii {
+            matchers.redact_entry(entry, &mut bodies, replacement, &mut pii_report);
+        }
+
+        let changed = redact_report.total() > 0 || pii_report.total() > 0;
+        if changed {
+            encode_bodies(entry, bodies);
+        }
+
+        let mut report = self.report.lock().expect("redaction report");
+        report.entries_scanned += 1;
+        if changed {
+            report.entries_changed += 1;
+        }
+        report.redact.merge(redact_report);
+        report.pii.merge(pii_report);
+        Ok(())
+    }
+
+    fn rule_set(&self, plan: &RedactPlan, applicable: Vec<usize>) -> Result<Arc<RuleSet>> {
+        let mut rule_sets = self.rule_sets.lock().expect("redaction rule sets");
+        if let Some(rule_set) = rule_sets.get(&applicable) {
+            return Ok(Arc::clone(rule_set));
+        }
+        let rule_set = Arc::new(plan.compile(&applicable)?);
+        rule_sets.insert(applicable, Arc::clone(&rule_set));
+        Ok(rule_set)
+    }
+
+    /// Print what was redacted and, given a database, record the run in its
+    /// `redactions` table.
+    pub(super) fn finish(&self, conn: Option<&Connection>, command: &str) -> Result<()> {
+        let mut report = self.report.lock().expect("redaction report");
+        let report = std::mem::take(&mut *report);
+        let total = report.redact.total() + report.pii.total();
+
+        if let Some(conn) = conn {
+            ensure_schema_upgrades(conn)?;
+            record_redaction(
+                conn,
+                command,
+                self.redact.as_ref().and_then(|plan| plan.policy.as_ref()),
+                total,
+            )?;
+        }
+
+        println!(
+            "Redacted {} values across {} of {} entries before storing",
+            total, report.entries_changed, report.entries_scanned
+        );
+        if self.redact.is_some() {
+            print_breakdown(report.redact);
+        }
+        if self.pii.is_some() {
+            println!("PII: {}", report.pii.summary());
+        }
+        Ok(())
+    }
+}
+
+fn decode_bodies(entry: &Entry) -> EntryBodies {
+    let request = entry.request.post_data.as_ref().and_then(|post| {
+        if let Some(text) = &post.text {
+            return Some(BodyText::new(text.clone(), post.mime_type.clone()));
+        }
+        // Params-only form posts are stored as a synthesized urlencoded body.
+        let params = post.params.as_ref().filter(|params| !params.is_empty())?;
+        let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+        for param in params {
+            serializer.append_pair(&param.name, param.value.as_deref().unwrap_or(""));
+        }
+        Some(BodyText::new(
+            serializer.finish(),
+            Some("application/x-www-form-urlencoded".to_string()),
+        ))
+    });
+
+    let mut response_decompressed = false;
+    let content = &entry.response.content;
+    let response = content.text.as_ref().and_then(|text| {
+        let mime_type = content.mime_type.clone();
+        if content.encoding.as_deref() != Some("base64") {
+            return Some(BodyText::new(text.clone(), mime_type));
+        }
+        let bytes = STANDARD.decode(text).ok()?;
+        let bytes = match String::from_utf8(bytes) {
+            Ok(text) => return Some(BodyText::new(text, mime_type)),
+            Err(err) => err.into_bytes(),
+        };
+        let encoding = header_value(&entry.response.headers, "content-encoding")?;
+        let decompressed =
+            decompress_body(&bytes, &encoding, Some(DEFAULT_MAX_DECOMPRESSED_BYTES))?;
+        let text = String::from_utf8(decompressed).ok()?;
+        response_decompressed = true;
+        Some(BodyText::new(text, mime_type))
+    });
+
+    EntryBodies {
+        request,
+        response,
+        response_decompressed,
+    }
+}
+
+fn encode_bodies(entry: &mut Entry, bodies: EntryBodies) {
+    if let (Some(body), Some(post)) = (bodies.request, entry.request.post_data.as_mut()) {
+        if body.changed {
+            if post.text.is_some() {
+                post.text = Some(body.text);
+            } else if let Some(params) = post.params.as_mut() {
+                let values = url::form_urlencoded::parse(body.text.as_bytes());
+                for (param, (_, value)) in params.iter_mut().zip(values) {
+                    if param.value.is_some() || !value.is_empty() {
+                        param.value = Some(value.into_owned());
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(body) = bodies.response {
+        if body.changed {
+            let content = &mut entry.response.content;
+            content.text = Some(body.text);
+            content.encoding = None;
+            if bodies.response_decompressed {
+                entry
+                    .response
+                    .headers
+                    .retain(|h| !h.name.eq_ignore_ascii_case("content-encoding"));
+            }
+        }
+    }
+}
+
+fn header_value(headers: &[Header], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::NameMatchMode;
+    use crate::har::{Content, PostData, PostParam, QueryParam};
+
+    fn entry(url: &str, post_data: Option<PostData>, content: Content) -> Entry {
+        let har = serde_json::json!({
+            "startedDateTime": "2024-01-01T00:00:00.000Z",
+            "time": 1.0,
+            "request": {
+                "method": "POST",
+                "url": url,
+                "httpVersion": "HTTP/1.1",
+                "headers": [{ "name": "Authorization", "value": "Bearer abc" }],
+                "cookies": [{ "name": "sid", "value": "s1" }]
+            },
+            "response": {
+                "status": 200,
+                "statusText": "OK",
+                "httpVersion": "HTTP/1.1",
+                "headers": [],
+                "content": { "size": 0 }
+            }
+        });
+        let mut entry: Entry = serde_json::from_value(har).expect("entry");
+        entry.request.post_data = post_data;
+        entry.response.content = content;
+        entry
+    }
+
+    fn redact_options() -> RedactOptions {
+        RedactOptions {
+            output: None,
+            force: false,
+            dry_run: false,
+            no_defaults: false,
+            headers: Vec::new(),
+            cookies: Vec::new(),
+            query_params: vec!["token".to_string()],
+            body_regexes: Vec::new(),
+            body_paths: Vec::new(),
+            body_fields: vec!["password".to_string()],
+            match_mode: NameMatchMode::Wildcard,
+            token: "REDACTED".to_string(),
+            pseudonymize: false,
+            pseudonym_key_file: None,
+            policy: None,
+            verify: false,
+        }
+    }
+
+    #[test]
+    fn redacts_headers_params_and_bodies_before_insert() {
+        let redactor = EntryRedactor::new(Some(&redact_options()), None)
+            .expect("redactor")
+            .expect("enabled");
+        let post = PostData {
+            mime_type: Some("application/x-www-form-urlencoded".to_string()),
+            text: None,
+            params: Some(vec![
+                PostParam {
+                    name: "user".to_string(),
+                    value: Some("ann".to_string()),
+                    file_name: None,
+                    content_type: None,
+                },
+                PostParam {
+                    name: "password".to_string(),
+                    value: Some("hunter2".to_string()),
+                    file_name: None,
+                    content_type: None,
+                },
+            ]),
+            extensions: Default::default(),
+        };
+        let content = Content {
+            size: 0,
+            compression: None,
+            mime_type: Some("application/json".to_string()),
+            text: Some(STANDARD.encode(r#"{"password":"p","ok":true}"#)),
+            encoding: Some("base64".to_string()),
+            extensions: Default::default(),
+        };
+        let mut entry = entry(
+            "https://example.com/login?token=t1&x=1",
+            Some(post),
+            content,
+        );
+        entry.request.query_string = Some(
+            [("token", "t1"), ("x", "1"), ("token", "t2")]
+                .into_iter()
+                .map(|(name, value)| QueryParam {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        );
+        redactor.redact_entry(&mut entry).expect("redact");
+
+        assert_eq!(
+            entry.request.url,
+            "https://example.com/login?token=REDACTED&x=1"
+        );
+        let query: Vec<&str> = entry
+            .request
+            .query_string
+            .iter()
+            .flatten()
+            .map(|param| param.value.as_str())
+            .collect();
+        assert_eq!(query, ["REDACTED", "1", "REDACTED"]);
+        assert_eq!(entry.request.headers[0].value, "REDACTED");
+        assert_eq!(entry.request.cookies.as_ref().unwrap()[0].value, "REDACTED");
+        let params = entry
+            .request
+            .post_data
+            .as_ref()
+            .unwrap()
+            .params
+            .as_ref()
+            .unwrap();
+        assert_eq!(params[0].value.as_deref(), Some("ann"));
+        assert_eq!(params[1].value.as_deref(), Some("REDACTED"));
+        assert_eq!(entry.response.content.encoding, None);
+        assert_eq!(
+            entry.response.content.text.as_deref(),
+            Some(r#"{"password":"REDACTED","ok":true}"#)
+        );
+
+        let report = redactor.report.lock().unwrap();
+        assert_eq!(report.entries_changed, 1);
+        // The URL's token and the queryString-only second token; the mirrored first
+        // queryString token is not counted again.
+        assert_eq!(report.redact.total(), 6);
+    }
+}
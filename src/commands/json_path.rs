@@ -0,0 +1,251 @@
+use serde_json::Value;
+
+use crate::error::{HarliteError, Result};
+
+/// One step of a concrete location inside a JSON document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Selector {
+    Key(String),
+    AnyKey,
+    Index(usize),
+    AnyIndex,
+    Descendant(String),
+}
+
+/// A small JSONPath subset: `$`, `.key`, `['key']`, `[0]`, `[*]`, `.*` and `..key`.
+#[derive(Clone, Debug)]
+pub struct JsonPath {
+    selectors: Vec<Selector>,
+}
+
+impl JsonPath {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        let rest = raw
+            .strip_prefix('$')
+            .ok_or_else(|| invalid(raw, "must start with '$'"))?;
+        let chars: Vec<char> = rest.chars().collect();
+        let mut selectors = Vec::new();
+        let mut i = 0usize;
+
+        while i < chars.len() {
+            match chars[i] {
+                '.' if chars.get(i + 1) == Some(&'.') => {
+                    i += 2;
+                    let (name, next) = read_name(&chars, i);
+                    if name.is_empty() {
+                        return Err(invalid(raw, "expected a key after '..'"));
+                    }
+                    selectors.push(Selector::Descendant(name));
+                    i = next;
+                }
+                '.' => {
+                    i += 1;
+                    if chars.get(i) == Some(&'*') {
+                        selectors.push(Selector::AnyKey);
+                        i += 1;
+                        continue;
+                    }
+                    let (name, next) = read_name(&chars, i);
+                    if name.is_empty() {
+                        return Err(invalid(raw, "expected a key after '.'"));
+                    }
+                    selectors.push(Selector::Key(name));
+                    i = next;
+                }
+                '[' => {
+                    let end = chars[i..]
+                        .iter()
+                        .position(|c| *c == ']')
+                        .map(|p| p + i)
+                        .ok_or_else(|| invalid(raw, "unclosed '['"))?;
+                    let inner: String = chars[i + 1..end].iter().collect();
+                    let inner = inner.trim();
+                    let selector = if inner == "*" {
+                        Selector::AnyIndex
+                    } else if let Some(quoted) = inner
+                        .strip_prefix('\'')
+                        .and_then(|s| s.strip_suffix('\''))
+                        .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+                    {
+                        Selector::Key(quoted.to_string())
+                    } else {
+                        let index = inner
+                            .parse::<usize>()
+                            .map_err(|_| invalid(raw, "expected an index, '*' or a quoted key"))?;
+                        Selector::Index(index)
+                    };
+                    selectors.push(selector);
+                    i = end + 1;
+                }
+                _ => return Err(invalid(raw, "expected '.', '..' or '['")),
+            }
+        }
+
+        Ok(Self { selectors })
+    }
+
+    /// Whether a concrete document location is selected by this pattern.
+    pub fn matches(&self, path: &[PathSegment]) -> bool {
+        matches_from(&self.selectors, path)
+    }
+
+    /// All values selected by this pattern, in document order.
+    #[cfg_attr(not(feature = "replay"), allow(dead_code))]
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut out = Vec::new();
+        walk(value, &mut |path, node| {
+            if self.matches(path) {
+                out.push(node);
+            }
+        });
+        out
+    }
+}
+
+fn invalid(raw: &str, reason: &str) -> HarliteError {
+    HarliteError::InvalidArgs(format!("Invalid JSON path '{raw}': {reason}"))
+}
+
+fn read_name(chars: &[char], start: usize) -> (String, usize) {
+    let mut end = start;
+    while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+        end += 1;
+    }
+    (chars[start..end].iter().collect(), end)
+}
+
+fn matches_from(selectors: &[Selector], path: &[PathSegment]) -> bool {
+    let Some((first, rest)) = selectors.split_first() else {
+        return path.is_empty();
+    };
+
+    match first {
+        Selector::Descendant(name) => (0..path.len()).any(|skip| {
+            matches!(&path[skip], PathSegment::Key(key) if key == name)
+                && matches_from(rest, &path[skip + 1..])
+        }),
+        _ => {
+            let Some((segment, path_rest)) = path.split_first() else {
+                return false;
+            };
+            let hit = match (first, segment) {
+                (Selector::Key(name), PathSegment::Key(key)) => name == key,
+                (Selector::AnyKey, PathSegment::Key(_)) => true,
+                (Selector::Index(idx), PathSegment::Index(i)) => idx == i,
+                (Selector::AnyIndex, PathSegment::Index(_)) => true,
+                _ => false,
+            };
+            hit && matches_from(rest, path_rest)
+        }
+    }
+}
+
+/// Render a concrete path as `$.a[0].b`.
+pub fn format_path(path: &[PathSegment]) -> String {
+    let mut out = String::from("$");
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                {
+                    out.push('.');
+                    out.push_str(key);
+                } else {
+                    out.push_str(&format!("['{key}']"));
+                }
+            }
+            PathSegment::Index(idx) => out.push_str(&format!("[{idx}]")),
+        }
+    }
+    out
+}
+
+/// Visit every node of a JSON document together with its concrete path.
+pub fn walk<'a>(value: &'a Value, visit: &mut dyn FnMut(&[PathSegment], &'a Value)) {
+    fn inner<'a>(
+        value: &'a Value,
+        path: &mut Vec<PathSegment>,
+        visit: &mut dyn FnMut(&[PathSegment], &'a Value),
+    ) {
+        visit(path, value);
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    path.push(PathSegment::Key(key.clone()));
+                    inner(child, path, visit);
+                    path.pop();
+                }
+            }
+            Value::Array(items) => {
+                for (idx, child) in items.iter().enumerate() {
+                    path.push(PathSegment::Index(idx));
+                    inner(child, path, visit);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut path = Vec::new();
+    inner(value, &mut path, visit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> PathSegment {
+        PathSegment::Key(name.to_string())
+    }
+
+    #[test]
+    fn matches_keys_indexes_wildcards_and_descendants() {
+        let path = vec![key("a"), key("b"), PathSegment::Index(0), key("c")];
+        assert_eq!(format_path(&path), "$.a.b[0].c");
+        assert!(JsonPath::parse("$.a.b[0].c").unwrap().matches(&path));
+        assert!(JsonPath::parse("$.a.b[*].c").unwrap().matches(&path));
+        assert!(JsonPath::parse("$.*.b[0]['c']").unwrap().matches(&path));
+        assert!(JsonPath::parse("$..c").unwrap().matches(&path));
+        assert!(!JsonPath::parse("$.a.b[1].c").unwrap().matches(&path));
+        assert!(!JsonPath::parse("$.a.b").unwrap().matches(&path));
+
+        let quoted = vec![key("a"), key("x y")];
+        assert_eq!(format_path(&quoted), "$.a['x y']");
+        assert!(JsonPath::parse("$.a['x y']").unwrap().matches(&quoted));
+    }
+
+    #[test]
+    fn select_returns_matching_values_in_order() {
+        let doc: Value = serde_json::from_str(
+            r#"{"data": {"items": [{"id": "a"}, {"id": "b"}], "id": "root"}}"#,
+        )
+        .unwrap();
+
+        let ids: Vec<&Value> = JsonPath::parse("$.data.items[*].id").unwrap().select(&doc);
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(JsonPath::parse("$..id").unwrap().select(&doc).len(), 3);
+        assert!(JsonPath::parse("$.missing")
+            .unwrap()
+            .select(&doc)
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_paths() {
+        assert!(JsonPath::parse("a.b").is_err());
+        assert!(JsonPath::parse("$[").is_err());
+        assert!(JsonPath::parse("$.").is_err());
+        assert!(JsonPath::parse("$[x]").is_err());
+    }
+}
@@ -0,0 +1,418 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use crate::db::{ensure_schema_upgrades, open_database, EntryRow};
+use crate::error::{HarliteError, Result};
+
+use super::codegen::{
+    base64, body_of, build_request, js_string, load_request_bodies, Body, CodegenRequest,
+};
+use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::util::{open_output, parse_timestamp};
+
+/// Pauses shorter than this are treated as back-to-back requests.
+const MIN_THINK_TIME_SECS: f64 = 0.05;
+
+const STATIC_EXTENSIONS: [&str; 16] = [
+    "js", "mjs", "css", "map", "png", "jpg", "jpeg", "gif", "svg", "ico", "webp", "avif", "woff",
+    "woff2", "ttf", "otf",
+];
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum LoadTestFormat {
+    K6,
+    Locust,
+}
+
+pub struct LoadTestOptions {
+    pub output: Option<PathBuf>,
+    pub format: LoadTestFormat,
+    pub include_static: bool,
+    pub max_think_time: f64,
+    pub drop_sensitive: bool,
+    pub allow_external_paths: bool,
+    pub external_path_root: Option<PathBuf>,
+    pub filters: EntryFilterOptions,
+}
+
+/// A recorded request plus the pause that preceded it in the capture.
+struct ScriptStep {
+    request: CodegenRequest,
+    think_time: Option<f64>,
+}
+
+pub fn run_export_loadtest(database: PathBuf, options: &LoadTestOptions) -> Result<()> {
+    if options.max_think_time.is_nan() || options.max_think_time < 0.0 {
+        return Err(HarliteError::InvalidArgs(
+            "--max-think-time must be 0 or greater".to_string(),
+        ));
+    }
+
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let entries: Vec<EntryRow> = load_entries_with_filters(&conn, &options.filters)?
+        .into_iter()
+        .filter(|entry| options.include_static || !is_static_asset(entry))
+        .collect();
+
+    let external_root = if options.allow_external_paths {
+        let root = options
+            .external_path_root
+            .clone()
+            .or_else(|| database.parent().map(|p| p.to_path_buf()))
+            .ok_or_else(|| {
+                HarliteError::InvalidArgs(
+                    "Cannot resolve external path root; pass --external-path-root".to_string(),
+                )
+            })?;
+        Some(root.canonicalize()?)
+    } else {
+        None
+    };
+    let bodies = load_request_bodies(&conn, &entries, external_root.as_deref())?;
+
+    let mut steps: Vec<ScriptStep> = Vec::with_capacity(entries.len());
+    let mut previous: Option<&EntryRow> = None;
+    for entry in &entries {
+        let Some(request) = build_request(entry, &bodies, options.drop_sensitive) else {
+            continue;
+        };
+        let think_time = previous
+            .and_then(|prev| think_time_between(prev, entry))
+            .map(|secs| secs.min(options.max_think_time))
+            .filter(|secs| *secs >= MIN_THINK_TIME_SECS);
+        steps.push(ScriptStep {
+            request,
+            think_time,
+        });
+        previous = Some(entry);
+    }
+
+    let script = match options.format {
+        LoadTestFormat::K6 => render_k6(&steps),
+        LoadTestFormat::Locust => render_locust(&steps),
+    };
+
+    let output_path = match &options.output {
+        Some(p) => p.clone(),
+        None => {
+            let stem = database
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("loadtest");
+            match options.format {
+                LoadTestFormat::K6 => PathBuf::from(format!("{stem}-k6.js")),
+                LoadTestFormat::Locust => PathBuf::from(format!("{stem}-locustfile.py")),
+            }
+        }
+    };
+    let mut writer = open_output(&output_path)?;
+    writer.write_all(script.as_bytes())?;
+    writer.flush()?;
+
+    if output_path != Path::new("-") {
+        println!(
+            "Exported load test with {} request(s) to {}",
+            steps.len(),
+            output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn is_static_asset(entry: &EntryRow) -> bool {
+    let mime = entry
+        .response_mime_type
+        .as_deref()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if mime.starts_with("image/")
+        || mime.starts_with("font/")
+        || mime.contains("css")
+        || mime.contains("javascript")
+    {
+        return true;
+    }
+
+    let Some(url) = entry.url.as_deref().and_then(|u| Url::parse(u).ok()) else {
+        return false;
+    };
+    let last = url.path().rsplit('/').next().unwrap_or_default();
+    match last.rsplit_once('.') {
+        Some((_, ext)) => STATIC_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()),
+        None => false,
+    }
+}
+
+/// Idle time between the end of `prev` and the start of `next`, in seconds.
+fn think_time_between(prev: &EntryRow, next: &EntryRow) -> Option<f64> {
+    let prev_start = parse_timestamp(prev.started_at.as_deref()?)?;
+    let next_start = parse_timestamp(next.started_at.as_deref()?)?;
+    let gap_ms = (next_start - prev_start).num_milliseconds() as f64 - prev.time_ms.unwrap_or(0.0);
+    Some((gap_ms / 1000.0).max(0.0))
+}
+
+fn stat_name(request: &CodegenRequest) -> String {
+    let path = Url::parse(&request.url)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| request.url.clone());
+    format!("{} {}", request.method, path)
+}
+
+fn render_k6(steps: &[ScriptStep]) -> String {
+    let needs_encoding = steps
+        .iter()
+        .any(|s| matches!(body_of(&s.request), Some(Body::Binary(_))));
+
+    let mut out = String::new();
+    out.push_str("import http from \"k6/http\";\n");
+    out.push_str("import { check, sleep } from \"k6\";\n");
+    if needs_encoding {
+        out.push_str("import encoding from \"k6/encoding\";\n");
+    }
+    out.push_str("\nexport const options = {\n  vus: 1,\n  iterations: 1,\n};\n\n");
+    out.push_str("export default function () {\n  let res;\n");
+
+    for step in steps {
+        let request = &step.request;
+        if let Some(secs) = step.think_time {
+            out.push_str(&format!("  sleep({secs:.3});\n"));
+        }
+        out.push_str(&format!(
+            "\n  // {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        let body = match body_of(request) {
+            Some(Body::Text(text)) => js_string(text),
+            Some(Body::Binary(bytes)) => {
+                format!("encoding.b64decode({}, \"std\")", js_string(&base64(bytes)))
+            }
+            None => "null".to_string(),
+        };
+        out.push_str(&format!(
+            "  res = http.request({}, {}, {}, {{\n",
+            js_string(&request.method),
+            js_string(&request.url),
+            body
+        ));
+        if !request.headers.is_empty() {
+            out.push_str("    headers: {\n");
+            for (name, value) in &request.headers {
+                out.push_str(&format!(
+                    "      {}: {},\n",
+                    js_string(name),
+                    js_string(value)
+                ));
+            }
+            out.push_str("    },\n");
+        }
+        out.push_str(&format!(
+            "    tags: {{ name: {} }},\n",
+            js_string(&stat_name(request))
+        ));
+        out.push_str("  });\n");
+        out.push_str("  check(res, { \"status is not 5xx\": (r) => r.status < 500 });\n");
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn render_locust(steps: &[ScriptStep]) -> String {
+    let needs_base64 = steps
+        .iter()
+        .any(|s| matches!(body_of(&s.request), Some(Body::Binary(_))));
+    let host = steps
+        .first()
+        .and_then(|s| Url::parse(&s.request.url).ok())
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_else(|| "http://localhost".to_string());
+
+    let mut out = String::new();
+    if needs_base64 {
+        out.push_str("import base64\n");
+    }
+    out.push_str("import time\n\n");
+    out.push_str("from locust import HttpUser, task\n\n\n");
+    out.push_str("class RecordedUser(HttpUser):\n");
+    out.push_str(&format!("    host = {}\n\n", js_string(&host)));
+    out.push_str("    @task\n");
+    out.push_str("    def recorded_flow(self):\n");
+    if steps.is_empty() {
+        out.push_str("        pass\n");
+    }
+
+    for step in steps {
+        let request = &step.request;
+        if let Some(secs) = step.think_time {
+            out.push_str(&format!("        time.sleep({secs:.3})\n"));
+        }
+        out.push_str(&format!(
+            "\n        # {} {} {}\n",
+            request.id, request.method, request.url
+        ));
+        out.push_str("        self.client.request(\n");
+        out.push_str(&format!("            {},\n", js_string(&request.method)));
+        out.push_str(&format!("            {},\n", js_string(&request.url)));
+        out.push_str(&format!(
+            "            name={},\n",
+            js_string(&stat_name(request))
+        ));
+        if !request.headers.is_empty() {
+            out.push_str("            headers={\n");
+            for (name, value) in &request.headers {
+                out.push_str(&format!(
+                    "                {}: {},\n",
+                    js_string(name),
+                    js_string(value)
+                ));
+            }
+            out.push_str("            },\n");
+        }
+        match body_of(request) {
+            Some(Body::Text(text)) => out.push_str(&format!(
+                "            data={}.encode(\"utf-8\"),\n",
+                js_string(text)
+            )),
+            Some(Body::Binary(bytes)) => out.push_str(&format!(
+                "            data=base64.b64decode({}),\n",
+                js_string(&base64(bytes))
+            )),
+            None => {}
+        }
+        out.push_str("        )\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, started_at: &str, time_ms: f64, mime: &str) -> EntryRow {
+        EntryRow {
+            url: Some(url.to_string()),
+            started_at: Some(started_at.to_string()),
+            time_ms: Some(time_ms),
+            response_mime_type: Some(mime.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn step(method: &str, url: &str, body: Option<&[u8]>, think_time: Option<f64>) -> ScriptStep {
+        ScriptStep {
+            request: CodegenRequest {
+                id: 1,
+                method: method.to_string(),
+                url: url.to_string(),
+                headers: vec![("accept".to_string(), "application/json".to_string())],
+                body: body.map(|b| b.to_vec()),
+            },
+            think_time,
+        }
+    }
+
+    #[test]
+    fn think_time_is_idle_gap_after_previous_response() {
+        let first = entry(
+            "https://a.test/",
+            "2024-01-01T00:00:00.000Z",
+            250.0,
+            "text/html",
+        );
+        let second = entry(
+            "https://a.test/x",
+            "2024-01-01T00:00:01.500Z",
+            10.0,
+            "text/html",
+        );
+        let overlapping = entry(
+            "https://a.test/y",
+            "2024-01-01T00:00:01.505Z",
+            1.0,
+            "text/html",
+        );
+        assert_eq!(think_time_between(&first, &second), Some(1.25));
+        assert_eq!(think_time_between(&second, &overlapping), Some(0.0));
+    }
+
+    #[test]
+    fn static_assets_are_detected_by_mime_and_extension() {
+        let ts = "2024-01-01T00:00:00.000Z";
+        assert!(is_static_asset(&entry(
+            "https://a.test/app.js?v=1",
+            ts,
+            1.0,
+            ""
+        )));
+        assert!(is_static_asset(&entry(
+            "https://a.test/logo",
+            ts,
+            1.0,
+            "image/png"
+        )));
+        assert!(is_static_asset(&entry(
+            "https://a.test/f.WOFF2",
+            ts,
+            1.0,
+            ""
+        )));
+        assert!(!is_static_asset(&entry(
+            "https://a.test/api/items",
+            ts,
+            1.0,
+            "application/json"
+        )));
+        assert!(!is_static_asset(&entry(
+            "https://a.test/v1.2/items",
+            ts,
+            1.0,
+            ""
+        )));
+    }
+
+    #[test]
+    fn k6_script_keeps_order_sleeps_and_bodies() {
+        let steps = vec![
+            step("GET", "https://a.test/items?page=1", None, None),
+            step(
+                "POST",
+                "https://a.test/items",
+                Some(br#"{"name":"x"}"#),
+                Some(1.25),
+            ),
+            step("PUT", "https://a.test/blob", Some(&[0xff, 0x00]), None),
+        ];
+        let script = render_k6(&steps);
+        assert!(script.contains("import encoding from \"k6/encoding\";"));
+        let get = script
+            .find("http.request(\"GET\", \"https://a.test/items?page=1\", null")
+            .unwrap();
+        let sleep = script.find("sleep(1.250);").unwrap();
+        let post = script
+            .find(r#"http.request("POST", "https://a.test/items", "{\"name\":\"x\"}""#)
+            .unwrap();
+        assert!(get < sleep && sleep < post);
+        assert!(script.contains("encoding.b64decode(\"/wA=\", \"std\")"));
+        assert!(script.contains("tags: { name: \"GET /items\" },"));
+        assert!(script.contains("\"accept\": \"application/json\","));
+    }
+
+    #[test]
+    fn locust_script_uses_first_origin_as_host() {
+        let steps = vec![
+            step("GET", "https://a.test:8443/items", None, None),
+            step("POST", "https://a.test:8443/items", Some(b"x=1"), Some(0.5)),
+        ];
+        let script = render_locust(&steps);
+        assert!(script.contains("    host = \"https://a.test:8443\"\n"));
+        assert!(script.contains("        time.sleep(0.500)\n"));
+        assert!(script.contains("            name=\"POST /items\",\n"));
+        assert!(script.contains("            data=\"x=1\".encode(\"utf-8\"),\n"));
+        assert!(!script.contains("import base64"));
+    }
+}
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 
 use rusqlite::{params, Connection, OptionalExtension};
 
-use crate::db::create_schema;
+use crate::db::{create_schema, open_database};
 use crate::error::{HarliteError, Result};
 
 #[derive(Clone, Copy, Debug, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
@@ -115,6 +115,8 @@ struct EntryRow {
     graphql_operation_type: Option<String>,
     graphql_operation_name: Option<String>,
     graphql_top_level_fields: Option<String>,
+    graphql_query_hash: Option<String>,
+    graphql_variables_hash: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -207,6 +209,8 @@ const ENTRY_COLUMNS: &[&str] = &[
     "graphql_operation_type",
     "graphql_operation_name",
     "graphql_top_level_fields",
+    "graphql_query_hash",
+    "graphql_variables_hash",
 ];
 
 pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()> {
@@ -227,7 +231,7 @@ pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()>
     let output_conn = if options.dry_run {
         Connection::open_in_memory()?
     } else {
-        Connection::open(&output_path)?
+        open_database(&output_path)?
     };
     create_schema(&output_conn)?;
 
@@ -242,7 +246,7 @@ pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()>
     let input_count = databases.len();
 
     for db_path in databases {
-        let input_conn = Connection::open(&db_path)?;
+        let input_conn = open_database(&db_path)?;
         let input_columns = table_columns(&input_conn, "entries")?;
 
         merge_blobs(&input_conn, &tx, &mut stats)?;
@@ -309,7 +313,8 @@ pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()>
             }
         }
 
-        let graphql_fields = load_graphql_fields(&input_conn)?;
+        let graphql_fields = load_graphql_fields(&input_conn, "graphql_fields", "field")?;
+        let graphql_paths = load_graphql_fields(&input_conn, "graphql_field_paths", "path")?;
 
         let mut stmt = input_conn.prepare(&entry_select_sql(&input_columns))?;
         let rows = stmt.query_map([], |row| {
@@ -371,6 +376,8 @@ pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()>
                     graphql_operation_type: row.get(53)?,
                     graphql_operation_name: row.get(54)?,
                     graphql_top_level_fields: row.get(55)?,
+                    graphql_query_hash: row.get(56)?,
+                    graphql_variables_hash: row.get(57)?,
                 },
             ))
         })?;
@@ -393,7 +400,22 @@ pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()>
                 update_tls_fields(&tx, existing_entry_id, &entry)?;
                 update_graphql_fields(&tx, existing_entry_id, &entry)?;
                 if let Some(fields) = graphql_fields.get(&entry_id) {
-                    insert_graphql_fields(&tx, existing_entry_id, fields)?;
+                    insert_graphql_fields(
+                        &tx,
+                        existing_entry_id,
+                        "graphql_fields",
+                        "field",
+                        fields,
+                    )?;
+                }
+                if let Some(paths) = graphql_paths.get(&entry_id) {
+                    insert_graphql_fields(
+                        &tx,
+                        existing_entry_id,
+                        "graphql_field_paths",
+                        "path",
+                        paths,
+                    )?;
                 }
                 update_chain_fields(&tx, existing_entry_id, &entry)?;
                 stats.entries_deduped += 1;
@@ -403,7 +425,10 @@ pub fn run_merge(databases: Vec<PathBuf>, options: &MergeOptions) -> Result<()>
             let new_entry_id = insert_entry(&tx, mapped_import_id, &entry)?;
             keys.insert(key, new_entry_id);
             if let Some(fields) = graphql_fields.get(&entry_id) {
-                insert_graphql_fields(&tx, new_entry_id, fields)?;
+                insert_graphql_fields(&tx, new_entry_id, "graphql_fields", "field", fields)?;
+            }
+            if let Some(paths) = graphql_paths.get(&entry_id) {
+                insert_graphql_fields(&tx, new_entry_id, "graphql_field_paths", "path", paths)?;
             }
             stats.entries_added += 1;
         }
@@ -642,6 +667,8 @@ fn load_entry_keys_for_import(
                 graphql_operation_type: row.get(53)?,
                 graphql_operation_name: row.get(54)?,
                 graphql_top_level_fields: row.get(55)?,
+                graphql_query_hash: row.get(56)?,
+                graphql_variables_hash: row.get(57)?,
             },
         ))
     })?;
@@ -794,12 +821,16 @@ fn update_graphql_fields(conn: &Connection, entry_id: i64, entry: &EntryRow) ->
         "UPDATE entries SET
             graphql_operation_type = COALESCE(graphql_operation_type, ?1),
             graphql_operation_name = COALESCE(graphql_operation_name, ?2),
-            graphql_top_level_fields = COALESCE(graphql_top_level_fields, ?3)
-        WHERE id = ?4",
+            graphql_top_level_fields = COALESCE(graphql_top_level_fields, ?3),
+            graphql_query_hash = COALESCE(graphql_query_hash, ?4),
+            graphql_variables_hash = COALESCE(graphql_variables_hash, ?5)
+        WHERE id = ?6",
         params![
             entry.graphql_operation_type.as_deref(),
             entry.graphql_operation_name.as_deref(),
             entry.graphql_top_level_fields.as_deref(),
+            entry.graphql_query_hash.as_deref(),
+            entry.graphql_variables_hash.as_deref(),
             entry_id,
         ],
     )?;
@@ -817,14 +848,15 @@ fn insert_entry(conn: &Connection, import_id: i64, entry: &EntryRow) -> Result<i
             is_redirect, server_ip, connection_id, request_id, parent_request_id, initiator_type, initiator_url, initiator_line, initiator_column, redirect_url,
             tls_version, tls_cipher_suite, tls_cert_subject, tls_cert_issuer, tls_cert_expiry, entry_hash,
             entry_extensions, request_extensions, response_extensions, content_extensions, timings_extensions, post_data_extensions,
-            graphql_operation_type, graphql_operation_name, graphql_top_level_fields
+            graphql_operation_type, graphql_operation_name, graphql_top_level_fields, graphql_query_hash,
+            graphql_variables_hash
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
             ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
             ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
             ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40,
             ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50,
-            ?51, ?52, ?53, ?54, ?55
+            ?51, ?52, ?53, ?54, ?55, ?56, ?57
         )",
         params![
             import_id,
@@ -882,6 +914,8 @@ fn insert_entry(conn: &Connection, import_id: i64, entry: &EntryRow) -> Result<i
             entry.graphql_operation_type.as_deref(),
             entry.graphql_operation_name.as_deref(),
             entry.graphql_top_level_fields.as_deref(),
+            entry.graphql_query_hash.as_deref(),
+            entry.graphql_variables_hash.as_deref(),
         ],
     )?;
     Ok(conn.last_insert_rowid())
@@ -994,12 +1028,17 @@ fn load_existing_fts_hashes(conn: &Connection) -> Result<HashSet<String>> {
     Ok(out)
 }
 
-fn load_graphql_fields(conn: &Connection) -> Result<HashMap<i64, Vec<String>>> {
-    if !table_exists(conn, "graphql_fields")? {
+/// Load a per-entry GraphQL side table (`graphql_fields` or `graphql_field_paths`).
+fn load_graphql_fields(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+) -> Result<HashMap<i64, Vec<String>>> {
+    if !table_exists(conn, table)? {
         return Ok(HashMap::new());
     }
 
-    let mut stmt = conn.prepare("SELECT entry_id, field FROM graphql_fields")?;
+    let mut stmt = conn.prepare(&format!("SELECT entry_id, {column} FROM {table}"))?;
     let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
 
     let mut out: HashMap<i64, Vec<String>> = HashMap::new();
@@ -1010,14 +1049,20 @@ fn load_graphql_fields(conn: &Connection) -> Result<HashMap<i64, Vec<String>>> {
     Ok(out)
 }
 
-fn insert_graphql_fields(conn: &Connection, entry_id: i64, fields: &[String]) -> Result<()> {
-    if fields.is_empty() || !table_exists(conn, "graphql_fields")? {
+fn insert_graphql_fields(
+    conn: &Connection,
+    entry_id: i64,
+    table: &str,
+    column: &str,
+    fields: &[String],
+) -> Result<()> {
+    if fields.is_empty() || !table_exists(conn, table)? {
         return Ok(());
     }
 
-    let mut stmt = conn.prepare_cached(
-        "INSERT OR IGNORE INTO graphql_fields (entry_id, field) VALUES (?1, ?2)",
-    )?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "INSERT OR IGNORE INTO {table} (entry_id, {column}) VALUES (?1, ?2)"
+    ))?;
     let mut seen = HashSet::new();
     for field in fields {
         if !seen.insert(field) {
@@ -1,30 +1,51 @@
 mod diff;
 mod analyze;
+#[cfg(feature = "replay")]
+mod body_diff;
+mod body_fields;
+mod codegen;
+mod collection;
+mod conform;
 mod entry_filter;
 mod export;
 mod export_data;
 mod openapi;
 mod fts;
+#[cfg(feature = "graphql")]
+mod graphql;
 mod import;
+mod inline_redact;
 mod imports;
 mod info;
+mod loadtest;
+mod json_path;
 mod merge;
 #[cfg(feature = "otel")]
 mod otel;
+#[cfg(feature = "otel")]
+mod otel_metrics;
 mod report;
 mod prune;
 mod query;
 mod pii;
+mod pseudonym;
 mod redact;
+mod redact_policy;
 #[cfg(feature = "repl")]
 mod repl;
 #[cfg(feature = "replay")]
 mod replay;
+#[cfg(feature = "replay")]
+mod replay_load;
+#[cfg(feature = "replay")]
+mod replay_session;
 #[cfg(feature = "serve")]
 mod serve;
 mod schema;
+mod scrub;
 mod search;
 mod stats;
+mod types;
 pub mod util;
 #[cfg(feature = "watch")]
 mod watch;
@@ -32,16 +53,22 @@ mod waterfall;
 
 pub use diff::{run_diff, DiffOptions};
 pub use analyze::{run_analyze, AnalyzeOptions};
+pub use codegen::{run_codegen, CodegenFormat, CodegenOptions};
+pub use collection::{run_export_collection, CollectionFormat, CollectionOptions};
+pub use conform::{run_conform, ConformFormat, ConformOptions};
 pub use entry_filter::EntryFilterOptions;
 pub use export::{run_export, ExportOptions};
 pub use export_data::{run_export_data, DataExportFormat, ExportDataOptions};
 pub use fts::{run_fts_rebuild, FtsTokenizer};
+#[cfg(feature = "graphql")]
+pub use graphql::{run_graphql, GraphqlFormat, GraphqlOptions};
 pub use import::{run_import, ImportOptions};
 pub use imports::run_imports;
 pub use info::{run_info, InfoOptions};
+pub use loadtest::{run_export_loadtest, LoadTestFormat, LoadTestOptions};
 pub use merge::{run_merge, DedupStrategy, MergeOptions};
 #[cfg(feature = "otel")]
-pub use otel::{run_otel, OtelExportFormat, OtelExportOptions};
+pub use otel::{run_otel, OtelExportFormat, OtelExportOptions, OtelSignal};
 pub use report::{run_report, ReportOptions};
 pub use prune::run_prune;
 pub use query::{run_query, OutputFormat, QueryOptions};
@@ -57,6 +84,7 @@ pub use schema::run_schema;
 pub use search::run_search;
 pub use openapi::{run_openapi, OpenApiOptions};
 pub use stats::{run_stats, StatsOptions};
+pub use types::{run_types, TypesFormat, TypesOptions};
 pub use waterfall::{run_waterfall, WaterfallFormat, WaterfallGroupBy, WaterfallOptions};
 #[cfg(feature = "cdp")]
 mod cdp;
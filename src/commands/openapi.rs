@@ -1,16 +1,50 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, NaiveDate};
 use rusqlite::Connection;
 use url::Url;
 
-use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, BlobRow, EntryRow};
+use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, open_database, EntryRow};
 use crate::error::{HarliteError, Result};
 use crate::size;
 
 use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::redact::is_credential_name;
+use super::util::{load_external_blob_content, open_output};
+
+/// Most distinct string values a field may take and still be reported as an `enum`.
+const MAX_ENUM_VALUES: usize = 8;
+/// Longer strings are never treated as enum candidates.
+const MAX_ENUM_VALUE_LEN: usize = 40;
+
+/// Request headers that describe the client or transport rather than the API itself.
+const SKIPPED_HEADERS: &[&str] = &[
+    "accept",
+    "accept-encoding",
+    "accept-language",
+    "authorization",
+    "cache-control",
+    "connection",
+    "content-length",
+    "content-type",
+    "cookie",
+    "dnt",
+    "host",
+    "if-modified-since",
+    "if-none-match",
+    "keep-alive",
+    "origin",
+    "pragma",
+    "priority",
+    "proxy-connection",
+    "range",
+    "referer",
+    "te",
+    "upgrade-insecure-requests",
+    "user-agent",
+];
 
 pub struct OpenApiOptions {
     pub output: Option<PathBuf>,
@@ -18,13 +52,17 @@ pub struct OpenApiOptions {
     pub version: Option<String>,
     pub sample_bodies: Option<usize>,
     pub sample_body_max_size: Option<String>,
+    /// Keep literal URL paths instead of templating ID-like segments.
+    pub literal_paths: bool,
+    /// Distinct values at one path position that turn it into a parameter (0 disables).
+    pub path_param_threshold: usize,
     pub allow_external_paths: bool,
     pub external_path_root: Option<PathBuf>,
     pub filters: EntryFilterOptions,
 }
 
 pub fn run_openapi(database: PathBuf, options: &OpenApiOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     ensure_schema_upgrades(&conn)?;
 
     let entries = load_entries_with_filters(&conn, &options.filters)?;
@@ -61,20 +99,33 @@ pub fn run_openapi(database: PathBuf, options: &OpenApiOptions) -> Result<()> {
         None => None,
     };
 
+    let parsed: Vec<(&EntryRow, Url)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let url = Url::parse(entry.url.as_deref()?).ok()?;
+            Some((entry, url))
+        })
+        .collect();
+
+    let threshold = if options.literal_paths {
+        0
+    } else {
+        options.path_param_threshold
+    };
+    let templater = PathTemplater::new(
+        parsed.iter().map(|(_, url)| path_segments(url)),
+        threshold,
+        !options.literal_paths,
+    );
+
     let mut servers: BTreeSet<String> = BTreeSet::new();
     let mut paths: BTreeMap<String, BTreeMap<String, OperationData>> = BTreeMap::new();
 
-    for entry in &entries {
-        let Some(url) = entry.url.as_deref() else {
-            continue;
-        };
-        let Ok(parsed) = Url::parse(url) else {
-            continue;
-        };
-        let path = parsed.path().to_string();
-        let path = if path.is_empty() { "/".to_string() } else { path };
+    for (entry, parsed) in &parsed {
+        let entry = *entry;
+        let templated = templater.template(&path_segments(parsed));
 
-        if let Some(server) = server_from_url(&parsed) {
+        if let Some(server) = server_from_url(parsed) {
             servers.insert(server);
         }
 
@@ -84,13 +135,32 @@ pub fn run_openapi(database: PathBuf, options: &OpenApiOptions) -> Result<()> {
             .unwrap_or("GET")
             .to_ascii_lowercase();
 
-        let method_map = paths.entry(path).or_default();
+        let method_map = paths.entry(templated.path).or_default();
         let op = method_map.entry(method).or_default();
+        op.requests += 1;
 
-        for (name, _) in parsed.query_pairs() {
-            if !name.is_empty() {
-                op.query_params.insert(name.to_string());
+        for (name, value) in templated.params {
+            op.path_params.entry(name).or_default().observe(&value);
+        }
+
+        let mut seen_query: BTreeSet<String> = BTreeSet::new();
+        for (name, value) in parsed.query_pairs() {
+            if name.is_empty() {
+                continue;
+            }
+            let param = op.query_params.entry(name.to_string()).or_default();
+            if seen_query.insert(name.to_string()) {
+                param.observe(&value);
+            } else {
+                param.observe_repeat(&value);
+            }
+        }
+
+        for (name, value) in headers_map(entry.request_headers.as_deref()) {
+            if is_skipped_header(&name) {
+                continue;
             }
+            op.header_params.entry(name).or_default().observe(&value);
         }
 
         if let Some(content_type) = request_content_type(entry) {
@@ -154,13 +224,6 @@ pub fn run_openapi(database: PathBuf, options: &OpenApiOptions) -> Result<()> {
     Ok(())
 }
 
-fn open_output(path: &Path) -> Result<Box<dyn Write>> {
-    if path == Path::new("-") {
-        return Ok(Box::new(io::stdout().lock()));
-    }
-    Ok(Box::new(BufWriter::new(File::create(path)?)))
-}
-
 fn server_from_url(url: &Url) -> Option<String> {
     let scheme = url.scheme();
     let host = url.host_str()?;
@@ -199,12 +262,12 @@ fn normalize_mime(value: &str) -> Option<String> {
     }
 }
 
-fn request_content_type(entry: &EntryRow) -> Option<String> {
+pub(super) fn request_content_type(entry: &EntryRow) -> Option<String> {
     let headers = headers_map(entry.request_headers.as_deref());
     headers.get("content-type").and_then(|s| normalize_mime(s))
 }
 
-fn response_content_type(entry: &EntryRow) -> Option<String> {
+pub(super) fn response_content_type(entry: &EntryRow) -> Option<String> {
     if let Some(mime) = entry.response_mime_type.as_deref() {
         return normalize_mime(mime);
     }
@@ -212,11 +275,11 @@ fn response_content_type(entry: &EntryRow) -> Option<String> {
     headers.get("content-type").and_then(|s| normalize_mime(s))
 }
 
-fn is_json_mime(value: &str) -> bool {
+pub(super) fn is_json_mime(value: &str) -> bool {
     value.to_ascii_lowercase().contains("json")
 }
 
-fn should_sample_body(size: Option<i64>, max_size: Option<i64>) -> bool {
+pub(super) fn should_sample_body(size: Option<i64>, max_size: Option<i64>) -> bool {
     match (size, max_size) {
         (Some(size), Some(limit)) => size > 0 && size <= limit,
         (Some(size), None) => size > 0,
@@ -225,7 +288,7 @@ fn should_sample_body(size: Option<i64>, max_size: Option<i64>) -> bool {
     }
 }
 
-fn load_json_schema(
+pub(super) fn load_json_schema(
     conn: &Connection,
     hash: &str,
     external_root: Option<&Path>,
@@ -250,43 +313,195 @@ fn load_json_schema(
     Ok(Some(infer_schema(&value)))
 }
 
-fn load_external_blob_content(mut blob: BlobRow, external_root: Option<&Path>) -> Result<BlobRow> {
-    if !blob.content.is_empty() || blob.size <= 0 {
-        return Ok(blob);
+fn is_skipped_header(name: &str) -> bool {
+    name.starts_with(':') || name.starts_with("sec-") || SKIPPED_HEADERS.contains(&name)
+}
+
+/// Path segments after the leading slash; `/` yields a single empty segment.
+pub(super) fn path_segments(url: &Url) -> Vec<String> {
+    let path = url.path();
+    let path = if path.is_empty() { "/" } else { path };
+    path.split('/').skip(1).map(|s| s.to_string()).collect()
+}
+
+fn is_id_segment(segment: &str) -> bool {
+    if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()) {
+        return true;
     }
-    let Some(path) = &blob.external_path else {
-        return Ok(blob);
-    };
-    let Some(root) = external_root else {
-        return Ok(blob);
-    };
+    let hex: String = segment.chars().filter(|c| *c != '-').collect();
+    let is_hex = !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit());
+    is_uuid(segment) || (hex.len() >= 16 && is_hex && hex.chars().any(|c| c.is_ascii_digit()))
+}
 
-    let candidate = PathBuf::from(path);
-    let candidate = if candidate.is_absolute() {
-        candidate
-    } else {
-        root.join(candidate)
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(g, len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+pub(super) struct TemplatedPath {
+    pub(super) path: String,
+    pub(super) params: Vec<(String, String)>,
+}
+
+/// Maps concrete URL paths onto OpenAPI path templates.
+///
+/// ID-like segments (numbers, UUIDs, long hex strings) always become parameters. With a
+/// non-zero threshold, a position whose literal values vary that many times under the same
+/// parent becomes a parameter as well.
+pub(super) struct PathTemplater {
+    ids: bool,
+    dynamic: BTreeSet<String>,
+}
+
+impl PathTemplater {
+    pub(super) fn new(
+        paths: impl Iterator<Item = Vec<String>>,
+        threshold: usize,
+        ids: bool,
+    ) -> Self {
+        let mut templater = Self {
+            ids,
+            dynamic: BTreeSet::new(),
+        };
+        if threshold == 0 {
+            return templater;
+        }
+
+        let paths: BTreeSet<Vec<String>> = paths.collect();
+        let depth = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+        for pos in 0..depth {
+            let mut children: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+            for path in paths.iter().filter(|p| p.len() > pos) {
+                let segment = path[pos].as_str();
+                if segment.is_empty() || (ids && is_id_segment(segment)) {
+                    continue;
+                }
+                let (_, key) = templater.shape(&path[..pos]);
+                children.entry(key).or_default().insert(segment);
+            }
+            for (key, values) in children {
+                if values.len() >= threshold {
+                    templater.dynamic.insert(key);
+                }
+            }
+        }
+        templater
+    }
+
+    /// Which segments are parameters, plus the shape of the path with those blanked out.
+    fn shape(&self, segments: &[String]) -> (Vec<bool>, String) {
+        let mut key = String::new();
+        let mut params = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let param = !segment.is_empty()
+                && ((self.ids && is_id_segment(segment)) || self.dynamic.contains(&key));
+            key.push('/');
+            key.push_str(if param { "{}" } else { segment });
+            params.push(param);
+        }
+        (params, key)
+    }
+
+    pub(super) fn template(&self, segments: &[String]) -> TemplatedPath {
+        let (flags, _) = self.shape(segments);
+        let mut path = String::new();
+        let mut params: Vec<(String, String)> = Vec::new();
+        let mut previous: Option<&str> = None;
+        for (segment, is_param) in segments.iter().zip(flags) {
+            path.push('/');
+            if !is_param {
+                path.push_str(segment);
+                previous = Some(segment.as_str()).filter(|s| !s.is_empty());
+                continue;
+            }
+            let base = param_name(previous);
+            let mut name = base.clone();
+            let mut n = 2;
+            while params.iter().any(|(existing, _)| *existing == name) {
+                name = format!("{base}{n}");
+                n += 1;
+            }
+            path.push_str(&format!("{{{name}}}"));
+            params.push((name, segment.clone()));
+            previous = None;
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        TemplatedPath { path, params }
+    }
+}
+
+/// `users` -> `userId`, `order-items` -> `orderItemId`; `id` when there is no parent segment.
+fn param_name(previous: Option<&str>) -> String {
+    let Some(previous) = previous else {
+        return "id".to_string();
     };
-    let resolved = match candidate.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return Ok(blob),
+    let singular = if let Some(stem) = previous.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if previous.len() > 3 && previous.ends_with('s') && !previous.ends_with("ss") {
+        previous[..previous.len() - 1].to_string()
+    } else {
+        previous.to_string()
     };
-    if !resolved.starts_with(root) {
-        return Ok(blob);
+    let mut name = String::new();
+    let mut upper = false;
+    for c in singular.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = !name.is_empty();
+            continue;
+        }
+        if upper {
+            name.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return "id".to_string();
     }
-    blob.content = std::fs::read(resolved)?;
-    Ok(blob)
+    name.push_str("Id");
+    name
 }
 
 #[derive(Default)]
 struct OperationData {
-    query_params: BTreeSet<String>,
+    requests: usize,
+    path_params: BTreeMap<String, ParamData>,
+    query_params: BTreeMap<String, ParamData>,
+    header_params: BTreeMap<String, ParamData>,
     request_content_types: BTreeSet<String>,
     request_schema: Option<Schema>,
     request_samples: usize,
     responses: BTreeMap<String, ResponseData>,
 }
 
+/// Observed values of one parameter across the requests of an operation.
+#[derive(Default)]
+struct ParamData {
+    present: usize,
+    repeated: bool,
+    schema: Option<Schema>,
+}
+
+impl ParamData {
+    fn observe(&mut self, value: &str) {
+        self.present += 1;
+        self.schema = Some(merge_param_schema(self.schema.take(), scalar_schema(value)));
+    }
+
+    /// Another value for a parameter already seen in the same request.
+    fn observe_repeat(&mut self, value: &str) {
+        self.repeated = true;
+        self.schema = Some(merge_param_schema(self.schema.take(), scalar_schema(value)));
+    }
+}
+
 #[derive(Default)]
 struct ResponseData {
     content_types: BTreeSet<String>,
@@ -321,7 +536,9 @@ struct PathItem {
 }
 
 #[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 struct Operation {
+    operation_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     parameters: Option<Vec<Parameter>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -357,21 +574,38 @@ struct MediaType {
     schema: Option<Schema>,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, Default, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
-struct Schema {
+pub(super) struct Schema {
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
-    schema_type: Option<String>,
+    pub(super) schema_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) format: Option<String>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub(super) enum_values: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    properties: Option<BTreeMap<String, Schema>>,
+    pub(super) properties: Option<BTreeMap<String, Schema>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    items: Option<Box<Schema>>,
+    pub(super) required: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    additional_properties: Option<bool>,
+    pub(super) items: Option<Box<Schema>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    nullable: Option<bool>,
+    pub(super) additional_properties: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    one_of: Option<Vec<Schema>>,
+    pub(super) nullable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) one_of: Option<Vec<Schema>>,
+    /// Number of values merged into this schema; drives `required` detection.
+    #[serde(skip)]
+    pub(super) samples: usize,
+    /// Distinct string values seen, until there are too many to be an enum.
+    #[serde(skip)]
+    pub(super) observed: BTreeSet<String>,
+    /// Number of non-null values behind `observed`.
+    #[serde(skip)]
+    pub(super) observed_samples: usize,
+    #[serde(skip)]
+    pub(super) observed_overflow: bool,
 }
 
 impl OpenApiSpec {
@@ -391,42 +625,43 @@ impl OpenApiSpec {
             Some(servers.into_iter().map(|url| Server { url }).collect())
         };
 
+        let mut operation_ids: BTreeSet<String> = BTreeSet::new();
         let mut out_paths: BTreeMap<String, PathItem> = BTreeMap::new();
         for (path, ops) in paths {
             let mut operations: BTreeMap<String, Operation> = BTreeMap::new();
             for (method, data) in ops {
-                let parameters = if data.query_params.is_empty() {
+                let requests = data.requests;
+                let mut parameters: Vec<Parameter> = Vec::new();
+                for (name, param) in data.path_params {
+                    parameters.push(param.into_parameter(name, "path", true, false));
+                }
+                // Recorded credentials (`?api_key=`, `x-session-id`) must not become enums.
+                for (name, param) in data.query_params {
+                    let required = param.present >= requests;
+                    let allow_enum = !is_credential_name(&name);
+                    parameters.push(param.into_parameter(name, "query", required, allow_enum));
+                }
+                for (name, param) in data.header_params {
+                    let required = param.present >= requests;
+                    let allow_enum = !is_credential_name(&name);
+                    parameters.push(param.into_parameter(name, "header", required, allow_enum));
+                }
+                let parameters = if parameters.is_empty() {
                     None
                 } else {
-                    Some(
-                        data.query_params
-                            .into_iter()
-                            .map(|name| Parameter {
-                                name,
-                                location: "query".to_string(),
-                                required: false,
-                                schema: Schema {
-                                    schema_type: Some("string".to_string()),
-                                    properties: None,
-                                    items: None,
-                                    additional_properties: None,
-                                    nullable: None,
-                                    one_of: None,
-                                },
-                            })
-                            .collect(),
-                    )
+                    Some(parameters)
                 };
 
                 let request_body = if data.request_content_types.is_empty() {
                     None
                 } else {
+                    let schema = data.request_schema.map(finalize_schema);
                     let mut content: BTreeMap<String, MediaType> = BTreeMap::new();
                     for mime in data.request_content_types {
                         content.insert(
                             mime,
                             MediaType {
-                                schema: data.request_schema.clone(),
+                                schema: schema.clone(),
                             },
                         );
                     }
@@ -440,12 +675,13 @@ impl OpenApiSpec {
                     let content = if resp.content_types.is_empty() {
                         None
                     } else {
+                        let schema = resp.schema.map(finalize_schema);
                         let mut content: BTreeMap<String, MediaType> = BTreeMap::new();
                         for mime in resp.content_types {
                             content.insert(
                                 mime,
                                 MediaType {
-                                    schema: resp.schema.clone(),
+                                    schema: schema.clone(),
                                 },
                             );
                         }
@@ -454,7 +690,7 @@ impl OpenApiSpec {
                     responses.insert(
                         status.clone(),
                         Response {
-                            description: format!("Status {status}"),
+                            description: status_description(&status),
                             content,
                         },
                     );
@@ -470,9 +706,18 @@ impl OpenApiSpec {
                     );
                 }
 
+                let base_id = operation_id(&method, &path);
+                let mut id = base_id.clone();
+                let mut n = 2;
+                while !operation_ids.insert(id.clone()) {
+                    id = format!("{base_id}{n}");
+                    n += 1;
+                }
+
                 operations.insert(
                     method,
                     Operation {
+                        operation_id: id,
                         parameters,
                         request_body,
                         responses,
@@ -491,49 +736,243 @@ impl OpenApiSpec {
     }
 }
 
-fn infer_schema(value: &serde_json::Value) -> Schema {
+impl ParamData {
+    fn into_parameter(
+        self,
+        name: String,
+        location: &str,
+        required: bool,
+        allow_enum: bool,
+    ) -> Parameter {
+        let mut schema = self.schema.unwrap_or_else(|| Schema {
+            schema_type: Some("string".to_string()),
+            ..Schema::default()
+        });
+        if !allow_enum {
+            schema.observed_overflow = true;
+        }
+        let schema = finalize_schema(schema);
+        let schema = if self.repeated {
+            Schema {
+                schema_type: Some("array".to_string()),
+                items: Some(Box::new(schema)),
+                ..Schema::default()
+            }
+        } else {
+            schema
+        };
+        Parameter {
+            name,
+            location: location.to_string(),
+            required,
+            schema,
+        }
+    }
+}
+
+/// `get` + `/users/{userId}/posts` -> `getUsersByUserIdPosts`.
+pub(super) fn operation_id(method: &str, path: &str) -> String {
+    let mut id = method.to_ascii_lowercase();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let (prefix, word) = match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(param) => ("By", param),
+            None => ("", segment),
+        };
+        id.push_str(prefix);
+        let mut upper = true;
+        for c in word.chars() {
+            if !c.is_ascii_alphanumeric() {
+                upper = true;
+                continue;
+            }
+            if upper {
+                id.push(c.to_ascii_uppercase());
+                upper = false;
+            } else {
+                id.push(c);
+            }
+        }
+    }
+    id
+}
+
+fn status_description(status: &str) -> String {
+    let reason = match status {
+        "200" => "OK",
+        "201" => "Created",
+        "202" => "Accepted",
+        "204" => "No Content",
+        "301" => "Moved Permanently",
+        "302" => "Found",
+        "304" => "Not Modified",
+        "307" => "Temporary Redirect",
+        "308" => "Permanent Redirect",
+        "400" => "Bad Request",
+        "401" => "Unauthorized",
+        "403" => "Forbidden",
+        "404" => "Not Found",
+        "405" => "Method Not Allowed",
+        "409" => "Conflict",
+        "410" => "Gone",
+        "415" => "Unsupported Media Type",
+        "422" => "Unprocessable Entity",
+        "429" => "Too Many Requests",
+        "500" => "Internal Server Error",
+        "502" => "Bad Gateway",
+        "503" => "Service Unavailable",
+        "504" => "Gateway Timeout",
+        "default" => return "Default response".to_string(),
+        _ => return format!("Status {status}"),
+    };
+    reason.to_string()
+}
+
+/// Recognised string formats, most specific first.
+fn string_format(value: &str) -> Option<&'static str> {
+    if is_uuid(value) {
+        return Some("uuid");
+    }
+    if DateTime::parse_from_rfc3339(value).is_ok() {
+        return Some("date-time");
+    }
+    if value.len() == 10 && NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+        return Some("date");
+    }
+    if is_email(value) {
+        return Some("email");
+    }
+    None
+}
+
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !value.chars().any(|c| c.is_whitespace())
+}
+
+fn string_schema(value: &str) -> Schema {
+    let mut schema = Schema {
+        schema_type: Some("string".to_string()),
+        format: string_format(value).map(|f| f.to_string()),
+        samples: 1,
+        observed_samples: 1,
+        ..Schema::default()
+    };
+    if value.len() <= MAX_ENUM_VALUE_LEN {
+        schema.observed.insert(value.to_string());
+    } else {
+        schema.observed_overflow = true;
+    }
+    schema
+}
+
+/// Schema for a query, header or path value, which arrives as text.
+fn scalar_schema(value: &str) -> Schema {
+    let schema_type = if value.parse::<i64>().is_ok() {
+        "integer"
+    } else if value.parse::<f64>().map(|v| v.is_finite()).unwrap_or(false) {
+        "number"
+    } else if value == "true" || value == "false" {
+        "boolean"
+    } else {
+        return string_schema(value);
+    };
+    let mut schema = string_schema(value);
+    schema.schema_type = Some(schema_type.to_string());
+    schema.format = None;
+    schema
+}
+
+/// Merge parameter schemas, widening to `string` when the observed types disagree.
+fn merge_param_schema(current: Option<Schema>, next: Schema) -> Schema {
+    let Some(current) = current else {
+        return next;
+    };
+    let numeric = |s: &Schema| matches!(s.schema_type.as_deref(), Some("integer" | "number"));
+    if current.schema_type == next.schema_type || (numeric(&current) && numeric(&next)) {
+        return merge_schema(current, next);
+    }
+    let as_string = |mut s: Schema| {
+        s.schema_type = Some("string".to_string());
+        s.format = None;
+        s
+    };
+    merge_schema(as_string(current), as_string(next))
+}
+
+/// Derive `required` and `enum` from the sample counts gathered while merging.
+pub(super) fn finalize_schema(mut schema: Schema) -> Schema {
+    if let Some(properties) = schema.properties.take() {
+        let required: Vec<String> = properties
+            .iter()
+            .filter(|(_, prop)| schema.samples > 0 && prop.samples >= schema.samples)
+            .map(|(name, _)| name.clone())
+            .collect();
+        if !required.is_empty() {
+            schema.required = Some(required);
+        }
+        schema.properties = Some(
+            properties
+                .into_iter()
+                .map(|(name, mut prop)| {
+                    if is_credential_name(&name) {
+                        prop.observed_overflow = true;
+                    }
+                    (name, finalize_schema(prop))
+                })
+                .collect(),
+        );
+    }
+    if let Some(items) = schema.items.take() {
+        schema.items = Some(Box::new(finalize_schema(*items)));
+    }
+    if let Some(variants) = schema.one_of.take() {
+        schema.one_of = Some(variants.into_iter().map(finalize_schema).collect());
+    }
+    let distinct = schema.observed.len();
+    if schema.schema_type.as_deref() == Some("string")
+        && schema.format.is_none()
+        && !schema.observed_overflow
+        && distinct > 0
+        && distinct <= MAX_ENUM_VALUES
+        && schema.observed_samples >= distinct * 2
+    {
+        schema.enum_values = Some(schema.observed.iter().cloned().collect());
+    }
+    schema
+}
+
+pub(super) fn infer_schema(value: &serde_json::Value) -> Schema {
     match value {
         serde_json::Value::Null => Schema {
-            schema_type: None,
-            properties: None,
-            items: None,
-            additional_properties: None,
             nullable: Some(true),
-            one_of: None,
+            samples: 1,
+            ..Schema::default()
         },
         serde_json::Value::Bool(_) => Schema {
             schema_type: Some("boolean".to_string()),
-            properties: None,
-            items: None,
-            additional_properties: None,
-            nullable: None,
-            one_of: None,
+            samples: 1,
+            ..Schema::default()
         },
         serde_json::Value::Number(num) => Schema {
             schema_type: Some(if num.is_i64() { "integer" } else { "number" }.to_string()),
-            properties: None,
-            items: None,
-            additional_properties: None,
-            nullable: None,
-            one_of: None,
-        },
-        serde_json::Value::String(_) => Schema {
-            schema_type: Some("string".to_string()),
-            properties: None,
-            items: None,
-            additional_properties: None,
-            nullable: None,
-            one_of: None,
+            samples: 1,
+            ..Schema::default()
         },
+        serde_json::Value::String(s) => string_schema(s),
         serde_json::Value::Array(items) => {
             let merged = items.iter().map(infer_schema).reduce(merge_schema);
             Schema {
                 schema_type: Some("array".to_string()),
-                properties: None,
                 items: merged.map(Box::new),
-                additional_properties: None,
-                nullable: None,
-                one_of: None,
+                samples: 1,
+                ..Schema::default()
             }
         }
         serde_json::Value::Object(map) => {
@@ -544,35 +983,47 @@ fn infer_schema(value: &serde_json::Value) -> Schema {
             Schema {
                 schema_type: Some("object".to_string()),
                 properties: Some(properties),
-                items: None,
                 additional_properties: Some(true),
-                nullable: None,
-                one_of: None,
+                samples: 1,
+                ..Schema::default()
             }
         }
     }
 }
 
-fn merge_schema_option(current: Option<Schema>, next: Schema) -> Option<Schema> {
+pub(super) fn merge_schema_option(current: Option<Schema>, next: Schema) -> Option<Schema> {
     Some(match current {
         Some(existing) => merge_schema(existing, next),
         None => next,
     })
 }
 
-fn merge_schema(a: Schema, b: Schema) -> Schema {
-    if a.schema_type.is_none() && a.nullable == Some(true) {
+pub(super) fn merge_schema(a: Schema, b: Schema) -> Schema {
+    let samples = a.samples + b.samples;
+    if a.schema_type.is_none() && a.one_of.is_none() && a.nullable == Some(true) {
         let mut out = b;
         out.nullable = Some(true);
+        out.samples = samples;
         return out;
     }
-    if b.schema_type.is_none() && b.nullable == Some(true) {
+    if b.schema_type.is_none() && b.one_of.is_none() && b.nullable == Some(true) {
         let mut out = a;
         out.nullable = Some(true);
+        out.samples = samples;
         return out;
     }
 
-    if a.schema_type == b.schema_type {
+    let numeric = |s: &Schema| matches!(s.schema_type.as_deref(), Some("integer" | "number"));
+    if a.schema_type != b.schema_type && numeric(&a) && numeric(&b) {
+        return Schema {
+            schema_type: Some("number".to_string()),
+            nullable: a.nullable.or(b.nullable),
+            samples,
+            ..Schema::default()
+        };
+    }
+
+    if a.schema_type.is_some() && a.schema_type == b.schema_type {
         return match a.schema_type.as_deref() {
             Some("object") => {
                 let mut properties = a.properties.unwrap_or_default();
@@ -588,42 +1039,215 @@ fn merge_schema(a: Schema, b: Schema) -> Schema {
                 Schema {
                     schema_type: Some("object".to_string()),
                     properties: Some(properties),
-                    items: None,
                     additional_properties: Some(true),
                     nullable: a.nullable.or(b.nullable),
-                    one_of: None,
+                    samples,
+                    ..Schema::default()
                 }
             }
             Some("array") => Schema {
                 schema_type: Some("array".to_string()),
-                properties: None,
                 items: match (a.items, b.items) {
-                    (Some(a_items), Some(b_items)) => Some(Box::new(merge_schema(*a_items, *b_items))),
+                    (Some(a_items), Some(b_items)) => {
+                        Some(Box::new(merge_schema(*a_items, *b_items)))
+                    }
                     (Some(items), None) => Some(items),
                     (None, Some(items)) => Some(items),
                     (None, None) => None,
                 },
-                additional_properties: None,
                 nullable: a.nullable.or(b.nullable),
-                one_of: None,
-            },
-            _ => Schema {
-                schema_type: a.schema_type,
-                properties: None,
-                items: None,
-                additional_properties: None,
-                nullable: a.nullable.or(b.nullable),
-                one_of: None,
+                samples,
+                ..Schema::default()
             },
+            _ => {
+                let mut observed = a.observed;
+                observed.extend(b.observed);
+                let observed_overflow =
+                    a.observed_overflow || b.observed_overflow || observed.len() > MAX_ENUM_VALUES;
+                if observed_overflow {
+                    observed.clear();
+                }
+                Schema {
+                    schema_type: a.schema_type,
+                    format: if a.format == b.format { a.format } else { None },
+                    nullable: a.nullable.or(b.nullable),
+                    samples,
+                    observed,
+                    observed_samples: a.observed_samples + b.observed_samples,
+                    observed_overflow,
+                    ..Schema::default()
+                }
+            }
         };
     }
 
+    // Fold the new shape into an existing `oneOf` rather than nesting another level.
+    let nullable = a.nullable.or(b.nullable);
+    let mut variants = into_variants(a);
+    for next in into_variants(b) {
+        match variants
+            .iter()
+            .position(|v| v.schema_type == next.schema_type)
+        {
+            Some(idx) => {
+                let existing = variants.remove(idx);
+                variants.insert(idx, merge_schema(existing, next));
+            }
+            None => variants.push(next),
+        }
+    }
     Schema {
-        schema_type: None,
-        properties: None,
-        items: None,
-        additional_properties: None,
-        nullable: None,
-        one_of: Some(vec![a, b]),
+        one_of: Some(variants),
+        nullable,
+        samples,
+        ..Schema::default()
+    }
+}
+
+fn into_variants(mut schema: Schema) -> Vec<Schema> {
+    match schema.one_of.take() {
+        Some(variants) => variants,
+        None => vec![schema],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(path: &str) -> Vec<String> {
+        path.split('/').skip(1).map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn templater_collapses_ids_and_high_cardinality_segments() {
+        let mut paths: Vec<Vec<String>> = vec![
+            segments("/users/123"),
+            segments("/users/456/orders/3f2b6c1e-8a4d-4f5e-9b7a-1c2d3e4f5a6b"),
+            segments("/users/me"),
+        ];
+        for name in ["alpha", "beta", "gamma"] {
+            paths.push(segments(&format!("/repos/{name}/issues")));
+        }
+        let templater = PathTemplater::new(paths.into_iter(), 3, true);
+
+        let templated = templater.template(&segments(
+            "/users/456/orders/3f2b6c1e-8a4d-4f5e-9b7a-1c2d3e4f5a6b",
+        ));
+        assert_eq!(templated.path, "/users/{userId}/orders/{orderId}");
+        assert_eq!(
+            templated.params[0],
+            ("userId".to_string(), "456".to_string())
+        );
+        assert_eq!(templater.template(&segments("/users/me")).path, "/users/me");
+        assert_eq!(
+            templater.template(&segments("/repos/beta/issues")).path,
+            "/repos/{repoId}/issues"
+        );
+        assert_eq!(templater.template(&segments("/")).path, "/");
+
+        let literal = PathTemplater::new(std::iter::empty(), 0, false);
+        assert_eq!(literal.template(&segments("/users/123")).path, "/users/123");
+    }
+
+    #[test]
+    fn finalize_marks_required_fields_formats_and_enums() {
+        let bodies = [
+            serde_json::json!({"id": "3f2b6c1e-8a4d-4f5e-9b7a-1c2d3e4f5a6b", "status": "active", "email": "a@example.com", "created": "2024-01-01T00:00:00Z", "note": "x"}),
+            serde_json::json!({"id": "4f2b6c1e-8a4d-4f5e-9b7a-1c2d3e4f5a6b", "status": "active", "email": "b@example.com", "created": "2024-01-02T00:00:00Z"}),
+            serde_json::json!({"id": "5f2b6c1e-8a4d-4f5e-9b7a-1c2d3e4f5a6b", "status": "disabled", "email": null, "created": "2024-01-03"}),
+            serde_json::json!({"id": "6f2b6c1e-8a4d-4f5e-9b7a-1c2d3e4f5a6b", "status": "active", "email": "c@example.com", "created": "2024-01-04T00:00:00Z"}),
+        ];
+        let schema = bodies
+            .iter()
+            .map(infer_schema)
+            .reduce(merge_schema)
+            .map(finalize_schema)
+            .unwrap();
+
+        assert_eq!(
+            schema.required.as_deref(),
+            Some(
+                &[
+                    "created".to_string(),
+                    "email".to_string(),
+                    "id".to_string(),
+                    "status".to_string()
+                ][..]
+            )
+        );
+        let props = schema.properties.as_ref().unwrap();
+        assert_eq!(props["id"].format.as_deref(), Some("uuid"));
+        assert_eq!(props["email"].format.as_deref(), Some("email"));
+        assert_eq!(props["email"].nullable, Some(true));
+        assert_eq!(props["created"].format, None);
+        assert_eq!(
+            props["status"].enum_values,
+            Some(vec!["active".to_string(), "disabled".to_string()])
+        );
+        assert_eq!(props["note"].enum_values, None);
+    }
+
+    #[test]
+    fn credential_like_names_never_become_enums() {
+        let body = serde_json::json!({"session_token": "s3cr3t", "mode": "fast"});
+        let schema = [&body, &body, &body, &body]
+            .into_iter()
+            .map(infer_schema)
+            .reduce(merge_schema)
+            .map(finalize_schema)
+            .unwrap();
+        let props = schema.properties.as_ref().unwrap();
+        assert_eq!(props["session_token"].enum_values, None);
+        assert_eq!(props["mode"].enum_values, Some(vec!["fast".to_string()]));
+
+        let param = |value: &str| {
+            let mut data = ParamData::default();
+            for _ in 0..4 {
+                data.observe(value);
+            }
+            data
+        };
+        for name in ["api_key", "session", "x-session-id", "access_token"] {
+            assert!(is_credential_name(name), "{name}");
+        }
+        assert!(!is_credential_name("page"));
+        let parameter = param("s3cr3t").into_parameter("api_key".into(), "query", true, false);
+        assert_eq!(parameter.schema.enum_values, None);
+        let parameter = param("asc").into_parameter("order".into(), "query", true, true);
+        assert_eq!(parameter.schema.enum_values, Some(vec!["asc".to_string()]));
+    }
+
+    #[test]
+    fn parameters_are_typed_from_observed_values() {
+        let mut page = ParamData::default();
+        for value in ["1", "2", "10"] {
+            page.observe(value);
+        }
+        let param = page.into_parameter("page".to_string(), "query", true, true);
+        assert_eq!(param.schema.schema_type.as_deref(), Some("integer"));
+
+        let mut mixed = ParamData::default();
+        mixed.observe("1");
+        mixed.observe("abc");
+        let param = mixed.into_parameter("q".to_string(), "query", false, true);
+        assert_eq!(param.schema.schema_type.as_deref(), Some("string"));
+
+        let mut since = ParamData::default();
+        since.observe("2024-01-01");
+        since.observe("2024-02-01");
+        let param = since.into_parameter("since".to_string(), "query", false, true);
+        assert_eq!(param.schema.format.as_deref(), Some("date"));
+
+        let mut tags = ParamData::default();
+        tags.observe("a");
+        tags.observe_repeat("b");
+        let param = tags.into_parameter("tag".to_string(), "query", false, true);
+        assert_eq!(param.schema.schema_type.as_deref(), Some("array"));
+
+        assert_eq!(
+            operation_id("get", "/users/{userId}/order-items"),
+            "getUsersByUserIdOrderItems"
+        );
     }
 }
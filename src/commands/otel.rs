@@ -1,18 +1,18 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
 
 use chrono::{DateTime, Utc};
 use clap::ValueEnum;
-use rusqlite::Connection;
 use serde::Serialize;
 use url::Url;
 
-use crate::db::{ensure_schema_upgrades, EntryRow};
+use crate::db::{ensure_schema_upgrades, load_pages_for_imports, open_database, EntryRow};
 use crate::error::{HarliteError, Result};
 
 use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::otel_metrics::export_metrics;
+use super::util::open_output;
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 #[clap(rename_all = "kebab-case")]
@@ -20,39 +20,84 @@ pub enum OtelExportFormat {
     Json,
     OtlpHttp,
     OtlpGrpc,
+    /// Prometheus text exposition format (metrics only)
+    Prometheus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum OtelSignal {
+    Traces,
+    Metrics,
 }
 
 pub struct OtelExportOptions {
     pub format: OtelExportFormat,
+    pub signal: OtelSignal,
     pub output: Option<PathBuf>,
     pub endpoint: Option<String>,
     pub service_name: String,
     pub resource_attr: Vec<String>,
     pub include_phases: bool,
+    pub page_spans: bool,
     pub sample_rate: f64,
     pub max_spans: Option<usize>,
+    pub path_param_threshold: usize,
     pub filters: EntryFilterOptions,
 }
 
 pub fn run_otel(database: PathBuf, options: &OtelExportOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     ensure_schema_upgrades(&conn)?;
 
     validate_sampling(options.sample_rate)?;
 
     let entries = load_entries_with_filters(&conn, &options.filters)?;
+    let resource_attrs = build_resource_attributes(&options.service_name, &options.resource_attr)?;
+    // Metrics count every matching request; sampling and --max-spans only thin out spans.
+    if options.signal == OtelSignal::Metrics {
+        return export_metrics(&entries, resource_attrs, options);
+    }
+
     let entries = sample_entries(entries, options.sample_rate);
     let entries = match options.max_spans {
         Some(max) => entries.into_iter().take(max).collect::<Vec<_>>(),
         None => entries,
     };
-
-    let resource_attrs = build_resource_attributes(&options.service_name, &options.resource_attr)?;
     let mut spans: Vec<SpanRecord> = Vec::new();
     let mut skipped = 0usize;
 
-    for entry in entries {
-        match entry_to_spans(&entry, options.include_phases) {
+    let contexts: Vec<Option<TraceContext>> = entries
+        .iter()
+        .map(|entry| trace_context_from_headers(entry.request_headers.as_deref()))
+        .collect();
+    let pages = if options.page_spans {
+        build_page_spans(&conn, &entries, &contexts)?
+    } else {
+        BTreeMap::new()
+    };
+
+    for page in pages.values() {
+        spans.push(page.span.clone());
+    }
+    // Retries and replays resend the same header; only the first request keeps the
+    // captured span ID, so span IDs stay unique within the trace.
+    let mut claimed_span_ids: HashSet<([u8; 16], [u8; 8])> = HashSet::new();
+    for (entry, context) in entries.iter().zip(contexts.iter()) {
+        let page = entry
+            .page_id
+            .as_ref()
+            .and_then(|page_id| pages.get(&(entry.import_id, page_id.clone())));
+        let owns_span_id = context
+            .as_ref()
+            .is_none_or(|ctx| claimed_span_ids.insert((ctx.trace_id, ctx.span_id)));
+        match entry_to_spans(
+            entry,
+            options.include_phases,
+            context.as_ref(),
+            owns_span_id,
+            page,
+        ) {
             Some(mut entry_spans) => spans.append(&mut entry_spans),
             None => skipped += 1,
         }
@@ -78,7 +123,7 @@ pub fn run_otel(database: PathBuf, options: &OtelExportOptions) -> Result<()> {
                 .endpoint
                 .as_deref()
                 .ok_or_else(|| HarliteError::InvalidArgs("--endpoint is required for OTLP export".to_string()))?;
-            let endpoint = normalize_otlp_http_endpoint(endpoint)?;
+            let endpoint = normalize_otlp_http_endpoint(endpoint, "/v1/traces")?;
             let request = build_otlp_request(resource_attrs, spans);
             send_otlp_http(&endpoint, request)?;
             if skipped > 0 {
@@ -98,16 +143,12 @@ pub fn run_otel(database: PathBuf, options: &OtelExportOptions) -> Result<()> {
             }
             Ok(())
         }
+        OtelExportFormat::Prometheus => Err(HarliteError::InvalidArgs(
+            "--format prometheus requires --signal metrics".to_string(),
+        )),
     }
 }
 
-fn open_output(path: &Path) -> Result<Box<dyn Write>> {
-    if path == Path::new("-") {
-        return Ok(Box::new(io::stdout().lock()));
-    }
-    Ok(Box::new(BufWriter::new(File::create(path)?)))
-}
-
 fn validate_sampling(sample_rate: f64) -> Result<()> {
     if !(0.0..=1.0).contains(&sample_rate) {
         return Err(HarliteError::InvalidArgs(
@@ -147,13 +188,13 @@ fn sample_entries(entries: Vec<EntryRow>, sample_rate: f64) -> Vec<EntryRow> {
 }
 
 #[derive(Clone, Debug)]
-struct Attribute {
-    key: String,
-    value: AttrValue,
+pub(super) struct Attribute {
+    pub(super) key: String,
+    pub(super) value: AttrValue,
 }
 
 #[derive(Clone, Debug)]
-enum AttrValue {
+pub(super) enum AttrValue {
     String(String),
     Int(i64),
     Bool(bool),
@@ -164,12 +205,20 @@ struct SpanRecord {
     trace_id: [u8; 16],
     span_id: [u8; 8],
     parent_span_id: Option<[u8; 8]>,
+    trace_state: Option<String>,
     name: String,
     kind: SpanKind,
     start_unix_nano: u64,
     end_unix_nano: u64,
     attributes: Vec<Attribute>,
     status: Option<SpanStatus>,
+    links: Vec<SpanLink>,
+}
+
+#[derive(Clone, Debug)]
+struct SpanLink {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
 }
 
 #[derive(Clone, Debug)]
@@ -190,7 +239,7 @@ enum SpanKind {
     Client,
 }
 
-fn build_resource_attributes(
+pub(super) fn build_resource_attributes(
     service_name: &str,
     extra: &[String],
 ) -> Result<Vec<Attribute>> {
@@ -224,7 +273,17 @@ fn build_resource_attributes(
     Ok(attrs)
 }
 
-fn entry_to_spans(entry: &EntryRow, include_phases: bool) -> Option<Vec<SpanRecord>> {
+/// Build the client span (and phase spans) for an entry.
+///
+/// `owns_span_id` is false when an earlier entry already used the captured span ID;
+/// the span then gets a synthetic ID and links to the captured one.
+fn entry_to_spans(
+    entry: &EntryRow,
+    include_phases: bool,
+    context: Option<&TraceContext>,
+    owns_span_id: bool,
+    page: Option<&PageSpan>,
+) -> Option<Vec<SpanRecord>> {
     let start = parse_started_at(entry.started_at.as_deref())?;
     let base_ns = start.timestamp_nanos_opt()? as i128;
     if base_ns < 0 {
@@ -232,24 +291,60 @@ fn entry_to_spans(entry: &EntryRow, include_phases: bool) -> Option<Vec<SpanReco
     }
     let base_ns = base_ns as u64;
 
-    let trace_id = trace_id_for_entry(entry);
-    let span_id = span_id_for_entry(entry, "request");
+    // A captured context fixes the trace and the client span ID (the parent the
+    // backend saw); otherwise the span joins its page's trace or gets a synthetic one.
+    let page_in_trace =
+        page.filter(|page| context.is_none_or(|ctx| ctx.trace_id == page.trace_id));
+    let trace_id = match (context, page) {
+        (Some(ctx), _) => ctx.trace_id,
+        (None, Some(page)) => page.trace_id,
+        (None, None) => trace_id_for_entry(entry),
+    };
+    let span_id = context
+        .filter(|_| owns_span_id)
+        .map(|ctx| ctx.span_id)
+        .unwrap_or_else(|| span_id_for_entry(entry, "request"));
+    let parent_span_id = match page_in_trace {
+        Some(page) => Some(page.span.span_id),
+        None => context.and_then(|ctx| ctx.parent_span_id),
+    };
+    let mut links = match (page, page_in_trace) {
+        (Some(page), None) => vec![SpanLink {
+            trace_id: page.trace_id,
+            span_id: page.span.span_id,
+        }],
+        _ => Vec::new(),
+    };
+    if let Some(ctx) = context.filter(|_| !owns_span_id) {
+        links.push(SpanLink {
+            trace_id: ctx.trace_id,
+            span_id: ctx.span_id,
+        });
+    }
     let mut spans = Vec::new();
 
     let (name, mut attributes) = request_name_and_attributes(entry);
+    if let Some(ctx) = context {
+        attributes.push(Attribute {
+            key: "har.trace_context".to_string(),
+            value: AttrValue::String(ctx.source.to_string()),
+        });
+    }
     let (start_ns, end_ns) = request_bounds(entry, base_ns);
     let status = status_from_http(entry.status);
 
     spans.push(SpanRecord {
         trace_id,
         span_id,
-        parent_span_id: None,
+        parent_span_id,
+        trace_state: context.and_then(|ctx| ctx.trace_state.clone()),
         name,
         kind: SpanKind::Client,
         start_unix_nano: start_ns,
         end_unix_nano: end_ns,
-        attributes: attributes.drain(..).collect(),
+        attributes,
         status,
+        links,
     });
 
     if include_phases {
@@ -260,6 +355,7 @@ fn entry_to_spans(entry: &EntryRow, include_phases: bool) -> Option<Vec<SpanReco
                 trace_id,
                 span_id: phase_span_id,
                 parent_span_id: Some(span_id),
+                trace_state: None,
                 name: format!("har.{}", phase.name),
                 kind: SpanKind::Internal,
                 start_unix_nano: base_ns + ms_to_ns(phase.start_ms),
@@ -269,6 +365,7 @@ fn entry_to_spans(entry: &EntryRow, include_phases: bool) -> Option<Vec<SpanReco
                     value: AttrValue::String(phase.name.to_string()),
                 }],
                 status: None,
+                links: Vec::new(),
             });
         }
     }
@@ -276,7 +373,223 @@ fn entry_to_spans(entry: &EntryRow, include_phases: bool) -> Option<Vec<SpanReco
     Some(spans)
 }
 
-fn parse_started_at(value: Option<&str>) -> Option<DateTime<Utc>> {
+/// Trace context a captured request propagated to the server.
+#[derive(Clone, Debug, PartialEq)]
+struct TraceContext {
+    trace_id: [u8; 16],
+    /// ID of the client span, i.e. the parent of the server span.
+    span_id: [u8; 8],
+    /// Parent of the client span (B3 `ParentSpanId` only).
+    parent_span_id: Option<[u8; 8]>,
+    trace_state: Option<String>,
+    source: &'static str,
+}
+
+/// Parse W3C `traceparent`/`tracestate`, then single-header `b3`, then multi-header
+/// `X-B3-*` from a request's headers.
+fn trace_context_from_headers(headers_json: Option<&str>) -> Option<TraceContext> {
+    let headers = headers_from_json(headers_json)?;
+    if let Some(value) = headers.get("traceparent") {
+        if let Some(mut ctx) = parse_traceparent(value) {
+            ctx.trace_state = headers
+                .get("tracestate")
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+            return Some(ctx);
+        }
+    }
+    if let Some(ctx) = headers.get("b3").and_then(|value| parse_b3_single(value)) {
+        return Some(ctx);
+    }
+    let trace_id = parse_b3_trace_id(headers.get("x-b3-traceid")?)?;
+    let span_id = parse_span_id(headers.get("x-b3-spanid")?)?;
+    let parent_span_id = headers
+        .get("x-b3-parentspanid")
+        .and_then(|value| parse_span_id(value));
+    Some(TraceContext {
+        trace_id,
+        span_id,
+        parent_span_id,
+        trace_state: None,
+        source: "b3",
+    })
+}
+
+/// `version-traceid-parentid-flags`; later versions may append fields.
+fn parse_traceparent(value: &str) -> Option<TraceContext> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let span_id = parts.next()?;
+    let flags = parts.next()?;
+    if version.len() != 2
+        || version.eq_ignore_ascii_case("ff")
+        || decode_hex::<1>(version).is_none()
+        || decode_hex::<1>(flags).is_none()
+        || (version == "00" && parts.next().is_some())
+    {
+        return None;
+    }
+    Some(TraceContext {
+        trace_id: nonzero(decode_hex::<16>(trace_id)?)?,
+        span_id: parse_span_id(span_id)?,
+        parent_span_id: None,
+        trace_state: None,
+        source: "w3c",
+    })
+}
+
+/// `traceid-spanid[-sampled[-parentspanid]]`; a bare sampling flag carries no IDs.
+fn parse_b3_single(value: &str) -> Option<TraceContext> {
+    let mut parts = value.trim().split('-');
+    let trace_id = parse_b3_trace_id(parts.next()?)?;
+    let span_id = parse_span_id(parts.next()?)?;
+    let _sampled = parts.next();
+    let parent_span_id = parts.next().and_then(parse_span_id);
+    Some(TraceContext {
+        trace_id,
+        span_id,
+        parent_span_id,
+        trace_state: None,
+        source: "b3",
+    })
+}
+
+/// B3 trace IDs are 64 or 128 bits; 64-bit IDs are left-padded with zeros.
+fn parse_b3_trace_id(value: &str) -> Option<[u8; 16]> {
+    let value = value.trim();
+    if value.len() == 16 {
+        let mut out = [0u8; 16];
+        out[8..].copy_from_slice(&decode_hex::<8>(value)?);
+        return nonzero(out);
+    }
+    nonzero(decode_hex::<16>(value)?)
+}
+
+fn parse_span_id(value: &str) -> Option<[u8; 8]> {
+    nonzero(decode_hex::<8>(value.trim())?)
+}
+
+fn nonzero<const N: usize>(bytes: [u8; N]) -> Option<[u8; N]> {
+    bytes.iter().any(|b| *b != 0).then_some(bytes)
+}
+
+fn decode_hex<const N: usize>(value: &str) -> Option<[u8; N]> {
+    if value.len() != N * 2 || !value.is_ascii() {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
+/// Root span for one page load; its entries become children when they share its trace.
+struct PageSpan {
+    trace_id: [u8; 16],
+    span: SpanRecord,
+}
+
+/// One root span per `(import_id, page_id)` covering the page and its exported entries.
+///
+/// The page adopts the first captured trace among its entries, so requests carrying
+/// that `traceparent` nest under it; other traced requests keep their own trace and
+/// link back to the page.
+fn build_page_spans(
+    conn: &rusqlite::Connection,
+    entries: &[EntryRow],
+    contexts: &[Option<TraceContext>],
+) -> Result<BTreeMap<(i64, String), PageSpan>> {
+    let mut import_ids: Vec<i64> = entries
+        .iter()
+        .filter(|entry| entry.page_id.is_some())
+        .map(|entry| entry.import_id)
+        .collect();
+    import_ids.sort();
+    import_ids.dedup();
+    let page_rows: HashMap<(i64, String), _> = load_pages_for_imports(conn, &import_ids)?
+        .into_iter()
+        .map(|page| ((page.import_id, page.id.clone()), page))
+        .collect();
+
+    // (start, end, first captured trace) per page.
+    type PageBounds = (u64, u64, Option<[u8; 16]>);
+    let mut bounds: BTreeMap<(i64, String), PageBounds> = BTreeMap::new();
+    for (entry, context) in entries.iter().zip(contexts) {
+        let Some(page_id) = entry.page_id.as_ref() else {
+            continue;
+        };
+        let Some(start) = parse_started_at(entry.started_at.as_deref())
+            .and_then(|start| start.timestamp_nanos_opt())
+            .filter(|ns| *ns >= 0)
+        else {
+            continue;
+        };
+        let (start_ns, end_ns) = request_bounds(entry, start as u64);
+        let slot = bounds
+            .entry((entry.import_id, page_id.clone()))
+            .or_insert((start_ns, end_ns, None));
+        slot.0 = slot.0.min(start_ns);
+        slot.1 = slot.1.max(end_ns);
+        if slot.2.is_none() {
+            slot.2 = context.as_ref().map(|ctx| ctx.trace_id);
+        }
+    }
+
+    let mut pages = BTreeMap::new();
+    for ((import_id, page_id), (mut start_ns, mut end_ns, captured)) in bounds {
+        let key = format!("page:{import_id}:{page_id}");
+        let row = page_rows.get(&(import_id, page_id.clone()));
+        if let Some(page_start) = row
+            .and_then(|row| parse_started_at(row.started_at.as_deref()))
+            .and_then(|start| start.timestamp_nanos_opt())
+            .filter(|ns| *ns >= 0)
+        {
+            let page_start = page_start as u64;
+            start_ns = start_ns.min(page_start);
+            if let Some(on_load) = row.and_then(|row| normalize_ms(row.on_load_ms)) {
+                end_ns = end_ns.max(page_start + ms_to_ns(on_load));
+            }
+        }
+        let trace_id = captured.unwrap_or_else(|| {
+            let mut out = [0u8; 16];
+            out.copy_from_slice(&blake3::hash(key.as_bytes()).as_bytes()[..16]);
+            out
+        });
+        let mut span_id = [0u8; 8];
+        span_id.copy_from_slice(&blake3::hash(format!("{key}:span").as_bytes()).as_bytes()[..8]);
+
+        let title = row.and_then(|row| row.title.clone()).filter(|t| !t.is_empty());
+        let mut attributes = vec![Attribute {
+            key: "har.page.id".to_string(),
+            value: AttrValue::String(page_id.clone()),
+        }];
+        if let Some(title) = title.as_ref() {
+            attributes.push(Attribute {
+                key: "har.page.title".to_string(),
+                value: AttrValue::String(title.clone()),
+            });
+        }
+        let span = SpanRecord {
+            trace_id,
+            span_id,
+            parent_span_id: None,
+            trace_state: None,
+            name: format!("page {}", title.as_deref().unwrap_or(&page_id)),
+            kind: SpanKind::Internal,
+            start_unix_nano: start_ns,
+            end_unix_nano: end_ns,
+            attributes,
+            status: None,
+            links: Vec::new(),
+        };
+        pages.insert((import_id, page_id), PageSpan { trace_id, span });
+    }
+    Ok(pages)
+}
+
+pub(super) fn parse_started_at(value: Option<&str>) -> Option<DateTime<Utc>> {
     let value = value?;
     let parsed = DateTime::parse_from_rfc3339(value).ok()?;
     Some(parsed.with_timezone(&Utc))
@@ -509,7 +822,7 @@ fn status_from_http(status: Option<i32>) -> Option<SpanStatus> {
     }
 }
 
-fn normalize_ms(value: Option<f64>) -> Option<f64> {
+pub(super) fn normalize_ms(value: Option<f64>) -> Option<f64> {
     match value {
         Some(v) if v >= 0.0 => Some(v),
         _ => None,
@@ -619,17 +932,18 @@ fn hex_encode(bytes: &[u8]) -> String {
     out
 }
 
-fn normalize_otlp_http_endpoint(endpoint: &str) -> Result<String> {
+/// Append the signal path (`/v1/traces`, `/v1/metrics`) unless the endpoint already has it.
+pub(super) fn normalize_otlp_http_endpoint(endpoint: &str, signal_path: &str) -> Result<String> {
     let parsed = Url::parse(endpoint).map_err(|_| {
         HarliteError::InvalidArgs("--endpoint must be a valid URL".to_string())
     })?;
     let trimmed = endpoint.trim_end_matches('/');
     let path = parsed.path().trim_end_matches('/');
-    if path.ends_with("/v1/traces") {
+    if path.ends_with(signal_path) {
         return Ok(trimmed.to_string());
     }
     let mut base = trimmed.to_string();
-    base.push_str("/v1/traces");
+    base.push_str(signal_path);
     Ok(base)
 }
 
@@ -655,6 +969,7 @@ fn span_to_json(span: SpanRecord) -> JsonSpan {
         trace_id: hex_encode(&span.trace_id),
         span_id: hex_encode(&span.span_id),
         parent_span_id: span.parent_span_id.map(|id| hex_encode(&id)),
+        trace_state: span.trace_state,
         name: span.name,
         kind: match span.kind {
             SpanKind::Internal => 1,
@@ -664,6 +979,14 @@ fn span_to_json(span: SpanRecord) -> JsonSpan {
         end_time_unix_nano: span.end_unix_nano,
         attributes: attrs_to_json(span.attributes),
         status: span.status.map(status_to_json),
+        links: span
+            .links
+            .into_iter()
+            .map(|link| JsonLink {
+                trace_id: hex_encode(&link.trace_id),
+                span_id: hex_encode(&link.span_id),
+            })
+            .collect(),
     }
 }
 
@@ -677,7 +1000,7 @@ fn status_to_json(status: SpanStatus) -> JsonStatus {
     }
 }
 
-fn attrs_to_json(attrs: Vec<Attribute>) -> Vec<JsonKeyValue> {
+pub(super) fn attrs_to_json(attrs: Vec<Attribute>) -> Vec<JsonKeyValue> {
     attrs
         .into_iter()
         .map(|attr| JsonKeyValue {
@@ -751,6 +1074,8 @@ struct JsonSpan {
     span_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     parent_span_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_state: Option<String>,
     name: String,
     kind: i32,
     start_time_unix_nano: u64,
@@ -758,6 +1083,15 @@ struct JsonSpan {
     attributes: Vec<JsonKeyValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     status: Option<JsonStatus>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    links: Vec<JsonLink>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonLink {
+    trace_id: String,
+    span_id: String,
 }
 
 #[derive(Serialize)]
@@ -770,14 +1104,14 @@ struct JsonStatus {
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct JsonKeyValue {
+pub(super) struct JsonKeyValue {
     key: String,
     value: JsonAnyValue,
 }
 
 #[derive(Default, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct JsonAnyValue {
+pub(super) struct JsonAnyValue {
     #[serde(skip_serializing_if = "Option::is_none")]
     string_value: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -793,28 +1127,14 @@ fn build_otlp_request(
     spans: Vec<SpanRecord>,
 ) -> opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest {
     use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
-    use opentelemetry_proto::tonic::common::v1::{
-        any_value, AnyValue, InstrumentationScope, KeyValue,
-    };
+    use opentelemetry_proto::tonic::common::v1::InstrumentationScope;
     use opentelemetry_proto::tonic::resource::v1::Resource;
     use opentelemetry_proto::tonic::trace::v1::{
         span, status, ResourceSpans, ScopeSpans, Span, Status,
     };
 
     let resource = Resource {
-        attributes: resource_attrs
-            .into_iter()
-            .map(|attr| KeyValue {
-                key: attr.key,
-                value: Some(AnyValue {
-                    value: Some(match attr.value {
-                        AttrValue::String(value) => any_value::Value::StringValue(value),
-                        AttrValue::Int(value) => any_value::Value::IntValue(value),
-                        AttrValue::Bool(value) => any_value::Value::BoolValue(value),
-                    }),
-                }),
-            })
-            .collect(),
+        attributes: attrs_to_proto(resource_attrs),
         dropped_attributes_count: 0,
     };
 
@@ -835,6 +1155,7 @@ fn build_otlp_request(
                     .parent_span_id
                     .map(|id| id.to_vec())
                     .unwrap_or_default(),
+                trace_state: span_record.trace_state.unwrap_or_default(),
                 name: span_record.name,
                 kind: match span_record.kind {
                     SpanKind::Internal => span::SpanKind::Internal as i32,
@@ -842,24 +1163,19 @@ fn build_otlp_request(
                 },
                 start_time_unix_nano: span_record.start_unix_nano,
                 end_time_unix_nano: span_record.end_unix_nano,
-                attributes: span_record
-                    .attributes
-                    .into_iter()
-                    .map(|attr| KeyValue {
-                        key: attr.key,
-                        value: Some(AnyValue {
-                            value: Some(match attr.value {
-                                AttrValue::String(value) => any_value::Value::StringValue(value),
-                        AttrValue::Int(value) => any_value::Value::IntValue(value),
-                        AttrValue::Bool(value) => any_value::Value::BoolValue(value),
-                            }),
-                        }),
-                    })
-                    .collect(),
+                attributes: attrs_to_proto(span_record.attributes),
                 dropped_attributes_count: 0,
                 events: Vec::new(),
                 dropped_events_count: 0,
-                links: Vec::new(),
+                links: span_record
+                    .links
+                    .into_iter()
+                    .map(|link| span::Link {
+                        trace_id: link.trace_id.to_vec(),
+                        span_id: link.span_id.to_vec(),
+                        ..Default::default()
+                    })
+                    .collect(),
                 dropped_links_count: 0,
                 status,
                 ..Default::default()
@@ -889,12 +1205,27 @@ fn build_otlp_request(
     }
 }
 
-fn send_otlp_http(
-    endpoint: &str,
-    request: opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest,
-) -> Result<()> {
-    use prost::Message;
+pub(super) fn attrs_to_proto(
+    attrs: Vec<Attribute>,
+) -> Vec<opentelemetry_proto::tonic::common::v1::KeyValue> {
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
 
+    attrs
+        .into_iter()
+        .map(|attr| KeyValue {
+            key: attr.key,
+            value: Some(AnyValue {
+                value: Some(match attr.value {
+                    AttrValue::String(value) => any_value::Value::StringValue(value),
+                    AttrValue::Int(value) => any_value::Value::IntValue(value),
+                    AttrValue::Bool(value) => any_value::Value::BoolValue(value),
+                }),
+            }),
+        })
+        .collect()
+}
+
+pub(super) fn send_otlp_http(endpoint: &str, request: impl prost::Message) -> Result<()> {
     let mut buf = Vec::new();
     request
         .encode(&mut buf)
@@ -923,12 +1254,7 @@ fn send_otlp_grpc(
 ) -> Result<()> {
     use opentelemetry_proto::tonic::collector::trace::v1::trace_service_client::TraceServiceClient;
 
-    let endpoint = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
-        endpoint.to_string()
-    } else {
-        format!("http://{}", endpoint)
-    };
-
+    let endpoint = grpc_endpoint(endpoint);
     let rt = tokio::runtime::Runtime::new()
         .map_err(|err| HarliteError::InvalidArgs(format!("Failed to start runtime: {err}")))?;
     rt.block_on(async move {
@@ -945,13 +1271,26 @@ fn send_otlp_grpc(
     })
 }
 
+/// gRPC endpoints may be given as `host:port`; default those to plaintext HTTP/2.
+pub(super) fn grpc_endpoint(endpoint: &str) -> String {
+    if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+        endpoint.to_string()
+    } else {
+        format!("http://{}", endpoint)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{phase_ranges_ms, request_bounds};
+    use super::{
+        entry_to_spans, phase_ranges_ms, request_bounds, trace_context_from_headers, AttrValue,
+        Attribute, PageSpan, SpanKind, SpanRecord,
+    };
     use crate::db::EntryRow;
 
     fn entry_with_times() -> EntryRow {
         EntryRow {
+            id: 1,
             import_id: 1,
             page_id: None,
             started_at: Some("2024-01-15T12:00:00.000Z".to_string()),
@@ -1007,6 +1346,8 @@ mod tests {
             graphql_operation_type: None,
             graphql_operation_name: None,
             graphql_top_level_fields: None,
+            graphql_query_hash: None,
+            graphql_variables_hash: None,
         }
     }
 
@@ -1026,4 +1367,114 @@ mod tests {
         let (start, end) = request_bounds(&entry, 1_000_000);
         assert!(end > start);
     }
+
+    fn page_span(trace_id: [u8; 16]) -> PageSpan {
+        PageSpan {
+            trace_id,
+            span: SpanRecord {
+                trace_id,
+                span_id: [7; 8],
+                parent_span_id: None,
+                trace_state: None,
+                name: "page Home".to_string(),
+                kind: SpanKind::Internal,
+                start_unix_nano: 0,
+                end_unix_nano: 0,
+                attributes: vec![Attribute {
+                    key: "har.page.id".to_string(),
+                    value: AttrValue::String("page_1".to_string()),
+                }],
+                status: None,
+                links: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn traceparent_and_tracestate_are_parsed() {
+        let ctx = trace_context_from_headers(Some(
+            r#"{"traceparent":"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01","tracestate":"vendor=abc"}"#,
+        ))
+        .unwrap();
+        assert_eq!(super::hex_encode(&ctx.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(super::hex_encode(&ctx.span_id), "00f067aa0ba902b7");
+        assert_eq!(ctx.parent_span_id, None);
+        assert_eq!(ctx.trace_state.as_deref(), Some("vendor=abc"));
+        assert_eq!(ctx.source, "w3c");
+
+        for invalid in [
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            let headers = serde_json::json!({ "traceparent": invalid }).to_string();
+            assert!(trace_context_from_headers(Some(&headers)).is_none(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn b3_single_and_multi_headers_are_parsed() {
+        let ctx = trace_context_from_headers(Some(
+            r#"{"b3":"a3ce929d0e0e4736-00f067aa0ba902b7-1-05e3ac9a4f6e3b90"}"#,
+        ))
+        .unwrap();
+        assert_eq!(super::hex_encode(&ctx.trace_id), "0000000000000000a3ce929d0e0e4736");
+        assert_eq!(super::hex_encode(&ctx.span_id), "00f067aa0ba902b7");
+        assert_eq!(super::hex_encode(&ctx.parent_span_id.unwrap()), "05e3ac9a4f6e3b90");
+        assert_eq!(ctx.source, "b3");
+
+        let ctx = trace_context_from_headers(Some(
+            r#"{"x-b3-traceid":"4bf92f3577b34da6a3ce929d0e0e4736","x-b3-spanid":"00f067aa0ba902b7","x-b3-sampled":"1"}"#,
+        ))
+        .unwrap();
+        assert_eq!(super::hex_encode(&ctx.trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(ctx.parent_span_id, None);
+
+        assert!(trace_context_from_headers(Some(r#"{"b3":"1"}"#)).is_none());
+    }
+
+    #[test]
+    fn captured_context_sets_ids_and_nests_under_matching_page() {
+        let mut entry = entry_with_times();
+        entry.request_headers = Some(
+            r#"{"traceparent":"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"}"#
+                .to_string(),
+        );
+        let ctx = trace_context_from_headers(entry.request_headers.as_deref()).unwrap();
+
+        let page = page_span(ctx.trace_id);
+        let spans = entry_to_spans(&entry, true, Some(&ctx), true, Some(&page)).unwrap();
+        let request = &spans[0];
+        assert_eq!(request.trace_id, ctx.trace_id);
+        assert_eq!(request.span_id, ctx.span_id);
+        assert_eq!(request.parent_span_id, Some(page.span.span_id));
+        assert!(request.links.is_empty());
+        assert!(spans[1..]
+            .iter()
+            .all(|span| span.trace_id == ctx.trace_id && span.parent_span_id == Some(ctx.span_id)));
+
+        let other_page = page_span([9; 16]);
+        let spans = entry_to_spans(&entry, false, Some(&ctx), true, Some(&other_page)).unwrap();
+        assert_eq!(spans[0].trace_id, ctx.trace_id);
+        assert_eq!(spans[0].parent_span_id, None);
+        assert_eq!(spans[0].links.len(), 1);
+        assert_eq!(spans[0].links[0].span_id, other_page.span.span_id);
+
+        let spans = entry_to_spans(&entry, false, None, true, Some(&other_page)).unwrap();
+        assert_eq!(spans[0].trace_id, [9; 16]);
+        assert_eq!(spans[0].parent_span_id, Some(other_page.span.span_id));
+        let spans = entry_to_spans(&entry, true, Some(&ctx), false, Some(&page)).unwrap();
+        let request = &spans[0];
+        assert_eq!(request.trace_id, ctx.trace_id);
+        assert_ne!(request.span_id, ctx.span_id);
+        assert_eq!(request.parent_span_id, Some(page.span.span_id));
+        assert_eq!(request.links.len(), 1);
+        assert_eq!(request.links[0].trace_id, ctx.trace_id);
+        assert_eq!(request.links[0].span_id, ctx.span_id);
+        assert!(spans[1..]
+            .iter()
+            .all(|span| span.parent_span_id == Some(request.span_id)));
+    }
 }
@@ -0,0 +1,858 @@
+//! Metrics derived from captured requests, for `harlite otel --signal metrics`.
+//!
+//! Entries are aggregated into cumulative metrics covering the capture window: a
+//! request-duration histogram, request counts by status class, and request/response
+//! bytes, each labelled by host, method and templated route. Names and attributes
+//! follow the OpenTelemetry HTTP client conventions where one exists, so capture data
+//! lines up with production `http.client.*` metrics.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use url::Url;
+
+use crate::db::EntryRow;
+use crate::error::{HarliteError, Result};
+
+use super::openapi::{path_segments, PathTemplater};
+use super::otel::{
+    attrs_to_json, attrs_to_proto, grpc_endpoint, normalize_ms, normalize_otlp_http_endpoint,
+    parse_started_at, send_otlp_http, AttrValue, Attribute, JsonKeyValue, OtelExportFormat,
+    OtelExportOptions,
+};
+use super::util::open_output;
+
+/// Semantic-convention buckets for `http.client.request.duration`, in seconds.
+const DURATION_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0,
+];
+
+/// OTLP `AGGREGATION_TEMPORALITY_CUMULATIVE`.
+const CUMULATIVE: i32 = 2;
+
+pub(super) fn export_metrics(
+    entries: &[EntryRow],
+    resource_attrs: Vec<Attribute>,
+    options: &OtelExportOptions,
+) -> Result<()> {
+    let metrics = aggregate(entries, options.path_param_threshold);
+    let points: usize = metrics.records.iter().map(MetricRecord::point_count).sum();
+
+    match options.format {
+        OtelExportFormat::Json | OtelExportFormat::Prometheus => {
+            let output_path = options.output.clone().unwrap_or_else(|| PathBuf::from("-"));
+            let mut writer = open_output(&output_path)?;
+            if matches!(options.format, OtelExportFormat::Json) {
+                let payload = build_json_export(resource_attrs, &metrics);
+                serde_json::to_writer(&mut writer, &payload)?;
+                writer.write_all(b"\n")?;
+            } else {
+                writer.write_all(render_prometheus(&metrics, &resource_attrs).as_bytes())?;
+            }
+            writer.flush()?;
+            if output_path.as_path() != Path::new("-") {
+                println!(
+                    "Exported {} metric points from {} entries to {}",
+                    points,
+                    entries.len(),
+                    output_path.display()
+                );
+            }
+            Ok(())
+        }
+        OtelExportFormat::OtlpHttp => {
+            let endpoint = require_endpoint(options)?;
+            let endpoint = normalize_otlp_http_endpoint(endpoint, "/v1/metrics")?;
+            send_otlp_http(&endpoint, build_otlp_request(resource_attrs, metrics))
+        }
+        OtelExportFormat::OtlpGrpc => {
+            let endpoint = require_endpoint(options)?;
+            send_otlp_grpc(endpoint, build_otlp_request(resource_attrs, metrics))
+        }
+    }
+}
+
+fn require_endpoint(options: &OtelExportOptions) -> Result<&str> {
+    options.endpoint.as_deref().ok_or_else(|| {
+        HarliteError::InvalidArgs("--endpoint is required for OTLP export".to_string())
+    })
+}
+
+/// Aggregated metrics plus the window they cover.
+struct Metrics {
+    start_unix_nano: u64,
+    time_unix_nano: u64,
+    records: Vec<MetricRecord>,
+}
+
+struct MetricRecord {
+    name: &'static str,
+    prometheus_name: &'static str,
+    description: &'static str,
+    unit: &'static str,
+    data: MetricData,
+}
+
+impl MetricRecord {
+    fn point_count(&self) -> usize {
+        match &self.data {
+            MetricData::Histogram(points) => points.len(),
+            MetricData::Counter(points) => points.len(),
+        }
+    }
+}
+
+enum MetricData {
+    Histogram(Vec<HistogramPoint>),
+    /// Monotonic cumulative sum.
+    Counter(Vec<CounterPoint>),
+}
+
+struct HistogramPoint {
+    attributes: Vec<Attribute>,
+    count: u64,
+    sum: f64,
+    /// Per-bucket (not cumulative) counts; one more than [`DURATION_BUCKETS`].
+    bucket_counts: Vec<u64>,
+    min: f64,
+    max: f64,
+}
+
+struct CounterPoint {
+    attributes: Vec<Attribute>,
+    value: i64,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    host: String,
+    method: String,
+    route: String,
+}
+
+impl SeriesKey {
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            string_attr("server.address", &self.host),
+            string_attr("http.request.method", &self.method),
+            string_attr("http.route", &self.route),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct Series {
+    duration: Option<HistogramPoint>,
+    requests_by_class: BTreeMap<String, i64>,
+    request_bytes: i64,
+    response_bytes: i64,
+}
+
+fn aggregate(entries: &[EntryRow], path_param_threshold: usize) -> Metrics {
+    let urls: Vec<Option<Url>> = entries
+        .iter()
+        .map(|entry| entry.url.as_deref().and_then(|url| Url::parse(url).ok()))
+        .collect();
+    let templater = PathTemplater::new(
+        urls.iter().flatten().map(path_segments),
+        path_param_threshold,
+        true,
+    );
+
+    let mut series: BTreeMap<SeriesKey, Series> = BTreeMap::new();
+    let mut window: Option<(u64, u64)> = None;
+    for (entry, url) in entries.iter().zip(&urls) {
+        let route = match url {
+            Some(url) => templater.template(&path_segments(url)).path,
+            None => entry.path.clone().unwrap_or_else(|| "/".to_string()),
+        };
+        let key = SeriesKey {
+            host: entry
+                .host
+                .clone()
+                .or_else(|| url.as_ref().and_then(|u| u.host_str().map(str::to_string)))
+                .unwrap_or_else(|| "unknown".to_string()),
+            method: entry
+                .method
+                .as_deref()
+                .map(str::to_ascii_uppercase)
+                .unwrap_or_else(|| "_OTHER".to_string()),
+            route,
+        };
+        let slot = series.entry(key).or_default();
+
+        let duration_ms = normalize_ms(entry.time_ms);
+        if let Some(ms) = duration_ms {
+            record_duration(slot, ms / 1000.0);
+        }
+        *slot
+            .requests_by_class
+            .entry(status_class(entry.status))
+            .or_default() += 1;
+        slot.request_bytes += entry.request_body_size.filter(|n| *n > 0).unwrap_or(0);
+        slot.response_bytes += entry
+            .response_body_size_raw
+            .or(entry.response_body_size)
+            .filter(|n| *n > 0)
+            .unwrap_or(0);
+
+        if let Some(start) = parse_started_at(entry.started_at.as_deref())
+            .and_then(|start| start.timestamp_nanos_opt())
+            .filter(|ns| *ns >= 0)
+        {
+            let start = start as u64;
+            let end = start + (duration_ms.unwrap_or(0.0) * 1_000_000.0).round() as u64;
+            window = Some(match window {
+                Some((lo, hi)) => (lo.min(start), hi.max(end)),
+                None => (start, end),
+            });
+        }
+    }
+
+    let mut durations = Vec::new();
+    let mut requests = Vec::new();
+    let mut request_bytes = Vec::new();
+    let mut response_bytes = Vec::new();
+    for (key, data) in series {
+        if let Some(mut point) = data.duration {
+            point.attributes = key.attributes();
+            durations.push(point);
+        }
+        for (class, count) in data.requests_by_class {
+            let mut attributes = key.attributes();
+            attributes.push(string_attr("http.response.status_class", &class));
+            requests.push(CounterPoint {
+                attributes,
+                value: count,
+            });
+        }
+        request_bytes.push(CounterPoint {
+            attributes: key.attributes(),
+            value: data.request_bytes,
+        });
+        response_bytes.push(CounterPoint {
+            attributes: key.attributes(),
+            value: data.response_bytes,
+        });
+    }
+
+    let (start_unix_nano, time_unix_nano) = window.unwrap_or((0, 0));
+    Metrics {
+        start_unix_nano,
+        time_unix_nano,
+        records: vec![
+            MetricRecord {
+                name: "http.client.request.duration",
+                prometheus_name: "http_client_request_duration_seconds",
+                description: "Duration of captured HTTP client requests",
+                unit: "s",
+                data: MetricData::Histogram(durations),
+            },
+            MetricRecord {
+                name: "http.client.requests",
+                prometheus_name: "http_client_requests_total",
+                description: "Captured HTTP client requests by response status class",
+                unit: "{request}",
+                data: MetricData::Counter(requests),
+            },
+            MetricRecord {
+                name: "harlite.http.client.request.bytes",
+                prometheus_name: "harlite_http_client_request_bytes_total",
+                description: "Request body bytes sent by captured HTTP client requests",
+                unit: "By",
+                data: MetricData::Counter(request_bytes),
+            },
+            MetricRecord {
+                name: "harlite.http.client.response.bytes",
+                prometheus_name: "harlite_http_client_response_bytes_total",
+                description: "Response body bytes received (as transferred, before decoding)",
+                unit: "By",
+                data: MetricData::Counter(response_bytes),
+            },
+        ],
+    }
+}
+
+fn record_duration(series: &mut Series, seconds: f64) {
+    let point = series.duration.get_or_insert_with(|| HistogramPoint {
+        attributes: Vec::new(),
+        count: 0,
+        sum: 0.0,
+        bucket_counts: vec![0; DURATION_BUCKETS.len() + 1],
+        min: seconds,
+        max: seconds,
+    });
+    let bucket = DURATION_BUCKETS
+        .iter()
+        .position(|bound| seconds <= *bound)
+        .unwrap_or(DURATION_BUCKETS.len());
+    point.bucket_counts[bucket] += 1;
+    point.count += 1;
+    point.sum += seconds;
+    point.min = point.min.min(seconds);
+    point.max = point.max.max(seconds);
+}
+
+/// `2xx`, `4xx`, ...; `none` for requests without a usable status (e.g. aborted).
+fn status_class(status: Option<i32>) -> String {
+    match status {
+        Some(code) if (100..600).contains(&code) => format!("{}xx", code / 100),
+        _ => "none".to_string(),
+    }
+}
+
+fn string_attr(key: &str, value: &str) -> Attribute {
+    Attribute {
+        key: key.to_string(),
+        value: AttrValue::String(value.to_string()),
+    }
+}
+
+/// Prometheus text exposition, with resource attributes following the OpenTelemetry
+/// compatibility rules: `service.name` (and `service.namespace`) become the `job`
+/// label and `service.instance.id` the `instance` label on every series, and the
+/// remaining resource attributes are labels of a `target_info` gauge.
+fn render_prometheus(metrics: &Metrics, resource_attrs: &[Attribute]) -> String {
+    let identity = prometheus_labels(&target_identity(resource_attrs));
+    let series_labels = |attrs: &[Attribute]| {
+        let labels = prometheus_labels(attrs);
+        if labels.is_empty() {
+            identity.clone()
+        } else {
+            format!("{identity},{labels}")
+        }
+    };
+
+    let mut out = String::new();
+    let info: Vec<Attribute> = resource_attrs
+        .iter()
+        .filter(|attr| {
+            !matches!(
+                attr.key.as_str(),
+                "service.name" | "service.namespace" | "service.instance.id"
+            )
+        })
+        .cloned()
+        .collect();
+    let _ = writeln!(out, "# HELP target_info Target metadata");
+    let _ = writeln!(out, "# TYPE target_info gauge");
+    let _ = writeln!(out, "target_info{{{}}} 1", series_labels(&info));
+
+    for record in &metrics.records {
+        if record.point_count() == 0 {
+            continue;
+        }
+        let name = record.prometheus_name;
+        let kind = match record.data {
+            MetricData::Histogram(_) => "histogram",
+            MetricData::Counter(_) => "counter",
+        };
+        let _ = writeln!(out, "# HELP {name} {}", record.description);
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        match &record.data {
+            MetricData::Histogram(points) => {
+                for point in points {
+                    let labels = series_labels(&point.attributes);
+                    let mut cumulative = 0;
+                    for (bound, count) in DURATION_BUCKETS.iter().zip(&point.bucket_counts) {
+                        cumulative += count;
+                        let _ =
+                            writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+                    }
+                    let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", point.count);
+                    let _ = writeln!(out, "{name}_sum{{{labels}}} {}", point.sum);
+                    let _ = writeln!(out, "{name}_count{{{labels}}} {}", point.count);
+                }
+            }
+            MetricData::Counter(points) => {
+                for point in points {
+                    let labels = series_labels(&point.attributes);
+                    let _ = writeln!(out, "{name}{{{labels}}} {}", point.value);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `job` and `instance` labels derived from the service resource attributes.
+fn target_identity(resource_attrs: &[Attribute]) -> Vec<Attribute> {
+    let lookup = |key: &str| {
+        resource_attrs.iter().find_map(|attr| match &attr.value {
+            AttrValue::String(value) if attr.key == key => Some(value.as_str()),
+            _ => None,
+        })
+    };
+    let mut identity = Vec::new();
+    if let Some(service) = lookup("service.name") {
+        let job = match lookup("service.namespace") {
+            Some(namespace) => format!("{namespace}/{service}"),
+            None => service.to_string(),
+        };
+        identity.push(string_attr("job", &job));
+    }
+    if let Some(instance) = lookup("service.instance.id") {
+        identity.push(string_attr("instance", instance));
+    }
+    identity
+}
+
+/// `server.address="x",...` with OpenTelemetry attribute keys mapped to label names.
+fn prometheus_labels(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .map(|attr| {
+            let value = match &attr.value {
+                AttrValue::String(value) => value.clone(),
+                AttrValue::Int(value) => value.to_string(),
+                AttrValue::Bool(value) => value.to_string(),
+            };
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            let name: String = attr
+                .key
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            format!("{name}=\"{value}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn build_json_export(resource_attrs: Vec<Attribute>, metrics: &Metrics) -> JsonMetricsExport {
+    let metric_list = metrics
+        .records
+        .iter()
+        .filter(|record| record.point_count() > 0)
+        .map(|record| {
+            let mut metric = JsonMetric {
+                name: record.name.to_string(),
+                description: record.description.to_string(),
+                unit: record.unit.to_string(),
+                histogram: None,
+                sum: None,
+            };
+            match &record.data {
+                MetricData::Histogram(points) => {
+                    metric.histogram = Some(JsonHistogram {
+                        data_points: points
+                            .iter()
+                            .map(|point| JsonHistogramPoint {
+                                attributes: attrs_to_json(point.attributes.clone()),
+                                start_time_unix_nano: metrics.start_unix_nano,
+                                time_unix_nano: metrics.time_unix_nano,
+                                count: point.count,
+                                sum: point.sum,
+                                bucket_counts: point.bucket_counts.clone(),
+                                explicit_bounds: DURATION_BUCKETS.to_vec(),
+                                min: point.min,
+                                max: point.max,
+                            })
+                            .collect(),
+                        aggregation_temporality: CUMULATIVE,
+                    });
+                }
+                MetricData::Counter(points) => {
+                    metric.sum = Some(JsonSum {
+                        data_points: points
+                            .iter()
+                            .map(|point| JsonNumberPoint {
+                                attributes: attrs_to_json(point.attributes.clone()),
+                                start_time_unix_nano: metrics.start_unix_nano,
+                                time_unix_nano: metrics.time_unix_nano,
+                                as_int: point.value,
+                            })
+                            .collect(),
+                        aggregation_temporality: CUMULATIVE,
+                        is_monotonic: true,
+                    });
+                }
+            }
+            metric
+        })
+        .collect();
+
+    JsonMetricsExport {
+        resource_metrics: vec![JsonResourceMetrics {
+            resource: JsonResource {
+                attributes: attrs_to_json(resource_attrs),
+            },
+            scope_metrics: vec![JsonScopeMetrics {
+                scope: JsonScope {
+                    name: "harlite".to_string(),
+                },
+                metrics: metric_list,
+            }],
+        }],
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMetricsExport {
+    resource_metrics: Vec<JsonResourceMetrics>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonResourceMetrics {
+    resource: JsonResource,
+    scope_metrics: Vec<JsonScopeMetrics>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonResource {
+    attributes: Vec<JsonKeyValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonScopeMetrics {
+    scope: JsonScope,
+    metrics: Vec<JsonMetric>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonScope {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMetric {
+    name: String,
+    description: String,
+    unit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    histogram: Option<JsonHistogram>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sum: Option<JsonSum>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonHistogram {
+    data_points: Vec<JsonHistogramPoint>,
+    aggregation_temporality: i32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonHistogramPoint {
+    attributes: Vec<JsonKeyValue>,
+    start_time_unix_nano: u64,
+    time_unix_nano: u64,
+    count: u64,
+    sum: f64,
+    bucket_counts: Vec<u64>,
+    explicit_bounds: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonSum {
+    data_points: Vec<JsonNumberPoint>,
+    aggregation_temporality: i32,
+    is_monotonic: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonNumberPoint {
+    attributes: Vec<JsonKeyValue>,
+    start_time_unix_nano: u64,
+    time_unix_nano: u64,
+    as_int: i64,
+}
+
+fn build_otlp_request(
+    resource_attrs: Vec<Attribute>,
+    metrics: Metrics,
+) -> proto::ExportMetricsServiceRequest {
+    use opentelemetry_proto::tonic::common::v1::InstrumentationScope;
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+
+    let start_time_unix_nano = metrics.start_unix_nano;
+    let time_unix_nano = metrics.time_unix_nano;
+    let metric_list = metrics
+        .records
+        .into_iter()
+        .filter(|record| record.point_count() > 0)
+        .map(|record| proto::Metric {
+            name: record.name.to_string(),
+            description: record.description.to_string(),
+            unit: record.unit.to_string(),
+            data: Some(match record.data {
+                MetricData::Histogram(points) => proto::metric::Data::Histogram(proto::Histogram {
+                    data_points: points
+                        .into_iter()
+                        .map(|point| proto::HistogramDataPoint {
+                            attributes: attrs_to_proto(point.attributes),
+                            start_time_unix_nano,
+                            time_unix_nano,
+                            count: point.count,
+                            sum: Some(point.sum),
+                            bucket_counts: point.bucket_counts,
+                            explicit_bounds: DURATION_BUCKETS.to_vec(),
+                            min: Some(point.min),
+                            max: Some(point.max),
+                        })
+                        .collect(),
+                    aggregation_temporality: CUMULATIVE,
+                }),
+                MetricData::Counter(points) => proto::metric::Data::Sum(proto::Sum {
+                    data_points: points
+                        .into_iter()
+                        .map(|point| proto::NumberDataPoint {
+                            attributes: attrs_to_proto(point.attributes),
+                            start_time_unix_nano,
+                            time_unix_nano,
+                            value: Some(proto::number_data_point::Value::AsInt(point.value)),
+                        })
+                        .collect(),
+                    aggregation_temporality: CUMULATIVE,
+                    is_monotonic: true,
+                }),
+            }),
+        })
+        .collect();
+
+    proto::ExportMetricsServiceRequest {
+        resource_metrics: vec![proto::ResourceMetrics {
+            resource: Some(Resource {
+                attributes: attrs_to_proto(resource_attrs),
+                dropped_attributes_count: 0,
+            }),
+            scope_metrics: vec![proto::ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: "harlite".to_string(),
+                    version: "".to_string(),
+                    attributes: Vec::new(),
+                    dropped_attributes_count: 0,
+                }),
+                metrics: metric_list,
+                schema_url: "".to_string(),
+            }],
+            schema_url: "".to_string(),
+        }],
+    }
+}
+
+fn send_otlp_grpc(endpoint: &str, request: proto::ExportMetricsServiceRequest) -> Result<()> {
+    use tonic::codegen::http::uri::PathAndQuery;
+
+    let endpoint = grpc_endpoint(endpoint);
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|err| HarliteError::InvalidArgs(format!("Failed to start runtime: {err}")))?;
+    rt.block_on(async move {
+        let channel = tonic::transport::Endpoint::new(endpoint)
+            .map_err(|err| HarliteError::InvalidArgs(format!("OTLP gRPC connect failed: {err}")))?
+            .connect()
+            .await
+            .map_err(|err| HarliteError::InvalidArgs(format!("OTLP gRPC connect failed: {err}")))?;
+        let mut client = tonic::client::Grpc::new(channel);
+        client
+            .ready()
+            .await
+            .map_err(|err| HarliteError::InvalidArgs(format!("OTLP gRPC connect failed: {err}")))?;
+        let path = PathAndQuery::from_static(
+            "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export",
+        );
+        let codec: tonic::codec::ProstCodec<
+            proto::ExportMetricsServiceRequest,
+            proto::ExportMetricsServiceResponse,
+        > = tonic::codec::ProstCodec::default();
+        client
+            .unary(tonic::Request::new(request), path, codec)
+            .await
+            .map_err(|err| HarliteError::InvalidArgs(format!("OTLP gRPC export failed: {err}")))?;
+        Ok(())
+    })
+}
+
+/// The subset of the OTLP metrics protocol harlite emits.
+///
+/// `opentelemetry-proto` only generates these behind its `metrics` feature, which pulls
+/// in the SDK's metrics runtime; the messages below keep the upstream field numbers.
+mod proto {
+    use opentelemetry_proto::tonic::common::v1::{InstrumentationScope, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsServiceRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub resource_metrics: Vec<ResourceMetrics>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsServiceResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceMetrics {
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        #[prost(message, repeated, tag = "2")]
+        pub scope_metrics: Vec<ScopeMetrics>,
+        #[prost(string, tag = "3")]
+        pub schema_url: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeMetrics {
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        #[prost(message, repeated, tag = "2")]
+        pub metrics: Vec<Metric>,
+        #[prost(string, tag = "3")]
+        pub schema_url: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Metric {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub description: String,
+        #[prost(string, tag = "3")]
+        pub unit: String,
+        #[prost(oneof = "metric::Data", tags = "7, 9")]
+        pub data: Option<metric::Data>,
+    }
+
+    pub mod metric {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Data {
+            #[prost(message, tag = "7")]
+            Sum(super::Sum),
+            #[prost(message, tag = "9")]
+            Histogram(super::Histogram),
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sum {
+        #[prost(message, repeated, tag = "1")]
+        pub data_points: Vec<NumberDataPoint>,
+        #[prost(int32, tag = "2")]
+        pub aggregation_temporality: i32,
+        #[prost(bool, tag = "3")]
+        pub is_monotonic: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Histogram {
+        #[prost(message, repeated, tag = "1")]
+        pub data_points: Vec<HistogramDataPoint>,
+        #[prost(int32, tag = "2")]
+        pub aggregation_temporality: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NumberDataPoint {
+        #[prost(message, repeated, tag = "7")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(fixed64, tag = "2")]
+        pub start_time_unix_nano: u64,
+        #[prost(fixed64, tag = "3")]
+        pub time_unix_nano: u64,
+        #[prost(oneof = "number_data_point::Value", tags = "4, 6")]
+        pub value: Option<number_data_point::Value>,
+    }
+
+    pub mod number_data_point {
+        #[derive(Clone, Copy, PartialEq, prost::Oneof)]
+        pub enum Value {
+            #[prost(double, tag = "4")]
+            AsDouble(f64),
+            #[prost(sfixed64, tag = "6")]
+            AsInt(i64),
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HistogramDataPoint {
+        #[prost(message, repeated, tag = "9")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(fixed64, tag = "2")]
+        pub start_time_unix_nano: u64,
+        #[prost(fixed64, tag = "3")]
+        pub time_unix_nano: u64,
+        #[prost(fixed64, tag = "4")]
+        pub count: u64,
+        #[prost(double, optional, tag = "5")]
+        pub sum: Option<f64>,
+        #[prost(fixed64, repeated, tag = "6")]
+        pub bucket_counts: Vec<u64>,
+        #[prost(double, repeated, tag = "7")]
+        pub explicit_bounds: Vec<f64>,
+        #[prost(double, optional, tag = "11")]
+        pub min: Option<f64>,
+        #[prost(double, optional, tag = "12")]
+        pub max: Option<f64>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_land_in_buckets_and_render_cumulatively() {
+        let mut series = Series::default();
+        for seconds in [0.004, 0.2, 0.2, 30.0] {
+            record_duration(&mut series, seconds);
+        }
+        let mut point = series.duration.unwrap();
+        assert_eq!(point.count, 4);
+        assert_eq!(point.bucket_counts[0], 1);
+        assert_eq!(point.bucket_counts[6], 2);
+        assert_eq!(point.bucket_counts[DURATION_BUCKETS.len()], 1);
+        assert_eq!((point.min, point.max), (0.004, 30.0));
+
+        point.attributes = vec![string_attr("http.route", "/say \"hi\"")];
+        let metrics = Metrics {
+            start_unix_nano: 0,
+            time_unix_nano: 0,
+            records: vec![MetricRecord {
+                name: "http.client.request.duration",
+                prometheus_name: "http_client_request_duration_seconds",
+                description: "Duration",
+                unit: "s",
+                data: MetricData::Histogram(vec![point]),
+            }],
+        };
+        let resource = vec![
+            string_attr("service.name", "harlite"),
+            string_attr("deployment.environment", "staging"),
+        ];
+        let text = render_prometheus(&metrics, &resource);
+        assert!(text.contains(
+            "# TYPE target_info gauge\ntarget_info{job=\"harlite\",deployment_environment=\"staging\"} 1\n"
+        ));
+        assert!(text.contains("# TYPE http_client_request_duration_seconds histogram\n"));
+        assert!(text.contains(
+            "http_client_request_duration_seconds_bucket{job=\"harlite\",http_route=\"/say \\\"hi\\\"\",le=\"0.25\"} 3\n"
+        ));
+        assert!(text.contains(
+            "http_client_request_duration_seconds_bucket{job=\"harlite\",http_route=\"/say \\\"hi\\\"\",le=\"+Inf\"} 4\n"
+        ));
+    }
+
+    #[test]
+    fn status_classes() {
+        assert_eq!(status_class(Some(204)), "2xx");
+        assert_eq!(status_class(Some(503)), "5xx");
+        assert_eq!(status_class(Some(0)), "none");
+        assert_eq!(status_class(None), "none");
+    }
+}
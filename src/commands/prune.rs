@@ -1,19 +1,17 @@
-use std::fs;
 use std::path::PathBuf;
 
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{params, OptionalExtension};
 
+use crate::db::{ensure_schema_upgrades, open_database};
 use crate::error::{HarliteError, Result};
 
-const HASH_CHUNK: usize = 500;
+use super::scrub::{delete_blobs, external_root, orphaned_blob_hashes, ExternalFiles};
 
 /// Remove all records for a specific import and prune orphaned blobs.
 pub fn run_prune(database: PathBuf, import_id: i64) -> Result<()> {
-    let conn = Connection::open(&database)?;
-    let external_root = database
-        .parent()
-        .map(|p| p.to_path_buf())
-        .and_then(|p| p.canonicalize().ok());
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+    let external_root = external_root(&database);
 
     let import_exists: Option<String> = conn
         .query_row(
@@ -36,7 +34,11 @@ pub fn run_prune(database: PathBuf, import_id: i64) -> Result<()> {
              UNION\n\
              SELECT DISTINCT response_body_hash FROM entries WHERE import_id = ?1 AND response_body_hash IS NOT NULL\n\
              UNION\n\
-             SELECT DISTINCT response_body_hash_raw FROM entries WHERE import_id = ?1 AND response_body_hash_raw IS NOT NULL",
+             SELECT DISTINCT response_body_hash_raw FROM entries WHERE import_id = ?1 AND response_body_hash_raw IS NOT NULL\n\
+             UNION\n\
+             SELECT DISTINCT graphql_query_hash FROM entries WHERE import_id = ?1 AND graphql_query_hash IS NOT NULL\n\
+             UNION\n\
+             SELECT DISTINCT graphql_variables_hash FROM entries WHERE import_id = ?1 AND graphql_variables_hash IS NOT NULL",
         )?;
         let hashes = stmt
             .query_map(params![import_id], |row| row.get(0))?
@@ -45,6 +47,15 @@ pub fn run_prune(database: PathBuf, import_id: i64) -> Result<()> {
         hashes
     };
 
+    for table in ["graphql_fields", "graphql_field_paths"] {
+        tx.execute(
+            &format!(
+                "DELETE FROM {table} WHERE entry_id IN (SELECT id FROM entries WHERE import_id = ?1)"
+            ),
+            params![import_id],
+        )?;
+    }
+
     let entries_deleted = tx.execute(
         "DELETE FROM entries WHERE import_id = ?1",
         params![import_id],
@@ -52,110 +63,18 @@ pub fn run_prune(database: PathBuf, import_id: i64) -> Result<()> {
     let pages_deleted = tx.execute("DELETE FROM pages WHERE import_id = ?1", params![import_id])?;
     let imports_deleted = tx.execute("DELETE FROM imports WHERE id = ?1", params![import_id])?;
 
-    let mut blobs_deleted = 0usize;
-    let mut fts_deleted = 0usize;
-    let mut external_deleted = 0usize;
-    let mut external_skipped = 0usize;
-
-    if !hashes.is_empty() {
-        let has_fts: i64 = tx.query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='response_body_fts'",
-            [],
-            |row| row.get(0),
-        )?;
-
-        for chunk in hashes.chunks(HASH_CHUNK) {
-            let placeholders = std::iter::repeat("?")
-                .take(chunk.len())
-                .collect::<Vec<_>>()
-                .join(", ");
-
-            let mut params_vec: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(chunk.len());
-            for hash in chunk {
-                params_vec.push(hash);
-            }
-
-            let sql_orphans = format!(
-                "SELECT hash FROM blobs\n\
-                 WHERE hash IN ({placeholders})\n\
-                 AND NOT EXISTS (\n\
-                     SELECT 1 FROM entries e\n\
-                     WHERE e.request_body_hash = blobs.hash\n\
-                        OR e.response_body_hash = blobs.hash\n\
-                        OR e.response_body_hash_raw = blobs.hash\n\
-                 )"
-            );
-
-            let orphan_hashes: Vec<String> = tx
-                .prepare(&sql_orphans)?
-                .query_map(params_vec.as_slice(), |row| row.get(0))?
-                .filter_map(|row| row.ok())
-                .collect();
-
-            if orphan_hashes.is_empty() {
-                continue;
-            }
-
-            let orphan_placeholders = std::iter::repeat("?")
-                .take(orphan_hashes.len())
-                .collect::<Vec<_>>()
-                .join(", ");
-            let mut orphan_params: Vec<&dyn rusqlite::ToSql> =
-                Vec::with_capacity(orphan_hashes.len());
-            for hash in &orphan_hashes {
-                orphan_params.push(hash);
-            }
-
-            let external_paths: Vec<String> = tx
-                .prepare(&format!(
-                    "SELECT external_path FROM blobs WHERE hash IN ({orphan_placeholders}) AND external_path IS NOT NULL"
-                ))?
-                .query_map(orphan_params.as_slice(), |row| row.get(0))?
-                .filter_map(|row| row.ok())
-                .collect();
-
-            for raw_path in external_paths {
-                let candidate = PathBuf::from(&raw_path);
-                let resolved = if candidate.is_absolute() {
-                    candidate.canonicalize().ok()
-                } else if let Some(root) = external_root.as_ref() {
-                    let joined = root.join(&candidate);
-                    let resolved = joined.canonicalize().ok();
-                    if let Some(resolved_path) = resolved.as_ref() {
-                        if !resolved_path.starts_with(root) {
-                            external_skipped += 1;
-                            continue;
-                        }
-                    }
-                    resolved
-                } else {
-                    external_skipped += 1;
-                    continue;
-                };
-
-                let Some(path) = resolved else {
-                    continue;
-                };
-                if path.is_file() && fs::remove_file(&path).is_ok() {
-                    external_deleted += 1;
-                }
-            }
-
-            if has_fts > 0 {
-                let fts_sql =
-                    format!("DELETE FROM response_body_fts WHERE hash IN ({orphan_placeholders})");
-                fts_deleted += tx.execute(&fts_sql, orphan_params.as_slice())?;
-            }
-
-            let blobs_sql = format!("DELETE FROM blobs WHERE hash IN ({orphan_placeholders})");
-            blobs_deleted += tx.execute(&blobs_sql, orphan_params.as_slice())?;
-        }
-    }
+    let orphans = orphaned_blob_hashes(&tx, &hashes)?;
+    let mut scrubbed = delete_blobs(&tx, &orphans, external_root.as_deref())?;
 
     tx.commit()?;
+    scrubbed.remove_external_files(&conn, ExternalFiles::Remove)?;
 
     println!(
-        "Pruned import {import_id} ({source_file}). Removed {imports_deleted} import record, {entries_deleted} entries, {pages_deleted} pages, {blobs_deleted} blobs, {fts_deleted} FTS rows, deleted {external_deleted} external files (skipped {external_skipped})."
+        "Pruned import {import_id} ({source_file}). Removed {imports_deleted} import record, {entries_deleted} entries, {pages_deleted} pages, {} blobs, {} FTS rows, deleted {} external files (skipped {}).",
+        scrubbed.blobs_deleted,
+        scrubbed.fts_deleted,
+        scrubbed.external_deleted,
+        scrubbed.external_skipped
     );
 
     Ok(())
@@ -0,0 +1,399 @@
+//! Deterministic keyed pseudonyms for `redact` and `pii`.
+//!
+//! Every replacement is derived from a keyed BLAKE3 MAC of the original value, so
+//! the same input always maps to the same pseudonym under the same key. That keeps
+//! joins and grouping working on a redacted database without exposing the values.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{HarliteError, Result};
+
+/// Environment variable consulted when no `--pseudonym-key-file` is given.
+const PSEUDONYM_KEY_ENV: &str = "HARLITE_PSEUDONYM_KEY";
+
+const KEY_CONTEXT: &str = "harlite pseudonymize v1";
+const GENERIC_PREFIX: &str = "tok_";
+const GENERIC_HEX_LEN: usize = 16;
+const EMAIL_PREFIX: &str = "user_";
+const EMAIL_HEX_LEN: usize = 12;
+const EMAIL_DOMAIN: &str = "example.invalid";
+/// Exchange and line prefix of the NANP numbers reserved for fiction (555-0100..0199).
+const PHONE_MARKER: [u8; 5] = [5, 5, 5, 0, 1];
+/// Digits after the network digit of a pseudonymous card number; no issuer uses them.
+const CARD_MARKER: [u8; 4] = [0, 0, 0, 0];
+
+/// The shape of a value being replaced, which decides the output format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ValueKind {
+    Generic,
+    Email,
+    Phone,
+    CreditCard,
+    Ssn,
+}
+
+impl ValueKind {
+    fn tag(self) -> &'static [u8] {
+        match self {
+            ValueKind::Generic => b"value",
+            ValueKind::Email => b"email",
+            ValueKind::Phone => b"phone",
+            ValueKind::CreditCard => b"credit_card",
+            ValueKind::Ssn => b"ssn",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Pseudonymizer {
+    key: [u8; 32],
+}
+
+impl Pseudonymizer {
+    pub(super) fn new(secret: &[u8]) -> Result<Self> {
+        if secret.is_empty() {
+            return Err(HarliteError::InvalidArgs(
+                "Pseudonymization key must not be empty".to_string(),
+            ));
+        }
+        Ok(Self {
+            key: blake3::derive_key(KEY_CONTEXT, secret),
+        })
+    }
+
+    /// Load the key from `key_file`, falling back to `HARLITE_PSEUDONYM_KEY`.
+    pub(super) fn from_key_source(key_file: Option<&Path>) -> Result<Self> {
+        let secret = match key_file {
+            Some(path) => {
+                let bytes = fs::read(path).map_err(|err| {
+                    HarliteError::InvalidArgs(format!(
+                        "Failed to read pseudonymization key {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+                trim_trailing_newlines(bytes)
+            }
+            None => match std::env::var(PSEUDONYM_KEY_ENV) {
+                Ok(value) if !value.is_empty() => value.into_bytes(),
+                _ => {
+                    return Err(HarliteError::InvalidArgs(format!(
+                        "--pseudonymize requires --pseudonym-key-file or {}",
+                        PSEUDONYM_KEY_ENV
+                    )))
+                }
+            },
+        };
+        Self::new(&secret)
+    }
+
+    pub(super) fn pseudonymize(&self, value: &str, kind: ValueKind) -> String {
+        match kind {
+            ValueKind::Generic => self.generic(value),
+            ValueKind::Email => {
+                let normalized = value.trim().to_lowercase();
+                let hex = self.hex(kind, normalized.as_bytes(), EMAIL_HEX_LEN);
+                format!("{EMAIL_PREFIX}{hex}@{EMAIL_DOMAIN}")
+            }
+            ValueKind::Phone => self.phone(value),
+            ValueKind::CreditCard => self.credit_card(value),
+            ValueKind::Ssn => {
+                let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+                let mut fresh = self.digits(kind, digits.as_bytes(), digits.len());
+                // Area numbers 900-999 are never issued as SSNs and group 00 is never
+                // issued at all (not even as an ITIN), so the output can't be a real one.
+                if fresh.len() == 9 {
+                    fresh[0] = 9;
+                    fresh[3] = 0;
+                    fresh[4] = 0;
+                }
+                replace_digits(value, &fresh)
+            }
+        }
+    }
+
+    /// Whether `value` already has the shape of a pseudonym for `kind`.
+    pub(super) fn is_pseudonym(value: &str, kind: ValueKind) -> bool {
+        match kind {
+            ValueKind::Generic => {
+                let credential = split_auth_scheme(value).map_or(value, |(_, rest)| rest);
+                is_prefixed_hex(credential, GENERIC_PREFIX, GENERIC_HEX_LEN)
+            }
+            ValueKind::Email => value
+                .strip_suffix(EMAIL_DOMAIN)
+                .and_then(|local| local.strip_suffix('@'))
+                .is_some_and(|local| is_prefixed_hex(local, EMAIL_PREFIX, EMAIL_HEX_LEN)),
+            ValueKind::Phone => {
+                let digits = ascii_digits(value);
+                digits.len() >= 7 && digits[digits.len() - 7..digits.len() - 2] == PHONE_MARKER
+            }
+            ValueKind::CreditCard => {
+                let digits = ascii_digits(value);
+                digits.len() > CARD_MARKER.len() + 1
+                    && digits[1..=CARD_MARKER.len()] == CARD_MARKER
+                    && !is_luhn_valid(&digits)
+            }
+            ValueKind::Ssn => {
+                let digits = ascii_digits(value);
+                digits.len() == 9 && digits[0] == 9 && digits[3..5] == [0, 0]
+            }
+        }
+    }
+
+    fn generic(&self, value: &str) -> String {
+        // Keep an HTTP auth scheme so `Bearer <secret>` and a bare `<secret>` elsewhere
+        // still map to the same pseudonym.
+        if let Some((scheme, credential)) = split_auth_scheme(value) {
+            let hex = self.hex(ValueKind::Generic, credential.as_bytes(), GENERIC_HEX_LEN);
+            return format!("{scheme} {GENERIC_PREFIX}{hex}");
+        }
+        let hex = self.hex(ValueKind::Generic, value.as_bytes(), GENERIC_HEX_LEN);
+        format!("{GENERIC_PREFIX}{hex}")
+    }
+
+    fn phone(&self, value: &str) -> String {
+        let digits = ascii_digits(value);
+        // Keep a leading North American country code so the number keeps its shape.
+        let keep = usize::from(digits.len() == 11 && digits[0] == 1);
+        let subscriber: String = digits[keep..]
+            .iter()
+            .map(|d| char::from(b'0' + d))
+            .collect();
+        let mut fresh = digits[..keep].to_vec();
+        let derived = self.digits(ValueKind::Phone, subscriber.as_bytes(), digits.len() - keep);
+        fresh.extend(derived);
+        if let Some(first) = fresh.get_mut(keep) {
+            *first = 2 + *first % 8;
+        }
+        // End in 555-01xx, the range reserved for fictional numbers, so the output is
+        // never a reachable number and is recognised on later runs.
+        if fresh.len() >= 7 {
+            let start = fresh.len() - 7;
+            fresh[start..start + PHONE_MARKER.len()].copy_from_slice(&PHONE_MARKER);
+        }
+        replace_digits(value, &fresh)
+    }
+
+    fn credit_card(&self, value: &str) -> String {
+        let digits = ascii_digits(value);
+        if digits.len() <= CARD_MARKER.len() + 1 {
+            let fresh = self.digits(ValueKind::CreditCard, value.as_bytes(), digits.len());
+            return replace_digits(value, &fresh);
+        }
+        let normalized: String = digits.iter().map(|d| char::from(b'0' + d)).collect();
+        // Keep the network digit and mark the issuer digits, then force a failing
+        // checksum so the output is never a usable card number and is not re-detected
+        // as one.
+        let mut fresh = vec![digits[0]];
+        fresh.extend(CARD_MARKER);
+        fresh.extend(self.digits(
+            ValueKind::CreditCard,
+            normalized.as_bytes(),
+            digits.len() - 1 - CARD_MARKER.len(),
+        ));
+        let last = fresh.len() - 1;
+        fresh[last] = (luhn_check_digit(&fresh[..last]) + 1) % 10;
+        replace_digits(value, &fresh)
+    }
+
+    fn hasher(&self, kind: ValueKind, value: &[u8]) -> blake3::Hasher {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        hasher.update(kind.tag());
+        hasher.update(&[0]);
+        hasher.update(value);
+        hasher
+    }
+
+    fn hex(&self, kind: ValueKind, value: &[u8], len: usize) -> String {
+        let hash = self.hasher(kind, value).finalize();
+        hash.to_hex()[..len].to_string()
+    }
+
+    fn digits(&self, kind: ValueKind, value: &[u8], len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; len];
+        self.hasher(kind, value).finalize_xof().fill(&mut buf);
+        buf.iter().map(|b| b % 10).collect()
+    }
+}
+
+/// What to write in place of a sensitive value.
+#[derive(Clone)]
+pub(super) enum Replacement {
+    Token(String),
+    Pseudonym(Pseudonymizer),
+}
+
+impl Replacement {
+    pub(super) fn new(token: &str, pseudonymize: bool, key_file: Option<&Path>) -> Result<Self> {
+        if pseudonymize {
+            Ok(Self::Pseudonym(Pseudonymizer::from_key_source(key_file)?))
+        } else {
+            Ok(Self::Token(token.to_string()))
+        }
+    }
+
+    pub(super) fn apply(&self, value: &str, kind: ValueKind) -> String {
+        match self {
+            Self::Token(token) => token.clone(),
+            Self::Pseudonym(p) => p.pseudonymize(value, kind),
+        }
+    }
+
+    /// Whether `value` was already written by this replacement and should be left alone.
+    pub(super) fn is_applied(&self, value: &str, kind: ValueKind) -> bool {
+        match self {
+            Self::Token(token) => value == token,
+            Self::Pseudonym(_) => Pseudonymizer::is_pseudonym(value, kind),
+        }
+    }
+}
+
+fn trim_trailing_newlines(mut bytes: Vec<u8>) -> Vec<u8> {
+    while matches!(bytes.last(), Some(b'\n' | b'\r')) {
+        bytes.pop();
+    }
+    bytes
+}
+
+fn split_auth_scheme(value: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = value.split_once(' ')?;
+    let rest = rest.trim_start();
+    let is_scheme = !scheme.is_empty()
+        && scheme.len() <= 16
+        && scheme.bytes().all(|b| b.is_ascii_alphabetic() || b == b'-');
+    if is_scheme && !rest.is_empty() && !rest.contains(' ') {
+        Some((scheme, rest))
+    } else {
+        None
+    }
+}
+
+fn is_prefixed_hex(value: &str, prefix: &str, len: usize) -> bool {
+    value.strip_prefix(prefix).is_some_and(|hex| {
+        hex.len() == len && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+fn ascii_digits(value: &str) -> Vec<u8> {
+    value
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .map(|b| b - b'0')
+        .collect()
+}
+
+/// Replace each ASCII digit in `template` with the next digit from `digits`.
+fn replace_digits(template: &str, digits: &[u8]) -> String {
+    let mut next = digits.iter().copied();
+    template
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                next.next().map_or(c, |d| char::from(b'0' + d))
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn is_luhn_valid(digits: &[u8]) -> bool {
+    digits
+        .split_last()
+        .is_some_and(|(last, payload)| luhn_check_digit(payload) == *last)
+}
+
+fn luhn_check_digit(payload: &[u8]) -> u8 {
+    let mut sum = 0u32;
+    for (i, digit) in payload.iter().rev().enumerate() {
+        let mut val = u32::from(*digit);
+        if i % 2 == 0 {
+            val *= 2;
+            if val > 9 {
+                val -= 9;
+            }
+        }
+        sum += val;
+    }
+    ((10 - sum % 10) % 10) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudonymizer() -> Pseudonymizer {
+        Pseudonymizer::new(b"test key").expect("key")
+    }
+
+    #[test]
+    fn pseudonyms_are_stable_and_keyed() {
+        let p = pseudonymizer();
+        let a = p.pseudonymize("Alice@Example.com", ValueKind::Email);
+        let b = p.pseudonymize("alice@example.com", ValueKind::Email);
+        assert_eq!(a, b);
+        assert!(a.starts_with("user_") && a.ends_with("@example.invalid"));
+        assert!(Pseudonymizer::is_pseudonym(&a, ValueKind::Email));
+
+        let other = Pseudonymizer::new(b"other key").expect("key");
+        assert_ne!(a, other.pseudonymize("alice@example.com", ValueKind::Email));
+    }
+
+    #[test]
+    fn generic_values_keep_auth_scheme() {
+        let p = pseudonymizer();
+        let header = p.pseudonymize("Bearer abc123", ValueKind::Generic);
+        let bare = p.pseudonymize("abc123", ValueKind::Generic);
+        assert_eq!(header, format!("Bearer {bare}"));
+        assert!(Pseudonymizer::is_pseudonym(&header, ValueKind::Generic));
+        assert!(!Pseudonymizer::is_pseudonym("abc123", ValueKind::Generic));
+    }
+
+    #[test]
+    fn numbers_keep_their_format() {
+        let p = pseudonymizer();
+
+        let phone = p.pseudonymize("+1 (415) 555-1212", ValueKind::Phone);
+        assert!(phone.starts_with("+1 ("));
+        assert_eq!(phone.len(), "+1 (415) 555-1212".len());
+        assert_ne!(phone, "+1 (415) 555-1212");
+        assert!(phone[phone.len() - 8..].starts_with("555-01"));
+        assert!(Pseudonymizer::is_pseudonym(&phone, ValueKind::Phone));
+        assert!(!Pseudonymizer::is_pseudonym(
+            "+1 (415) 555-1212",
+            ValueKind::Phone
+        ));
+
+        let card = p.pseudonymize("4111 1111 1111 1111", ValueKind::CreditCard);
+        assert!(card.starts_with('4'));
+        assert_eq!(card.matches(' ').count(), 3);
+        assert_eq!(card.len(), 19);
+        let payload: Vec<u8> = card
+            .bytes()
+            .filter(u8::is_ascii_digit)
+            .map(|b| b - b'0')
+            .collect();
+        assert!(!is_luhn_valid(&payload));
+        assert!(card.starts_with("4000 0"));
+        assert!(Pseudonymizer::is_pseudonym(&card, ValueKind::CreditCard));
+        assert!(!Pseudonymizer::is_pseudonym(
+            "4000 0566 5566 5556",
+            ValueKind::CreditCard
+        ));
+
+        let ssn = p.pseudonymize("123-45-6789", ValueKind::Ssn);
+        assert!(ssn.starts_with('9'));
+        assert_eq!(ssn.as_bytes()[3], b'-');
+        assert_eq!(ssn.as_bytes()[6], b'-');
+        assert_eq!(&ssn[4..6], "00");
+        assert!(Pseudonymizer::is_pseudonym(&ssn, ValueKind::Ssn));
+        assert!(!Pseudonymizer::is_pseudonym("123-45-6789", ValueKind::Ssn));
+        assert!(!Pseudonymizer::is_pseudonym("912-70-1234", ValueKind::Ssn));
+    }
+
+    #[test]
+    fn empty_key_is_rejected() {
+        assert!(Pseudonymizer::new(b"").is_err());
+    }
+}
@@ -5,6 +5,7 @@ use clap::ValueEnum;
 use rusqlite::types::{Value, ValueRef};
 use rusqlite::{params_from_iter, Connection, OpenFlags};
 
+use crate::db::open_database_with_flags;
 use crate::error::{HarliteError, Result};
 
 use super::util::resolve_database;
@@ -35,7 +36,7 @@ pub struct QueryOptions {
 }
 
 pub fn open_readonly_connection(database: &Path) -> Result<Connection> {
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         database,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
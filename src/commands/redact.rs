@@ -1,15 +1,26 @@
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::ValueEnum;
-use regex::{NoExpand, Regex, RegexBuilder};
+use regex::{Regex, RegexBuilder};
 use rusqlite::{params, Connection, OptionalExtension};
 use url::Url;
 
-use crate::db::store_blob;
+use crate::db::{
+    ensure_schema_upgrades, open_database, read_external_blob, store_blob, write_sibling_blob,
+};
 use crate::error::{HarliteError, Result};
-
+use crate::har::{Cookie, Entry, Header};
+
+use super::body_fields::{detect_body_format, rewrite_body_values};
+use super::json_path::{format_path, JsonPath, PathSegment};
+use super::inline_redact::EntryBodies;
+use super::pseudonym::{Replacement, ValueKind};
+use super::redact_policy::{load_policy, LoadedPolicy, PolicyRule};
+use super::scrub::{
+    compact_database, delete_blobs, external_root, orphaned_blob_hashes, ExternalFiles,
+};
 use super::util::{canonicalize_path_for_compare, resolve_database};
 
 #[derive(Clone, Copy, Debug, ValueEnum, serde::Serialize, serde::Deserialize)]
@@ -20,6 +31,7 @@ pub enum NameMatchMode {
     Regex,
 }
 
+#[derive(Clone)]
 pub struct RedactOptions {
     pub output: Option<PathBuf>,
     pub force: bool,
@@ -29,12 +41,18 @@ pub struct RedactOptions {
     pub cookies: Vec<String>,
     pub query_params: Vec<String>,
     pub body_regexes: Vec<String>,
+    pub body_paths: Vec<String>,
+    pub body_fields: Vec<String>,
     pub match_mode: NameMatchMode,
     pub token: String,
+    pub pseudonymize: bool,
+    pub pseudonym_key_file: Option<PathBuf>,
+    pub policy: Option<PathBuf>,
+    pub verify: bool,
 }
 
 #[derive(Default)]
-struct RedactionReport {
+pub(super) struct RedactionReport {
     entries_scanned: u64,
     entries_changed: u64,
     request_headers: u64,
@@ -45,19 +63,44 @@ struct RedactionReport {
     request_bodies: u64,
     response_bodies: u64,
     body_matches: u64,
+    body_fields: u64,
     matched_header_names: HashSet<String>,
     matched_cookie_names: HashSet<String>,
     matched_query_param_names: HashSet<String>,
+    matched_body_fields: HashSet<String>,
+    /// Hashes of blobs that rewritten entries no longer point to.
+    replaced_blobs: HashSet<String>,
 }
 
 impl RedactionReport {
-    fn total(&self) -> u64 {
+    pub(super) fn total(&self) -> u64 {
         self.request_headers
             + self.response_headers
             + self.request_cookies
             + self.response_cookies
             + self.query_params
             + self.body_matches
+            + self.body_fields
+    }
+
+    pub(super) fn merge(&mut self, other: RedactionReport) {
+        self.entries_scanned += other.entries_scanned;
+        self.entries_changed += other.entries_changed;
+        self.request_headers += other.request_headers;
+        self.response_headers += other.response_headers;
+        self.request_cookies += other.request_cookies;
+        self.response_cookies += other.response_cookies;
+        self.query_params += other.query_params;
+        self.request_bodies += other.request_bodies;
+        self.response_bodies += other.response_bodies;
+        self.body_matches += other.body_matches;
+        self.body_fields += other.body_fields;
+        self.matched_header_names.extend(other.matched_header_names);
+        self.matched_cookie_names.extend(other.matched_cookie_names);
+        self.matched_query_param_names
+            .extend(other.matched_query_param_names);
+        self.matched_body_fields.extend(other.matched_body_fields);
+        self.replaced_blobs.extend(other.replaced_blobs);
     }
 }
 
@@ -157,14 +200,264 @@ fn default_header_patterns() -> Vec<String> {
     ]
 }
 
+/// Whether a header name matches one of the built-in sensitive header patterns.
+pub(super) fn is_default_sensitive_header(name: &str) -> bool {
+    let name = name.to_lowercase();
+    default_header_patterns()
+        .iter()
+        .any(|pattern| wildcard_match(pattern, &name))
+}
+
+/// Name fragments of query parameters, headers and fields that usually carry credentials.
+const CREDENTIAL_NAME_PATTERNS: [&str; 14] = [
+    "*token*",
+    "*secret*",
+    "*passw*",
+    "*pwd*",
+    "*key*",
+    "*session*",
+    "*sid",
+    "*auth*",
+    "*credential*",
+    "*signature*",
+    "sig",
+    "*csrf*",
+    "*xsrf*",
+    "*nonce*",
+];
+
+/// Whether a header, query parameter or field name looks like it carries a credential.
+pub(super) fn is_credential_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    is_default_sensitive_header(&name)
+        || CREDENTIAL_NAME_PATTERNS
+            .iter()
+            .any(|pattern| wildcard_match(pattern, &name))
+}
+
 fn default_cookie_patterns() -> Vec<String> {
     vec!["*".to_string()]
 }
 
+/// How stored bodies are redacted: structured fields first, then raw-text regexes.
+struct BodyRules {
+    paths: Vec<JsonPath>,
+    fields: NameMatcher,
+    regexes: Vec<Regex>,
+}
+
+impl BodyRules {
+    fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.fields.is_empty() && self.regexes.is_empty()
+    }
+
+    fn has_structured(&self) -> bool {
+        !self.paths.is_empty() || !self.fields.is_empty()
+    }
+
+    fn matches_field(&self, path: &[PathSegment]) -> bool {
+        if self.paths.iter().any(|p| p.matches(path)) {
+            return true;
+        }
+        matches!(path.last(), Some(PathSegment::Key(key)) if self.fields.matches(key))
+    }
+}
+
+/// Matchers merged from every policy rule that applies to an entry.
+pub(super) struct RuleSet {
+    headers: NameMatcher,
+    cookies: NameMatcher,
+    query_params: NameMatcher,
+    body: BodyRules,
+}
+
+impl RuleSet {
+    fn compile<'a>(
+        mode: NameMatchMode,
+        rules: impl IntoIterator<Item = &'a PolicyRule>,
+    ) -> Result<Self> {
+        let mut merged = PolicyRule::default();
+        for rule in rules {
+            merged.header.extend(rule.header.iter().cloned());
+            merged.cookie.extend(rule.cookie.iter().cloned());
+            merged.query_param.extend(rule.query_param.iter().cloned());
+            merged.body_path.extend(rule.body_path.iter().cloned());
+            merged.body_field.extend(rule.body_field.iter().cloned());
+            merged.body_regex.extend(rule.body_regex.iter().cloned());
+        }
+
+        Ok(Self {
+            headers: NameMatcher::new(mode, &merged.header)?,
+            cookies: NameMatcher::new(mode, &merged.cookie)?,
+            query_params: NameMatcher::new(mode, &merged.query_param)?,
+            body: BodyRules {
+                paths: merged
+                    .body_path
+                    .iter()
+                    .map(|p| JsonPath::parse(p))
+                    .collect::<Result<Vec<_>>>()?,
+                fields: NameMatcher::new(mode, &merged.body_field)?,
+                regexes: merged
+                    .body_regex
+                    .iter()
+                    .map(|p| Regex::new(p))
+                    .collect::<std::result::Result<Vec<_>, _>>()?,
+            },
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+            && self.cookies.is_empty()
+            && self.query_params.is_empty()
+            && self.body.is_empty()
+    }
+
+    /// Redact a HAR entry before it is stored (used by inline redaction).
+    pub(super) fn redact_entry(
+        &self,
+        entry: &mut Entry,
+        bodies: &mut EntryBodies,
+        replacement: &Replacement,
+        report: &mut RedactionReport,
+    ) {
+        report.request_headers += redact_header_list(
+            &mut entry.request.headers,
+            &self.headers,
+            replacement,
+            &mut report.matched_header_names,
+        );
+        report.response_headers += redact_header_list(
+            &mut entry.response.headers,
+            &self.headers,
+            replacement,
+            &mut report.matched_header_names,
+        );
+        if let Some(cookies) = entry.request.cookies.as_mut() {
+            report.request_cookies += redact_cookie_list(
+                cookies,
+                &self.cookies,
+                replacement,
+                &mut report.matched_cookie_names,
+            );
+        }
+        if let Some(cookies) = entry.response.cookies.as_mut() {
+            report.response_cookies += redact_cookie_list(
+                cookies,
+                &self.cookies,
+                replacement,
+                &mut report.matched_cookie_names,
+            );
+        }
+
+        if !self.query_params.is_empty() {
+            if let Some((url, _, n)) = redact_url_params(
+                &entry.request.url,
+                &self.query_params,
+                replacement,
+                &mut report.matched_query_param_names,
+            ) {
+                entry.request.url = url;
+                report.query_params += n;
+            }
+            // Take queryString values from the redacted URL so each parameter is counted once,
+            // as standalone `redact` (which only sees the URL) counts it.
+            let mut url_pairs: Vec<(String, String)> = Url::parse(&entry.request.url)
+                .map(|url| {
+                    url.query_pairs()
+                        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            for param in entry.request.query_string.iter_mut().flatten() {
+                if !self.query_params.matches(&param.name) {
+                    continue;
+                }
+                if let Some(pos) = url_pairs.iter().position(|(name, _)| *name == param.name) {
+                    param.value = url_pairs.remove(pos).1;
+                } else if !replacement.is_applied(&param.value, ValueKind::Generic) {
+                    report.matched_query_param_names.insert(param.name.clone());
+                    param.value = replacement.apply(&param.value, ValueKind::Generic);
+                    report.query_params += 1;
+                }
+            }
+        }
+
+        if self.body.is_empty() {
+            return;
+        }
+        let body_columns = [
+            (bodies.request.as_mut(), &mut report.request_bodies),
+            (bodies.response.as_mut(), &mut report.response_bodies),
+        ];
+        for (body, counter) in body_columns {
+            let Some(body) = body else {
+                continue;
+            };
+            if let Some((out, fields, matches)) =
+                redact_text_body(&body.text, body.mime_type.as_deref(), &self.body, replacement)
+            {
+                *counter += 1;
+                report.body_matches += matches;
+                report.body_fields += fields.len() as u64;
+                report.matched_body_fields.extend(fields);
+                body.replace(out);
+            }
+        }
+    }
+}
+
+fn redact_header_list(
+    headers: &mut [Header],
+    matcher: &NameMatcher,
+    replacement: &Replacement,
+    matched_names: &mut HashSet<String>,
+) -> u64 {
+    let mut changed = 0u64;
+    for header in headers.iter_mut() {
+        if !matcher.matches(&header.name)
+            || replacement.is_applied(&header.value, ValueKind::Generic)
+        {
+            continue;
+        }
+        header.value = replacement.apply(&header.value, ValueKind::Generic);
+        matched_names.insert(header.name.clone());
+        changed += 1;
+    }
+    changed
+}
+
+fn redact_cookie_list(
+    cookies: &mut [Cookie],
+    matcher: &NameMatcher,
+    replacement: &Replacement,
+    matched_names: &mut HashSet<String>,
+) -> u64 {
+    let mut changed = 0u64;
+    for cookie in cookies.iter_mut() {
+        if cookie.name.is_empty()
+            || !matcher.matches(&cookie.name)
+            || replacement.is_applied(&cookie.value, ValueKind::Generic)
+        {
+            continue;
+        }
+        cookie.value = replacement.apply(&cookie.value, ValueKind::Generic);
+        matched_names.insert(cookie.name.clone());
+        changed += 1;
+    }
+    changed
+}
+
+/// Rules and blob cache for one combination of applicable policy rules.
+struct ScopeState {
+    rules: RuleSet,
+    blobs: HashMap<String, Option<RedactedBlob>>,
+}
+
 fn redact_headers_json(
     json: &str,
     matcher: &NameMatcher,
-    token: &str,
+    replacement: &Replacement,
     matched_names: &mut HashSet<String>,
 ) -> Result<(String, u64)> {
     let mut value: serde_json::Value = serde_json::from_str(json)?;
@@ -179,11 +472,11 @@ fn redact_headers_json(
         }
 
         let cur = v.as_str().unwrap_or_default();
-        if cur == token {
+        if replacement.is_applied(cur, ValueKind::Generic) {
             continue;
         }
 
-        *v = serde_json::Value::String(token.to_string());
+        *v = serde_json::Value::String(replacement.apply(cur, ValueKind::Generic));
         changed += 1;
         matched_names.insert(name.to_string());
     }
@@ -197,7 +490,7 @@ fn redact_headers_json(
 fn redact_cookies_json(
     json: &str,
     matcher: &NameMatcher,
-    token: &str,
+    replacement: &Replacement,
     matched_names: &mut HashSet<String>,
 ) -> Result<(String, u64)> {
     let mut value: serde_json::Value = serde_json::from_str(json)?;
@@ -219,18 +512,16 @@ fn redact_cookies_json(
             continue;
         }
 
-        let is_already_token = obj
+        let cur = obj
             .get("value")
             .and_then(|v| v.as_str())
-            .is_some_and(|v| v == token);
-        if is_already_token {
+            .unwrap_or_default();
+        if replacement.is_applied(cur, ValueKind::Generic) {
             continue;
         }
 
-        obj.insert(
-            "value".to_string(),
-            serde_json::Value::String(token.to_string()),
-        );
+        let value = replacement.apply(cur, ValueKind::Generic);
+        obj.insert("value".to_string(), serde_json::Value::String(value));
         changed += 1;
         matched_names.insert(name);
     }
@@ -244,7 +535,7 @@ fn redact_cookies_json(
 fn redact_url_params(
     url: &str,
     matcher: &NameMatcher,
-    token: &str,
+    replacement: &Replacement,
     matched_names: &mut HashSet<String>,
 ) -> Option<(String, Option<String>, u64)> {
     let mut parsed = Url::parse(url).ok()?;
@@ -263,8 +554,8 @@ fn redact_url_params(
         for (name, mut value) in pairs {
             if matcher.matches(&name) {
                 matched_names.insert(name.clone());
-                if value != token {
-                    value = token.to_string();
+                if !replacement.is_applied(&value, ValueKind::Generic) {
+                    value = replacement.apply(&value, ValueKind::Generic);
                     changed += 1;
                 }
             }
@@ -286,10 +577,15 @@ struct RedactedBlob {
     new_hash: String,
     new_size: i64,
     matches: u64,
+    fields: Vec<String>,
     text: String,
 }
 
-fn redact_body_text(text: &str, regexes: &[Regex], token: &str) -> Option<(String, u64)> {
+fn redact_body_text(
+    text: &str,
+    regexes: &[Regex],
+    replacement: &Replacement,
+) -> Option<(String, u64)> {
     if regexes.is_empty() {
         return None;
     }
@@ -297,12 +593,21 @@ fn redact_body_text(text: &str, regexes: &[Regex], token: &str) -> Option<(Strin
     let mut out = text.to_string();
     let mut total_matches = 0u64;
     for re in regexes {
-        let matches = re.find_iter(&out).count();
+        let mut matches = 0u64;
+        let replaced = re.replace_all(&out, |caps: &regex::Captures| {
+            let m = caps.get(0).map(|c| c.as_str()).unwrap_or_default();
+            if replacement.is_applied(m, ValueKind::Generic) {
+                m.to_string()
+            } else {
+                matches += 1;
+                replacement.apply(m, ValueKind::Generic)
+            }
+        });
         if matches == 0 {
             continue;
         }
-        total_matches += matches as u64;
-        out = re.replace_all(&out, NoExpand(token)).into_owned();
+        total_matches += matches;
+        out = replaced.into_owned();
     }
 
     if total_matches == 0 || out == text {
@@ -312,10 +617,71 @@ fn redact_body_text(text: &str, regexes: &[Regex], token: &str) -> Option<(Strin
     Some((out, total_matches))
 }
 
-fn load_blob_for_redaction(
-    conn: &Connection,
-    hash: &str,
-) -> Result<Option<(Vec<u8>, Option<String>)>> {
+/// Redact JSON, form and multipart fields selected by `rules`, keeping the body valid.
+fn redact_body_fields(
+    text: &str,
+    mime_type: Option<&str>,
+    rules: &BodyRules,
+    replacement: &Replacement,
+) -> Option<(String, Vec<String>)> {
+    if !rules.has_structured() {
+        return None;
+    }
+    let format = detect_body_format(mime_type, text)?;
+
+    let mut fields: Vec<String> = Vec::new();
+    let out = rewrite_body_values(text, &format, &mut |path, value| {
+        if value.is_null() || !rules.matches_field(path) {
+            return None;
+        }
+        let current = match value {
+            serde_json::Value::String(s) => {
+                if replacement.is_applied(s, ValueKind::Generic) {
+                    return None;
+                }
+                s.clone()
+            }
+            other => other.to_string(),
+        };
+        fields.push(format_path(path));
+        Some(serde_json::Value::String(
+            replacement.apply(&current, ValueKind::Generic),
+        ))
+    })?;
+    Some((out, fields))
+}
+
+/// Apply structured field rules, then regexes, to a text body.
+fn redact_text_body(
+    text: &str,
+    mime_type: Option<&str>,
+    rules: &BodyRules,
+    replacement: &Replacement,
+) -> Option<(String, Vec<String>, u64)> {
+    let mut redacted_text = text.to_string();
+    let mut fields: Vec<String> = Vec::new();
+    if let Some((out, matched)) = redact_body_fields(text, mime_type, rules, replacement) {
+        redacted_text = out;
+        fields = matched;
+    }
+    let mut matches = 0u64;
+    if let Some((out, n)) = redact_body_text(&redacted_text, &rules.regexes, replacement) {
+        redacted_text = out;
+        matches = n;
+    }
+    if fields.is_empty() && matches == 0 {
+        return None;
+    }
+    Some((redacted_text, fields, matches))
+}
+
+pub(super) struct LoadedBlob {
+    pub content: Vec<u8>,
+    pub mime_type: Option<String>,
+    pub external_path: Option<String>,
+}
+
+pub(super) fn load_blob_for_redaction(conn: &Connection, hash: &str) -> Result<Option<LoadedBlob>> {
     let row = conn
         .query_row(
             "SELECT content, size, mime_type, external_path FROM blobs WHERE hash = ?1",
@@ -336,8 +702,8 @@ fn load_blob_for_redaction(
     };
 
     if content.is_empty() && size > 0 {
-        if let Some(path) = external_path {
-            if let Ok(bytes) = std::fs::read(path) {
+        if let Some(path) = &external_path {
+            if let Ok(bytes) = read_external_blob(Path::new(path)) {
                 content = bytes;
             }
         }
@@ -347,14 +713,18 @@ fn load_blob_for_redaction(
         return Ok(None);
     }
 
-    Ok(Some((content, mime_type)))
+    Ok(Some(LoadedBlob {
+        content,
+        mime_type,
+        external_path,
+    }))
 }
 
 fn redact_blob_cached(
     conn: &Connection,
     hash: &str,
-    regexes: &[Regex],
-    token: &str,
+    rules: &BodyRules,
+    replacement: &Replacement,
     write: bool,
     cache: &mut HashMap<String, Option<RedactedBlob>>,
 ) -> Result<(Option<RedactedBlob>, bool)> {
@@ -362,7 +732,12 @@ fn redact_blob_cached(
         return Ok((existing.clone(), false));
     }
 
-    let Some((content, mime_type)) = load_blob_for_redaction(conn, hash)? else {
+    let Some(LoadedBlob {
+        content,
+        mime_type,
+        external_path,
+    }) = load_blob_for_redaction(conn, hash)?
+    else {
         cache.insert(hash.to_string(), None);
         return Ok((None, true));
     };
@@ -375,14 +750,28 @@ fn redact_blob_cached(
         }
     };
 
-    let Some((redacted_text, matches)) = redact_body_text(text, regexes, token) else {
+    let Some((redacted_text, fields, matches)) =
+        redact_text_body(text, mime_type.as_deref(), rules, replacement)
+    else {
         cache.insert(hash.to_string(), None);
         return Ok((None, true));
     };
 
     let bytes = redacted_text.as_bytes();
     let new_hash = if write {
-        let (hash, _) = store_blob(conn, bytes, mime_type.as_deref(), None, true)?;
+        // Keep extracted bodies extracted: the redacted copy goes next to the original file.
+        let external = match external_path.as_deref() {
+            Some(path) => write_sibling_blob(Path::new(path), hash, bytes)?
+                .map(|path| path.to_string_lossy().to_string()),
+            None => None,
+        };
+        let (hash, _) = store_blob(
+            conn,
+            bytes,
+            mime_type.as_deref(),
+            external.as_deref(),
+            external.is_none(),
+        )?;
         hash
     } else {
         hash.to_string()
@@ -392,6 +781,7 @@ fn redact_blob_cached(
         new_hash,
         new_size: bytes.len() as i64,
         matches,
+        fields,
         text: redacted_text,
     };
 
@@ -417,11 +807,17 @@ fn upsert_response_fts(conn: &Connection, hash: &str, text: &str) -> Result<()>
 #[cfg(test)]
 mod tests {
     use super::{
-        redact_body_text, redact_cookies_json, redact_headers_json, redact_url_params, NameMatcher,
-        NameMatchMode,
+        redact_body_fields, redact_body_text, redact_cookies_json, redact_headers_json,
+        redact_url_params, BodyRules, NameMatcher, NameMatchMode,
     };
+    use crate::commands::json_path::JsonPath;
+    use crate::commands::pseudonym::{Pseudonymizer, Replacement};
     use std::collections::HashSet;
 
+    fn token() -> Replacement {
+        Replacement::Token("REDACTED".to_string())
+    }
+
     #[test]
     fn redacts_headers_with_exact_match() {
         let matcher = NameMatcher::new(
@@ -432,7 +828,7 @@ mod tests {
         let mut matched = HashSet::new();
         let json = r#"{"Authorization":"secret","x-api-key":"abc","other":"keep"}"#;
         let (out, count) =
-            redact_headers_json(json, &matcher, "REDACTED", &mut matched).expect("redact");
+            redact_headers_json(json, &matcher, &token(), &mut matched).expect("redact");
 
         assert_eq!(count, 2);
         assert!(matched.contains("Authorization"));
@@ -451,7 +847,7 @@ mod tests {
         let mut matched = HashSet::new();
         let json = r#"[{"name":"sessionid","value":"abc"},{"name":"pref","value":"1"}]"#;
         let (out, count) =
-            redact_cookies_json(json, &matcher, "REDACTED", &mut matched).expect("redact");
+            redact_cookies_json(json, &matcher, &token(), &mut matched).expect("redact");
 
         assert_eq!(count, 1);
         assert!(matched.contains("sessionid"));
@@ -474,7 +870,7 @@ mod tests {
         let mut matched = HashSet::new();
         let url = "https://example.com/path?token=abc&keep=1&secret=REDACTED";
         let (new_url, new_query, count) =
-            redact_url_params(url, &matcher, "REDACTED", &mut matched)
+            redact_url_params(url, &matcher, &token(), &mut matched)
                 .expect("should redact");
 
         assert_eq!(count, 1);
@@ -488,27 +884,99 @@ mod tests {
     #[test]
     fn redacts_body_text_with_regexes() {
         let regexes = vec![regex::Regex::new("secret").expect("regex")];
-        let out = redact_body_text("secret token", &regexes, "REDACTED")
+        let out = redact_body_text("secret token", &regexes, &token())
             .expect("redacted");
         assert_eq!(out.0, "REDACTED token");
         assert_eq!(out.1, 1);
 
-        let no_change = redact_body_text("no match", &regexes, "REDACTED");
+        let no_change = redact_body_text("no match", &regexes, &token());
         assert!(no_change.is_none());
     }
+
+    #[test]
+    fn redacts_body_fields_by_path_and_name() {
+        let rules = BodyRules {
+            paths: vec![JsonPath::parse("$.user.password").expect("path")],
+            fields: NameMatcher::new(NameMatchMode::Wildcard, &["*_token".to_string()])
+                .expect("matcher"),
+            regexes: Vec::new(),
+        };
+
+        let json = r#"{"user":{"name":"a","password":"p"},"refresh_token":"r","n":1}"#;
+        let (out, mut fields) =
+            redact_body_fields(json, Some("application/json"), &rules, &token()).expect("json");
+        fields.sort();
+        assert_eq!(fields, vec!["$.refresh_token", "$.user.password"]);
+        let value: serde_json::Value = serde_json::from_str(&out).expect("valid json");
+        assert_eq!(value["user"]["password"], "REDACTED");
+        assert_eq!(value["refresh_token"], "REDACTED");
+        assert_eq!(value["user"]["name"], "a");
+        assert!(redact_body_fields(&out, Some("application/json"), &rules, &token()).is_none());
+
+        let form = "access_token=abc&grant_type=password";
+        let (out, fields) = redact_body_fields(
+            form,
+            Some("application/x-www-form-urlencoded"),
+            &rules,
+            &token(),
+        )
+        .expect("form");
+        assert_eq!(out, "access_token=REDACTED&grant_type=password");
+        assert_eq!(fields, vec!["$.access_token"]);
+
+        assert!(redact_body_fields("password=p", Some("text/plain"), &rules, &token()).is_none());
+    }
+
+    #[test]
+    fn pseudonymizes_consistently_across_locations() {
+        let replacement = Replacement::Pseudonym(Pseudonymizer::new(b"test key").expect("key"));
+        let header_matcher = NameMatcher::new(NameMatchMode::Exact, &["authorization".to_string()])
+            .expect("matcher");
+        let query_matcher =
+            NameMatcher::new(NameMatchMode::Exact, &["token".to_string()]).expect("matcher");
+        let mut matched = HashSet::new();
+
+        let (headers, _) = redact_headers_json(
+            r#"{"authorization":"Bearer abc123"}"#,
+            &header_matcher,
+            &replacement,
+            &mut matched,
+        )
+        .expect("redact");
+        let headers: serde_json::Value = serde_json::from_str(&headers).expect("json");
+        let header_value = headers["authorization"].as_str().expect("header").to_string();
+        let pseudonym = header_value.strip_prefix("Bearer ").expect("scheme kept");
+
+        let (url, _, _) = redact_url_params(
+            "https://example.com/?token=abc123",
+            &query_matcher,
+            &replacement,
+            &mut matched,
+        )
+        .expect("should redact");
+        assert!(url.ends_with(&format!("token={pseudonym}")));
+
+        let regexes = vec![regex::Regex::new("abc123").expect("regex")];
+        let (body, count) =
+            redact_body_text("{\"t\":\"abc123\"}", &regexes, &replacement).expect("redacted");
+        assert_eq!(count, 1);
+        assert!(body.contains(pseudonym));
+
+        let (_, again) = redact_headers_json(
+            &serde_json::to_string(&headers).expect("json"),
+            &header_matcher,
+            &replacement,
+            &mut matched,
+        )
+        .expect("redact");
+        assert_eq!(again, 0);
+    }
 }
 
-fn redact_entries(
-    conn: &Connection,
-    header_matcher: &NameMatcher,
-    cookie_matcher: &NameMatcher,
-    query_matcher: &NameMatcher,
-    body_regexes: &[Regex],
-    token: &str,
-    write: bool,
-) -> Result<RedactionReport> {
+fn redact_entries(conn: &Connection, plan: &RedactPlan, write: bool) -> Result<RedactionReport> {
+    let replacement = &plan.replacement;
     let mut stmt = conn.prepare(
-        "SELECT id, url, query_string, request_headers, response_headers, request_cookies, response_cookies, request_body_hash, request_body_size, response_body_hash, response_body_size, response_body_hash_raw, response_body_size_raw FROM entries ORDER BY id",
+        "SELECT id, url, query_string, request_headers, response_headers, request_cookies, response_cookies, request_body_hash, request_body_size, response_body_hash, response_body_size, response_body_hash_raw, response_body_size_raw, host, graphql_query_hash, graphql_variables_hash FROM entries ORDER BY id",
     )?;
 
     let mut report = RedactionReport::default();
@@ -528,15 +996,17 @@ fn redact_entries(
             row.get::<_, Option<i64>>(10)?,
             row.get::<_, Option<String>>(11)?,
             row.get::<_, Option<i64>>(12)?,
+            row.get::<_, Option<String>>(13)?,
+            row.get::<_, Option<String>>(14)?,
+            row.get::<_, Option<String>>(15)?,
         ))
     })?;
 
     let mut update = conn.prepare(
-        "UPDATE entries SET url=?1, query_string=?2, request_headers=?3, response_headers=?4, request_cookies=?5, response_cookies=?6, request_body_hash=?7, request_body_size=?8, response_body_hash=?9, response_body_size=?10, response_body_hash_raw=?11, response_body_size_raw=?12 WHERE id=?13",
+        "UPDATE entries SET url=?1, query_string=?2, request_headers=?3, response_headers=?4, request_cookies=?5, response_cookies=?6, request_body_hash=?7, request_body_size=?8, response_body_hash=?9, response_body_size=?10, response_body_hash_raw=?11, response_body_size_raw=?12, graphql_query_hash=?13, graphql_variables_hash=?14 WHERE id=?15",
     )?;
 
-    let mut blob_cache: HashMap<String, Option<RedactedBlob>> = HashMap::new();
-    let mut changed_response_hashes: HashSet<String> = HashSet::new();
+    let mut scopes: HashMap<Vec<usize>, ScopeState> = HashMap::new();
     let has_fts: bool = conn
         .query_row(
             "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='response_body_fts'",
@@ -561,9 +1031,36 @@ fn redact_entries(
             resp_body_size,
             resp_body_hash_raw,
             resp_body_size_raw,
+            host,
+            graphql_query_hash,
+            graphql_variables_hash,
         ) = row?;
         report.entries_scanned += 1;
 
+        let applicable = plan.applicable_rules(host.as_deref());
+        if applicable.is_empty() {
+            continue;
+        }
+        if !scopes.contains_key(&applicable) {
+            let rule_set = plan.compile(&applicable)?;
+            scopes.insert(
+                applicable.clone(),
+                ScopeState {
+                    rules: rule_set,
+                    blobs: HashMap::new(),
+                },
+            );
+        }
+        let scope = scopes
+            .get_mut(&applicable)
+            .expect("scope was just inserted");
+        let rule_set = &scope.rules;
+        let blob_cache = &mut scope.blobs;
+        let header_matcher = &rule_set.headers;
+        let cookie_matcher = &rule_set.cookies;
+        let query_matcher = &rule_set.query_params;
+        let body_rules = &rule_set.body;
+
         let mut changed = false;
         let mut new_url = url.clone();
         let mut new_query_string = query_string.clone();
@@ -577,12 +1074,14 @@ fn redact_entries(
         let mut new_resp_body_size = resp_body_size;
         let mut new_resp_body_hash_raw = resp_body_hash_raw.clone();
         let mut new_resp_body_size_raw = resp_body_size_raw;
+        let mut new_graphql_query_hash = graphql_query_hash.clone();
+        let mut new_graphql_variables_hash = graphql_variables_hash.clone();
 
         if let Some(json) = req_h.as_deref() {
             let (out, n) = redact_headers_json(
                 json,
                 header_matcher,
-                token,
+                replacement,
                 &mut report.matched_header_names,
             )?;
             if n > 0 {
@@ -595,7 +1094,7 @@ fn redact_entries(
             let (out, n) = redact_headers_json(
                 json,
                 header_matcher,
-                token,
+                replacement,
                 &mut report.matched_header_names,
             )?;
             if n > 0 {
@@ -608,7 +1107,7 @@ fn redact_entries(
             let (out, n) = redact_cookies_json(
                 json,
                 cookie_matcher,
-                token,
+                replacement,
                 &mut report.matched_cookie_names,
             )?;
             if n > 0 {
@@ -621,7 +1120,7 @@ fn redact_entries(
             let (out, n) = redact_cookies_json(
                 json,
                 cookie_matcher,
-                token,
+                replacement,
                 &mut report.matched_cookie_names,
             )?;
             if n > 0 {
@@ -635,7 +1134,7 @@ fn redact_entries(
                 if let Some((out, new_query, n)) = redact_url_params(
                     url_str,
                     query_matcher,
-                    token,
+                    replacement,
                     &mut report.matched_query_param_names,
                 ) {
                     new_url = Some(out);
@@ -645,17 +1144,22 @@ fn redact_entries(
                 }
             }
         }
-        if !body_regexes.is_empty() {
+        if !body_rules.is_empty() {
             if let Some(hash) = req_body_hash.as_deref() {
                 let (redacted, counted) =
-                    redact_blob_cached(conn, hash, body_regexes, token, write, &mut blob_cache)?;
+                    redact_blob_cached(conn, hash, body_rules, replacement, write, blob_cache)?;
                 if let Some(redacted) = redacted {
                     if counted {
                         report.body_matches += redacted.matches;
+                        report.body_fields += redacted.fields.len() as u64;
+                        report
+                            .matched_body_fields
+                            .extend(redacted.fields.iter().cloned());
                     }
                     report.request_bodies += 1;
                     changed = true;
                     if write {
+                        report.replaced_blobs.insert(hash.to_string());
                         new_req_body_hash = Some(redacted.new_hash);
                         new_req_body_size = Some(redacted.new_size);
                     }
@@ -663,19 +1167,23 @@ fn redact_entries(
             }
             if let Some(hash) = resp_body_hash.as_deref() {
                 let (redacted, counted) =
-                    redact_blob_cached(conn, hash, body_regexes, token, write, &mut blob_cache)?;
+                    redact_blob_cached(conn, hash, body_rules, replacement, write, blob_cache)?;
                 if let Some(redacted) = redacted {
                     if counted {
                         report.body_matches += redacted.matches;
+                        report.body_fields += redacted.fields.len() as u64;
+                        report
+                            .matched_body_fields
+                            .extend(redacted.fields.iter().cloned());
                     }
                     report.response_bodies += 1;
                     changed = true;
                     if write {
                         new_resp_body_hash = Some(redacted.new_hash.clone());
                         new_resp_body_size = Some(redacted.new_size);
-                        new_resp_body_hash_raw = None;
+                        report.replaced_blobs.insert(hash.to_string());
+                        report.replaced_blobs.extend(new_resp_body_hash_raw.take());
                         new_resp_body_size_raw = None;
-                        changed_response_hashes.insert(hash.to_string());
                         if has_fts {
                             let has_old_fts = conn
                                 .query_row(
@@ -692,6 +1200,35 @@ fn redact_entries(
                     }
                 }
             }
+            // The GraphQL document and variables are stored as their own blobs, so the
+            // literals and variables they carry need the same treatment as the body.
+            for (hash, new_hash) in [
+                (graphql_query_hash.as_deref(), &mut new_graphql_query_hash),
+                (
+                    graphql_variables_hash.as_deref(),
+                    &mut new_graphql_variables_hash,
+                ),
+            ] {
+                let Some(hash) = hash else {
+                    continue;
+                };
+                let (redacted, counted) =
+                    redact_blob_cached(conn, hash, body_rules, replacement, write, blob_cache)?;
+                if let Some(redacted) = redacted {
+                    if counted {
+                        report.body_matches += redacted.matches;
+                        report.body_fields += redacted.fields.len() as u64;
+                        report
+                            .matched_body_fields
+                            .extend(redacted.fields.iter().cloned());
+                    }
+                    changed = true;
+                    if write {
+                        report.replaced_blobs.insert(hash.to_string());
+                        *new_hash = Some(redacted.new_hash);
+                    }
+                }
+            }
         }
 
         if changed {
@@ -710,31 +1247,122 @@ fn redact_entries(
                     new_resp_body_size,
                     new_resp_body_hash_raw,
                     new_resp_body_size_raw,
+                    new_graphql_query_hash,
+                    new_graphql_variables_hash,
                     id
                 ])?;
             }
         }
     }
 
-    if write && has_fts && !changed_response_hashes.is_empty() {
-        let mut check_stmt =
-            conn.prepare("SELECT COUNT(*) FROM entries WHERE response_body_hash = ?1")?;
-        let mut delete_stmt = conn.prepare("DELETE FROM response_body_fts WHERE hash = ?1")?;
-        for hash in changed_response_hashes {
-            let count: i64 = check_stmt.query_row([hash.as_str()], |row| row.get(0))?;
-            if count == 0 {
-                delete_stmt.execute([hash])?;
-            }
+    Ok(report)
+}
+
+/// Rules, replacement and optional policy resolved from [`RedactOptions`].
+///
+/// Shared by the standalone command and inline redaction during import.
+pub(super) struct RedactPlan {
+    pub(super) match_mode: NameMatchMode,
+    pub(super) rules: Vec<PolicyRule>,
+    pub(super) policy: Option<LoadedPolicy>,
+    pub(super) replacement: Replacement,
+}
+
+impl RedactPlan {
+    pub(super) fn new(options: &RedactOptions) -> Result<Self> {
+        let mut policy = options.policy.as_deref().map(load_policy).transpose()?;
+        let (match_mode, use_defaults, mut rules) = match policy.as_mut() {
+            Some(loaded) => (
+                loaded.policy.match_mode.unwrap_or(NameMatchMode::Wildcard),
+                loaded.policy.defaults.unwrap_or(true),
+                std::mem::take(&mut loaded.policy.rules),
+            ),
+            None => (
+                options.match_mode,
+                !options.no_defaults,
+                vec![PolicyRule {
+                    header: options.headers.clone(),
+                    cookie: options.cookies.clone(),
+                    query_param: options.query_params.clone(),
+                    body_path: options.body_paths.clone(),
+                    body_field: options.body_fields.clone(),
+                    body_regex: options.body_regexes.clone(),
+                    ..PolicyRule::default()
+                }],
+            ),
+        };
+        // Only apply defaults when using wildcard mode, since defaults are wildcard patterns
+        if use_defaults && matches!(match_mode, NameMatchMode::Wildcard) {
+            rules.insert(
+                0,
+                PolicyRule {
+                    header: default_header_patterns(),
+                    cookie: default_cookie_patterns(),
+                    ..PolicyRule::default()
+                },
+            );
+        }
+
+        // Compile each rule up front so bad patterns fail before any entry is touched.
+        let mut has_patterns = false;
+        for rule in &rules {
+            has_patterns |= !RuleSet::compile(match_mode, [rule])?.is_empty();
+        }
+        if !has_patterns {
+            let hint = if !matches!(match_mode, NameMatchMode::Wildcard) {
+                " (defaults only available in wildcard mode)"
+            } else {
+                ""
+            };
+            return Err(HarliteError::InvalidArgs(format!(
+                "No redaction patterns provided{}",
+                hint
+            )));
         }
+
+        let token = policy
+            .as_ref()
+            .and_then(|loaded| loaded.policy.token.clone())
+            .unwrap_or_else(|| options.token.clone());
+        let pseudonymize = options.pseudonymize
+            || policy
+                .as_ref()
+                .and_then(|loaded| loaded.policy.pseudonymize)
+                .unwrap_or(false);
+        let replacement =
+            Replacement::new(&token, pseudonymize, options.pseudonym_key_file.as_deref())?;
+
+        Ok(Self {
+            match_mode,
+            rules,
+            policy,
+            replacement,
+        })
     }
 
-    Ok(report)
+    /// Indices of the rules that apply to an entry with the given host.
+    pub(super) fn applicable_rules(&self, host: Option<&str>) -> Vec<usize> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.applies_to(host))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Merge the given rules into one set of matchers.
+    pub(super) fn compile(&self, applicable: &[usize]) -> Result<RuleSet> {
+        RuleSet::compile(
+            self.match_mode,
+            applicable.iter().map(|&idx| &self.rules[idx]),
+        )
+    }
 }
 
 pub fn run_redact(database: Option<PathBuf>, options: &RedactOptions) -> Result<()> {
     let input_db = resolve_database(database)?;
 
-    let target_db = if options.dry_run {
+    let target_db = if options.dry_run || options.verify {
         input_db.clone()
     } else if let Some(out) = &options.output {
         let input_cmp = canonicalize_path_for_compare(&input_db)?;
@@ -759,70 +1387,40 @@ pub fn run_redact(database: Option<PathBuf>, options: &RedactOptions) -> Result<
         input_db.clone()
     };
 
-    let mut header_patterns: Vec<String> = Vec::new();
-    let mut cookie_patterns: Vec<String> = Vec::new();
-    let mut query_patterns: Vec<String> = Vec::new();
-    // Only apply defaults when using wildcard mode, since defaults are wildcard patterns
-    if !options.no_defaults && matches!(options.match_mode, NameMatchMode::Wildcard) {
-        header_patterns.extend(default_header_patterns());
-        cookie_patterns.extend(default_cookie_patterns());
-    }
-    header_patterns.extend(options.headers.iter().cloned());
-    cookie_patterns.extend(options.cookies.iter().cloned());
-    query_patterns.extend(options.query_params.iter().cloned());
-
-    let header_matcher = NameMatcher::new(options.match_mode, &header_patterns)?;
-    let cookie_matcher = NameMatcher::new(options.match_mode, &cookie_patterns)?;
-    let query_matcher = NameMatcher::new(options.match_mode, &query_patterns)?;
+    let plan = RedactPlan::new(options)?;
 
-    let body_regexes: Vec<Regex> = options
-        .body_regexes
-        .iter()
-        .map(|p| Regex::new(p))
-        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut conn = open_database(&target_db)?;
+    conn.execute_batch("PRAGMA foreign_keys=ON; PRAGMA secure_delete=ON;")?;
 
-    if header_matcher.is_empty()
-        && cookie_matcher.is_empty()
-        && query_matcher.is_empty()
-        && body_regexes.is_empty()
-    {
-        let hint = if !matches!(options.match_mode, NameMatchMode::Wildcard) {
-            " (defaults only available in wildcard mode)"
-        } else {
-            ""
-        };
-        return Err(HarliteError::InvalidArgs(format!(
-            "No redaction patterns provided{}",
-            hint
-        )));
+    if options.verify {
+        let report = redact_entries(&conn, &plan, false)?;
+        return verify_report(&conn, &input_db, plan.policy.as_ref(), report);
     }
 
-    let mut conn = Connection::open(&target_db)?;
-    conn.execute_batch("PRAGMA foreign_keys=ON;")?;
-
-    let report = if options.dry_run {
-        redact_entries(
-            &conn,
-            &header_matcher,
-            &cookie_matcher,
-            &query_matcher,
-            &body_regexes,
-            &options.token,
-            false,
-        )?
+    let (report, scrubbed) = if options.dry_run {
+        (redact_entries(&conn, &plan, false)?, None)
     } else {
         let tx = conn.transaction()?;
-        let report = redact_entries(
-            &tx,
-            &header_matcher,
-            &cookie_matcher,
-            &query_matcher,
-            &body_regexes,
-            &options.token,
-            true,
-        )?;
+        let report = redact_entries(&tx, &plan, true)?;
+        ensure_schema_upgrades(&tx)?;
+        record_redaction(&tx, "redact", plan.policy.as_ref(), report.total())?;
+        let replaced: Vec<String> = report.replaced_blobs.iter().cloned().collect();
+        let orphans = orphaned_blob_hashes(&tx, &replaced)?;
+        let mut scrubbed = delete_blobs(&tx, &orphans, external_root(&target_db).as_deref())?;
         tx.commit()?;
-        report
+
+        // A copied database still shares extracted body files with its input.
+        let external = if options.output.is_some() {
+            ExternalFiles::Keep
+        } else {
+            ExternalFiles::Wipe
+        };
+        scrubbed.remove_external_files(&conn, external)?;
+
+        let size_before = fs::metadata(&target_db)?.len();
+        compact_database(&conn)?;
+        let size_after = fs::metadata(&target_db)?.len();
+        (report, Some((scrubbed, size_before, size_after)))
     };
 
     if options.dry_run {
@@ -846,8 +1444,27 @@ pub fn run_redact(database: Option<PathBuf>, options: &RedactOptions) -> Result<
         );
     }
 
+    print_breakdown(report);
+
+    if let Some((scrubbed, size_before, size_after)) = scrubbed {
+        println!(
+            "Scrubbed {} replaced blobs and {} FTS rows, wiped {} extracted body files (skipped {}); vacuumed {} ({} -> {} bytes)",
+            scrubbed.blobs_deleted,
+            scrubbed.fts_deleted,
+            scrubbed.external_deleted,
+            scrubbed.external_skipped,
+            target_db.display(),
+            size_before,
+            size_after
+        );
+    }
+
+    Ok(())
+}
+
+pub(super) fn print_breakdown(report: RedactionReport) {
     println!(
-        "Breakdown: request_headers={}, response_headers={}, request_cookies={}, response_cookies={}, query_params={}, request_bodies={}, response_bodies={}, body_matches={}",
+        "Breakdown: request_headers={}, response_headers={}, request_cookies={}, response_cookies={}, query_params={}, request_bodies={}, response_bodies={}, body_matches={}, body_fields={}",
         report.request_headers,
         report.response_headers,
         report.request_cookies,
@@ -855,7 +1472,8 @@ pub fn run_redact(database: Option<PathBuf>, options: &RedactOptions) -> Result<
         report.query_params,
         report.request_bodies,
         report.response_bodies,
-        report.body_matches
+        report.body_matches,
+        report.body_fields
     );
 
     if !report.matched_header_names.is_empty() {
@@ -873,6 +1491,107 @@ pub fn run_redact(database: Option<PathBuf>, options: &RedactOptions) -> Result<
         names.sort();
         println!("Matched query params: {}", names.join(", "));
     }
+    if !report.matched_body_fields.is_empty() {
+        let mut names: Vec<String> = report.matched_body_fields.into_iter().collect();
+        names.sort();
+        println!("Matched body fields: {}", names.join(", "));
+    }
+}
+
+fn verify_report(
+    conn: &Connection,
+    database: &std::path::Path,
+    policy: Option<&LoadedPolicy>,
+    report: RedactionReport,
+) -> Result<()> {
+    let remaining = report.total();
+    if remaining > 0 {
+        let entries = report.entries_changed;
+        print_breakdown(report);
+        return Err(HarliteError::InvalidArgs(format!(
+            "Verification failed: {} values across {} entries in {} still match redaction rules",
+            remaining,
+            entries,
+            database.display()
+        )));
+    }
 
+    println!(
+        "Verified {}: no values match redaction rules ({} entries scanned)",
+        database.display(),
+        report.entries_scanned
+    );
+
+    let has_table: bool = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='redactions'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap_or(0)
+        > 0;
+    if !has_table {
+        println!("No redaction runs recorded");
+        return Ok(());
+    }
+
+    let last = conn
+        .query_row(
+            "SELECT applied_at, policy_name, policy_version, policy_hash FROM redactions ORDER BY id DESC LIMIT 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    match last {
+        Some((applied_at, name, version, hash)) => println!(
+            "Last redaction: {} policy={} version={} hash={}",
+            applied_at,
+            name.as_deref().unwrap_or("-"),
+            version.map(|v| v.to_string()).as_deref().unwrap_or("-"),
+            hash.as_deref().unwrap_or("-")
+        ),
+        None => println!("No redaction runs recorded"),
+    }
+
+    if let Some(loaded) = policy {
+        let recorded: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM redactions WHERE policy_hash = ?1",
+            params![loaded.hash],
+            |row| row.get(0),
+        )?;
+        if recorded > 0 {
+            println!("Policy {} has been applied to this database", loaded.hash);
+        } else {
+            println!("Policy {} is not recorded in this database", loaded.hash);
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn record_redaction(
+    conn: &Connection,
+    command: &str,
+    policy: Option<&LoadedPolicy>,
+    values_redacted: u64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO redactions (applied_at, command, policy_name, policy_version, policy_hash, values_redacted) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            chrono::Utc::now().to_rfc3339(),
+            command,
+            policy.and_then(|loaded| loaded.policy.name.clone()),
+            policy.map(|loaded| loaded.policy.version),
+            policy.map(|loaded| loaded.hash.clone()),
+            values_redacted as i64
+        ],
+    )?;
     Ok(())
 }
@@ -0,0 +1,157 @@
+//! Versioned redaction policy files.
+//!
+//! A policy is a TOML file that bundles the rules otherwise spread across `redact`
+//! flags and config, optionally scoped to hosts:
+//!
+//! ```toml
+//! version = 1
+//! name = "share-with-vendor"
+//!
+//! [[rules]]
+//! header = ["authorization", "x-api-key"]
+//! cookie = ["*"]
+//!
+//! [[rules]]
+//! hosts = ["*.example.com"]
+//! query_param = ["token"]
+//! body_path = ["$.user.password"]
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{HarliteError, Result};
+
+use super::redact::NameMatchMode;
+
+/// The only policy format version understood by this build.
+pub(super) const POLICY_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct RedactPolicy {
+    pub version: u32,
+    pub name: Option<String>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<NameMatchMode>,
+    pub defaults: Option<bool>,
+    pub token: Option<String>,
+    pub pseudonymize: Option<bool>,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+/// One block of rules; `hosts` limits it to matching entry hosts (empty = all hosts).
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct PolicyRule {
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub header: Vec<String>,
+    #[serde(default)]
+    pub cookie: Vec<String>,
+    #[serde(default)]
+    pub query_param: Vec<String>,
+    #[serde(default)]
+    pub body_path: Vec<String>,
+    #[serde(default)]
+    pub body_field: Vec<String>,
+    #[serde(default)]
+    pub body_regex: Vec<String>,
+}
+
+/// A parsed policy plus the BLAKE3 hash of the exact file contents.
+pub(super) struct LoadedPolicy {
+    pub policy: RedactPolicy,
+    pub hash: String,
+}
+
+pub(super) fn load_policy(path: &Path) -> Result<LoadedPolicy> {
+    let contents = fs::read_to_string(path)?;
+    let policy = parse_policy(&contents).map_err(|err| {
+        HarliteError::InvalidArgs(format!(
+            "Failed to parse redaction policy {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    Ok(LoadedPolicy {
+        policy,
+        hash: blake3::hash(contents.as_bytes()).to_hex().to_string(),
+    })
+}
+
+fn parse_policy(contents: &str) -> std::result::Result<RedactPolicy, String> {
+    let policy: RedactPolicy = toml::from_str(contents).map_err(|err| err.to_string())?;
+    if policy.version != POLICY_VERSION {
+        return Err(format!(
+            "unsupported policy version {} (expected {})",
+            policy.version, POLICY_VERSION
+        ));
+    }
+    Ok(policy)
+}
+
+impl PolicyRule {
+    /// Whether this rule applies to an entry with the given host.
+    pub fn applies_to(&self, host: Option<&str>) -> bool {
+        if self.hosts.is_empty() {
+            return true;
+        }
+        let Some(host) = host else {
+            return false;
+        };
+        let host = host.to_lowercase();
+        self.hosts
+            .iter()
+            .any(|pattern| host_matches(&pattern.trim().to_lowercase(), &host))
+    }
+}
+
+/// Match a host pattern; `*.example.com` also covers `example.com` itself.
+fn host_matches(pattern: &str, host: &str) -> bool {
+    if let Some(suffix) = pattern.strip_prefix("*.") {
+        return host == suffix || host.ends_with(&format!(".{suffix}"));
+    }
+    pattern == host
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scoped_rules_and_rejects_unknown_versions() {
+        let policy = parse_policy(
+            r#"
+version = 1
+name = "vendor"
+match = "exact"
+
+[[rules]]
+header = ["authorization"]
+
+[[rules]]
+hosts = ["*.example.com", "api.test"]
+query_param = ["token"]
+"#,
+        )
+        .expect("policy");
+        assert_eq!(policy.name.as_deref(), Some("vendor"));
+        assert!(matches!(policy.match_mode, Some(NameMatchMode::Exact)));
+        assert_eq!(policy.rules.len(), 2);
+        assert!(policy.rules[0].applies_to(None));
+        assert!(policy.rules[1].applies_to(Some("example.com")));
+        assert!(policy.rules[1].applies_to(Some("Login.Example.com")));
+        assert!(!policy.rules[1].applies_to(Some("notexample.com")));
+        assert!(policy.rules[1].applies_to(Some("api.test")));
+        assert!(!policy.rules[1].applies_to(None));
+
+        let err = parse_policy("version = 2").unwrap_err();
+        assert!(err.contains("unsupported policy version 2"));
+        assert!(parse_policy("version = 1\n[[rules]]\nheaders = [\"x\"]").is_err());
+    }
+}
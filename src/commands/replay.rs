@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{self, Read, Write};
@@ -6,14 +7,29 @@ use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::OpenFlags;
 use url::Url;
 
-use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, load_entries, BlobRow, EntryQuery};
+use crate::db::{
+    create_import_with_status, create_schema, ensure_schema_upgrades, insert_entry,
+    load_blobs_by_hashes, load_entries, open_database, open_database_with_flags,
+    update_import_count, BlobRow, EntryQuery, EntryRelations, InsertEntryOptions,
+};
+use crate::commands::util::{load_external_blob_content, parse_timestamp};
 use crate::error::{HarliteError, Result};
-use crate::har::{parse_har_file, Entry as HarEntry, Header, PostData};
-
+use crate::har::{
+    parse_har_file, Content, Cookie, Entry as HarEntry, Extensions, Header, PostData,
+    QueryParam, Request as HarRequest, Response as HarResponse, Timings,
+};
+
+use super::body_diff::diff_bodies;
+use super::json_path::JsonPath;
+use super::replay_load::{run_load, LoadPlan, LoadReport, LoadSample};
+use super::replay_session::{
+    parse_extract_rule, parse_set_cookie, ExtractRule, ReplaySession, ResponseView,
+};
 use super::OutputFormat;
 
 pub struct ReplayOptions {
@@ -34,18 +50,40 @@ pub struct ReplayOptions {
 
     pub override_host: Vec<String>,
     pub override_header: Vec<String>,
+
+    pub diff_body: bool,
+    pub ignore_json_path: Vec<String>,
+
+    pub record: Option<PathBuf>,
+
+    pub extract: Vec<String>,
+    pub cookie_jar: bool,
+
+    pub load: bool,
+    pub duration_secs: u64,
+    pub ramp_up_secs: u64,
+    pub vus: Option<usize>,
+    pub rps: Option<f64>,
+
+    pub preserve_timing: bool,
+    pub speed: f64,
 }
 
 #[derive(Clone, Debug)]
 struct ReplayEntry {
     index: usize,
+    source_entry_id: Option<i64>,
+    started_at: Option<DateTime<Utc>>,
     method: String,
     url: String,
     request_headers: HashMap<String, String>,
     request_body: Option<Vec<u8>>,
     original_status: Option<i32>,
     original_headers: HashMap<String, String>,
+    original_set_cookies: Vec<String>,
     original_body_size: Option<i64>,
+    original_body: Option<Vec<u8>>,
+    original_mime: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -60,9 +98,37 @@ struct ReplayRow {
     body_size_original: Option<i64>,
     body_size_replay: Option<i64>,
     body_size_delta: Option<i64>,
+    body_diff_kind: Option<&'static str>,
+    body_match: Option<bool>,
+    body_diff: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    body_changes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    substitutions: Option<usize>,
     error: Option<String>,
+    #[serde(skip)]
+    elapsed_ms: Option<f64>,
+}
+
+/// A completed exchange kept for `--record`.
+struct ReplayCapture {
+    source_entry_id: Option<i64>,
+    started_at: String,
+    wait_ms: f64,
+    receive_ms: f64,
+    method: String,
+    url: String,
+    request_headers: Vec<(String, String)>,
+    request_body: Option<Vec<u8>>,
+    status: u16,
+    status_text: String,
+    http_version: String,
+    response_headers: Vec<(String, String)>,
+    response_body: Vec<u8>,
 }
 
+type ReplayResult = (usize, ReplayRow, Option<ReplayCapture>);
+
 #[derive(Clone)]
 struct HostOverrideRule {
     pattern: Regex,
@@ -83,6 +149,40 @@ struct ReplayRuntime {
     rate_limiter: Option<Arc<RateLimiter>>,
     host_overrides: Vec<HostOverrideRule>,
     header_overrides: Vec<HeaderOverrideRule>,
+    ignore_json_paths: Vec<JsonPath>,
+    capture: bool,
+    compare_bodies: bool,
+    session: Option<Arc<Mutex<ReplaySession>>>,
+    schedule: Option<Arc<ReplaySchedule>>,
+}
+
+/// Maps recorded `started_at` times onto the replay clock for `--preserve-timing`.
+struct ReplaySchedule {
+    origin: DateTime<Utc>,
+    start: Instant,
+    speed: f64,
+}
+
+impl ReplaySchedule {
+    fn new(entries: &[ReplayEntry], speed: f64) -> Option<Self> {
+        let origin = entries.iter().filter_map(|entry| entry.started_at).min()?;
+        Some(Self {
+            origin,
+            start: Instant::now(),
+            speed,
+        })
+    }
+
+    /// Sleep until the entry's scaled offset from the first recorded request.
+    /// Entries without a timestamp are sent as soon as a worker is free.
+    fn wait(&self, started_at: Option<DateTime<Utc>>) {
+        let Some(started_at) = started_at else {
+            return;
+        };
+        let offset_ms = (started_at - self.origin).num_milliseconds().max(0) as f64;
+        let due = self.start + Duration::from_secs_f64(offset_ms / 1000.0 / self.speed);
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+    }
 }
 
 struct RateLimiter {
@@ -128,6 +228,22 @@ pub fn run_replay(input: PathBuf, options: &ReplayOptions) -> Result<()> {
         }
     }
 
+    if options.load {
+        validate_load_options(options)?;
+    }
+    if options.preserve_timing {
+        if options.load {
+            return Err(HarliteError::InvalidArgs(
+                "--preserve-timing cannot be combined with --load".to_string(),
+            ));
+        }
+        if !(options.speed > 0.0 && options.speed.is_finite()) {
+            return Err(HarliteError::InvalidArgs(
+                "--speed must be greater than 0".to_string(),
+            ));
+        }
+    }
+
     let compiled = compile_rules(options)?;
     let mut entries = if is_db_path(&input) {
         load_entries_from_db(&input, options)?
@@ -139,18 +255,52 @@ pub fn run_replay(input: PathBuf, options: &ReplayOptions) -> Result<()> {
         return Ok(());
     }
 
-    let concurrency = resolve_concurrency(options.concurrency, entries.len())?;
+    // Correlation and cookies only make sense one request at a time, in recorded order.
+    let correlate = !compiled.extract_rules.is_empty() || options.cookie_jar;
+    if correlate || options.preserve_timing {
+        entries.sort_by_key(|entry| (entry.started_at.is_none(), entry.started_at));
+    }
+    let session = if !correlate {
+        None
+    } else {
+        Some(Arc::new(Mutex::new(ReplaySession::new(
+            compiled.extract_rules,
+            options.cookie_jar,
+        ))))
+    };
+
+    let concurrency = if session.is_some() {
+        1
+    } else if options.preserve_timing && options.concurrency == 0 {
+        // Enough workers that overlapping requests in the capture also overlap on replay.
+        entries.len().min(64)
+    } else {
+        resolve_concurrency(options.concurrency, entries.len())?
+    };
     let runtime = ReplayRuntime {
         allow_unsafe: options.allow_unsafe,
         timeout: options.timeout_secs.map(Duration::from_secs),
         rate_limiter: options.rate_limit.map(|rl| Arc::new(RateLimiter::new(rl))),
         host_overrides: compiled.host_overrides,
         header_overrides: compiled.header_overrides,
+        ignore_json_paths: compiled.ignore_json_paths,
+        capture: options.record.is_some(),
+        compare_bodies: options.diff_body && !options.load,
+        session,
+        schedule: if options.preserve_timing {
+            ReplaySchedule::new(&entries, options.speed).map(Arc::new)
+        } else {
+            None
+        },
     };
 
+    if options.load {
+        return run_load_mode(entries, runtime, options);
+    }
+
     let (work_tx, work_rx) = mpsc::channel::<ReplayEntry>();
     let work_rx = Arc::new(Mutex::new(work_rx));
-    let (result_tx, result_rx) = mpsc::channel::<(usize, ReplayRow)>();
+    let (result_tx, result_rx) = mpsc::channel::<ReplayResult>();
 
     for entry in entries.drain(..) {
         work_tx.send(entry).ok();
@@ -167,7 +317,7 @@ pub fn run_replay(input: PathBuf, options: &ReplayOptions) -> Result<()> {
     }
     drop(result_tx);
 
-    let mut rows: Vec<(usize, ReplayRow)> = Vec::new();
+    let mut rows: Vec<ReplayResult> = Vec::new();
     while let Ok(row) = result_rx.recv() {
         rows.push(row);
     }
@@ -176,16 +326,166 @@ pub fn run_replay(input: PathBuf, options: &ReplayOptions) -> Result<()> {
         let _ = handle.join();
     }
 
-    rows.sort_by_key(|(idx, _)| *idx);
-    let data: Vec<ReplayRow> = rows.into_iter().map(|(_, row)| row).collect();
+    rows.sort_by_key(|(idx, _, _)| *idx);
+    let mut data: Vec<ReplayRow> = Vec::with_capacity(rows.len());
+    let mut captures: Vec<ReplayCapture> = Vec::new();
+    for (_, row, capture) in rows {
+        data.push(row);
+        captures.extend(capture);
+    }
+
+    if let Some(record) = options.record.as_ref() {
+        let skipped = data.len() - captures.len();
+        let import_id = record_replay(record, &input, &captures, skipped)?;
+        eprintln!(
+            "Recorded {} replayed entries as import {} in {}",
+            captures.len(),
+            import_id,
+            record.display()
+        );
+    }
 
     match options.format {
         OutputFormat::Json => write_json(&data),
-        OutputFormat::Csv => write_csv(&data),
-        OutputFormat::Table => write_table(&data),
+        OutputFormat::Csv => write_csv(&replay_columns(), &replay_rows(&data)),
+        OutputFormat::Table => write_table(&replay_columns(), &replay_rows(&data)),
     }
 }
 
+fn validate_load_options(options: &ReplayOptions) -> Result<()> {
+    if options.record.is_some() || !options.extract.is_empty() || options.cookie_jar {
+        return Err(HarliteError::InvalidArgs(
+            "--load cannot be combined with --record, --extract or --cookie-jar".to_string(),
+        ));
+    }
+    if options.duration_secs == 0 {
+        return Err(HarliteError::InvalidArgs(
+            "--duration must be greater than 0".to_string(),
+        ));
+    }
+    if options.vus == Some(0) {
+        return Err(HarliteError::InvalidArgs(
+            "--vus must be greater than 0".to_string(),
+        ));
+    }
+    if let Some(rps) = options.rps {
+        if rps <= 0.0 {
+            return Err(HarliteError::InvalidArgs(
+                "--rps must be greater than 0".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn run_load_mode(
+    entries: Vec<ReplayEntry>,
+    runtime: ReplayRuntime,
+    options: &ReplayOptions,
+) -> Result<()> {
+    // Unsafe requests would only produce one "skipped" error per iteration.
+    let entries: Vec<ReplayEntry> = entries
+        .into_iter()
+        .filter(|entry| runtime.allow_unsafe || is_safe_method(&entry.method))
+        .collect();
+    if entries.is_empty() {
+        return Err(HarliteError::InvalidArgs(
+            "No replayable entries for --load (use --allow-unsafe to include non-GET/HEAD requests)"
+                .to_string(),
+        ));
+    }
+
+    let vus = match options.vus {
+        Some(vus) => vus,
+        None if options.concurrency > 0 => options.concurrency,
+        None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    };
+    let plan = LoadPlan {
+        duration: Duration::from_secs(options.duration_secs),
+        ramp_up: Duration::from_secs(options.ramp_up_secs),
+        vus,
+        rps: options.rps.or(options.rate_limit),
+    };
+
+    let agent = build_agent(runtime.timeout);
+    let report = run_load(&entries, &plan, endpoint_key, |entry| {
+        let (_, row, _) = replay_entry(&agent, entry, &runtime);
+        let status_mismatch = row.status_changed.unwrap_or(false);
+        // A recorded 404 replaying as 404 is expected; new 4xx/5xx responses are not.
+        let error = row.error.is_some()
+            || row.status_replay.is_none()
+            || (row.status_replay.unwrap_or(0) >= 400 && status_mismatch);
+        LoadSample {
+            latency_ms: row.elapsed_ms.unwrap_or(0.0),
+            error,
+            status_mismatch,
+        }
+    });
+
+    match options.format {
+        OutputFormat::Json => {
+            let mut out = io::stdout().lock();
+            serde_json::to_writer(&mut out, &report)?;
+            out.write_all(b"\n")?;
+            Ok(())
+        }
+        OutputFormat::Csv => write_csv(&load_columns(), &load_rows(&report)),
+        OutputFormat::Table => write_table(&load_columns(), &load_rows(&report)),
+    }
+}
+
+/// Group key for load statistics: method plus the recorded URL without its query string.
+fn endpoint_key(entry: &ReplayEntry) -> String {
+    let path = match Url::parse(&entry.url) {
+        Ok(mut url) => {
+            url.set_query(None);
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => entry.url.split('?').next().unwrap_or_default().to_string(),
+    };
+    format!("{} {}", entry.method.to_ascii_uppercase(), path)
+}
+
+fn load_columns() -> Vec<&'static str> {
+    vec![
+        "endpoint",
+        "requests",
+        "errors",
+        "error_rate",
+        "status_mismatches",
+        "rps",
+        "mean_ms",
+        "p50_ms",
+        "p95_ms",
+        "p99_ms",
+        "max_ms",
+    ]
+}
+
+fn load_rows(report: &LoadReport) -> Vec<Vec<String>> {
+    report
+        .endpoints
+        .iter()
+        .chain(std::iter::once(&report.total))
+        .map(|summary| {
+            vec![
+                summary.endpoint.clone(),
+                summary.requests.to_string(),
+                summary.errors.to_string(),
+                format!("{:.2}%", summary.error_rate * 100.0),
+                summary.status_mismatches.to_string(),
+                format!("{:.2}", summary.rps),
+                format!("{:.1}", summary.mean_ms),
+                format!("{:.1}", summary.p50_ms),
+                format!("{:.1}", summary.p95_ms),
+                format!("{:.1}", summary.p99_ms),
+                format!("{:.1}", summary.max_ms),
+            ]
+        })
+        .collect()
+}
+
 fn resolve_concurrency(configured: usize, total: usize) -> Result<usize> {
     if configured == 0 {
         let available = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
@@ -196,7 +496,7 @@ fn resolve_concurrency(configured: usize, total: usize) -> Result<usize> {
 
 fn worker_loop(
     receiver: Arc<Mutex<mpsc::Receiver<ReplayEntry>>>,
-    sender: mpsc::Sender<(usize, ReplayRow)>,
+    sender: mpsc::Sender<ReplayResult>,
     runtime: Arc<ReplayRuntime>,
 ) {
     let agent = build_agent(runtime.timeout);
@@ -209,12 +509,14 @@ fn worker_loop(
             break;
         };
 
+        if let Some(schedule) = runtime.schedule.as_ref() {
+            schedule.wait(entry.started_at);
+        }
         if let Some(limiter) = runtime.rate_limiter.as_ref() {
             limiter.wait();
         }
 
-        let (idx, row) = replay_entry(&agent, entry, &runtime);
-        let _ = sender.send((idx, row));
+        let _ = sender.send(replay_entry(&agent, &entry, &runtime));
     }
 }
 
@@ -226,7 +528,7 @@ fn build_agent(timeout: Option<Duration>) -> ureq::Agent {
     builder.build()
 }
 
-fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime) -> (usize, ReplayRow) {
+fn replay_entry(agent: &ureq::Agent, entry: &ReplayEntry, runtime: &ReplayRuntime) -> ReplayResult {
     let method = entry.method.to_ascii_uppercase();
     let mut row = ReplayRow {
         method: method.clone(),
@@ -239,19 +541,41 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
         body_size_original: entry.original_body_size,
         body_size_replay: None,
         body_size_delta: None,
+        body_diff_kind: None,
+        body_match: None,
+        body_diff: None,
+        body_changes: Vec::new(),
+        substitutions: None,
         error: None,
+        elapsed_ms: None,
     };
 
     if !runtime.allow_unsafe && !is_safe_method(&method) {
         row.error = Some(format!("skipped unsafe method {method}"));
-        return (entry.index, row);
+        return (entry.index, row, None);
     }
 
-    let parsed = match Url::parse(&entry.url) {
+    // Only a session rewrites the URL or body, so other replays borrow them from the entry.
+    let mut url = Cow::Borrowed(entry.url.as_str());
+    let mut request_body = entry.request_body.as_deref().map(Cow::Borrowed);
+    let mut substitutions = 0usize;
+    if let Some(session) = runtime.session.as_ref() {
+        let session = session.lock().expect("replay session lock");
+        let mut owned = entry.url.clone();
+        substitutions += session.substitute(&mut owned);
+        url = Cow::Owned(owned);
+        if let Some(body) = entry.request_body.as_ref() {
+            let mut owned = body.clone();
+            substitutions += session.substitute_body(&mut owned);
+            request_body = Some(Cow::Owned(owned));
+        }
+    }
+
+    let parsed = match Url::parse(&url) {
         Ok(url) => url,
         Err(err) => {
             row.error = Some(format!("invalid url: {err}"));
-            return (entry.index, row);
+            return (entry.index, row, None);
         }
     };
 
@@ -260,7 +584,7 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
         if rule.pattern.is_match(parsed.as_str()) {
             if let Err(err) = apply_host_override(&mut replay_url, &rule.host) {
                 row.error = Some(format!("host override failed: {err}"));
-                return (entry.index, row);
+                return (entry.index, row, None);
             }
         }
     }
@@ -268,6 +592,12 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
 
     let mut headers = entry.request_headers.clone();
     headers.remove("content-length");
+    if runtime.compare_bodies || runtime.capture || runtime.session.is_some() {
+        // Let ureq negotiate gzip itself so replayed bodies arrive decoded and can be compared
+        // with, stored as, or extracted like the decoded blobs from import (a recorded `br`
+        // would stay opaque).
+        headers.remove("accept-encoding");
+    }
 
     for rule in &runtime.header_overrides {
         if rule.pattern.is_match(replay_url.as_str()) {
@@ -275,6 +605,15 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
         }
     }
 
+    if let Some(session) = runtime.session.as_ref() {
+        let session = session.lock().expect("replay session lock");
+        for value in headers.values_mut() {
+            substitutions += session.substitute(value);
+        }
+        session.apply_cookies(&replay_url, &mut headers);
+        row.substitutions = Some(substitutions);
+    }
+
     if let Some(host) = replay_url.host_str() {
         let host_header = if let Some(port) = replay_url.port() {
             format!("{host}:{port}")
@@ -287,8 +626,10 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
     let request = agent.request(&method, replay_url.as_str());
     let request = apply_headers(request, &headers);
 
-    let response = match entry.request_body {
-        Some(body) if !body.is_empty() => request.send_bytes(&body),
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let started = Instant::now();
+    let response = match request_body.as_deref() {
+        Some(body) if !body.is_empty() => request.send_bytes(body),
         _ => request.call(),
     };
 
@@ -297,19 +638,30 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
         Err(ureq::Error::Status(_, resp)) => resp,
         Err(ureq::Error::Transport(err)) => {
             row.error = Some(err.to_string());
-            return (entry.index, row);
+            row.elapsed_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+            return (entry.index, row, None);
         }
     };
+    let wait_ms = started.elapsed().as_secs_f64() * 1000.0;
 
     let replay_status = response.status();
     let replay_headers = response_headers_map(&response);
-    let body_size = match read_response_size(response) {
-        Ok(size) => Some(size),
+    let header_list = (runtime.capture || runtime.session.is_some())
+        .then(|| response_header_list(&response));
+    let capture_meta = runtime.capture.then(|| {
+        (
+            response.status_text().to_string(),
+            response.http_version().to_string(),
+        )
+    });
+    let body = match read_response_body(response) {
+        Ok(body) => Some(body),
         Err(err) => {
             row.error = Some(err.to_string());
             None
         }
     };
+    row.elapsed_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
 
     row.status_replay = Some(i32::from(replay_status));
     row.status_changed = match (row.status_original, row.status_replay) {
@@ -320,10 +672,69 @@ fn replay_entry(agent: &ureq::Agent, entry: ReplayEntry, runtime: &ReplayRuntime
     let header_changes = diff_header_count(&entry.original_headers, &replay_headers);
     row.header_changes = Some(header_changes);
 
-    row.body_size_replay = body_size;
+    row.body_size_replay = body
+        .as_ref()
+        .map(|b| i64::try_from(b.len()).unwrap_or(i64::MAX));
     row.body_size_delta = diff_i64(row.body_size_original, row.body_size_replay);
 
-    (entry.index, row)
+    if let (true, Some(original), Some(replayed)) = (
+        runtime.compare_bodies,
+        entry.original_body.as_deref(),
+        body.as_deref(),
+    ) {
+        let diff = diff_bodies(
+            original,
+            entry.original_mime.as_deref(),
+            replayed,
+            replay_headers.get("content-type").map(|s| s.as_str()),
+            &runtime.ignore_json_paths,
+        );
+        row.body_diff_kind = Some(diff.kind.as_str());
+        row.body_match = Some(diff.matches);
+        row.body_diff = Some(diff.summary);
+        row.body_changes = diff.changes;
+    }
+
+    if let (Some(session), Some(list)) = (runtime.session.as_ref(), header_list.as_deref()) {
+        let recorded_headers = recorded_header_list(entry);
+        let recorded = ResponseView {
+            headers: &recorded_headers,
+            body: entry.original_body.as_deref().unwrap_or_default(),
+        };
+        let replayed = ResponseView {
+            headers: list,
+            body: body.as_deref().unwrap_or_default(),
+        };
+        session
+            .lock()
+            .expect("replay session lock")
+            .observe(&replay_url, Some(&recorded), &replayed);
+    }
+
+    let capture = match (capture_meta, header_list, body) {
+        (Some((status_text, http_version)), Some(response_headers), Some(response_body)) => {
+            let mut request_headers: Vec<(String, String)> = headers.into_iter().collect();
+            request_headers.sort();
+            Some(ReplayCapture {
+                source_entry_id: entry.source_entry_id,
+                started_at,
+                wait_ms,
+                receive_ms: started.elapsed().as_secs_f64() * 1000.0 - wait_ms,
+                method,
+                url: replay_url.to_string(),
+                request_headers,
+                request_body: request_body.map(Cow::into_owned),
+                status: replay_status,
+                status_text,
+                http_version,
+                response_headers,
+                response_body,
+            })
+        }
+        _ => None,
+    };
+
+    (entry.index, row, capture)
 }
 
 fn apply_headers(mut request: ureq::Request, headers: &HashMap<String, String>) -> ureq::Request {
@@ -333,10 +744,27 @@ fn apply_headers(mut request: ureq::Request, headers: &HashMap<String, String>)
     request
 }
 
-fn read_response_size(response: ureq::Response) -> Result<i64> {
-    let mut reader = response.into_reader();
-    let bytes = io::copy(&mut reader, &mut io::sink())?;
-    Ok(i64::try_from(bytes).unwrap_or(i64::MAX))
+fn read_response_body(response: ureq::Response) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)?;
+    Ok(body)
+}
+
+/// Recorded response headers with each recorded cookie as its own `Set-Cookie` pair.
+fn recorded_header_list(entry: &ReplayEntry) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = entry
+        .original_headers
+        .iter()
+        .filter(|(name, _)| name.as_str() != "set-cookie")
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    out.extend(
+        entry
+            .original_set_cookies
+            .iter()
+            .map(|value| ("set-cookie".to_string(), value.clone())),
+    );
+    out
 }
 
 fn response_headers_map(response: &ureq::Response) -> HashMap<String, String> {
@@ -350,6 +778,19 @@ fn response_headers_map(response: &ureq::Response) -> HashMap<String, String> {
     out
 }
 
+/// Response headers in wire order, keeping repeated headers such as `Set-Cookie`.
+fn response_header_list(response: &ureq::Response) -> Vec<(String, String)> {
+    let mut names = response.headers_names();
+    names.dedup();
+    let mut out = Vec::new();
+    for name in names {
+        for value in response.all(&name) {
+            out.push((name.clone(), value.to_string()));
+        }
+    }
+    out
+}
+
 fn diff_header_count(left: &HashMap<String, String>, right: &HashMap<String, String>) -> usize {
     let mut keys: HashSet<&String> = HashSet::new();
     for k in left.keys() {
@@ -453,14 +894,14 @@ fn load_entries_from_db(path: &Path, options: &ReplayOptions) -> Result<Vec<Repl
     // Best-effort schema upgrades: if the database is writable, run upgrades on a
     // separate read-write connection. If opening in read-write mode fails (e.g.
     // read-only filesystem), skip upgrades and continue with a read-only connection.
-    if let Ok(upgrade_conn) = Connection::open_with_flags(
+    if let Ok(upgrade_conn) = open_database_with_flags(
         path,
         OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     ) {
         ensure_schema_upgrades(&upgrade_conn)?;
     }
 
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         path,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
@@ -478,7 +919,8 @@ fn load_entries_from_db(path: &Path, options: &ReplayOptions) -> Result<Vec<Repl
 
     let mut hashes: Vec<String> = rows
         .iter()
-        .filter_map(|row| row.request_body_hash.clone())
+        .flat_map(|row| [row.request_body_hash.clone(), row.response_body_hash.clone()])
+        .flatten()
         .collect();
     hashes.sort();
     hashes.dedup();
@@ -500,9 +942,10 @@ fn load_entries_from_db(path: &Path, options: &ReplayOptions) -> Result<Vec<Repl
     };
 
     if options.allow_external_paths {
-        for blob in &mut blobs {
-            load_external_blob_content(blob, external_root.as_deref())?;
-        }
+        blobs = blobs
+            .into_iter()
+            .map(|b| load_external_blob_content(b, external_root.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
     }
 
     let blob_map: HashMap<String, BlobRow> = blobs.into_iter().map(|b| (b.hash.clone(), b)).collect();
@@ -517,6 +960,10 @@ fn load_entries_from_db(path: &Path, options: &ReplayOptions) -> Result<Vec<Repl
         let method = row.method.clone().unwrap_or_else(|| "GET".to_string());
         let request_headers = headers_from_json(row.request_headers.as_deref());
         let original_headers = headers_from_json(row.response_headers.as_deref());
+        let original_set_cookies = set_cookies_from_json(
+            row.response_cookies.as_deref(),
+            original_headers.get("set-cookie").map(|s| s.as_str()),
+        );
         let original_body_size = sanitize_size(row.response_body_size)
             .or_else(|| sanitize_size(row.response_body_size_raw));
 
@@ -526,15 +973,30 @@ fn load_entries_from_db(path: &Path, options: &ReplayOptions) -> Result<Vec<Repl
             .and_then(|hash| blob_map.get(hash))
             .and_then(|blob| if blob.content.is_empty() { None } else { Some(blob.content.clone()) });
 
+        // A blob with a size but no content lives in an unreadable external file; treat the
+        // recorded body as unknown rather than empty.
+        let original_body = match row.response_body_hash.as_ref() {
+            Some(hash) => blob_map
+                .get(hash)
+                .filter(|blob| !blob.content.is_empty() || blob.size <= 0)
+                .map(|blob| blob.content.clone()),
+            None => (original_body_size == Some(0)).then(Vec::new),
+        };
+
         out.push(ReplayEntry {
             index: idx,
+            source_entry_id: Some(row.id),
+            started_at: row.started_at.as_deref().and_then(parse_timestamp),
             method,
             url,
             request_headers,
             request_body,
             original_status: row.status,
             original_headers,
+            original_set_cookies,
             original_body_size,
+            original_body,
+            original_mime: row.response_mime_type.clone(),
         });
     }
 
@@ -569,19 +1031,32 @@ fn load_entries_from_har(path: &Path, options: &ReplayOptions) -> Result<Vec<Rep
         let url = entry.request.url.clone();
         let mut request_headers = headers_from_list(&entry.request.headers);
         let original_headers = headers_from_list(&entry.response.headers);
+        let original_set_cookies: Vec<String> = entry
+            .response
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("set-cookie"))
+            .map(|h| h.value.clone())
+            .collect();
         let original_body_size = sanitize_size(entry.response.body_size)
             .or_else(|| sanitize_size(Some(entry.response.content.size)));
         let request_body = post_data_to_body(&entry.request.post_data, &mut request_headers)?;
+        let original_body = content_to_body(&entry.response.content);
 
         out.push(ReplayEntry {
             index: idx,
+            source_entry_id: None,
+            started_at: parse_timestamp(&entry.started_date_time),
             method,
             url,
             request_headers,
             request_body,
             original_status: Some(entry.response.status),
             original_headers,
+            original_set_cookies,
             original_body_size,
+            original_body,
+            original_mime: entry.response.content.mime_type.clone(),
         });
     }
 
@@ -693,6 +1168,29 @@ fn post_data_to_body(
     Ok(Some(encoded.into_bytes()))
 }
 
+/// Recorded response body, or `None` when the HAR omitted `content.text` (unless the
+/// response was recorded as empty) or its encoding cannot be decoded. Only used for body
+/// diffing and value extraction, so an unreadable body must not stop the replay.
+fn content_to_body(content: &Content) -> Option<Vec<u8>> {
+    let Some(text) = &content.text else {
+        return (content.size == 0).then(Vec::new);
+    };
+
+    match content
+        .encoding
+        .as_deref()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .as_deref()
+    {
+        None => Some(text.as_bytes().to_vec()),
+        Some("base64") => {
+            use base64::{engine::general_purpose::STANDARD, Engine as _};
+            STANDARD.decode(text).ok()
+        }
+        Some(_) => None,
+    }
+}
+
 fn headers_from_json(json: Option<&str>) -> HashMap<String, String> {
     let Some(json) = json else {
         return HashMap::new();
@@ -710,6 +1208,22 @@ fn headers_from_json(json: Option<&str>) -> HashMap<String, String> {
         .collect()
 }
 
+/// Stored headers collapse repeated `Set-Cookie`s, so prefer the recorded cookie list.
+fn set_cookies_from_json(cookies_json: Option<&str>, header: Option<&str>) -> Vec<String> {
+    let cookies: Vec<Cookie> = cookies_json
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    if cookies.is_empty() {
+        return header
+            .map(|value| value.split('\n').map(str::to_string).collect())
+            .unwrap_or_default();
+    }
+    cookies
+        .into_iter()
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect()
+}
+
 fn headers_from_list(headers: &[Header]) -> HashMap<String, String> {
     headers
         .iter()
@@ -732,6 +1246,8 @@ fn compile_url_regexes(values: &[String]) -> Result<Vec<Regex>> {
 struct CompiledOverrides {
     host_overrides: Vec<HostOverrideRule>,
     header_overrides: Vec<HeaderOverrideRule>,
+    ignore_json_paths: Vec<JsonPath>,
+    extract_rules: Vec<ExtractRule>,
 }
 
 fn compile_rules(options: &ReplayOptions) -> Result<CompiledOverrides> {
@@ -747,9 +1263,23 @@ fn compile_rules(options: &ReplayOptions) -> Result<CompiledOverrides> {
         .map(|raw| parse_header_override(raw))
         .collect::<Result<Vec<_>>>()?;
 
+    let ignore_json_paths = options
+        .ignore_json_path
+        .iter()
+        .map(|raw| JsonPath::parse(raw))
+        .collect::<Result<Vec<_>>>()?;
+
+    let extract_rules = options
+        .extract
+        .iter()
+        .map(|raw| parse_extract_rule(raw))
+        .collect::<Result<Vec<_>>>()?;
+
     Ok(CompiledOverrides {
         host_overrides,
         header_overrides,
+        ignore_json_paths,
+        extract_rules,
     })
 }
 
@@ -805,32 +1335,165 @@ fn compile_override_pattern(raw: &str) -> Result<Regex> {
     Ok(Regex::new(raw)?)
 }
 
-fn load_external_blob_content(blob: &mut BlobRow, external_root: Option<&Path>) -> Result<()> {
-    if !blob.content.is_empty() || blob.size <= 0 {
-        return Ok(());
+/// Store a replay run as a new import, linking each entry to the entry it replayed.
+fn record_replay(
+    path: &Path,
+    input: &Path,
+    captures: &[ReplayCapture],
+    skipped: usize,
+) -> Result<i64> {
+    let mut conn = open_database(path)?;
+    conn.busy_timeout(Duration::from_secs(30))?;
+    create_schema(&conn)?;
+
+    let insert_options = InsertEntryOptions {
+        store_bodies: true,
+        max_body_size: None,
+        ..Default::default()
+    };
+
+    let tx = conn.transaction()?;
+    let source = format!("replay:{}", input.display());
+    let import_id = create_import_with_status(
+        &tx,
+        &source,
+        None,
+        "complete",
+        Some(captures.len() + skipped),
+        Some(skipped),
+    )?;
+
+    let mut inserted = 0usize;
+    for capture in captures {
+        let relations = EntryRelations {
+            replay_of_entry_id: capture.source_entry_id,
+            ..Default::default()
+        };
+        let entry = capture_to_har_entry(capture);
+        if insert_entry(&tx, import_id, &entry, &insert_options, &relations)?.inserted {
+            inserted += 1;
+        }
     }
-    let Some(path) = &blob.external_path else {
-        return Ok(());
+    update_import_count(&tx, import_id, inserted)?;
+    tx.commit()?;
+    Ok(import_id)
+}
+
+fn capture_to_har_entry(capture: &ReplayCapture) -> HarEntry {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    let to_headers = |list: &[(String, String)]| -> Vec<Header> {
+        list.iter()
+            .map(|(name, value)| Header {
+                name: name.clone(),
+                value: value.clone(),
+            })
+            .collect()
     };
-    let Some(root) = external_root else {
-        return Ok(());
+    let header = |list: &[(String, String)], name: &str| -> Option<String> {
+        list.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
     };
 
-    let candidate = PathBuf::from(path);
-    let candidate = if candidate.is_absolute() {
-        candidate
-    } else {
-        root.join(candidate)
-    };
-    let resolved = match candidate.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return Ok(()),
+    let query_string = Url::parse(&capture.url).ok().map(|url| {
+        url.query_pairs()
+            .map(|(name, value)| QueryParam {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let post_data = capture.request_body.as_ref().map(|body| {
+        let mut extensions = Extensions::new();
+        let text = match std::str::from_utf8(body) {
+            Ok(text) => text.to_string(),
+            Err(_) => {
+                extensions.insert("encoding".to_string(), "base64".into());
+                STANDARD.encode(body)
+            }
+        };
+        PostData {
+            mime_type: header(&capture.request_headers, "content-type"),
+            text: Some(text),
+            params: None,
+            extensions,
+        }
+    });
+
+    let (text, encoding) = match std::str::from_utf8(&capture.response_body) {
+        Ok(text) => (text.to_string(), None),
+        Err(_) => (
+            STANDARD.encode(&capture.response_body),
+            Some("base64".to_string()),
+        ),
     };
-    if !resolved.starts_with(root) {
-        return Ok(());
+    let cookies: Vec<Cookie> = capture
+        .response_headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+        .filter_map(|(_, value)| parse_set_cookie(value))
+        .collect();
+    // ureq hands back decoded bodies, so the stored headers must not claim an encoding.
+    let response_headers: Vec<(String, String)> = capture
+        .response_headers
+        .iter()
+        .filter(|(name, _)| !name.eq_ignore_ascii_case("content-encoding"))
+        .cloned()
+        .collect();
+    let body_size = capture.response_body.len() as i64;
+
+    HarEntry {
+        pageref: None,
+        started_date_time: capture.started_at.clone(),
+        time: capture.wait_ms + capture.receive_ms,
+        request: HarRequest {
+            method: capture.method.clone(),
+            url: capture.url.clone(),
+            http_version: capture.http_version.clone(),
+            cookies: None,
+            headers: to_headers(&capture.request_headers),
+            query_string,
+            post_data,
+            headers_size: None,
+            body_size: Some(capture.request_body.as_ref().map_or(0, |b| b.len() as i64)),
+            extensions: Extensions::new(),
+        },
+        response: HarResponse {
+            status: i32::from(capture.status),
+            status_text: capture.status_text.clone(),
+            http_version: capture.http_version.clone(),
+            cookies: if cookies.is_empty() { None } else { Some(cookies) },
+            headers: to_headers(&response_headers),
+            content: Content {
+                size: body_size,
+                compression: None,
+                mime_type: header(&capture.response_headers, "content-type"),
+                text: Some(text),
+                encoding,
+                extensions: Extensions::new(),
+            },
+            redirect_url: header(&capture.response_headers, "location"),
+            headers_size: None,
+            body_size: Some(body_size),
+            extensions: Extensions::new(),
+        },
+        cache: None,
+        timings: Some(Timings {
+            blocked: None,
+            dns: None,
+            connect: None,
+            send: 0.0,
+            wait: capture.wait_ms,
+            receive: capture.receive_ms,
+            ssl: None,
+            extensions: Extensions::new(),
+        }),
+        server_ip_address: None,
+        connection: None,
+        extensions: Extensions::new(),
     }
-    blob.content = std::fs::read(resolved)?;
-    Ok(())
 }
 
 fn diff_i64(left: Option<i64>, right: Option<i64>) -> Option<i64> {
@@ -848,26 +1511,18 @@ fn write_json(rows: &[ReplayRow]) -> Result<()> {
     Ok(())
 }
 
-fn write_csv(rows: &[ReplayRow]) -> Result<()> {
-    let columns = replay_columns();
+fn write_csv(columns: &[&str], data: &[Vec<String>]) -> Result<()> {
     let mut out = io::stdout().lock();
     write_csv_row(&mut out, columns.iter().copied())?;
-    for row in rows {
-        let fields = replay_row_values(row);
-        write_csv_row(&mut out, fields.iter().map(|s| s.as_str()))?;
+    for row in data {
+        write_csv_row(&mut out, row.iter().map(|s| s.as_str()))?;
     }
     Ok(())
 }
 
-fn write_table(rows: &[ReplayRow]) -> Result<()> {
-    let columns = replay_columns();
-    let mut data: Vec<Vec<String>> = Vec::with_capacity(rows.len());
-    for row in rows {
-        data.push(replay_row_values(row));
-    }
-
+fn write_table(columns: &[&str], data: &[Vec<String>]) -> Result<()> {
     let mut widths: Vec<usize> = columns.iter().map(|c| c.len()).collect();
-    for row in &data {
+    for row in data {
         for (i, value) in row.iter().enumerate() {
             widths[i] = widths[i].max(value.chars().count());
         }
@@ -886,6 +1541,10 @@ fn write_table(rows: &[ReplayRow]) -> Result<()> {
     Ok(())
 }
 
+fn replay_rows(rows: &[ReplayRow]) -> Vec<Vec<String>> {
+    rows.iter().map(replay_row_values).collect()
+}
+
 fn replay_columns() -> Vec<&'static str> {
     vec![
         "method",
@@ -898,6 +1557,9 @@ fn replay_columns() -> Vec<&'static str> {
         "body_size_original",
         "body_size_replay",
         "body_size_delta",
+        "body_diff_kind",
+        "body_match",
+        "body_diff",
         "error",
     ]
 }
@@ -914,6 +1576,9 @@ fn replay_row_values(row: &ReplayRow) -> Vec<String> {
         opt_i64(row.body_size_original),
         opt_i64(row.body_size_replay),
         opt_i64_signed(row.body_size_delta),
+        row.body_diff_kind.unwrap_or_default().to_string(),
+        opt_bool(row.body_match),
+        row.body_diff.clone().unwrap_or_default(),
         row.error.clone().unwrap_or_default(),
     ]
 }
@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Upper bounds (ms) of the latency histogram buckets; a final bucket catches the rest.
+const HISTOGRAM_BOUNDS_MS: [f64; 12] = [
+    1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0,
+];
+
+pub struct LoadPlan {
+    pub duration: Duration,
+    pub ramp_up: Duration,
+    pub vus: usize,
+    pub rps: Option<f64>,
+}
+
+/// Outcome of one request issued by a virtual user.
+pub struct LoadSample {
+    pub latency_ms: f64,
+    pub error: bool,
+    pub status_mismatch: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket {
+    pub le_ms: Option<f64>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointSummary {
+    pub endpoint: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub status_mismatches: usize,
+    pub rps: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoadReport {
+    pub elapsed_secs: f64,
+    pub vus: usize,
+    pub target_rps: Option<f64>,
+    pub endpoints: Vec<EndpointSummary>,
+    pub total: EndpointSummary,
+}
+
+/// Running statistics for one endpoint, updated as each request completes.
+///
+/// Latencies are kept as counts per value rounded to three significant digits, so memory is
+/// bounded by the latency range rather than the number of requests.
+#[derive(Debug, Default)]
+struct LatencyStats {
+    requests: usize,
+    errors: usize,
+    status_mismatches: usize,
+    sum_ms: f64,
+    max_ms: f64,
+    histogram: [usize; HISTOGRAM_BOUNDS_MS.len() + 1],
+    rounded_us: BTreeMap<u64, usize>,
+}
+
+impl LatencyStats {
+    fn record(&mut self, sample: &LoadSample) {
+        let latency = sample.latency_ms.max(0.0);
+        self.requests += 1;
+        self.errors += usize::from(sample.error);
+        self.status_mismatches += usize::from(sample.status_mismatch);
+        self.sum_ms += latency;
+        self.max_ms = self.max_ms.max(latency);
+        let bucket = HISTOGRAM_BOUNDS_MS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+        self.histogram[bucket] += 1;
+        *self
+            .rounded_us
+            .entry(round_significant((latency * 1000.0).round() as u64))
+            .or_default() += 1;
+    }
+
+    fn merge(&mut self, other: &LatencyStats) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.status_mismatches += other.status_mismatches;
+        self.sum_ms += other.sum_ms;
+        self.max_ms = self.max_ms.max(other.max_ms);
+        for (total, count) in self.histogram.iter_mut().zip(other.histogram) {
+            *total += count;
+        }
+        for (value, count) in &other.rounded_us {
+            *self.rounded_us.entry(*value).or_default() += count;
+        }
+    }
+
+    /// Nearest-rank percentile over the rounded latencies.
+    fn percentile(&self, pct: f64) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        let rank = ((pct / 100.0) * (self.requests as f64 - 1.0)).round() as usize;
+        let mut seen = 0usize;
+        for (value, count) in &self.rounded_us {
+            seen += count;
+            if seen > rank {
+                return *value as f64 / 1000.0;
+            }
+        }
+        self.max_ms
+    }
+
+    fn summary(&self, endpoint: String, secs: f64) -> EndpointSummary {
+        let requests = self.requests;
+        let mut histogram: Vec<HistogramBucket> = HISTOGRAM_BOUNDS_MS
+            .iter()
+            .zip(self.histogram)
+            .map(|(bound, count)| HistogramBucket {
+                le_ms: Some(*bound),
+                count,
+            })
+            .collect();
+        histogram.push(HistogramBucket {
+            le_ms: None,
+            count: self.histogram[HISTOGRAM_BOUNDS_MS.len()],
+        });
+
+        EndpointSummary {
+            endpoint,
+            requests,
+            errors: self.errors,
+            error_rate: if requests == 0 {
+                0.0
+            } else {
+                self.errors as f64 / requests as f64
+            },
+            status_mismatches: self.status_mismatches,
+            rps: if secs > 0.0 {
+                requests as f64 / secs
+            } else {
+                0.0
+            },
+            mean_ms: if requests == 0 {
+                0.0
+            } else {
+                self.sum_ms / requests as f64
+            },
+            p50_ms: self.percentile(50.0),
+            p95_ms: self.percentile(95.0),
+            p99_ms: self.percentile(99.0),
+            max_ms: self.max_ms,
+            histogram,
+        }
+    }
+}
+
+/// Round to three significant digits (e.g. 123456 -> 123000).
+fn round_significant(value: u64) -> u64 {
+    let mut scale = 1u64;
+    while value / scale >= 1000 {
+        scale *= 10;
+    }
+    (value + scale / 2) / scale * scale
+}
+
+/// Drive `exec` from `plan.vus` threads until the duration elapses. The virtual users share
+/// one round-robin cursor over `items`, so each endpoint is hit as often as it was recorded;
+/// results are aggregated per `endpoint` key as they come in.
+pub fn run_load<T, K, F>(items: &[T], plan: &LoadPlan, endpoint: K, exec: F) -> LoadReport
+where
+    T: Sync,
+    K: Fn(&T) -> String,
+    F: Fn(&T) -> LoadSample + Sync,
+{
+    let vus = plan.vus.max(1);
+    let mut groups: BTreeMap<String, usize> = BTreeMap::new();
+    let item_group: Vec<usize> = items
+        .iter()
+        .map(|item| {
+            let next = groups.len();
+            *groups.entry(endpoint(item)).or_insert(next)
+        })
+        .collect();
+
+    let started = Instant::now();
+    let deadline = started + plan.duration;
+    let cursor = AtomicUsize::new(0);
+    let limiter = plan
+        .rps
+        .map(|rps| RampLimiter::new(rps, plan.ramp_up, started));
+
+    let mut stats: Vec<LatencyStats> = (0..groups.len()).map(|_| LatencyStats::default()).collect();
+    let per_vu: Vec<Vec<LatencyStats>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..vus)
+            .map(|vu| {
+                let cursor = &cursor;
+                let limiter = limiter.as_ref();
+                let exec = &exec;
+                let item_group = &item_group;
+                let group_count = groups.len();
+                scope.spawn(move || {
+                    let mut out: Vec<LatencyStats> =
+                        (0..group_count).map(|_| LatencyStats::default()).collect();
+                    // Without a rate target, ramp up by staggering virtual-user start times.
+                    if limiter.is_none() && !plan.ramp_up.is_zero() {
+                        let delay = plan.ramp_up.mul_f64(vu as f64 / vus as f64);
+                        let start_at = started + delay;
+                        if start_at >= deadline {
+                            return out;
+                        }
+                        thread::sleep(start_at.saturating_duration_since(Instant::now()));
+                    }
+
+                    while Instant::now() < deadline {
+                        if let Some(limiter) = limiter {
+                            if !limiter.wait(deadline) {
+                                break;
+                            }
+                        }
+                        let idx = cursor.fetch_add(1, Ordering::Relaxed) % items.len();
+                        out[item_group[idx]].record(&exec(&items[idx]));
+                    }
+                    out
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    });
+    for vu_stats in &per_vu {
+        for (total, partial) in stats.iter_mut().zip(vu_stats) {
+            total.merge(partial);
+        }
+    }
+
+    let named = groups
+        .into_iter()
+        .map(|(name, idx)| (name, std::mem::take(&mut stats[idx])))
+        .collect();
+    summarize(named, started.elapsed(), vus, plan.rps)
+}
+
+fn summarize(
+    groups: Vec<(String, LatencyStats)>,
+    elapsed: Duration,
+    vus: usize,
+    target_rps: Option<f64>,
+) -> LoadReport {
+    let secs = elapsed.as_secs_f64();
+    let mut all = LatencyStats::default();
+    let endpoints = groups
+        .into_iter()
+        .filter(|(_, group)| group.requests > 0)
+        .map(|(endpoint, group)| {
+            all.merge(&group);
+            group.summary(endpoint, secs)
+        })
+        .collect();
+
+    LoadReport {
+        elapsed_secs: secs,
+        vus,
+        target_rps,
+        endpoints,
+        total: all.summary("TOTAL".to_string(), secs),
+    }
+}
+
+/// Paces requests to a target rate that grows linearly during the ramp-up period.
+struct RampLimiter {
+    rps: f64,
+    ramp_up: Duration,
+    started: Instant,
+    next_allowed: Mutex<Instant>,
+}
+
+impl RampLimiter {
+    fn new(rps: f64, ramp_up: Duration, started: Instant) -> Self {
+        Self {
+            rps,
+            ramp_up,
+            started,
+            next_allowed: Mutex::new(started),
+        }
+    }
+
+    fn current_rate(&self, at: Instant) -> f64 {
+        if self.ramp_up.is_zero() {
+            return self.rps;
+        }
+        let progress = (at.saturating_duration_since(self.started).as_secs_f64()
+            / self.ramp_up.as_secs_f64())
+        .min(1.0);
+        (self.rps * progress).max(self.rps.min(1.0))
+    }
+
+    /// Block until the next request slot; `false` once the slot would fall past `deadline`.
+    fn wait(&self, deadline: Instant) -> bool {
+        let slot = {
+            let mut next = self.next_allowed.lock().expect("load limiter lock");
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + Duration::from_secs_f64(1.0 / self.current_rate(slot));
+            slot
+        };
+        if slot >= deadline {
+            return false;
+        }
+        thread::sleep(slot.saturating_duration_since(Instant::now()));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(latency_ms: f64, error: bool) -> LoadSample {
+        LoadSample {
+            latency_ms,
+            error,
+            status_mismatch: error,
+        }
+    }
+
+    #[test]
+    fn summarize_computes_percentiles_errors_and_histogram() {
+        let mut a = LatencyStats::default();
+        for ms in 1..=100 {
+            a.record(&sample(ms as f64, ms > 95));
+        }
+        let mut b = LatencyStats::default();
+        b.record(&sample(3.0, false));
+
+        let groups = vec![("GET /a".to_string(), a), ("GET /b".to_string(), b)];
+        let report = summarize(groups, Duration::from_secs(10), 4, Some(20.0));
+        assert_eq!(report.endpoints.len(), 2);
+        let a = &report.endpoints[0];
+        assert_eq!(a.endpoint, "GET /a");
+        assert_eq!(a.requests, 100);
+        assert_eq!(a.errors, 5);
+        assert!((a.error_rate - 0.05).abs() < 1e-9);
+        assert_eq!(a.p50_ms, 51.0);
+        assert_eq!(a.p95_ms, 95.0);
+        assert_eq!(a.p99_ms, 99.0);
+        assert_eq!(a.max_ms, 100.0);
+        assert_eq!(a.histogram.iter().map(|b| b.count).sum::<usize>(), 100);
+        assert_eq!(a.histogram[0].count, 1);
+        assert_eq!(a.histogram[6].count, 50);
+
+        assert_eq!(report.total.requests, 101);
+        assert_eq!(report.total.max_ms, 100.0);
+        assert!((report.total.rps - 10.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn latency_stats_stay_bounded_and_close_to_exact() {
+        let mut stats = LatencyStats::default();
+        for i in 0..200_000u64 {
+            stats.record(&sample(0.5 + (i % 40_000) as f64 * 0.0731, false));
+        }
+        assert_eq!(stats.requests, 200_000);
+        assert!(stats.rounded_us.len() < 4_000, "{}", stats.rounded_us.len());
+        let exact_p50 = 0.5 + 19_999.5 * 0.0731;
+        assert!((stats.percentile(50.0) - exact_p50).abs() / exact_p50 < 0.005);
+        assert_eq!(round_significant(123_456), 123_000);
+        assert_eq!(round_significant(999), 999);
+        assert_eq!(round_significant(9_996), 10_000);
+    }
+
+    #[test]
+    fn run_load_loops_until_duration_and_respects_rps() {
+        let items = vec!["a", "b", "a"];
+        let plan = LoadPlan {
+            duration: Duration::from_millis(500),
+            ramp_up: Duration::ZERO,
+            vus: 2,
+            rps: Some(20.0),
+        };
+        let report = run_load(
+            &items,
+            &plan,
+            |item| format!("GET /{item}"),
+            |_| sample(1.0, false),
+        );
+        assert!(
+            (8..=12).contains(&report.total.requests),
+            "requests: {}",
+            report.total.requests
+        );
+        assert_eq!(report.endpoints.len(), 2);
+        let a = report.endpoints[0].requests;
+        let b = report.endpoints[1].requests;
+        assert!(a >= 2 * b - 1 && a <= 2 * b + 2, "a={a} b={b}");
+    }
+}
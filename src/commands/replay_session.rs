@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::Value;
+use url::Url;
+
+use crate::error::{HarliteError, Result};
+use crate::har::Cookie;
+
+use super::json_path::JsonPath;
+
+/// Where a correlation rule reads its value from.
+#[derive(Clone, Debug)]
+enum ExtractSource {
+    Json(JsonPath),
+    Regex(Regex),
+    Header(String),
+    Cookie(String),
+}
+
+/// `--extract '<name>=<source>:<expr>'`: capture a value from each response so later
+/// requests can use it.
+#[derive(Clone, Debug)]
+pub struct ExtractRule {
+    name: String,
+    source: ExtractSource,
+}
+
+pub fn parse_extract_rule(raw: &str) -> Result<ExtractRule> {
+    let usage = || {
+        HarliteError::InvalidArgs(format!(
+            "extract rule '{raw}' must be '<name>=json:<path>', '<name>=regex:<pattern>', '<name>=header:<name>' or '<name>=cookie:<name>'"
+        ))
+    };
+
+    let (name, spec) = raw.split_once('=').ok_or_else(usage)?;
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(HarliteError::InvalidArgs(format!(
+            "extract rule name '{name}' must be non-empty and use only letters, digits, '_' or '-'"
+        )));
+    }
+
+    let (kind, expr) = spec.split_once(':').ok_or_else(usage)?;
+    if expr.trim().is_empty() {
+        return Err(usage());
+    }
+    let source = match kind.trim().to_ascii_lowercase().as_str() {
+        "json" => ExtractSource::Json(JsonPath::parse(expr)?),
+        "regex" => ExtractSource::Regex(Regex::new(expr)?),
+        "header" => ExtractSource::Header(expr.trim().to_ascii_lowercase()),
+        "cookie" => ExtractSource::Cookie(expr.trim().to_string()),
+        _ => return Err(usage()),
+    };
+
+    Ok(ExtractRule {
+        name: name.to_string(),
+        source,
+    })
+}
+
+impl ExtractRule {
+    fn extract(&self, response: &ResponseView<'_>) -> Option<String> {
+        match &self.source {
+            ExtractSource::Json(path) => {
+                let doc: Value = serde_json::from_slice(response.body).ok()?;
+                path.select(&doc).into_iter().find_map(|value| match value {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Number(n) => Some(n.to_string()),
+                    Value::Bool(b) => Some(b.to_string()),
+                    _ => None,
+                })
+            }
+            ExtractSource::Regex(re) => {
+                let text = String::from_utf8_lossy(response.body);
+                let caps = re.captures(&text)?;
+                caps.get(1)
+                    .or_else(|| caps.get(0))
+                    .map(|m| m.as_str().to_string())
+            }
+            ExtractSource::Header(name) => response
+                .headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone()),
+            ExtractSource::Cookie(name) => response
+                .set_cookies()
+                .filter_map(parse_set_cookie)
+                .filter(|cookie| cookie.name == *name)
+                .last()
+                .map(|cookie| cookie.value),
+        }
+    }
+}
+
+/// Response headers (repeated `Set-Cookie` kept as separate pairs) and body.
+pub struct ResponseView<'a> {
+    pub headers: &'a [(String, String)],
+    pub body: &'a [u8],
+}
+
+impl ResponseView<'_> {
+    fn set_cookies(&self) -> impl Iterator<Item = &str> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .flat_map(|(_, value)| value.split('\n'))
+    }
+}
+
+/// Recorded values shorter than this are only substituted through `{{name}}` placeholders;
+/// short ids such as `1` or `true` would otherwise rewrite unrelated text like `HTTP/1.1`.
+const MIN_IMPLICIT_VALUE_LEN: usize = 8;
+
+#[derive(Clone, Debug)]
+struct Variable {
+    recorded: Option<String>,
+    current: String,
+}
+
+#[derive(Clone, Debug)]
+struct JarCookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+}
+
+/// State carried from one replayed request to the next: correlated variables and cookies.
+pub struct ReplaySession {
+    rules: Vec<ExtractRule>,
+    vars: HashMap<String, Variable>,
+    jar: Option<Vec<JarCookie>>,
+}
+
+impl ReplaySession {
+    pub fn new(rules: Vec<ExtractRule>, cookie_jar: bool) -> Self {
+        Self {
+            rules,
+            vars: HashMap::new(),
+            jar: cookie_jar.then(Vec::new),
+        }
+    }
+
+    /// Rewrite part of a request before it is sent: `{{name}}` placeholders become the
+    /// latest extracted value, and values seen in the recorded responses (of at least
+    /// [`MIN_IMPLICIT_VALUE_LEN`] characters) are swapped for their replayed counterparts.
+    /// Returns the number of substitutions made.
+    pub fn substitute(&self, text: &mut String) -> usize {
+        let mut count = 0usize;
+        for (name, var) in &self.vars {
+            let placeholder = format!("{{{{{name}}}}}");
+            count += replace_counting(text, &placeholder, &var.current);
+            if let Some(recorded) = var.recorded.as_deref() {
+                if recorded.chars().count() >= MIN_IMPLICIT_VALUE_LEN && recorded != var.current {
+                    count += replace_token(text, recorded, &var.current);
+                }
+            }
+        }
+        count
+    }
+
+    /// Like [`ReplaySession::substitute`], for request bodies that are valid UTF-8.
+    pub fn substitute_body(&self, body: &mut Vec<u8>) -> usize {
+        let Ok(text) = std::str::from_utf8(body) else {
+            return 0;
+        };
+        let mut text = text.to_string();
+        let count = self.substitute(&mut text);
+        if count > 0 {
+            *body = text.into_bytes();
+        }
+        count
+    }
+
+    /// Merge jar cookies for `url` into the request's `Cookie` header, replacing recorded
+    /// values for cookies the server has since re-issued.
+    pub fn apply_cookies(&self, url: &Url, headers: &mut HashMap<String, String>) {
+        let Some(jar) = &self.jar else {
+            return;
+        };
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let path = url.path();
+        let matching: Vec<&JarCookie> = jar
+            .iter()
+            .filter(|cookie| cookie_matches(cookie, &host, path))
+            .collect();
+        if matching.is_empty() {
+            return;
+        }
+
+        let mut pairs: Vec<(String, String)> = headers
+            .get("cookie")
+            .map(|raw| {
+                raw.split(';')
+                    .filter_map(|pair| {
+                        let (name, value) = pair.split_once('=')?;
+                        Some((name.trim().to_string(), value.trim().to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        for cookie in matching {
+            match pairs.iter_mut().find(|(name, _)| *name == cookie.name) {
+                Some(pair) => pair.1 = cookie.value.clone(),
+                None => pairs.push((cookie.name.clone(), cookie.value.clone())),
+            }
+        }
+
+        let header = pairs
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        headers.insert("cookie".to_string(), header);
+    }
+
+    /// Learn from a replayed response: run the extraction rules (also against the recorded
+    /// response, to know which old value to replace) and store any `Set-Cookie`s.
+    pub fn observe(
+        &mut self,
+        url: &Url,
+        recorded: Option<&ResponseView<'_>>,
+        replayed: &ResponseView<'_>,
+    ) {
+        for rule in &self.rules {
+            let Some(current) = rule.extract(replayed) else {
+                continue;
+            };
+            let recorded_value = recorded.and_then(|view| rule.extract(view));
+            let var = self.vars.entry(rule.name.clone()).or_insert(Variable {
+                recorded: None,
+                current: String::new(),
+            });
+            if recorded_value.is_some() {
+                var.recorded = recorded_value;
+            }
+            var.current = current;
+        }
+
+        let Some(jar) = self.jar.as_mut() else {
+            return;
+        };
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        for raw in replayed.set_cookies() {
+            let Some(cookie) = parse_set_cookie(raw) else {
+                continue;
+            };
+            let (domain, host_only) = match cookie.domain.as_deref() {
+                Some(domain) if !domain.is_empty() => {
+                    (domain.trim_start_matches('.').to_ascii_lowercase(), false)
+                }
+                _ => (host.clone(), true),
+            };
+            let path = cookie
+                .path
+                .clone()
+                .filter(|p| p.starts_with('/'))
+                .unwrap_or_else(|| default_cookie_path(url.path()));
+            jar.retain(|c| !(c.name == cookie.name && c.domain == domain && c.path == path));
+            if !is_expired(raw) {
+                jar.push(JarCookie {
+                    name: cookie.name,
+                    value: cookie.value,
+                    domain,
+                    host_only,
+                    path,
+                });
+            }
+        }
+    }
+}
+
+fn replace_counting(text: &mut String, from: &str, to: &str) -> usize {
+    if from.is_empty() {
+        return 0;
+    }
+    let count = text.matches(from).count();
+    if count > 0 {
+        *text = text.replace(from, to);
+    }
+    count
+}
+
+/// Replace whole-token occurrences only, so a recorded id `41` does not rewrite `1410`.
+fn replace_token(text: &mut String, from: &str, to: &str) -> usize {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let check_start = from.chars().next().is_some_and(is_word);
+    let check_end = from.chars().last().is_some_and(is_word);
+
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0usize;
+    let mut count = 0usize;
+    for (idx, _) in text.match_indices(from) {
+        if idx < last {
+            continue;
+        }
+        let end = idx + from.len();
+        let before_ok = !check_start || !text[..idx].chars().next_back().is_some_and(is_word);
+        let after_ok = !check_end || !text[end..].chars().next().is_some_and(is_word);
+        if before_ok && after_ok {
+            out.push_str(&text[last..idx]);
+            out.push_str(to);
+            last = end;
+            count += 1;
+        }
+    }
+    if count > 0 {
+        out.push_str(&text[last..]);
+        *text = out;
+    }
+    count
+}
+
+fn cookie_matches(cookie: &JarCookie, host: &str, path: &str) -> bool {
+    let domain_ok = if cookie.host_only {
+        host == cookie.domain
+    } else {
+        host == cookie.domain || host.ends_with(&format!(".{}", cookie.domain))
+    };
+    let path_ok = path == cookie.path
+        || (path.starts_with(&cookie.path)
+            && (cookie.path.ends_with('/') || path[cookie.path.len()..].starts_with('/')));
+    domain_ok && path_ok
+}
+
+fn default_cookie_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(idx) => request_path[..idx].to_string(),
+    }
+}
+
+/// `Max-Age<=0` (or an empty value) is how servers delete cookies.
+fn is_expired(raw: &str) -> bool {
+    raw.split(';').skip(1).any(|attr| {
+        let Some((key, value)) = attr.split_once('=') else {
+            return false;
+        };
+        key.trim().eq_ignore_ascii_case("max-age")
+            && value.trim().parse::<i64>().is_ok_and(|v| v <= 0)
+    })
+}
+
+/// Parse a `Set-Cookie` header value into a HAR cookie.
+pub fn parse_set_cookie(value: &str) -> Option<Cookie> {
+    let mut parts = value.split(';');
+    let (name, cookie_value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: cookie_value.trim().to_string(),
+        path: None,
+        domain: None,
+        expires: None,
+        http_only: None,
+        secure: None,
+    };
+    for attr in parts {
+        let (key, val) = match attr.split_once('=') {
+            Some((key, val)) => (key.trim(), Some(val.trim())),
+            None => (attr.trim(), None),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "path" => cookie.path = val.map(str::to_string),
+            "domain" => cookie.domain = val.map(str::to_string),
+            "expires" => cookie.expires = val.map(str::to_string),
+            "httponly" => cookie.http_only = Some(true),
+            "secure" => cookie.secure = Some(true),
+            _ => {}
+        }
+    }
+    Some(cookie)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_extract_rule_validates_input() {
+        assert!(parse_extract_rule("token=json:$.data.token").is_ok());
+        assert!(parse_extract_rule("csrf=regex:name=\"csrf\" value=\"([^\"]+)\"").is_ok());
+        assert!(parse_extract_rule("sid=cookie:session").is_ok());
+        assert!(parse_extract_rule("loc=header:Location").is_ok());
+        assert!(parse_extract_rule("token").is_err());
+        assert!(parse_extract_rule("token=xpath://a").is_err());
+        assert!(parse_extract_rule("bad name=json:$.a").is_err());
+        assert!(parse_extract_rule("t=json:a.b").is_err());
+    }
+
+    #[test]
+    fn correlates_recorded_values_and_placeholders() {
+        let rules = vec![
+            parse_extract_rule("id=json:$.data.id").unwrap(),
+            parse_extract_rule("csrf=regex:csrf=([a-z0-9]+)").unwrap(),
+        ];
+        let mut session = ReplaySession::new(rules, false);
+        let url = Url::parse("https://api.example.com/items").unwrap();
+
+        let recorded_headers = headers(&[]);
+        let replayed_headers = headers(&[]);
+        session.observe(
+            &url,
+            Some(&ResponseView {
+                headers: &recorded_headers,
+                body: br#"{"data": {"id": 41000001}, "html": "csrf=old1old1"}"#,
+            }),
+            &ResponseView {
+                headers: &replayed_headers,
+                body: br#"{"data": {"id": 97000002}, "html": "csrf=new2new2"}"#,
+            },
+        );
+
+        let mut next_url = "https://api.example.com/items/41000001?x=1".to_string();
+        assert_eq!(session.substitute(&mut next_url), 1);
+        assert_eq!(next_url, "https://api.example.com/items/97000002?x=1");
+
+        let mut header = "{{csrf}}".to_string();
+        assert_eq!(session.substitute(&mut header), 1);
+        assert_eq!(header, "new2new2");
+
+        let mut body = br#"{"parent": 41000001, "token": "old1old1"}"#.to_vec();
+        assert_eq!(session.substitute_body(&mut body), 2);
+        assert_eq!(
+            body,
+            br#"{"parent": 97000002, "token": "new2new2"}"#.to_vec()
+        );
+
+        let mut binary = vec![0xff, 0xfe, b'4', b'1'];
+        assert_eq!(session.substitute_body(&mut binary), 0);
+    }
+
+    #[test]
+    fn short_recorded_values_need_placeholders() {
+        let mut session =
+            ReplaySession::new(vec![parse_extract_rule("v=json:$.v").unwrap()], false);
+        let url = Url::parse("https://api.example.com/").unwrap();
+        let none = headers(&[]);
+        session.observe(
+            &url,
+            Some(&ResponseView {
+                headers: &none,
+                body: br#"{"v": 1}"#,
+            }),
+            &ResponseView {
+                headers: &none,
+                body: br#"{"v": 2}"#,
+            },
+        );
+
+        let mut text = "HTTP/1.1 v1.1 page=1 {{v}}".to_string();
+        assert_eq!(session.substitute(&mut text), 1);
+        assert_eq!(text, "HTTP/1.1 v1.1 page=1 2");
+    }
+
+    #[test]
+    fn recorded_values_are_replaced_as_whole_tokens() {
+        let mut text = "id=41&other=1410&list=41,41".to_string();
+        assert_eq!(replace_token(&mut text, "41", "7"), 3);
+        assert_eq!(text, "id=7&other=1410&list=7,7");
+    }
+
+    #[test]
+    fn cookie_jar_overrides_recorded_cookies_and_honours_scope() {
+        let mut session = ReplaySession::new(Vec::new(), true);
+        let login = Url::parse("https://app.example.com/auth/login").unwrap();
+        let set = headers(&[
+            ("Set-Cookie", "session=fresh; Path=/; HttpOnly"),
+            ("Set-Cookie", "scoped=1; Path=/admin"),
+            ("Set-Cookie", "wide=1; Domain=.example.com; Path=/"),
+        ]);
+        session.observe(
+            &login,
+            None,
+            &ResponseView {
+                headers: &set,
+                body: b"",
+            },
+        );
+
+        let mut request = HashMap::from([(
+            "cookie".to_string(),
+            "session=stale; theme=dark".to_string(),
+        )]);
+        session.apply_cookies(
+            &Url::parse("https://app.example.com/home").unwrap(),
+            &mut request,
+        );
+        assert_eq!(request["cookie"], "session=fresh; theme=dark; wide=1");
+
+        let mut other = HashMap::new();
+        session.apply_cookies(
+            &Url::parse("https://cdn.example.com/a.js").unwrap(),
+            &mut other,
+        );
+        assert_eq!(other["cookie"], "wide=1");
+
+        let cleared = headers(&[("Set-Cookie", "session=; Max-Age=0; Path=/")]);
+        session.observe(
+            &login,
+            None,
+            &ResponseView {
+                headers: &cleared,
+                body: b"",
+            },
+        );
+        let mut after = HashMap::new();
+        session.apply_cookies(&Url::parse("https://app.example.com/").unwrap(), &mut after);
+        assert_eq!(after["cookie"], "wide=1");
+    }
+
+    #[test]
+    fn extracts_cookie_and_header_values() {
+        let rule = parse_extract_rule("sid=cookie:sid").unwrap();
+        let list = headers(&[
+            ("set-cookie", "a=1\nsid=abc; Path=/"),
+            ("Location", "/next"),
+        ]);
+        let view = ResponseView {
+            headers: &list,
+            body: b"",
+        };
+        assert_eq!(rule.extract(&view).as_deref(), Some("abc"));
+
+        let rule = parse_extract_rule("next=header:location").unwrap();
+        assert_eq!(rule.extract(&view).as_deref(), Some("/next"));
+    }
+}
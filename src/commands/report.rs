@@ -1,19 +1,17 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, SecondsFormat, Utc};
 use regex::Regex;
-use rusqlite::Connection;
 use serde::Serialize;
 use url::Url;
 
 use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
 use super::waterfall::WaterfallGroupBy;
-use crate::commands::util::parse_timestamp;
-use crate::db::{ensure_schema_upgrades, load_pages_for_imports, EntryRow, PageRow};
+use crate::commands::util::{open_output, parse_timestamp};
+use crate::db::{ensure_schema_upgrades, load_pages_for_imports, open_database, EntryRow, PageRow};
 use crate::error::{HarliteError, Result};
 use crate::har::{parse_har_file, Entry as HarEntry, Page as HarPage};
 use crate::size;
@@ -201,13 +199,6 @@ fn normalize_i64(v: Option<i64>) -> Option<i64> {
     }
 }
 
-fn open_output(path: &Path) -> Result<Box<dyn Write>> {
-    if path == Path::new("-") {
-        return Ok(Box::new(io::stdout().lock()));
-    }
-    Ok(Box::new(BufWriter::new(File::create(path)?)))
-}
-
 fn default_output_for_input(input: &Path) -> PathBuf {
     // If the input already has an extension, swap it for .html; otherwise append .html.
     let mut out = input.to_path_buf();
@@ -251,7 +242,7 @@ fn detect_input_kind(input: &Path) -> Result<InputKind> {
     }
 
     // Heuristic fallback: try SQLite, then HAR.
-    if let Ok(conn) = Connection::open(input) {
+    if let Ok(conn) = open_database(input) {
         let has_entries: i64 = conn
             .query_row(
                 "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='entries'",
@@ -1257,7 +1248,7 @@ pub fn run_report(input: PathBuf, options: &ReportOptions) -> Result<()> {
 
     let (mut entries, pages) = match kind {
         InputKind::Db => {
-            let conn = Connection::open(&input)?;
+            let conn = open_database(&input)?;
             ensure_schema_upgrades(&conn)?;
 
             let rows = load_entries_with_filters(&conn, &options.filters)?;
@@ -1,8 +1,6 @@
 use std::path::PathBuf;
 
-use rusqlite::Connection;
-
-use crate::db::SCHEMA;
+use crate::db::{open_database, SCHEMA};
 use crate::error::Result;
 
 /// Print the schema for a harlite database or the default schema.
@@ -12,7 +10,7 @@ pub fn run_schema(database: Option<PathBuf>) -> Result<()> {
             println!("{}", SCHEMA);
         }
         Some(path) => {
-            let conn = Connection::open(&path)?;
+            let conn = open_database(&path)?;
             let mut stmt = conn.prepare(
                 "SELECT sql FROM sqlite_master WHERE type IN ('table', 'index') AND sql IS NOT NULL ORDER BY type DESC, name",
             )?;
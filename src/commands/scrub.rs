@@ -0,0 +1,272 @@
+//! Removing blobs that are no longer referenced, so their contents do not linger.
+//!
+//! Shared by `prune`, `redact` and `pii --redact`. Rewriting an entry leaves the
+//! original blob row, its FTS row and any extracted body file behind, and SQLite keeps
+//! deleted content in free pages and the WAL until they are overwritten.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params_from_iter, Connection};
+
+use crate::error::Result;
+
+const HASH_CHUNK: usize = 500;
+const WIPE_CHUNK: usize = 64 * 1024;
+
+/// What to do with the extracted body file of a deleted blob.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum ExternalFiles {
+    /// Unlink the file.
+    Remove,
+    /// Overwrite the file with zeros, then unlink it.
+    Wipe,
+    /// Leave the file alone and count it as skipped (another database still uses it).
+    Keep,
+}
+
+/// Counts of what [`delete_blobs`] removed.
+#[derive(Default)]
+pub(super) struct ScrubReport {
+    pub blobs_deleted: usize,
+    pub fts_deleted: usize,
+    pub external_deleted: usize,
+    pub external_skipped: usize,
+    /// Extracted files of deleted blobs, as stored and as resolved, awaiting
+    /// [`ScrubReport::remove_external_files`].
+    pending_files: Vec<(String, PathBuf)>,
+}
+
+impl ScrubReport {
+    /// Wipe or unlink the extracted files of the deleted blobs.
+    ///
+    /// Call this after the transaction that ran [`delete_blobs`] commits, so a
+    /// rollback never leaves blob rows pointing at destroyed files. A file that a
+    /// blob row still points to is kept.
+    pub(super) fn remove_external_files(
+        &mut self,
+        conn: &Connection,
+        external: ExternalFiles,
+    ) -> Result<()> {
+        let mut still_referenced =
+            conn.prepare("SELECT COUNT(*) FROM blobs WHERE external_path = ?1")?;
+        for (raw_path, path) in std::mem::take(&mut self.pending_files) {
+            let references: i64 = still_referenced.query_row([&raw_path], |row| row.get(0))?;
+            if references > 0 || !path.is_file() {
+                self.external_skipped += usize::from(references > 0);
+                continue;
+            }
+            let wiped = match external {
+                ExternalFiles::Remove => true,
+                ExternalFiles::Wipe => wipe_file(&path).is_ok(),
+                ExternalFiles::Keep => false,
+            };
+            if wiped && fs::remove_file(&path).is_ok() {
+                self.external_deleted += 1;
+            } else {
+                self.external_skipped += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Directory that relative `external_path` values are resolved against.
+pub(super) fn external_root(database: &Path) -> Option<PathBuf> {
+    database
+        .parent()
+        .map(|p| p.to_path_buf())
+        .and_then(|p| p.canonicalize().ok())
+}
+
+fn has_fts_table(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='response_body_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// The blobs among `hashes` that no entry or recorded serve miss references.
+pub(super) fn orphaned_blob_hashes(conn: &Connection, hashes: &[String]) -> Result<Vec<String>> {
+    let mut orphans = Vec::new();
+    for chunk in hashes.chunks(HASH_CHUNK) {
+        let sql = format!(
+            "SELECT hash FROM blobs\n\
+             WHERE hash IN ({})\n\
+             AND NOT EXISTS (\n\
+                 SELECT 1 FROM entries e\n\
+                 WHERE e.request_body_hash = blobs.hash\n\
+                    OR e.response_body_hash = blobs.hash\n\
+                    OR e.response_body_hash_raw = blobs.hash\n\
+                    OR e.graphql_query_hash = blobs.hash\n\
+                    OR e.graphql_variables_hash = blobs.hash\n\
+             )\n\
+             AND NOT EXISTS (\n\
+                 SELECT 1 FROM serve_misses m WHERE m.request_body_hash = blobs.hash\n\
+             )",
+            placeholders(chunk.len())
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(chunk), |row| row.get::<_, String>(0))?;
+        for row in rows {
+            orphans.push(row?);
+        }
+    }
+    Ok(orphans)
+}
+
+/// Delete blob rows and their FTS rows, and collect their extracted body files for
+/// [`ScrubReport::remove_external_files`].
+pub(super) fn delete_blobs(
+    conn: &Connection,
+    hashes: &[String],
+    root: Option<&Path>,
+) -> Result<ScrubReport> {
+    let mut report = ScrubReport::default();
+    if hashes.is_empty() {
+        return Ok(report);
+    }
+    let has_fts = has_fts_table(conn)?;
+
+    for chunk in hashes.chunks(HASH_CHUNK) {
+        let placeholders = placeholders(chunk.len());
+
+        let external_paths: Vec<String> = conn
+            .prepare(&format!(
+                "SELECT external_path FROM blobs WHERE hash IN ({placeholders}) AND external_path IS NOT NULL"
+            ))?
+            .query_map(params_from_iter(chunk), |row| row.get(0))?
+            .filter_map(|row| row.ok())
+            .collect();
+
+        for raw_path in external_paths {
+            match resolve_external_path(&raw_path, root) {
+                ExternalPath::Found(path) => report.pending_files.push((raw_path, path)),
+                ExternalPath::Missing => {}
+                ExternalPath::Skipped => report.external_skipped += 1,
+            }
+        }
+
+        if has_fts {
+            report.fts_deleted += conn.execute(
+                &format!("DELETE FROM response_body_fts WHERE hash IN ({placeholders})"),
+                params_from_iter(chunk),
+            )?;
+        }
+        report.blobs_deleted += conn.execute(
+            &format!("DELETE FROM blobs WHERE hash IN ({placeholders})"),
+            params_from_iter(chunk),
+        )?;
+    }
+
+    Ok(report)
+}
+
+/// Rebuild the database so deleted content does not survive in free pages, FTS
+/// segments or the WAL. Run outside a transaction, with `secure_delete` enabled
+/// before the deletes.
+pub(super) fn compact_database(conn: &Connection) -> Result<()> {
+    if has_fts_table(conn)? {
+        conn.execute(
+            "INSERT INTO response_body_fts(response_body_fts) VALUES('optimize')",
+            [],
+        )?;
+    }
+    conn.execute_batch("VACUUM;")?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    Ok(())
+}
+
+enum ExternalPath {
+    Found(PathBuf),
+    Missing,
+    /// A relative path outside the database directory, or no directory to resolve against.
+    Skipped,
+}
+
+fn resolve_external_path(raw_path: &str, root: Option<&Path>) -> ExternalPath {
+    let candidate = PathBuf::from(raw_path);
+    let resolved = if candidate.is_absolute() {
+        candidate.canonicalize().ok()
+    } else if let Some(root) = root {
+        let resolved = root.join(&candidate).canonicalize().ok();
+        if let Some(resolved_path) = resolved.as_ref() {
+            if !resolved_path.starts_with(root) {
+                return ExternalPath::Skipped;
+            }
+        }
+        resolved
+    } else {
+        return ExternalPath::Skipped;
+    };
+    match resolved {
+        Some(path) => ExternalPath::Found(path),
+        None => ExternalPath::Missing,
+    }
+}
+
+fn wipe_file(path: &Path) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let mut remaining = file.metadata()?.len();
+    let zeros = [0u8; WIPE_CHUNK];
+    while remaining > 0 {
+        let n = remaining.min(WIPE_CHUNK as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        remaining -= n as u64;
+    }
+    file.sync_all()
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_schema, store_blob};
+
+    #[test]
+    fn deletes_only_orphans_and_wipes_their_files() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let conn = Connection::open_in_memory().expect("db");
+        create_schema(&conn).expect("schema");
+
+        let file = dir.path().join("extracted");
+        fs::write(&file, b"secret body").expect("write");
+        let (orphan, _) = store_blob(
+            &conn,
+            b"secret body",
+            None,
+            Some(file.to_str().unwrap()),
+            false,
+        )
+        .expect("blob");
+        let (kept, _) = store_blob(&conn, b"kept", None, None, true).expect("blob");
+        conn.execute(
+            "INSERT INTO entries (started_at, method, url, status, request_body_hash) VALUES ('2024-01-01T00:00:00Z', 'GET', 'https://example.com/', 200, ?1)",
+            [&kept],
+        )
+        .expect("entry");
+
+        let orphans =
+            orphaned_blob_hashes(&conn, &[orphan.clone(), kept.clone()]).expect("orphans");
+        assert_eq!(orphans, vec![orphan]);
+
+        let mut report = delete_blobs(&conn, &orphans, None).expect("delete");
+        assert_eq!(report.blobs_deleted, 1);
+        assert!(file.exists());
+        report
+            .remove_external_files(&conn, ExternalFiles::Wipe)
+            .expect("wipe");
+        assert_eq!(report.external_deleted, 1);
+        assert!(!file.exists());
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM blobs", [], |row| row.get(0))
+            .expect("count");
+        assert_eq!(remaining, 1);
+    }
+}
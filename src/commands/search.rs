@@ -3,8 +3,9 @@ use std::path::PathBuf;
 
 use rusqlite::types::Value;
 use rusqlite::types::ValueRef;
-use rusqlite::{params_from_iter, Connection, OpenFlags};
+use rusqlite::{params_from_iter, OpenFlags};
 
+use crate::db::open_database_with_flags;
 use crate::error::{HarliteError, Result};
 
 use super::query::{OutputFormat, QueryOptions};
@@ -19,7 +20,7 @@ pub fn run_search(query: String, database: Option<PathBuf>, options: &QueryOptio
     }
 
     let database = resolve_database(database)?;
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         &database,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
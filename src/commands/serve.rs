@@ -1,24 +1,33 @@
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use bytes::Bytes;
 use clap::ValueEnum;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Request, Response, StatusCode};
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::OpenFlags;
+use serde_json::{json, Value};
 use tokio::sync::oneshot;
 use tokio_rustls::TlsAcceptor;
 use url::Url;
 
-use crate::db::{ensure_schema_upgrades, load_blobs_by_hashes, load_entries, BlobRow, EntryQuery};
+use crate::db::{
+    ensure_schema_upgrades, load_blobs_by_hashes, load_entries, open_database,
+    open_database_with_flags, store_blob, BlobRow, EntryQuery,
+};
 use crate::error::{HarliteError, Result};
-use crate::har::{parse_har_file, Content, Header};
+use crate::har::{
+    parse_har_file, Content, Cookie, Creator, Entry, Extensions, Har, Header, Log, PostData,
+    QueryParam, Request as HarRequest, Response as HarResponse,
+};
+use super::util::load_external_blob_content;
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum MatchMode {
@@ -34,10 +43,29 @@ pub struct ServeOptions {
     pub external_path_root: Option<PathBuf>,
     pub tls_cert: Option<PathBuf>,
     pub tls_key: Option<PathBuf>,
+    pub admin: bool,
+    pub max_unmatched: usize,
+    pub misses_har: Option<PathBuf>,
+    pub record_misses: bool,
 }
 
+/// Path prefix for the admin/introspection endpoints.
+const ADMIN_PREFIX: &str = "/__harlite/";
+
+/// Connection-specific headers that are illegal in HTTP/2 responses (RFC 9113 §8.2.2).
+const HTTP1_ONLY_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+    "trailer",
+];
+
 #[derive(Clone)]
 struct ServeEntry {
+    entry_id: Option<i64>,
     method: String,
     url: String,
     status: u16,
@@ -56,11 +84,119 @@ struct NormalizedUrl {
     query: Vec<(String, String)>,
 }
 
+struct RouteTable {
+    entries: Vec<ServeEntry>,
+    hits: Vec<AtomicU64>,
+}
+
+impl RouteTable {
+    fn new(entries: Vec<ServeEntry>) -> Self {
+        let hits = entries.iter().map(|_| AtomicU64::new(0)).collect();
+        Self { entries, hits }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct UnmatchedRequest {
+    method: String,
+    url: String,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+    seen_at: String,
+}
+
 #[derive(Clone)]
+struct ServeSource {
+    input: PathBuf,
+    allow_external_paths: bool,
+    external_path_root: Option<PathBuf>,
+}
+
 struct ServeState {
-    entries: Vec<ServeEntry>,
+    routes: RwLock<Arc<RouteTable>>,
+    misses: AtomicU64,
+    /// Most recent unmatched requests, oldest first; capped at `max_unmatched`.
+    unmatched: Mutex<VecDeque<UnmatchedRequest>>,
+    max_unmatched: usize,
+    /// Every unmatched request since startup, kept only when `--misses-har` or
+    /// `--record-misses` will persist them on shutdown.
+    persisted_misses: Option<Mutex<Vec<UnmatchedRequest>>>,
+    reloads: AtomicU64,
     match_mode: MatchMode,
     scheme: String,
+    source: ServeSource,
+    admin: bool,
+}
+
+impl ServeState {
+    fn routes(&self) -> Arc<RouteTable> {
+        self.routes
+            .read()
+            .map(|routes| routes.clone())
+            .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+    }
+
+    fn replace_routes(&self, table: RouteTable) {
+        let table = Arc::new(table);
+        match self.routes.write() {
+            Ok(mut routes) => *routes = table,
+            Err(poisoned) => *poisoned.into_inner() = table,
+        }
+    }
+
+    fn record_miss(&self, miss: UnmatchedRequest) {
+        self.misses.fetch_add(1, AtomicOrdering::Relaxed);
+        if let Some(persisted) = &self.persisted_misses {
+            persisted
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(miss.clone());
+        }
+        if self.max_unmatched == 0 {
+            return;
+        }
+        let mut unmatched = self
+            .unmatched
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while unmatched.len() >= self.max_unmatched {
+            unmatched.pop_front();
+        }
+        unmatched.push_back(miss);
+    }
+
+    fn unmatched(&self) -> Vec<UnmatchedRequest> {
+        self.unmatched
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn take_persisted_misses(&self) -> Vec<UnmatchedRequest> {
+        match &self.persisted_misses {
+            Some(persisted) => std::mem::take(
+                &mut *persisted
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    fn reset_counters(&self) {
+        let routes = self.routes();
+        for hits in &routes.hits {
+            hits.store(0, AtomicOrdering::Relaxed);
+        }
+        self.misses.store(0, AtomicOrdering::Relaxed);
+        match self.unmatched.lock() {
+            Ok(mut unmatched) => unmatched.clear(),
+            Err(poisoned) => poisoned.into_inner().clear(),
+        }
+    }
 }
 
 pub fn run_serve(input: PathBuf, options: &ServeOptions) -> Result<()> {
@@ -70,18 +206,19 @@ pub fn run_serve(input: PathBuf, options: &ServeOptions) -> Result<()> {
         ));
     }
 
-    let entries = if is_db_path(&input) {
-        load_entries_from_db(&input, options)?
-    } else {
-        load_entries_from_har(&input)?
-    };
-
-    if entries.is_empty() {
+    if options.record_misses && !is_db_path(&input) {
         return Err(HarliteError::InvalidArgs(
-            "No entries found to serve".to_string(),
+            "--record-misses requires a SQLite database input".to_string(),
         ));
     }
 
+    let source = ServeSource {
+        input,
+        allow_external_paths: options.allow_external_paths,
+        external_path_root: options.external_path_root.clone(),
+    };
+    let entries = load_serve_entries(&source)?;
+
     let scheme = if options.tls_cert.is_some() {
         "https".to_string()
     } else {
@@ -92,10 +229,19 @@ pub fn run_serve(input: PathBuf, options: &ServeOptions) -> Result<()> {
         .parse()
         .map_err(|err| HarliteError::InvalidArgs(format!("Invalid bind address: {err}")))?;
 
+    let entry_count = entries.len();
     let state = Arc::new(ServeState {
-        entries,
+        routes: RwLock::new(Arc::new(RouteTable::new(entries))),
+        misses: AtomicU64::new(0),
+        unmatched: Mutex::new(VecDeque::new()),
+        max_unmatched: options.max_unmatched,
+        persisted_misses: (options.misses_har.is_some() || options.record_misses)
+            .then(|| Mutex::new(Vec::new())),
+        reloads: AtomicU64::new(0),
         match_mode: options.match_mode,
         scheme: scheme.clone(),
+        source,
+        admin: options.admin,
     });
 
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
@@ -113,11 +259,14 @@ pub fn run_serve(input: PathBuf, options: &ServeOptions) -> Result<()> {
 
     println!(
         "Serving {} entries on {}://{}:{}",
-        state.entries.len(),
-        scheme,
-        options.bind,
-        options.port
+        entry_count, scheme, options.bind, options.port
     );
+    if options.admin {
+        println!(
+            "Admin API on {}://{}:{}{}",
+            scheme, options.bind, options.port, ADMIN_PREFIX
+        );
+    }
 
     let rt = tokio::runtime::Runtime::new()
         .map_err(|err| HarliteError::InvalidArgs(format!("Failed to start runtime: {err}")))?;
@@ -130,13 +279,39 @@ pub fn run_serve(input: PathBuf, options: &ServeOptions) -> Result<()> {
         let tls_acceptor = TlsAcceptor::from(Arc::new(tls_config));
         rt.block_on(run_tls_server(
             addr,
-            state,
+            state.clone(),
             tls_acceptor,
             shutdown_rx,
-        ))
+        ))?;
     } else {
-        rt.block_on(run_plain_server(addr, state, shutdown_rx))
+        rt.block_on(run_plain_server(addr, state.clone(), shutdown_rx))?;
+    }
+
+    persist_misses(&state, options)
+}
+
+fn persist_misses(state: &ServeState, options: &ServeOptions) -> Result<()> {
+    let misses = state.take_persisted_misses();
+
+    if let Some(path) = &options.misses_har {
+        write_misses_har(path, &misses)?;
+        println!(
+            "Wrote {} unmatched requests to {}",
+            misses.len(),
+            path.display()
+        );
+    }
+
+    if options.record_misses {
+        let stored = store_misses_in_db(&state.source.input, &misses)?;
+        println!(
+            "Recorded {} unmatched requests in serve_misses ({})",
+            stored,
+            state.source.input.display()
+        );
     }
+
+    Ok(())
 }
 
 async fn run_plain_server(
@@ -153,6 +328,8 @@ async fn run_plain_server(
         }
     });
 
+    // hyper serves HTTP/1.1 and falls back to HTTP/2 when a client opens with the
+    // prior-knowledge preface, which covers h2c.
     let server = hyper::Server::bind(&addr).serve(make_svc);
 
     server
@@ -198,8 +375,10 @@ async fn run_tls_server(
                             return;
                         }
                     };
+                    let negotiated_h2 = tls_stream.get_ref().1.alpn_protocol() == Some(b"h2");
                     let service = service_fn(move |req| handle_request(req, state.clone()));
                     if let Err(err) = hyper::server::conn::Http::new()
+                        .http2_only(negotiated_h2)
                         .serve_connection(tls_stream, service)
                         .await
                     {
@@ -217,17 +396,24 @@ async fn handle_request(
     req: Request<Body>,
     state: Arc<ServeState>,
 ) -> std::result::Result<Response<Body>, std::convert::Infallible> {
-    let method = req.method().as_str().to_string();
-    let host = req
-        .headers()
+    if state.admin && req.uri().path().starts_with(ADMIN_PREFIX) {
+        return Ok(handle_admin(req, state).await);
+    }
+
+    let (parts, body) = req.into_parts();
+    let method = parts.method.as_str().to_string();
+    // HTTP/2 carries the host in the :authority pseudo-header, which hyper exposes on the URI.
+    let host = parts
+        .headers
         .get(hyper::header::HOST)
         .and_then(|h| h.to_str().ok())
+        .or_else(|| parts.uri.authority().map(|a| a.as_str()))
         .unwrap_or("");
-    let path = req
-        .uri()
+    let path = parts
+        .uri
         .path_and_query()
         .map(|p| p.as_str())
-        .unwrap_or_else(|| req.uri().path());
+        .unwrap_or_else(|| parts.uri.path());
 
     if host.is_empty() {
         return Ok(Response::builder()
@@ -239,21 +425,36 @@ async fn handle_request(
     let full_url = format!("{}://{}{}", state.scheme, host, path);
     let normalized = normalize_url(&full_url);
 
-    let entry = select_entry(
-        &state.entries,
+    let routes = state.routes();
+    let selected = select_entry(
+        &routes.entries,
         &method,
         &full_url,
         normalized.as_ref(),
         state.match_mode,
     );
 
-    match entry {
-        Some(entry) => {
-            println!("HIT {} {} -> {} ({})", method, path, entry.status, entry.url);
-            Ok(build_response(entry))
+    match selected {
+        Some(index) => {
+            let entry = &routes.entries[index];
+            routes.hits[index].fetch_add(1, AtomicOrdering::Relaxed);
+            println!(
+                "HIT {} {} -> {} ({})",
+                method, path, entry.status, entry.url
+            );
+            Ok(build_response(entry, parts.version))
         }
         None => {
             eprintln!("MISS {} {}", method, path);
+            let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+            state.record_miss(UnmatchedRequest {
+                method,
+                url: full_url,
+                http_version: http_version_label(parts.version).to_string(),
+                headers: headers_from_request(&parts.headers),
+                body,
+                seen_at: chrono::Utc::now().to_rfc3339(),
+            });
             Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
                 .body(Body::from("Not found"))
@@ -262,7 +463,348 @@ async fn handle_request(
     }
 }
 
-fn build_response(entry: &ServeEntry) -> Response<Body> {
+async fn handle_admin(req: Request<Body>, state: Arc<ServeState>) -> Response<Body> {
+    let endpoint = req.uri().path()[ADMIN_PREFIX.len()..].trim_end_matches('/');
+    let method = req.method().clone();
+
+    match (&method, endpoint) {
+        (&hyper::Method::GET, "routes") => json_response(StatusCode::OK, &routes_json(&state)),
+        (&hyper::Method::GET, "stats") => json_response(StatusCode::OK, &stats_json(&state)),
+        (&hyper::Method::GET, "unmatched") => {
+            json_response(StatusCode::OK, &unmatched_json(&state))
+        }
+        (&hyper::Method::GET, "unmatched.har") => {
+            let har = misses_to_har(&state.unmatched());
+            match serde_json::to_value(&har) {
+                Ok(value) => json_response(StatusCode::OK, &value),
+                Err(err) => json_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &json!({ "error": err.to_string() }),
+                ),
+            }
+        }
+        (&hyper::Method::POST, "reset") => {
+            state.reset_counters();
+            json_response(StatusCode::OK, &stats_json(&state))
+        }
+        (&hyper::Method::POST, "reload") => {
+            let source = state.source.clone();
+            let loaded = tokio::task::spawn_blocking(move || load_serve_entries(&source))
+                .await
+                .map_err(|err| HarliteError::InvalidArgs(format!("Reload task failed: {err}")))
+                .and_then(|result| result);
+            match loaded {
+                Ok(entries) => {
+                    let count = entries.len();
+                    state.replace_routes(RouteTable::new(entries));
+                    state.reloads.fetch_add(1, AtomicOrdering::Relaxed);
+                    println!(
+                        "Reloaded {} entries from {}",
+                        count,
+                        state.source.input.display()
+                    );
+                    json_response(
+                        StatusCode::OK,
+                        &json!({ "reloaded": true, "entries": count }),
+                    )
+                }
+                Err(err) => {
+                    eprintln!("Reload failed: {err}");
+                    json_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        &json!({ "reloaded": false, "error": err.to_string() }),
+                    )
+                }
+            }
+        }
+        (_, "routes" | "stats" | "unmatched" | "unmatched.har" | "reset" | "reload") => {
+            json_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                &json!({ "error": format!("{} not allowed on {}{}", method, ADMIN_PREFIX, endpoint) }),
+            )
+        }
+        _ => json_response(
+            StatusCode::NOT_FOUND,
+            &json!({ "error": format!("Unknown admin endpoint: {}{}", ADMIN_PREFIX, endpoint) }),
+        ),
+    }
+}
+
+fn routes_json(state: &ServeState) -> Value {
+    let routes = state.routes();
+    let items = routes
+        .entries
+        .iter()
+        .zip(routes.hits.iter())
+        .enumerate()
+        .map(|(index, (entry, hits))| {
+            json!({
+                "index": index,
+                "entry_id": entry.entry_id,
+                "method": entry.method,
+                "url": entry.url,
+                "status": entry.status,
+                "mime_type": entry.mime_type,
+                "started_at": entry.started_at,
+                "hits": hits.load(AtomicOrdering::Relaxed),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "routes": items })
+}
+
+fn stats_json(state: &ServeState) -> Value {
+    let routes = state.routes();
+    let hits: u64 = routes
+        .hits
+        .iter()
+        .map(|hits| hits.load(AtomicOrdering::Relaxed))
+        .sum();
+    let unhit = routes
+        .hits
+        .iter()
+        .filter(|hits| hits.load(AtomicOrdering::Relaxed) == 0)
+        .count();
+    json!({
+        "entries": routes.entries.len(),
+        "hits": hits,
+        "misses": state.misses.load(AtomicOrdering::Relaxed),
+        "unhit_entries": unhit,
+        "reloads": state.reloads.load(AtomicOrdering::Relaxed),
+    })
+}
+
+fn unmatched_json(state: &ServeState) -> Value {
+    let items = state
+        .unmatched()
+        .into_iter()
+        .map(|miss| {
+            let headers = miss
+                .headers
+                .iter()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect::<Vec<_>>();
+            json!({
+                "method": miss.method,
+                "url": miss.url,
+                "http_version": miss.http_version,
+                "headers": headers,
+                "body_size": miss.body.len(),
+                "seen_at": miss.seen_at,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "unmatched": items })
+}
+
+fn json_response(status: StatusCode, value: &Value) -> Response<Body> {
+    let body = serde_json::to_vec_pretty(value).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .header(hyper::header::CONTENT_LENGTH, body.len())
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn http_version_label(version: hyper::Version) -> &'static str {
+    match version {
+        hyper::Version::HTTP_09 => "HTTP/0.9",
+        hyper::Version::HTTP_10 => "HTTP/1.0",
+        hyper::Version::HTTP_2 => "HTTP/2.0",
+        hyper::Version::HTTP_3 => "HTTP/3.0",
+        _ => "HTTP/1.1",
+    }
+}
+
+fn headers_from_request(headers: &hyper::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+fn request_content_type(headers: &[(String, String)]) -> Option<String> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.clone())
+}
+
+fn misses_to_har(misses: &[UnmatchedRequest]) -> Har {
+    let entries = misses
+        .iter()
+        .map(|miss| {
+            let query_string = Url::parse(&miss.url).ok().map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| QueryParam {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+            });
+            let post_data = if miss.body.is_empty() {
+                None
+            } else {
+                let mut extensions = Extensions::new();
+                let text = match std::str::from_utf8(&miss.body) {
+                    Ok(text) => text.to_string(),
+                    Err(_) => {
+                        extensions.insert("encoding".to_string(), "base64".into());
+                        STANDARD.encode(&miss.body)
+                    }
+                };
+                Some(PostData {
+                    mime_type: request_content_type(&miss.headers),
+                    text: Some(text),
+                    params: None,
+                    extensions,
+                })
+            };
+
+            Entry {
+                pageref: None,
+                started_date_time: miss.seen_at.clone(),
+                time: 0.0,
+                request: HarRequest {
+                    method: miss.method.clone(),
+                    url: miss.url.clone(),
+                    http_version: miss.http_version.clone(),
+                    cookies: None,
+                    headers: miss
+                        .headers
+                        .iter()
+                        .map(|(name, value)| Header {
+                            name: name.clone(),
+                            value: value.clone(),
+                        })
+                        .collect(),
+                    query_string,
+                    post_data,
+                    headers_size: None,
+                    body_size: Some(miss.body.len() as i64),
+                    extensions: Extensions::new(),
+                },
+                response: HarResponse {
+                    status: 0,
+                    status_text: String::new(),
+                    http_version: miss.http_version.clone(),
+                    cookies: None,
+                    headers: Vec::new(),
+                    content: Content {
+                        size: 0,
+                        compression: None,
+                        mime_type: None,
+                        text: None,
+                        encoding: None,
+                        extensions: Extensions::new(),
+                    },
+                    redirect_url: None,
+                    headers_size: None,
+                    body_size: None,
+                    extensions: Extensions::new(),
+                },
+                cache: None,
+                timings: None,
+                server_ip_address: None,
+                connection: None,
+                extensions: Extensions::new(),
+            }
+        })
+        .collect();
+
+    Har {
+        log: Log {
+            version: Some("1.2".to_string()),
+            creator: Some(Creator {
+                name: "harlite".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }),
+            browser: None,
+            pages: None,
+            entries,
+            extensions: Extensions::new(),
+        },
+    }
+}
+
+fn write_misses_har(path: &Path, misses: &[UnmatchedRequest]) -> Result<()> {
+    let har = misses_to_har(misses);
+    let mut writer = std::io::BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &har)?;
+    std::io::Write::write_all(&mut writer, b"\n")?;
+    Ok(())
+}
+
+fn store_misses_in_db(path: &Path, misses: &[UnmatchedRequest]) -> Result<usize> {
+    let conn = open_database(path)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let tx = conn.unchecked_transaction()?;
+    for miss in misses {
+        let (host, path, query) = match Url::parse(&miss.url) {
+            Ok(url) => (
+                url.host_str().map(|s| s.to_string()),
+                Some(url.path().to_string()),
+                url.query().map(|s| s.to_string()),
+            ),
+            Err(_) => (None, None, None),
+        };
+        let headers: serde_json::Map<String, Value> = miss
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), Value::String(value.clone())))
+            .collect();
+        let body_hash = if miss.body.is_empty() {
+            None
+        } else {
+            let content_type = request_content_type(&miss.headers);
+            let (hash, _) = store_blob(&tx, &miss.body, content_type.as_deref(), None, true)?;
+            Some(hash)
+        };
+
+        tx.execute(
+            "INSERT INTO serve_misses (recorded_at, method, url, host, path, query_string, http_version, request_headers, request_body_hash, request_body_size)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                miss.seen_at,
+                miss.method,
+                miss.url,
+                host,
+                path,
+                query,
+                miss.http_version,
+                serde_json::to_string(&headers)?,
+                body_hash,
+                miss.body.len() as i64,
+            ],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(misses.len())
+}
+
+fn build_response(entry: &ServeEntry, version: hyper::Version) -> Response<Body> {
+    let is_http2 = version == hyper::Version::HTTP_2;
+    let connection_tokens: Vec<String> = if is_http2 {
+        entry
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("connection"))
+            .flat_map(|(_, value)| value.split(','))
+            .map(|token| token.trim().to_ascii_lowercase())
+            .filter(|token| !token.is_empty())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
     let mut builder = Response::builder().status(entry.status);
     {
         let headers = builder.headers_mut().expect("headers mut");
@@ -272,6 +814,16 @@ fn build_response(entry: &ServeEntry) -> Response<Body> {
             {
                 continue;
             }
+            if is_http2
+                && (HTTP1_ONLY_HEADERS
+                    .iter()
+                    .any(|h| name.eq_ignore_ascii_case(h))
+                    || connection_tokens
+                        .iter()
+                        .any(|t| name.eq_ignore_ascii_case(t)))
+            {
+                continue;
+            }
             if let Ok(header_name) = hyper::header::HeaderName::from_bytes(name.as_bytes()) {
                 if let Ok(header_value) = hyper::header::HeaderValue::from_str(value) {
                     headers.append(header_name, header_value);
@@ -295,29 +847,29 @@ fn build_response(entry: &ServeEntry) -> Response<Body> {
     builder.body(Body::from(entry.body.clone())).unwrap()
 }
 
-fn select_entry<'a>(
-    entries: &'a [ServeEntry],
+fn select_entry(
+    entries: &[ServeEntry],
     method: &str,
     full_url: &str,
     normalized: Option<&NormalizedUrl>,
     match_mode: MatchMode,
-) -> Option<&'a ServeEntry> {
+) -> Option<usize> {
     match match_mode {
         MatchMode::Strict => entries
             .iter()
-            .filter(|entry| {
-                entry.method.eq_ignore_ascii_case(method) && entry.url == full_url
-            })
-            .max_by(|a, b| compare_recency(a, b)),
+            .enumerate()
+            .filter(|(_, entry)| entry.method.eq_ignore_ascii_case(method) && entry.url == full_url)
+            .max_by(|(_, a), (_, b)| compare_recency(a, b))
+            .map(|(index, _)| index),
         MatchMode::Fuzzy => {
             let Some(req_norm) = normalized else {
                 return None;
             };
 
-            let mut best: Option<&ServeEntry> = None;
+            let mut best: Option<usize> = None;
             let mut best_score = 0usize;
 
-            for entry in entries.iter() {
+            for (index, entry) in entries.iter().enumerate() {
                 if !entry.method.eq_ignore_ascii_case(method) {
                     continue;
                 }
@@ -334,12 +886,12 @@ fn select_entry<'a>(
                     Some(current) => {
                         score > best_score
                             || (score == best_score
-                                && compare_recency(entry, current) == Ordering::Greater)
+                                && compare_recency(entry, &entries[current]) == Ordering::Greater)
                     }
                 };
 
                 if choose {
-                    best = Some(entry);
+                    best = Some(index);
                     best_score = score;
                 }
             }
@@ -413,15 +965,31 @@ fn query_score(request: &NormalizedUrl, entry: &NormalizedUrl) -> usize {
         .count()
 }
 
-fn load_entries_from_db(path: &Path, options: &ServeOptions) -> Result<Vec<ServeEntry>> {
-    if let Ok(upgrade_conn) = Connection::open_with_flags(
+fn load_serve_entries(source: &ServeSource) -> Result<Vec<ServeEntry>> {
+    let entries = if is_db_path(&source.input) {
+        load_entries_from_db(&source.input, source)?
+    } else {
+        load_entries_from_har(&source.input)?
+    };
+
+    if entries.is_empty() {
+        return Err(HarliteError::InvalidArgs(
+            "No entries found to serve".to_string(),
+        ));
+    }
+
+    Ok(entries)
+}
+
+fn load_entries_from_db(path: &Path, options: &ServeSource) -> Result<Vec<ServeEntry>> {
+    if let Ok(upgrade_conn) = open_database_with_flags(
         path,
         OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     ) {
         ensure_schema_upgrades(&upgrade_conn)?;
     }
 
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         path,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
@@ -457,9 +1025,10 @@ fn load_entries_from_db(path: &Path, options: &ServeOptions) -> Result<Vec<Serve
     };
 
     if options.allow_external_paths {
-        for blob in &mut blobs {
-            load_external_blob_content(blob, external_root.as_deref())?;
-        }
+        blobs = blobs
+            .into_iter()
+            .map(|b| load_external_blob_content(b, external_root.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
     }
 
     let blob_map: HashMap<String, BlobRow> =
@@ -467,19 +1036,29 @@ fn load_entries_from_db(path: &Path, options: &ServeOptions) -> Result<Vec<Serve
 
     let mut out = Vec::new();
     for row in rows {
-        let Some(url) = row.url.clone() else { continue; };
+        let Some(url) = row.url.clone() else {
+            continue;
+        };
         let method = row.method.unwrap_or_else(|| "GET".to_string());
-        let status = row.status.and_then(|s| u16::try_from(s).ok()).unwrap_or(200);
+        let status = row
+            .status
+            .and_then(|s| u16::try_from(s).ok())
+            .unwrap_or(200);
         let headers_map = headers_from_json(row.response_headers.as_deref());
         let mut headers = headers_from_map(&headers_map);
+        restore_set_cookie_headers(&mut headers, row.response_cookies.as_deref());
         let has_content_encoding = headers_map
             .keys()
             .any(|name| name.eq_ignore_ascii_case("content-encoding"));
 
         let body_hash = if has_content_encoding {
-            row.response_body_hash_raw.clone().or(row.response_body_hash.clone())
+            row.response_body_hash_raw
+                .clone()
+                .or(row.response_body_hash.clone())
         } else {
-            row.response_body_hash.clone().or(row.response_body_hash_raw.clone())
+            row.response_body_hash
+                .clone()
+                .or(row.response_body_hash_raw.clone())
         };
         let body = body_hash
             .as_ref()
@@ -492,6 +1071,7 @@ fn load_entries_from_db(path: &Path, options: &ServeOptions) -> Result<Vec<Serve
         }
 
         out.push(ServeEntry {
+            entry_id: Some(row.id),
             method,
             url: url.clone(),
             status,
@@ -519,6 +1099,7 @@ fn load_entries_from_har(path: &Path) -> Result<Vec<ServeEntry>> {
         let mime_type = entry.response.content.mime_type.clone();
 
         out.push(ServeEntry {
+            entry_id: None,
             method,
             url: url.clone(),
             status,
@@ -573,46 +1154,98 @@ fn headers_from_map(headers: &HashMap<String, String>) -> Vec<(String, String)>
 fn headers_from_list(headers: &[Header]) -> Vec<(String, String)> {
     headers
         .iter()
-        .filter_map(|h| {
-            if h.name.trim().is_empty() {
-                None
-            } else {
-                Some((h.name.to_ascii_lowercase(), h.value.clone()))
-            }
+        .filter(|h| !h.name.trim().is_empty())
+        .flat_map(|h| {
+            let name = h.name.to_ascii_lowercase();
+            split_set_cookie(&name, &h.value)
+                .into_iter()
+                .map(move |value| (name.clone(), value))
         })
         .collect()
 }
 
-fn strip_content_encoding(headers: &mut Vec<(String, String)>) {
-    headers.retain(|(name, _)| !name.eq_ignore_ascii_case("content-encoding"));
+/// Browsers sometimes fold several `Set-Cookie` headers into one value separated by newlines.
+fn split_set_cookie(name: &str, value: &str) -> Vec<String> {
+    if name.eq_ignore_ascii_case("set-cookie") && value.contains('\n') {
+        value
+            .split('\n')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    } else {
+        vec![value.to_string()]
+    }
 }
 
-fn load_external_blob_content(blob: &mut BlobRow, external_root: Option<&Path>) -> Result<()> {
-    if !blob.content.is_empty() || blob.size <= 0 {
-        return Ok(());
+/// Stored response headers are a JSON object, so databases imported before repeated `Set-Cookie`
+/// headers were folded keep only the last one. The recorded header values are served as-is (they
+/// carry attributes such as `SameSite` and `Max-Age`); cookies missing from them are rebuilt from
+/// the stored response cookies.
+fn restore_set_cookie_headers(headers: &mut Vec<(String, String)>, cookies_json: Option<&str>) {
+    let mut values: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| name == "set-cookie")
+        .flat_map(|(name, value)| split_set_cookie(name, value))
+        .collect();
+    let present: HashSet<String> = values
+        .iter()
+        .filter_map(|value| value.split(';').next())
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, _)| name.trim().to_string())
+        .collect();
+
+    let cookies: Vec<Cookie> = cookies_json
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    values.extend(
+        cookies
+            .iter()
+            .filter(|cookie| !present.contains(&cookie.name))
+            .map(set_cookie_value),
+    );
+    if values.is_empty() {
+        return;
     }
-    let Some(path) = &blob.external_path else {
-        return Ok(());
-    };
-    let Some(root) = external_root else {
-        return Ok(());
-    };
 
-    let candidate = PathBuf::from(path);
-    let candidate = if candidate.is_absolute() {
-        candidate
-    } else {
-        root.join(candidate)
-    };
-    let resolved = match candidate.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return Ok(()),
-    };
-    if !resolved.starts_with(root) {
-        return Ok(());
+    headers.retain(|(name, _)| name != "set-cookie");
+    headers.extend(values.into_iter().map(|v| ("set-cookie".to_string(), v)));
+}
+
+fn set_cookie_value(cookie: &Cookie) -> String {
+    let mut value = format!("{}={}", cookie.name, cookie.value);
+    if let Some(path) = cookie.path.as_deref().filter(|p| !p.is_empty()) {
+        value.push_str(&format!("; Path={path}"));
     }
-    blob.content = std::fs::read(resolved)?;
-    Ok(())
+    if let Some(domain) = cookie.domain.as_deref().filter(|d| !d.is_empty()) {
+        value.push_str(&format!("; Domain={domain}"));
+    }
+    if let Some(expires) = cookie.expires.as_deref().filter(|e| !e.is_empty()) {
+        let expires = chrono::DateTime::parse_from_rfc3339(expires)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Utc)
+                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| expires.to_string());
+        value.push_str(&format!("; Expires={expires}"));
+    }
+    if cookie.http_only == Some(true) {
+        value.push_str("; HttpOnly");
+    }
+    if cookie.secure == Some(true) {
+        value.push_str("; Secure");
+    }
+    value
+}
+
+fn strip_content_encoding(headers: &mut Vec<(String, String)>) {
+    headers.retain(|(name, _)| !name.eq_ignore_ascii_case("content-encoding"));
+}
+
+/// Both rustls backends end up enabled through the dependency graph, which stops rustls from
+/// picking a process default, so name one explicitly.
+fn tls_provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::aws_lc_rs::default_provider())
 }
 
 fn load_tls_config(cert_path: &Path, key_path: &Path) -> Result<rustls::ServerConfig> {
@@ -633,10 +1266,14 @@ fn load_tls_config(cert_path: &Path, key_path: &Path) -> Result<rustls::ServerCo
             HarliteError::InvalidArgs("No private key found in --tls-key".to_string())
         })?;
 
-    rustls::ServerConfig::builder()
+    let mut config = rustls::ServerConfig::builder_with_provider(tls_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|err| HarliteError::InvalidArgs(format!("Invalid TLS configuration: {err}")))?
         .with_no_client_auth()
         .with_single_cert(certs, key)
-        .map_err(|err| HarliteError::InvalidArgs(format!("Invalid TLS key/cert: {err}")))
+        .map_err(|err| HarliteError::InvalidArgs(format!("Invalid TLS key/cert: {err}")))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
 }
 
 fn is_db_path(path: &Path) -> bool {
@@ -659,14 +1296,25 @@ fn is_db_path(path: &Path) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        build_response, headers_from_list, normalize_url, query_score, select_entry, strip_content_encoding,
-        MatchMode, NormalizedUrl, ServeEntry,
+        build_response, handle_request, headers_from_list, load_tls_config, misses_to_har,
+        normalize_url, query_score, restore_set_cookie_headers, run_plain_server, run_tls_server,
+        select_entry, store_misses_in_db, strip_content_encoding, tls_provider, MatchMode,
+        NormalizedUrl, RouteTable, ServeEntry, ServeSource, ServeState, ADMIN_PREFIX,
     };
-    use bytes::Bytes;
+    use crate::db::create_schema;
     use crate::har::Header;
+    use bytes::Bytes;
+    use hyper::{Body, Request, StatusCode};
+    use rusqlite::Connection;
+    use std::collections::VecDeque;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+    use std::sync::{Arc, Mutex, RwLock};
+    use tokio_rustls::TlsAcceptor;
 
     fn entry(method: &str, url: &str, started_at: Option<&str>) -> ServeEntry {
         ServeEntry {
+            entry_id: None,
             method: method.to_string(),
             url: url.to_string(),
             status: 200,
@@ -687,8 +1335,16 @@ mod tests {
     #[test]
     fn fuzzy_match_prefers_query_hits() {
         let entries = vec![
-            entry("GET", "http://example.com/api?foo=1", Some("2024-01-01T00:00:00Z")),
-            entry("GET", "http://example.com/api?foo=2", Some("2024-01-02T00:00:00Z")),
+            entry(
+                "GET",
+                "http://example.com/api?foo=1",
+                Some("2024-01-01T00:00:00Z"),
+            ),
+            entry(
+                "GET",
+                "http://example.com/api?foo=2",
+                Some("2024-01-02T00:00:00Z"),
+            ),
         ];
 
         let req = normalize_url("http://example.com/api?foo=2").unwrap();
@@ -701,7 +1357,7 @@ mod tests {
         )
         .unwrap();
 
-        assert_eq!(found.url, "http://example.com/api?foo=2");
+        assert_eq!(entries[found].url, "http://example.com/api?foo=2");
     }
 
     #[test]
@@ -747,7 +1403,7 @@ mod tests {
             ("set-cookie".to_string(), "a=1".to_string()),
             ("set-cookie".to_string(), "b=2".to_string()),
         ];
-        let response = build_response(&entry);
+        let response = build_response(&entry, hyper::Version::HTTP_11);
         let values = response
             .headers()
             .get_all(hyper::header::SET_COOKIE)
@@ -766,4 +1422,370 @@ mod tests {
         assert_eq!(headers.len(), 1);
         assert_eq!(headers[0].0, "content-type");
     }
+
+    fn state(entries: Vec<ServeEntry>) -> Arc<ServeState> {
+        Arc::new(ServeState {
+            routes: RwLock::new(Arc::new(RouteTable::new(entries))),
+            misses: AtomicU64::new(0),
+            unmatched: Mutex::new(VecDeque::new()),
+            max_unmatched: 1000,
+            persisted_misses: None,
+            reloads: AtomicU64::new(0),
+            match_mode: MatchMode::Strict,
+            scheme: "http".to_string(),
+            source: ServeSource {
+                input: PathBuf::from("missing.har"),
+                allow_external_paths: false,
+                external_path_root: None,
+            },
+            admin: true,
+        })
+    }
+
+    fn send(state: &Arc<ServeState>, method: &str, path: &str) -> (StatusCode, serde_json::Value) {
+        send_with_body(state, method, path, "")
+    }
+
+    fn send_with_body(
+        state: &Arc<ServeState>,
+        method: &str,
+        path: &str,
+        body: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(hyper::header::HOST, "example.com")
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let response = handle_request(req, state.clone()).await.unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let value = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+            (status, value)
+        })
+    }
+
+    #[test]
+    fn admin_tracks_hits_and_unmatched_requests() {
+        let state = state(vec![
+            entry("GET", "http://example.com/a", None),
+            entry("GET", "http://example.com/b", None),
+        ]);
+
+        assert_eq!(send(&state, "GET", "/a").0, StatusCode::OK);
+        assert_eq!(send(&state, "GET", "/a").0, StatusCode::OK);
+        assert_eq!(send(&state, "GET", "/missing").0, StatusCode::NOT_FOUND);
+
+        let (status, routes) = send(&state, "GET", &format!("{ADMIN_PREFIX}routes"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(routes["routes"][0]["hits"], 2);
+        assert_eq!(routes["routes"][1]["hits"], 0);
+
+        let (_, stats) = send(&state, "GET", &format!("{ADMIN_PREFIX}stats"));
+        assert_eq!(stats["hits"], 2);
+        assert_eq!(stats["misses"], 1);
+        assert_eq!(stats["unhit_entries"], 1);
+
+        let (_, unmatched) = send(&state, "GET", &format!("{ADMIN_PREFIX}unmatched"));
+        assert_eq!(
+            unmatched["unmatched"][0]["url"],
+            "http://example.com/missing"
+        );
+
+        let (_, reset) = send(&state, "POST", &format!("{ADMIN_PREFIX}reset"));
+        assert_eq!(reset["hits"], 0);
+        assert_eq!(reset["misses"], 0);
+    }
+
+    #[test]
+    fn admin_rejects_unknown_endpoints_and_methods() {
+        let state = state(vec![entry("GET", "http://example.com/a", None)]);
+        assert_eq!(
+            send(&state, "GET", &format!("{ADMIN_PREFIX}nope")).0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            send(&state, "GET", &format!("{ADMIN_PREFIX}reload")).0,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        let (status, body) = send(&state, "POST", &format!("{ADMIN_PREFIX}reload"));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["reloaded"], false);
+    }
+
+    #[test]
+    fn unmatched_requests_keep_headers_and_body() {
+        let state = state(vec![entry("GET", "http://example.com/a", None)]);
+        let (status, _) = send_with_body(&state, "POST", "/api/items?x=1", "{\"id\":7}");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let misses = state.unmatched();
+        assert_eq!(misses.len(), 1);
+        assert_eq!(misses[0].method, "POST");
+        assert_eq!(misses[0].url, "http://example.com/api/items?x=1");
+        assert_eq!(&misses[0].body[..], b"{\"id\":7}");
+
+        let har = misses_to_har(&misses);
+        let request = &har.log.entries[0].request;
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.query_string.as_ref().unwrap()[0].name, "x");
+        let post_data = request.post_data.as_ref().unwrap();
+        assert_eq!(post_data.text.as_deref(), Some("{\"id\":7}"));
+        assert_eq!(post_data.mime_type.as_deref(), Some("application/json"));
+
+        let (_, har_json) = send(&state, "GET", &format!("{ADMIN_PREFIX}unmatched.har"));
+        assert_eq!(har_json["log"]["entries"][0]["request"]["method"], "POST");
+    }
+
+    #[test]
+    fn binary_miss_bodies_are_marked_base64() {
+        let state = state(Vec::new());
+        send_with_body(&state, "POST", "/upload", "\u{0}\u{1}");
+        let mut misses = state.unmatched();
+        misses[0].body = Bytes::from_static(&[0xff, 0xfe, 0x00]);
+
+        let har = misses_to_har(&misses);
+        let post_data = har.log.entries[0].request.post_data.as_ref().unwrap();
+        assert_eq!(post_data.text.as_deref(), Some("//4A"));
+        assert_eq!(post_data.extensions.get("encoding"), Some(&"base64".into()));
+    }
+
+    #[test]
+    fn unmatched_requests_keep_only_the_most_recent() {
+        let state = Arc::new(ServeState {
+            max_unmatched: 2,
+            persisted_misses: Some(Mutex::new(Vec::new())),
+            ..Arc::try_unwrap(state(Vec::new())).ok().unwrap()
+        });
+        for path in ["/one", "/two", "/three"] {
+            send(&state, "GET", path);
+        }
+
+        let urls: Vec<String> = state.unmatched().into_iter().map(|miss| miss.url).collect();
+        assert_eq!(
+            urls,
+            vec!["http://example.com/two", "http://example.com/three"]
+        );
+        assert_eq!(state.misses.load(AtomicOrdering::Relaxed), 3);
+
+        send(&state, "POST", &format!("{ADMIN_PREFIX}reset"));
+        assert_eq!(state.take_persisted_misses().len(), 3);
+    }
+
+    #[test]
+    fn store_misses_in_db_writes_rows_and_blobs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("serve.db");
+        create_schema(&Connection::open(&db_path).unwrap()).unwrap();
+
+        let state = state(vec![entry("GET", "http://example.com/a", None)]);
+        send_with_body(&state, "POST", "/missing?q=1", "payload");
+        send(&state, "GET", "/other");
+
+        let stored = store_misses_in_db(&db_path, &state.unmatched()).unwrap();
+        assert_eq!(stored, 2);
+
+        let conn = Connection::open(&db_path).unwrap();
+        let (path, query, size, hash): (String, String, i64, String) = conn
+            .query_row(
+                "SELECT path, query_string, request_body_size, request_body_hash FROM serve_misses WHERE method = 'POST'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(path, "/missing");
+        assert_eq!(query, "q=1");
+        assert_eq!(size, 7);
+        let content: Vec<u8> = conn
+            .query_row("SELECT content FROM blobs WHERE hash = ?1", [hash], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(content, b"payload");
+    }
+
+    #[test]
+    fn build_response_strips_http1_only_headers_for_h2() {
+        let mut entry = entry("GET", "http://example.com/", None);
+        entry.headers = vec![
+            ("connection".to_string(), "keep-alive, x-hop".to_string()),
+            ("keep-alive".to_string(), "timeout=5".to_string()),
+            ("x-hop".to_string(), "1".to_string()),
+            ("upgrade".to_string(), "h2c".to_string()),
+            ("set-cookie".to_string(), "a=1".to_string()),
+            ("set-cookie".to_string(), "b=2".to_string()),
+        ];
+
+        let h2 = build_response(&entry, hyper::Version::HTTP_2);
+        assert!(h2.headers().get("connection").is_none());
+        assert!(h2.headers().get("keep-alive").is_none());
+        assert!(h2.headers().get("x-hop").is_none());
+        assert!(h2.headers().get("upgrade").is_none());
+        assert_eq!(
+            h2.headers()
+                .get_all(hyper::header::SET_COOKIE)
+                .iter()
+                .count(),
+            2
+        );
+
+        let h1 = build_response(&entry, hyper::Version::HTTP_11);
+        assert!(h1.headers().get("keep-alive").is_some());
+        assert!(h1.headers().get("x-hop").is_some());
+    }
+
+    #[test]
+    fn set_cookie_headers_are_restored_from_cookies() {
+        let mut headers = vec![(
+            "set-cookie".to_string(),
+            "b=2; Max-Age=60; SameSite=Strict; Secure".to_string(),
+        )];
+        let cookies = r#"[
+            {"name":"a","value":"1","path":"/","httpOnly":true},
+            {"name":"b","value":"2","expires":"2030-01-01T00:00:00Z","secure":true}
+        ]"#;
+        restore_set_cookie_headers(&mut headers, Some(cookies));
+        let values = headers
+            .iter()
+            .filter(|(name, _)| name == "set-cookie")
+            .map(|(_, value)| value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec!["b=2; Max-Age=60; SameSite=Strict; Secure", "a=1; Path=/; HttpOnly"]
+        );
+    }
+
+    #[test]
+    fn folded_set_cookie_headers_are_served_verbatim() {
+        let mut headers = vec![(
+            "set-cookie".to_string(),
+            "a=1; SameSite=Lax\nb=2; Max-Age=60".to_string(),
+        )];
+        let cookies = r#"[{"name":"a","value":"1"},{"name":"b","value":"2"}]"#;
+        restore_set_cookie_headers(&mut headers, Some(cookies));
+        assert_eq!(
+            headers,
+            vec![
+                ("set-cookie".to_string(), "a=1; SameSite=Lax".to_string()),
+                ("set-cookie".to_string(), "b=2; Max-Age=60".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn headers_from_list_splits_folded_set_cookie() {
+        let headers = vec![Header {
+            name: "Set-Cookie".to_string(),
+            value: "a=1\nb=2".to_string(),
+        }];
+        assert_eq!(headers_from_list(&headers).len(), 2);
+    }
+
+    #[test]
+    fn plain_server_accepts_h2c_prior_knowledge() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr: std::net::SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+        let state = state(vec![entry(
+            "GET",
+            &format!("http://127.0.0.1:{port}/a"),
+            None,
+        )]);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let server = tokio::spawn(run_plain_server(addr, state.clone(), rx));
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+            let client = hyper::Client::builder()
+                .http2_only(true)
+                .build_http::<Body>();
+            let response = client
+                .get(format!("http://127.0.0.1:{port}/a").parse().unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.version(), hyper::Version::HTTP_2);
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let _ = tx.send(());
+            server.await.unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn tls_server_negotiates_h2_via_alpn() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr: std::net::SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+        let state = state(vec![entry(
+            "GET",
+            &format!("http://localhost:{port}/a"),
+            None,
+        )]);
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let cert_path = fixtures.join("serve_tls_cert.pem");
+        let server_config =
+            load_tls_config(&cert_path, &fixtures.join("serve_tls_key.pem")).unwrap();
+
+        let mut roots = rustls::RootCertStore::empty();
+        let mut cert_reader = std::io::BufReader::new(std::fs::File::open(&cert_path).unwrap());
+        for cert in rustls_pemfile::certs(&mut cert_reader) {
+            roots.add(cert.unwrap()).unwrap();
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let acceptor = TlsAcceptor::from(Arc::new(server_config));
+            let server = tokio::spawn(run_tls_server(addr, state.clone(), acceptor, rx));
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+            for (alpn, version) in [
+                (b"h2".to_vec(), hyper::Version::HTTP_2),
+                (b"http/1.1".to_vec(), hyper::Version::HTTP_11),
+            ] {
+                let mut client_config = rustls::ClientConfig::builder_with_provider(tls_provider())
+                    .with_safe_default_protocol_versions()
+                    .unwrap()
+                    .with_root_certificates(roots.clone())
+                    .with_no_client_auth();
+                client_config.alpn_protocols = vec![alpn];
+                let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+                let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+                let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+                let tls = connector.connect(server_name, tcp).await.unwrap();
+
+                let (mut sender, connection) = hyper::client::conn::Builder::new()
+                    .http2_only(version == hyper::Version::HTTP_2)
+                    .handshake::<_, Body>(tls)
+                    .await
+                    .unwrap();
+                tokio::spawn(connection);
+                let request = Request::builder()
+                    .uri(format!("https://localhost:{port}/a"))
+                    .header(hyper::header::HOST, format!("localhost:{port}"))
+                    .version(version)
+                    .body(Body::empty())
+                    .unwrap();
+                let response = sender.send_request(request).await.unwrap();
+                assert_eq!(response.version(), version);
+                assert_eq!(response.status(), StatusCode::OK);
+            }
+
+            let _ = tx.send(());
+            server.await.unwrap().unwrap();
+        });
+    }
 }
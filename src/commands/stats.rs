@@ -1,11 +1,12 @@
 use std::path::PathBuf;
 
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::OpenFlags;
 use serde::Serialize;
 
 use chrono::{Duration, Utc};
 
 use crate::commands::util::parse_cert_expiry;
+use crate::db::open_database_with_flags;
 use crate::error::Result;
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -29,7 +30,7 @@ struct StatsOutput {
 
 /// Show lightweight, script-friendly stats for a harlite database.
 pub fn run_stats(database: PathBuf, options: &StatsOptions) -> Result<()> {
-    let conn = Connection::open_with_flags(
+    let conn = open_database_with_flags(
         &database,
         OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
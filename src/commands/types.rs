@@ -0,0 +1,736 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use crate::db::{ensure_schema_upgrades, open_database, EntryRow};
+use crate::error::{HarliteError, Result};
+use crate::size;
+
+use super::entry_filter::{load_entries_with_filters, EntryFilterOptions};
+use super::openapi::{
+    finalize_schema, is_json_mime, load_json_schema, merge_schema_option, operation_id,
+    path_segments, request_content_type, response_content_type, should_sample_body, PathTemplater,
+    Schema,
+};
+use super::util::open_output;
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum TypesFormat {
+    JsonSchema,
+    Typescript,
+    Rust,
+}
+
+pub struct TypesOptions {
+    pub output: Option<PathBuf>,
+    pub format: TypesFormat,
+    pub sample_bodies: usize,
+    pub sample_body_max_size: Option<String>,
+    pub literal_paths: bool,
+    pub path_param_threshold: usize,
+    pub allow_external_paths: bool,
+    pub external_path_root: Option<PathBuf>,
+    pub filters: EntryFilterOptions,
+}
+
+/// Merged body schemas for one `METHOD /route` pair.
+#[derive(Default)]
+struct Endpoint {
+    request: Option<Schema>,
+    request_samples: usize,
+    response: Option<Schema>,
+    response_samples: usize,
+}
+
+pub fn run_types(database: PathBuf, options: &TypesOptions) -> Result<()> {
+    if options.sample_bodies == 0 {
+        return Err(HarliteError::InvalidArgs(
+            "--sample-bodies must be greater than 0".to_string(),
+        ));
+    }
+
+    let conn = open_database(&database)?;
+    ensure_schema_upgrades(&conn)?;
+
+    let external_root = if options.allow_external_paths {
+        let root = options
+            .external_path_root
+            .clone()
+            .or_else(|| database.parent().map(|p| p.to_path_buf()))
+            .ok_or_else(|| {
+                HarliteError::InvalidArgs(
+                    "Cannot resolve external path root; pass --external-path-root".to_string(),
+                )
+            })?;
+        Some(root.canonicalize()?)
+    } else {
+        None
+    };
+    let max_body_size = match options.sample_body_max_size.as_deref() {
+        Some(value) => size::parse_size_bytes_i64(value)?,
+        None => None,
+    };
+
+    let entries = load_entries_with_filters(&conn, &options.filters)?;
+    let parsed: Vec<(&EntryRow, Url)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let url = Url::parse(entry.url.as_deref()?).ok()?;
+            Some((entry, url))
+        })
+        .collect();
+    let threshold = if options.literal_paths {
+        0
+    } else {
+        options.path_param_threshold
+    };
+    let templater = PathTemplater::new(
+        parsed.iter().map(|(_, url)| path_segments(url)),
+        threshold,
+        !options.literal_paths,
+    );
+
+    let limit = options.sample_bodies;
+    let mut endpoints: BTreeMap<(String, String), Endpoint> = BTreeMap::new();
+    for (entry, url) in &parsed {
+        let route = templater.template(&path_segments(url)).path;
+        let method = entry
+            .method
+            .as_deref()
+            .unwrap_or("GET")
+            .to_ascii_lowercase();
+        let endpoint = endpoints.entry((route, method)).or_default();
+
+        let json_request = request_content_type(entry).is_some_and(|m| is_json_mime(&m));
+        if json_request
+            && endpoint.request_samples < limit
+            && should_sample_body(entry.request_body_size, max_body_size)
+        {
+            if let Some(hash) = entry.request_body_hash.as_deref() {
+                if let Some(schema) =
+                    load_json_schema(&conn, hash, external_root.as_deref(), max_body_size)?
+                {
+                    endpoint.request = merge_schema_option(endpoint.request.take(), schema);
+                    endpoint.request_samples += 1;
+                }
+            }
+        }
+
+        // Types describe the success shape; error payloads would only widen every field.
+        let success = entry.status.is_some_and(|s| (200..300).contains(&s));
+        let json_response = response_content_type(entry).is_some_and(|m| is_json_mime(&m));
+        if success
+            && json_response
+            && endpoint.response_samples < limit
+            && should_sample_body(entry.response_body_size, max_body_size)
+        {
+            if let Some(hash) = entry.response_body_hash.as_deref() {
+                if let Some(schema) =
+                    load_json_schema(&conn, hash, external_root.as_deref(), max_body_size)?
+                {
+                    endpoint.response = merge_schema_option(endpoint.response.take(), schema);
+                    endpoint.response_samples += 1;
+                }
+            }
+        }
+    }
+
+    let mut types: Vec<NamedType> = Vec::new();
+    for ((route, method), endpoint) in endpoints {
+        let base = pascal_case(&operation_id(&method, &route));
+        let label = format!("{} {route}", method.to_ascii_uppercase());
+        for (suffix, schema, samples) in [
+            ("Request", endpoint.request, endpoint.request_samples),
+            ("Response", endpoint.response, endpoint.response_samples),
+        ] {
+            if let Some(schema) = schema {
+                types.push(NamedType {
+                    name: format!("{base}{suffix}"),
+                    comment: format!(
+                        "{label} {} body ({samples} sample{})",
+                        suffix.to_ascii_lowercase(),
+                        if samples == 1 { "" } else { "s" }
+                    ),
+                    schema: finalize_schema(schema),
+                });
+            }
+        }
+    }
+
+    if types.is_empty() {
+        return Err(HarliteError::InvalidArgs(
+            "No JSON bodies found for the selected entries; import with --bodies".to_string(),
+        ));
+    }
+
+    let output = options.output.clone().unwrap_or_else(|| PathBuf::from("-"));
+    let mut writer = open_output(&output)?;
+    match options.format {
+        TypesFormat::JsonSchema => {
+            serde_json::to_writer_pretty(&mut writer, &json_schema_document(&types))?;
+            writer.write_all(b"\n")?;
+        }
+        TypesFormat::Typescript => writer.write_all(render_typescript(&types).as_bytes())?,
+        TypesFormat::Rust => writer.write_all(render_rust(&types).as_bytes())?,
+    }
+    writer.flush()?;
+
+    if output != Path::new("-") {
+        println!("Exported {} types to {}", types.len(), output.display());
+    }
+    Ok(())
+}
+
+struct NamedType {
+    name: String,
+    comment: String,
+    schema: Schema,
+}
+
+fn json_schema_document(types: &[NamedType]) -> Value {
+    let defs: Map<String, Value> = types
+        .iter()
+        .map(|t| {
+            let mut schema = json_schema(&t.schema);
+            if let Value::Object(obj) = &mut schema {
+                obj.insert("description".to_string(), Value::String(t.comment.clone()));
+            }
+            (t.name.clone(), schema)
+        })
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$defs": defs,
+    })
+}
+
+/// Translate the OpenAPI-flavoured schema (`nullable`) into plain JSON Schema.
+fn json_schema(schema: &Schema) -> Value {
+    let mut out = Map::new();
+    let nullable = schema.nullable == Some(true);
+    match (&schema.schema_type, &schema.one_of) {
+        (Some(t), _) if nullable => {
+            out.insert("type".to_string(), json!([t, "null"]));
+        }
+        (Some(t), _) => {
+            out.insert("type".to_string(), json!(t));
+        }
+        (None, Some(variants)) => {
+            let mut variants: Vec<Value> = variants.iter().map(json_schema).collect();
+            if nullable {
+                variants.push(json!({ "type": "null" }));
+            }
+            out.insert("anyOf".to_string(), Value::Array(variants));
+        }
+        (None, None) if nullable => {
+            out.insert("type".to_string(), json!("null"));
+        }
+        (None, None) => {}
+    }
+    if let Some(format) = &schema.format {
+        out.insert("format".to_string(), json!(format));
+    }
+    if let Some(values) = &schema.enum_values {
+        out.insert("enum".to_string(), json!(values));
+    }
+    if let Some(properties) = &schema.properties {
+        let props: Map<String, Value> = properties
+            .iter()
+            .map(|(name, prop)| (name.clone(), json_schema(prop)))
+            .collect();
+        out.insert("properties".to_string(), Value::Object(props));
+    }
+    if let Some(required) = &schema.required {
+        out.insert("required".to_string(), json!(required));
+    }
+    if let Some(items) = &schema.items {
+        out.insert("items".to_string(), json_schema(items));
+    }
+    Value::Object(out)
+}
+
+/// A schema lowered into named object declarations for the code generators.
+enum Ty {
+    String(Option<Vec<String>>),
+    Integer,
+    Number,
+    Boolean,
+    Array(Box<Ty>),
+    Object(String),
+    /// An object with no observed properties.
+    Map,
+    Union(Vec<Ty>),
+    Unknown,
+}
+
+struct Field {
+    name: String,
+    ty: Ty,
+    optional: bool,
+    nullable: bool,
+}
+
+enum Decl {
+    Struct {
+        name: String,
+        comment: Option<String>,
+        fields: Vec<Field>,
+    },
+    Alias {
+        name: String,
+        comment: Option<String>,
+        ty: Ty,
+        nullable: bool,
+    },
+}
+
+struct Lowering {
+    decls: Vec<Decl>,
+    names: BTreeSet<String>,
+}
+
+impl Lowering {
+    fn new() -> Self {
+        Self {
+            decls: Vec::new(),
+            names: BTreeSet::new(),
+        }
+    }
+
+    fn unique_name(&mut self, hint: &str) -> String {
+        let mut name = hint.to_string();
+        let mut n = 2;
+        while !self.names.insert(name.clone()) {
+            name = format!("{hint}{n}");
+            n += 1;
+        }
+        name
+    }
+
+    fn add_root(&mut self, named: &NamedType) {
+        let comment = Some(named.comment.clone());
+        let nullable = named.schema.nullable == Some(true);
+        if named.schema.schema_type.as_deref() == Some("object")
+            && named
+                .schema
+                .properties
+                .as_ref()
+                .is_some_and(|p| !p.is_empty())
+        {
+            self.lower_object(&named.name, &named.schema, comment);
+            return;
+        }
+        let name = self.unique_name(&named.name);
+        let idx = self.decls.len();
+        let ty = self.lower(&named.schema, &name);
+        self.decls.insert(
+            idx,
+            Decl::Alias {
+                name,
+                comment,
+                ty,
+                nullable,
+            },
+        );
+    }
+
+    fn lower_object(&mut self, hint: &str, schema: &Schema, comment: Option<String>) -> String {
+        let name = self.unique_name(hint);
+        let idx = self.decls.len();
+        let required: BTreeSet<&String> = schema.required.iter().flatten().collect();
+        let mut fields = Vec::new();
+        for (prop_name, prop) in schema.properties.iter().flatten() {
+            let ty = self.lower(prop, &format!("{name}{}", pascal_case(prop_name)));
+            fields.push(Field {
+                name: prop_name.clone(),
+                ty,
+                optional: !required.contains(prop_name),
+                nullable: prop.nullable == Some(true),
+            });
+        }
+        self.decls.insert(
+            idx,
+            Decl::Struct {
+                name: name.clone(),
+                comment,
+                fields,
+            },
+        );
+        name
+    }
+
+    fn lower(&mut self, schema: &Schema, hint: &str) -> Ty {
+        if let Some(variants) = &schema.one_of {
+            let tys = variants
+                .iter()
+                .enumerate()
+                .map(|(idx, v)| self.lower(v, &format!("{hint}{}", idx + 1)))
+                .collect();
+            return Ty::Union(tys);
+        }
+        match schema.schema_type.as_deref() {
+            Some("string") => Ty::String(schema.enum_values.clone()),
+            Some("integer") => Ty::Integer,
+            Some("number") => Ty::Number,
+            Some("boolean") => Ty::Boolean,
+            Some("array") => {
+                let item_hint = singular(hint);
+                let item = match &schema.items {
+                    Some(items) => self.lower(items, &item_hint),
+                    None => Ty::Unknown,
+                };
+                Ty::Array(Box::new(item))
+            }
+            Some("object") if schema.properties.as_ref().is_some_and(|p| !p.is_empty()) => {
+                Ty::Object(self.lower_object(hint, schema, None))
+            }
+            Some("object") => Ty::Map,
+            _ => Ty::Unknown,
+        }
+    }
+}
+
+/// `Tags` -> `Tag`, `Entries` -> `Entry`; names that are already singular get `Item`.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        return format!("{stem}y");
+    }
+    if name.len() > 3 && name.ends_with('s') && !name.ends_with("ss") {
+        return name[..name.len() - 1].to_string();
+    }
+    format!("{name}Item")
+}
+
+fn pascal_case(value: &str) -> String {
+    let mut out = String::new();
+    let mut upper = true;
+    for c in value.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+            continue;
+        }
+        if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, 'T');
+    }
+    out
+}
+
+fn lower_all(types: &[NamedType]) -> Vec<Decl> {
+    let mut lowering = Lowering::new();
+    for named in types {
+        lowering.add_root(named);
+    }
+    lowering.decls
+}
+
+fn render_typescript(types: &[NamedType]) -> String {
+    let mut out = String::from("// Generated by harlite from captured traffic.\n");
+    for decl in lower_all(types) {
+        out.push('\n');
+        match decl {
+            Decl::Struct {
+                name,
+                comment,
+                fields,
+            } => {
+                if let Some(comment) = comment {
+                    out.push_str(&format!("/** {comment} */\n"));
+                }
+                out.push_str(&format!("export interface {name} {{\n"));
+                for field in fields {
+                    let key = if is_ts_identifier(&field.name) {
+                        field.name.clone()
+                    } else {
+                        format!("{:?}", field.name)
+                    };
+                    let mut ty = ts_type(&field.ty);
+                    if field.nullable {
+                        ty.push_str(" | null");
+                    }
+                    let optional = if field.optional { "?" } else { "" };
+                    out.push_str(&format!("  {key}{optional}: {ty};\n"));
+                }
+                out.push_str("}\n");
+            }
+            Decl::Alias {
+                name,
+                comment,
+                ty,
+                nullable,
+            } => {
+                if let Some(comment) = comment {
+                    out.push_str(&format!("/** {comment} */\n"));
+                }
+                let mut ty = ts_type(&ty);
+                if nullable {
+                    ty.push_str(" | null");
+                }
+                out.push_str(&format!("export type {name} = {ty};\n"));
+            }
+        }
+    }
+    out
+}
+
+fn ts_type(ty: &Ty) -> String {
+    match ty {
+        Ty::String(Some(values)) => values
+            .iter()
+            .map(|v| format!("{v:?}"))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Ty::String(None) => "string".to_string(),
+        Ty::Integer | Ty::Number => "number".to_string(),
+        Ty::Boolean => "boolean".to_string(),
+        Ty::Array(item) => match item.as_ref() {
+            Ty::Union(_) | Ty::String(Some(_)) => format!("Array<{}>", ts_type(item)),
+            _ => format!("{}[]", ts_type(item)),
+        },
+        Ty::Object(name) => name.clone(),
+        Ty::Map => "Record<string, unknown>".to_string(),
+        Ty::Union(variants) => variants.iter().map(ts_type).collect::<Vec<_>>().join(" | "),
+        Ty::Unknown => "unknown".to_string(),
+    }
+}
+
+fn is_ts_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn render_rust(types: &[NamedType]) -> String {
+    let mut out = String::from(
+        "// Generated by harlite from captured traffic.\n\nuse serde::{Deserialize, Serialize};\n",
+    );
+    for decl in lower_all(types) {
+        out.push('\n');
+        match decl {
+            Decl::Struct {
+                name,
+                comment,
+                fields,
+            } => {
+                if let Some(comment) = comment {
+                    out.push_str(&format!("/// {comment}\n"));
+                }
+                out.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+                out.push_str(&format!("pub struct {name} {{\n"));
+                let mut used = HashSet::new();
+                for field in fields {
+                    let ident = unique_rust_field_name(&field.name, &mut used);
+                    if let Ty::String(Some(values)) = &field.ty {
+                        let values: Vec<String> = values.iter().map(|v| format!("{v:?}")).collect();
+                        out.push_str(&format!("    /// One of: {}\n", values.join(", ")));
+                    }
+                    if ident.trim_start_matches("r#") != field.name {
+                        out.push_str(&format!("    #[serde(rename = {:?})]\n", field.name));
+                    }
+                    let mut ty = rust_type(&field.ty);
+                    if field.optional || field.nullable {
+                        ty = format!("Option<{ty}>");
+                    }
+                    if field.optional {
+                        out.push_str(
+                            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n",
+                        );
+                    }
+                    out.push_str(&format!("    pub {ident}: {ty},\n"));
+                }
+                out.push_str("}\n");
+            }
+            Decl::Alias {
+                name,
+                comment,
+                ty,
+                nullable,
+            } => {
+                if let Some(comment) = comment {
+                    out.push_str(&format!("/// {comment}\n"));
+                }
+                let mut ty = rust_type(&ty);
+                if nullable {
+                    ty = format!("Option<{ty}>");
+                }
+                out.push_str(&format!("pub type {name} = {ty};\n"));
+            }
+        }
+    }
+    out
+}
+
+fn rust_type(ty: &Ty) -> String {
+    match ty {
+        Ty::String(_) => "String".to_string(),
+        Ty::Integer => "i64".to_string(),
+        Ty::Number => "f64".to_string(),
+        Ty::Boolean => "bool".to_string(),
+        Ty::Array(item) => format!("Vec<{}>", rust_type(item)),
+        Ty::Object(name) => name.clone(),
+        Ty::Map => "serde_json::Map<String, serde_json::Value>".to_string(),
+        Ty::Union(_) | Ty::Unknown => "serde_json::Value".to_string(),
+    }
+}
+
+/// `firstName` -> `first_name`; keywords become raw identifiers.
+/// `rust_field_name`, suffixed with `_2`, `_3`, ... when another key in the same struct
+/// already maps to that identifier (e.g. `firstName` and `first_name`).
+fn unique_rust_field_name(name: &str, used: &mut HashSet<String>) -> String {
+    let ident = rust_field_name(name);
+    if used.insert(ident.clone()) {
+        return ident;
+    }
+    let base = ident.trim_start_matches("r#");
+    let mut n = 2;
+    loop {
+        let candidate = format!("{base}_{n}");
+        if used.insert(candidate.clone()) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+fn rust_field_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !out.ends_with('_') && !out.is_empty() {
+                out.push('_');
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else {
+            out.push(c);
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        }
+    }
+    let out = out.trim_end_matches('_').to_string();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("field_{out}");
+    }
+    if RUST_KEYWORDS.contains(&out.as_str()) {
+        if matches!(out.as_str(), "self" | "Self" | "super" | "crate") {
+            return format!("{out}_");
+        }
+        return format!("r#{out}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::openapi::{infer_schema, merge_schema};
+
+    fn named(name: &str, bodies: &[Value]) -> NamedType {
+        let schema = bodies
+            .iter()
+            .map(infer_schema)
+            .reduce(merge_schema)
+            .map(finalize_schema)
+            .unwrap();
+        NamedType {
+            name: name.to_string(),
+            comment: "GET /users response body".to_string(),
+            schema,
+        }
+    }
+
+    fn users() -> Vec<NamedType> {
+        vec![named(
+            "GetUsersResponse",
+            &[
+                json!({"total": 2, "users": [{"id": 1, "firstName": "Ann", "type": "admin", "tags": ["a"]}]}),
+                json!({"total": 1, "users": [{"id": 2, "firstName": null, "type": "admin"}], "next": "x"}),
+            ],
+        )]
+    }
+
+    #[test]
+    fn typescript_interfaces_follow_required_and_nullable() {
+        let ts = render_typescript(&users());
+        assert!(ts.contains("/** GET /users response body */\nexport interface GetUsersResponse {"));
+        assert!(ts.contains("  next?: string;\n"));
+        assert!(ts.contains("  total: number;\n"));
+        assert!(ts.contains("  users: GetUsersResponseUser[];\n"));
+        assert!(ts.contains("export interface GetUsersResponseUser {"));
+        assert!(ts.contains("  firstName: string | null;\n"));
+        assert!(ts.contains("  tags?: string[];\n"));
+    }
+
+    #[test]
+    fn rust_structs_rename_fields_and_wrap_options() {
+        let rs = render_rust(&users());
+        assert!(rs.contains("pub struct GetUsersResponse {"));
+        assert!(rs.contains("    pub users: Vec<GetUsersResponseUser>,\n"));
+        assert!(rs.contains(
+            "    #[serde(rename = \"firstName\")]\n    pub first_name: Option<String>,\n"
+        ));
+        assert!(rs.contains("    pub r#type: String,\n"));
+        assert!(rs.contains(
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub next: Option<String>,\n"
+        ));
+        assert!(rs.contains("    pub id: i64,\n"));
+    }
+
+    #[test]
+    fn json_schema_uses_type_arrays_for_nullable() {
+        let doc = json_schema_document(&users());
+        let user = &doc["$defs"]["GetUsersResponse"]["properties"]["users"]["items"];
+        assert_eq!(
+            user["properties"]["firstName"]["type"],
+            json!(["string", "null"])
+        );
+        assert_eq!(user["required"], json!(["firstName", "id", "type"]));
+
+        let list = [named("ListResponse", &[json!([{"id": 1}])])];
+        assert!(
+            render_typescript(&list).contains("export type ListResponse = ListResponseItem[];\n")
+        );
+        assert_eq!(rust_field_name("self"), "self_");
+        assert_eq!(rust_field_name("HTTPStatus2"), "httpstatus2");
+    }
+
+    #[test]
+    fn rust_structs_dedupe_colliding_field_names() {
+        let types = [named(
+            "Profile",
+            &[json!({"firstName": "Ann", "first_name": "Ann", "Type": 1, "type": 2})],
+        )];
+        let rs = render_rust(&types);
+        assert!(rs.contains("    #[serde(rename = \"firstName\")]\n    pub first_name: String,\n"));
+        assert!(
+            rs.contains("    #[serde(rename = \"first_name\")]\n    pub first_name_2: String,\n")
+        );
+        assert!(rs.contains("    #[serde(rename = \"Type\")]\n    pub r#type: i64,\n"));
+        assert!(rs.contains("    #[serde(rename = \"type\")]\n    pub type_2: i64,\n"));
+    }
+}
@@ -1,10 +1,68 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 
+use crate::db::{read_external_blob, BlobRow};
 use crate::error::{HarliteError, Result};
 
+/// Open `path` for writing, with `-` meaning stdout.
+pub fn open_output(path: &Path) -> Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        return Ok(Box::new(io::stdout().lock()));
+    }
+    Ok(Box::new(BufWriter::new(File::create(path)?)))
+}
+
+/// Fill in the content of a blob stored under `external_root` (`--extract-bodies-dir`).
+///
+/// Blobs that already have content, have no external path, or resolve outside the root are
+/// returned unchanged.
+pub fn load_external_blob_content(
+    mut blob: BlobRow,
+    external_root: Option<&Path>,
+) -> Result<BlobRow> {
+    if !blob.content.is_empty() || blob.size <= 0 {
+        return Ok(blob);
+    }
+    let Some(path) = &blob.external_path else {
+        return Ok(blob);
+    };
+    let Some(root) = external_root else {
+        return Ok(blob);
+    };
+
+    let candidate = PathBuf::from(path);
+    let candidate = if candidate.is_absolute() {
+        candidate
+    } else {
+        root.join(candidate)
+    };
+    let resolved = match candidate.canonicalize() {
+        Ok(p) => p,
+        Err(_) => return Ok(blob),
+    };
+    if !resolved.starts_with(root) {
+        return Ok(blob);
+    }
+    blob.content = read_external_blob(&resolved)?;
+    Ok(blob)
+}
+
+/// Nearest-rank percentile of an ascending slice; 0 when empty.
+pub fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+    let rank = (pct / 100.0) * (sorted.len() as f64 - 1.0);
+    let idx = rank.round() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
 pub fn canonicalize_path_for_compare(path: &Path) -> Result<PathBuf> {
     if path.exists() {
         return Ok(fs::canonicalize(path)?);
@@ -8,10 +8,9 @@ use std::time::{Duration, Instant, SystemTime};
 
 use chrono::{DateTime, Utc};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
-use rusqlite::Connection;
 
 use crate::commands::{run_import, run_info, run_stats, ImportOptions, InfoOptions, StatsOptions};
-use crate::db::create_schema;
+use crate::db::{create_schema, open_database};
 use crate::error::{HarliteError, Result};
 
 #[derive(Clone)]
@@ -284,7 +283,7 @@ fn drain_events(
 }
 
 fn load_import_history(db_path: &Path) -> Result<HashMap<String, SystemTime>> {
-    let conn = Connection::open(db_path)?;
+    let conn = open_database(db_path)?;
     create_schema(&conn)?;
     let mut stmt = conn.prepare(
         "SELECT source_file, imported_at, status FROM imports WHERE source_file IS NOT NULL",
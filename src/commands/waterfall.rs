@@ -1,15 +1,18 @@
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufWriter, Write};
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::PathBuf;
 
 use chrono::SecondsFormat;
 use chrono::{DateTime, NaiveDate, Utc};
-use rusqlite::Connection;
 use serde::Serialize;
+use url::Url;
 
-use crate::db::{ensure_schema_upgrades, load_entries, load_pages_for_imports, EntryQuery, PageRow};
+use crate::db::{
+    ensure_schema_upgrades, load_entries, load_pages_for_imports, open_database, EntryQuery,
+    PageRow,
+};
 use crate::error::{HarliteError, Result};
+use super::util::open_output;
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
 pub enum WaterfallFormat {
@@ -62,13 +65,6 @@ fn parse_started_at_bound(s: &str, is_end: bool) -> Result<String> {
     Ok(dt.to_rfc3339_opts(SecondsFormat::Millis, true))
 }
 
-fn open_output(path: &Path) -> Result<Box<dyn Write>> {
-    if path == Path::new("-") {
-        return Ok(Box::new(io::stdout().lock()));
-    }
-    Ok(Box::new(BufWriter::new(File::create(path)?)))
-}
-
 #[derive(Debug, Clone)]
 struct WaterfallEntry {
     import_id: i64,
@@ -82,6 +78,34 @@ struct WaterfallEntry {
     host: String,
     status: Option<i32>,
     mime: Option<String>,
+    connection_id: Option<String>,
+    request_id: Option<String>,
+    parent_request_id: Option<String>,
+    initiator_type: Option<String>,
+    initiator_url: Option<String>,
+    redirect_url: Option<String>,
+    timings: PhaseTimings,
+}
+
+/// HAR timing phases in milliseconds; `None` for phases the capture did not record.
+#[derive(Debug, Clone, Default)]
+struct PhaseTimings {
+    blocked: Option<f64>,
+    dns: Option<f64>,
+    connect: Option<f64>,
+    ssl: Option<f64>,
+    send: Option<f64>,
+    wait: Option<f64>,
+    receive: Option<f64>,
+}
+
+/// `onContentLoad`/`onLoad` marks for one page, as offsets from the first request.
+#[derive(Debug, Clone)]
+struct PageMarks {
+    title: String,
+    start_ms: f64,
+    on_content_load_ms: Option<f64>,
+    on_load_ms: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -200,49 +224,346 @@ struct TraceEvent {
     ts: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     dur: Option<i64>,
+    /// Flow event id; `s` and `f` events with the same id form one arrow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    /// Flow binding point; `e` binds a flow end to the enclosing slice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bp: Option<String>,
+    /// Instant event scope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     args: Option<serde_json::Value>,
 }
 
-fn render_trace(groups: &[GroupInfo], writer: &mut dyn Write) -> Result<()> {
+impl TraceEvent {
+    fn new(name: impl Into<String>, ph: &str, pid: i32, tid: i32) -> Self {
+        Self {
+            name: name.into(),
+            ph: ph.to_string(),
+            pid,
+            tid,
+            cat: None,
+            ts: None,
+            dur: None,
+            id: None,
+            bp: None,
+            s: None,
+            args: None,
+        }
+    }
+
+    fn metadata(name: &str, pid: i32, tid: i32, args: serde_json::Value) -> Self {
+        let mut event = Self::new(name, "M", pid, tid);
+        event.ts = Some(0);
+        event.args = Some(args);
+        event
+    }
+}
+
+fn ms_to_us(ms: f64) -> i64 {
+    (ms.max(0.0) * 1000.0).round() as i64
+}
+
+/// Phase slices as `(name, start_ms, end_ms)` offsets from the request start. `ssl`
+/// nests inside `connect`, since HAR counts TLS setup as part of the connection time.
+fn phase_slices(timings: &PhaseTimings) -> Vec<(&'static str, f64, f64)> {
+    let mut out = Vec::new();
+    let mut cursor = 0.0;
+    for (name, ms) in [("blocked", timings.blocked), ("dns", timings.dns)] {
+        if let Some(ms) = ms {
+            out.push((name, cursor, cursor + ms));
+            cursor += ms;
+        }
+    }
+    match (timings.connect, timings.ssl) {
+        (Some(connect), ssl) => {
+            out.push(("connect", cursor, cursor + connect));
+            if let Some(ssl) = ssl.filter(|ssl| *ssl > 0.0 && *ssl <= connect) {
+                out.push(("ssl", cursor + connect - ssl, cursor + connect));
+            }
+            cursor += connect;
+        }
+        (None, Some(ssl)) => {
+            out.push(("ssl", cursor, cursor + ssl));
+            cursor += ssl;
+        }
+        (None, None) => {}
+    }
+    for (name, ms) in [
+        ("send", timings.send),
+        ("wait", timings.wait),
+        ("receive", timings.receive),
+    ] {
+        if let Some(ms) = ms {
+            out.push((name, cursor, cursor + ms));
+            cursor += ms;
+        }
+    }
+    out.retain(|(_, start, end)| end > start);
+    out
+}
+
+/// Arrows between requests: `(from, to, kind)` over `entries` indices.
+///
+/// An entry's parent is its recorded `parent_request_id`; failing that, the request
+/// that redirected to its URL, or the most recent request for its `initiator_url`.
+fn flow_edges(entries: &[&WaterfallEntry]) -> Vec<(usize, usize, &'static str)> {
+    let mut by_request_id: HashMap<(i64, &str), usize> = HashMap::new();
+    let mut by_url: HashMap<(i64, &str), Vec<usize>> = HashMap::new();
+    let mut by_redirect: HashMap<(i64, String), Vec<usize>> = HashMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        if let Some(id) = entry.request_id.as_deref() {
+            by_request_id.entry((entry.import_id, id)).or_insert(idx);
+        }
+        by_url
+            .entry((entry.import_id, entry.url.as_str()))
+            .or_default()
+            .push(idx);
+        if let Some(target) = entry.redirect_url.as_deref().filter(|t| !t.is_empty()) {
+            let resolved = Url::parse(&entry.url)
+                .and_then(|base| base.join(target))
+                .map(|url| url.to_string())
+                .unwrap_or_else(|_| target.to_string());
+            by_redirect
+                .entry((entry.import_id, resolved))
+                .or_default()
+                .push(idx);
+        }
+    }
+    // Most recent candidate that started no later than `entries[to]`.
+    let latest_before = |candidates: Option<&Vec<usize>>, to: usize| {
+        candidates?
+            .iter()
+            .copied()
+            .filter(|&from| from != to && entries[from].start_ms <= entries[to].start_ms)
+            .max_by(|a, b| {
+                entries[*a]
+                    .start_ms
+                    .partial_cmp(&entries[*b].start_ms)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    };
+
+    let mut edges = Vec::new();
+    for (to, entry) in entries.iter().enumerate() {
+        let parent = entry
+            .parent_request_id
+            .as_deref()
+            .and_then(|id| by_request_id.get(&(entry.import_id, id)).copied())
+            .filter(|&from| from != to && entries[from].start_ms <= entry.start_ms);
+        let edge = if let Some(from) = parent {
+            let redirected = entries[from]
+                .redirect_url
+                .as_deref()
+                .is_some_and(|t| !t.is_empty());
+            Some((from, if redirected { "redirect" } else { "initiator" }))
+        } else if let Some(from) =
+            latest_before(by_redirect.get(&(entry.import_id, entry.url.clone())), to)
+        {
+            Some((from, "redirect"))
+        } else {
+            entry
+                .initiator_url
+                .as_deref()
+                .and_then(|url| latest_before(by_url.get(&(entry.import_id, url)), to))
+                .map(|from| (from, "initiator"))
+        };
+        if let Some((from, kind)) = edge {
+            edges.push((from, to, kind));
+        }
+    }
+    edges
+}
+
+/// A connection track within a group; concurrent requests on one connection (HTTP/2
+/// streams) spill onto extra tracks so slices on each track stay properly nested.
+struct Lane {
+    connection: Option<String>,
+    tid: i32,
+    end_us: i64,
+}
+
+/// Chrome Trace Event JSON, as loaded by ui.perfetto.dev and chrome://tracing.
+///
+/// Each group is a process. Track 0 carries page markers; every connection gets its own
+/// track, with the request as a slice and its timing phases nested inside. Flow arrows
+/// follow initiator and redirect chains.
+fn render_trace(
+    groups: &[GroupInfo],
+    pages: &HashMap<(i64, String), PageMarks>,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let entries: Vec<&WaterfallEntry> = groups.iter().flat_map(|g| g.entries.iter()).collect();
+    let mut flows_out: HashMap<usize, Vec<(u64, &'static str)>> = HashMap::new();
+    let mut flows_in: HashMap<usize, Vec<(u64, &'static str)>> = HashMap::new();
+    for (flow_id, (from, to, kind)) in flow_edges(&entries).into_iter().enumerate() {
+        let flow_id = flow_id as u64 + 1;
+        flows_out.entry(from).or_default().push((flow_id, kind));
+        flows_in.entry(to).or_default().push((flow_id, kind));
+    }
+
     let mut events: Vec<TraceEvent> = Vec::new();
+    let mut global_idx = 0usize;
     for (idx, group) in groups.iter().enumerate() {
         let pid = idx as i32 + 1;
-        events.push(TraceEvent {
-            name: "process_name".to_string(),
-            ph: "M".to_string(),
+        events.push(TraceEvent::metadata(
+            "process_name",
             pid,
-            tid: 0,
-            cat: None,
-            ts: Some(0),
-            dur: None,
-            args: Some(serde_json::json!({ "name": group.name })),
-        });
+            0,
+            serde_json::json!({ "name": group.name }),
+        ));
+        events.push(TraceEvent::metadata(
+            "process_sort_index",
+            pid,
+            0,
+            serde_json::json!({ "sort_index": idx }),
+        ));
+
+        let mut page_keys: Vec<(i64, String)> = Vec::new();
+        for entry in &group.entries {
+            if let Some(page_id) = entry.page_id.as_ref() {
+                let key = (entry.import_id, page_id.clone());
+                if pages.contains_key(&key) && !page_keys.contains(&key) {
+                    page_keys.push(key);
+                }
+            }
+        }
+        if !page_keys.is_empty() {
+            events.push(TraceEvent::metadata(
+                "thread_name",
+                pid,
+                0,
+                serde_json::json!({ "name": "Page" }),
+            ));
+        }
+        for key in &page_keys {
+            let marks = &pages[key];
+            let points = [
+                ("page", Some(0.0)),
+                ("onContentLoad", marks.on_content_load_ms),
+                ("onLoad", marks.on_load_ms),
+            ];
+            for (name, offset) in points {
+                let Some(offset) = offset else { continue };
+                let at = marks.start_ms + offset;
+                if at < 0.0 {
+                    continue;
+                }
+                let mut event = TraceEvent::new(name, "i", pid, 0);
+                event.cat = Some("page".to_string());
+                event.ts = Some(ms_to_us(at));
+                event.s = Some("t".to_string());
+                event.args = Some(serde_json::json!({
+                    "page_id": key.1,
+                    "page_title": marks.title,
+                    "offset_ms": offset,
+                }));
+                events.push(event);
+            }
+        }
 
+        let mut lanes: Vec<Lane> = Vec::new();
         for (order, entry) in group.entries.iter().enumerate() {
-            let ts = (entry.start_ms * 1000.0).round() as i64;
-            let dur = (entry.duration_ms.max(0.0) * 1000.0).round() as i64;
+            let phases = phase_slices(&entry.timings);
+            let phase_end = phases.iter().map(|(_, _, end)| *end).fold(0.0, f64::max);
+            let ts = ms_to_us(entry.start_ms);
+            let dur = ms_to_us(entry.duration_ms.max(phase_end));
+
+            let lane = match lanes
+                .iter_mut()
+                .find(|lane| lane.connection == entry.connection_id && lane.end_us <= ts)
+            {
+                Some(lane) => lane,
+                None => {
+                    let tid = lanes.len() as i32 + 1;
+                    let siblings = lanes
+                        .iter()
+                        .filter(|lane| lane.connection == entry.connection_id)
+                        .count();
+                    let mut name = match entry.connection_id.as_deref() {
+                        Some(id) if entry.host.is_empty() => format!("Connection {id}"),
+                        Some(id) => format!("Connection {id} ({})", entry.host),
+                        None => "No connection id".to_string(),
+                    };
+                    if siblings > 0 {
+                        name.push_str(&format!(" #{}", siblings + 1));
+                    }
+                    events.push(TraceEvent::metadata(
+                        "thread_name",
+                        pid,
+                        tid,
+                        serde_json::json!({ "name": name }),
+                    ));
+                    events.push(TraceEvent::metadata(
+                        "thread_sort_index",
+                        pid,
+                        tid,
+                        serde_json::json!({ "sort_index": tid }),
+                    ));
+                    lanes.push(Lane {
+                        connection: entry.connection_id.clone(),
+                        tid,
+                        end_us: 0,
+                    });
+                    lanes.last_mut().unwrap()
+                }
+            };
+            lane.end_us = ts + dur;
+            let tid = lane.tid;
+
             let name = format!("{} {}", entry.method, entry.url);
-            events.push(TraceEvent {
-                name,
-                ph: "X".to_string(),
-                pid,
-                tid: 0,
-                cat: Some("net".to_string()),
-                ts: Some(ts),
-                dur: Some(dur),
-                args: Some(serde_json::json!({
-                    "url": entry.url.clone(),
-                    "method": entry.method.clone(),
-                    "status": entry.status,
-                    "host": entry.host.clone(),
-                    "started_at": entry.started_at.clone(),
-                    "time_ms": entry.duration_ms,
-                    "order": order as i64,
-                    "page_id": entry.page_id.clone(),
-                    "page_title": entry.page_title.clone(),
-                })),
-            });
+            let mut request = TraceEvent::new(name, "X", pid, tid);
+            request.cat = Some("net".to_string());
+            request.ts = Some(ts);
+            request.dur = Some(dur);
+            request.args = Some(serde_json::json!({
+                "url": entry.url.clone(),
+                "method": entry.method.clone(),
+                "status": entry.status,
+                "host": entry.host.clone(),
+                "started_at": entry.started_at.clone(),
+                "time_ms": entry.duration_ms,
+                "order": order as i64,
+                "page_id": entry.page_id.clone(),
+                "page_title": entry.page_title.clone(),
+                "connection_id": entry.connection_id.clone(),
+                "request_id": entry.request_id.clone(),
+                "initiator_type": entry.initiator_type.clone(),
+                "initiator_url": entry.initiator_url.clone(),
+                "redirect_url": entry.redirect_url.clone(),
+            }));
+            events.push(request);
+
+            // Flow events bind to the slice open at their timestamp, so they go right
+            // after the request slice and before its phases, which start at the same time.
+            for (flow_id, kind) in flows_out.get(&global_idx).into_iter().flatten() {
+                let mut event = TraceEvent::new(*kind, "s", pid, tid);
+                event.cat = Some(kind.to_string());
+                event.ts = Some(ts);
+                event.id = Some(*flow_id);
+                events.push(event);
+            }
+            for (flow_id, kind) in flows_in.get(&global_idx).into_iter().flatten() {
+                let mut event = TraceEvent::new(*kind, "f", pid, tid);
+                event.cat = Some(kind.to_string());
+                event.ts = Some(ts);
+                event.id = Some(*flow_id);
+                event.bp = Some("e".to_string());
+                events.push(event);
+            }
+
+            for (name, start_ms, end_ms) in phases {
+                let start = (ts + ms_to_us(start_ms)).min(ts + dur);
+                let end = (ts + ms_to_us(end_ms)).min(ts + dur);
+                let mut phase = TraceEvent::new(name, "X", pid, tid);
+                phase.cat = Some("net.phase".to_string());
+                phase.ts = Some(start);
+                phase.dur = Some(end - start);
+                events.push(phase);
+            }
+            global_idx += 1;
         }
     }
 
@@ -255,7 +576,7 @@ fn render_trace(groups: &[GroupInfo], writer: &mut dyn Write) -> Result<()> {
 }
 
 pub fn run_waterfall(database: PathBuf, options: &WaterfallOptions) -> Result<()> {
-    let conn = Connection::open(&database)?;
+    let conn = open_database(&database)?;
     ensure_schema_upgrades(&conn)?;
 
     let from_started_at = match options.from.as_deref() {
@@ -328,6 +649,21 @@ pub fn run_waterfall(database: PathBuf, options: &WaterfallOptions) -> Result<()
             host: row.host.unwrap_or_else(|| "".to_string()),
             status: row.status,
             mime: row.response_mime_type,
+            connection_id: row.connection_id,
+            request_id: row.request_id,
+            parent_request_id: row.parent_request_id,
+            initiator_type: row.initiator_type,
+            initiator_url: row.initiator_url,
+            redirect_url: row.redirect_url,
+            timings: PhaseTimings {
+                blocked: row.blocked_ms.filter(|ms| *ms >= 0.0),
+                dns: row.dns_ms.filter(|ms| *ms >= 0.0),
+                connect: row.connect_ms.filter(|ms| *ms >= 0.0),
+                ssl: row.ssl_ms.filter(|ms| *ms >= 0.0),
+                send: row.send_ms.filter(|ms| *ms >= 0.0),
+                wait: row.wait_ms.filter(|ms| *ms >= 0.0),
+                receive: row.receive_ms.filter(|ms| *ms >= 0.0),
+            },
         };
         parsed.push((entry, dt));
     }
@@ -339,6 +675,23 @@ pub fn run_waterfall(database: PathBuf, options: &WaterfallOptions) -> Result<()
     parsed.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.url.cmp(&b.0.url)));
     let base_time = parsed.first().map(|(_, dt)| *dt).unwrap();
 
+    let page_marks: HashMap<(i64, String), PageMarks> = page_map
+        .iter()
+        .filter_map(|(key, page)| {
+            let started = DateTime::parse_from_rfc3339(page.started_at.as_deref()?).ok()?;
+            let start_ms = (started.with_timezone(&Utc) - base_time).num_milliseconds() as f64;
+            Some((
+                key.clone(),
+                PageMarks {
+                    title: page.title.clone().unwrap_or_else(|| page.id.clone()),
+                    start_ms,
+                    on_content_load_ms: page.on_content_load_ms.filter(|ms| *ms >= 0.0),
+                    on_load_ms: page.on_load_ms.filter(|ms| *ms >= 0.0),
+                },
+            ))
+        })
+        .collect();
+
     let mut entries: Vec<WaterfallEntry> = Vec::with_capacity(parsed.len());
     let mut max_end = 0.0;
     for (mut entry, dt) in parsed {
@@ -453,7 +806,7 @@ pub fn run_waterfall(database: PathBuf, options: &WaterfallOptions) -> Result<()
             render_text(&groups, max_end, width, writer.as_mut())?;
         }
         WaterfallFormat::Trace => {
-            render_trace(&groups, writer.as_mut())?;
+            render_trace(&groups, &page_marks, writer.as_mut())?;
         }
     }
 
@@ -38,6 +38,10 @@ pub struct Config {
     #[serde(default)]
     pub stats: Option<StatsConfig>,
     #[serde(default)]
+    pub openapi: Option<OpenApiConfig>,
+    #[serde(default)]
+    pub otel: Option<OtelConfig>,
+    #[serde(default)]
     pub plugins: Vec<PluginConfig>,
 }
 
@@ -63,6 +67,9 @@ pub struct ImportConfig {
     pub url_regex: Option<Vec<String>>,
     pub from: Option<String>,
     pub to: Option<String>,
+    pub redact: Option<bool>,
+    pub redact_policy: Option<PathBuf>,
+    pub redact_pii: Option<bool>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -76,6 +83,9 @@ pub struct CdpConfig {
     pub max_body_size: Option<String>,
     pub text_only: Option<bool>,
     pub duration: Option<u64>,
+    pub redact: Option<bool>,
+    pub redact_policy: Option<PathBuf>,
+    pub redact_pii: Option<bool>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -97,6 +107,22 @@ pub struct ReplayConfig {
 
     pub override_host: Option<Vec<String>>,
     pub override_header: Option<Vec<String>>,
+
+    pub diff_body: Option<bool>,
+    pub ignore_json_path: Option<Vec<String>>,
+    pub record: Option<PathBuf>,
+
+    pub extract: Option<Vec<String>>,
+    pub cookie_jar: Option<bool>,
+
+    pub load: Option<bool>,
+    pub duration_secs: Option<u64>,
+    pub ramp_up_secs: Option<u64>,
+    pub vus: Option<usize>,
+    pub rps: Option<f64>,
+
+    pub preserve_timing: Option<bool>,
+    pub speed: Option<f64>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -137,8 +163,14 @@ pub struct RedactConfig {
     pub cookie: Option<Vec<String>>,
     pub query_param: Option<Vec<String>>,
     pub body_regex: Option<Vec<String>>,
+    pub body_path: Option<Vec<String>>,
+    pub body_field: Option<Vec<String>>,
     pub match_mode: Option<NameMatchMode>,
     pub token: Option<String>,
+    pub pseudonymize: Option<bool>,
+    pub pseudonym_key_file: Option<PathBuf>,
+    pub policy: Option<PathBuf>,
+    pub verify: Option<bool>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -153,11 +185,18 @@ pub struct PiiConfig {
     pub no_phone: Option<bool>,
     pub no_ssn: Option<bool>,
     pub no_credit_card: Option<bool>,
+    pub no_jwt: Option<bool>,
+    pub no_api_keys: Option<bool>,
+    pub no_private_keys: Option<bool>,
+    pub entropy: Option<bool>,
+    pub min_entropy: Option<f64>,
     pub email_regex: Option<Vec<String>>,
     pub phone_regex: Option<Vec<String>>,
     pub ssn_regex: Option<Vec<String>>,
     pub credit_card_regex: Option<Vec<String>>,
     pub token: Option<String>,
+    pub pseudonymize: Option<bool>,
+    pub pseudonym_key_file: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -211,6 +250,17 @@ pub struct StatsConfig {
     pub cert_expiring_days: Option<u64>,
 }
 
+/// Shared by `openapi` and `types`, which infer path templates the same way.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct OpenApiConfig {
+    pub path_param_threshold: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct OtelConfig {
+    pub path_param_threshold: Option<usize>,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct ResolvedConfig {
     pub import: ResolvedImportConfig,
@@ -226,6 +276,8 @@ pub struct ResolvedConfig {
     pub repl: ResolvedReplConfig,
     pub fts_rebuild: ResolvedFtsRebuildConfig,
     pub stats: ResolvedStatsConfig,
+    pub openapi: ResolvedOpenApiConfig,
+    pub otel: ResolvedOtelConfig,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -250,6 +302,9 @@ pub struct ResolvedImportConfig {
     pub url_regex: Vec<String>,
     pub from: Option<String>,
     pub to: Option<String>,
+    pub redact: bool,
+    pub redact_policy: Option<PathBuf>,
+    pub redact_pii: bool,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -263,6 +318,9 @@ pub struct ResolvedCdpConfig {
     pub max_body_size: String,
     pub text_only: bool,
     pub duration: Option<u64>,
+    pub redact: bool,
+    pub redact_policy: Option<PathBuf>,
+    pub redact_pii: bool,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -284,6 +342,22 @@ pub struct ResolvedReplayConfig {
 
     pub override_host: Vec<String>,
     pub override_header: Vec<String>,
+
+    pub diff_body: bool,
+    pub ignore_json_path: Vec<String>,
+    pub record: Option<PathBuf>,
+
+    pub extract: Vec<String>,
+    pub cookie_jar: bool,
+
+    pub load: bool,
+    pub duration_secs: u64,
+    pub ramp_up_secs: u64,
+    pub vus: Option<usize>,
+    pub rps: Option<f64>,
+
+    pub preserve_timing: bool,
+    pub speed: f64,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -324,8 +398,14 @@ pub struct ResolvedRedactConfig {
     pub cookie: Vec<String>,
     pub query_param: Vec<String>,
     pub body_regex: Vec<String>,
+    pub body_path: Vec<String>,
+    pub body_field: Vec<String>,
     pub match_mode: NameMatchMode,
     pub token: String,
+    pub pseudonymize: bool,
+    pub pseudonym_key_file: Option<PathBuf>,
+    pub policy: Option<PathBuf>,
+    pub verify: bool,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -340,11 +420,18 @@ pub struct ResolvedPiiConfig {
     pub no_phone: bool,
     pub no_ssn: bool,
     pub no_credit_card: bool,
+    pub no_jwt: bool,
+    pub no_api_keys: bool,
+    pub no_private_keys: bool,
+    pub entropy: bool,
+    pub min_entropy: Option<f64>,
     pub email_regex: Vec<String>,
     pub phone_regex: Vec<String>,
     pub ssn_regex: Vec<String>,
     pub credit_card_regex: Vec<String>,
     pub token: String,
+    pub pseudonymize: bool,
+    pub pseudonym_key_file: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -398,6 +485,16 @@ pub struct ResolvedStatsConfig {
     pub cert_expiring_days: Option<u64>,
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolvedOpenApiConfig {
+    pub path_param_threshold: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolvedOtelConfig {
+    pub path_param_threshold: usize,
+}
+
 impl Default for ResolvedConfig {
     fn default() -> Self {
         Self {
@@ -414,6 +511,8 @@ impl Default for ResolvedConfig {
             repl: ResolvedReplConfig::default(),
             fts_rebuild: ResolvedFtsRebuildConfig::default(),
             stats: ResolvedStatsConfig::default(),
+            openapi: ResolvedOpenApiConfig::default(),
+            otel: ResolvedOtelConfig::default(),
         }
     }
 }
@@ -441,6 +540,9 @@ impl Default for ResolvedImportConfig {
             url_regex: Vec::new(),
             from: None,
             to: None,
+            redact: false,
+            redact_policy: None,
+            redact_pii: false,
         }
     }
 }
@@ -457,6 +559,9 @@ impl Default for ResolvedCdpConfig {
             max_body_size: "100KB".to_string(),
             text_only: false,
             duration: None,
+            redact: false,
+            redact_policy: None,
+            redact_pii: false,
         }
     }
 }
@@ -479,6 +584,18 @@ impl Default for ResolvedReplayConfig {
             status: Vec::new(),
             override_host: Vec::new(),
             override_header: Vec::new(),
+            diff_body: false,
+            ignore_json_path: Vec::new(),
+            record: None,
+            extract: Vec::new(),
+            cookie_jar: false,
+            load: false,
+            duration_secs: 60,
+            ramp_up_secs: 0,
+            vus: None,
+            rps: None,
+            preserve_timing: false,
+            speed: 1.0,
         }
     }
 }
@@ -523,8 +640,14 @@ impl Default for ResolvedRedactConfig {
             cookie: Vec::new(),
             query_param: Vec::new(),
             body_regex: Vec::new(),
+            body_path: Vec::new(),
+            body_field: Vec::new(),
             match_mode: NameMatchMode::Wildcard,
             token: "REDACTED".to_string(),
+            pseudonymize: false,
+            pseudonym_key_file: None,
+            policy: None,
+            verify: false,
         }
     }
 }
@@ -542,11 +665,18 @@ impl Default for ResolvedPiiConfig {
             no_phone: false,
             no_ssn: false,
             no_credit_card: false,
+            no_jwt: false,
+            no_api_keys: false,
+            no_private_keys: false,
+            entropy: false,
+            min_entropy: None,
             email_regex: Vec::new(),
             phone_regex: Vec::new(),
             ssn_regex: Vec::new(),
             credit_card_regex: Vec::new(),
             token: "REDACTED".to_string(),
+            pseudonymize: false,
+            pseudonym_key_file: None,
         }
     }
 }
@@ -623,6 +753,22 @@ impl Default for ResolvedStatsConfig {
     }
 }
 
+impl Default for ResolvedOpenApiConfig {
+    fn default() -> Self {
+        Self {
+            path_param_threshold: 20,
+        }
+    }
+}
+
+impl Default for ResolvedOtelConfig {
+    fn default() -> Self {
+        Self {
+            path_param_threshold: 20,
+        }
+    }
+}
+
 impl ResolvedConfig {
     pub fn from_config(config: &Config) -> Self {
         let mut resolved = Self::default();
@@ -665,6 +811,12 @@ impl ResolvedConfig {
         if let Some(cfg) = &config.stats {
             resolved.stats.apply(cfg);
         }
+        if let Some(cfg) = &config.openapi {
+            resolved.openapi.apply(cfg);
+        }
+        if let Some(cfg) = &config.otel {
+            resolved.otel.apply(cfg);
+        }
         resolved
     }
 }
@@ -731,6 +883,15 @@ impl ResolvedImportConfig {
         if let Some(value) = cfg.to.clone() {
             self.to = Some(value);
         }
+        if let Some(value) = cfg.redact {
+            self.redact = value;
+        }
+        if let Some(value) = cfg.redact_policy.clone() {
+            self.redact_policy = Some(value);
+        }
+        if let Some(value) = cfg.redact_pii {
+            self.redact_pii = value;
+        }
     }
 }
 
@@ -763,6 +924,15 @@ impl ResolvedCdpConfig {
         if let Some(value) = cfg.duration {
             self.duration = Some(value);
         }
+        if let Some(value) = cfg.redact {
+            self.redact = value;
+        }
+        if let Some(value) = cfg.redact_policy.clone() {
+            self.redact_policy = Some(value);
+        }
+        if let Some(value) = cfg.redact_pii {
+            self.redact_pii = value;
+        }
     }
 }
 
@@ -813,6 +983,42 @@ impl ResolvedReplayConfig {
         if let Some(value) = cfg.override_header.clone() {
             self.override_header = value;
         }
+        if let Some(value) = cfg.diff_body {
+            self.diff_body = value;
+        }
+        if let Some(value) = cfg.ignore_json_path.clone() {
+            self.ignore_json_path = value;
+        }
+        if let Some(value) = cfg.record.clone() {
+            self.record = Some(value);
+        }
+        if let Some(value) = cfg.extract.clone() {
+            self.extract = value;
+        }
+        if let Some(value) = cfg.cookie_jar {
+            self.cookie_jar = value;
+        }
+        if let Some(value) = cfg.load {
+            self.load = value;
+        }
+        if let Some(value) = cfg.duration_secs {
+            self.duration_secs = value;
+        }
+        if let Some(value) = cfg.ramp_up_secs {
+            self.ramp_up_secs = value;
+        }
+        if let Some(value) = cfg.vus {
+            self.vus = Some(value);
+        }
+        if let Some(value) = cfg.rps {
+            self.rps = Some(value);
+        }
+        if let Some(value) = cfg.preserve_timing {
+            self.preserve_timing = value;
+        }
+        if let Some(value) = cfg.speed {
+            self.speed = value;
+        }
     }
 }
 
@@ -913,12 +1119,30 @@ impl ResolvedRedactConfig {
         if let Some(value) = cfg.body_regex.clone() {
             self.body_regex = value;
         }
+        if let Some(value) = cfg.body_path.clone() {
+            self.body_path = value;
+        }
+        if let Some(value) = cfg.body_field.clone() {
+            self.body_field = value;
+        }
         if let Some(value) = cfg.match_mode {
             self.match_mode = value;
         }
         if let Some(value) = cfg.token.clone() {
             self.token = value;
         }
+        if let Some(value) = cfg.pseudonymize {
+            self.pseudonymize = value;
+        }
+        if let Some(value) = cfg.pseudonym_key_file.clone() {
+            self.pseudonym_key_file = Some(value);
+        }
+        if let Some(value) = cfg.policy.clone() {
+            self.policy = Some(value);
+        }
+        if let Some(value) = cfg.verify {
+            self.verify = value;
+        }
     }
 }
 
@@ -954,6 +1178,21 @@ impl ResolvedPiiConfig {
         if let Some(value) = cfg.no_credit_card {
             self.no_credit_card = value;
         }
+        if let Some(value) = cfg.no_jwt {
+            self.no_jwt = value;
+        }
+        if let Some(value) = cfg.no_api_keys {
+            self.no_api_keys = value;
+        }
+        if let Some(value) = cfg.no_private_keys {
+            self.no_private_keys = value;
+        }
+        if let Some(value) = cfg.entropy {
+            self.entropy = value;
+        }
+        if let Some(value) = cfg.min_entropy {
+            self.min_entropy = Some(value);
+        }
         if let Some(value) = cfg.email_regex.clone() {
             self.email_regex = value;
         }
@@ -969,6 +1208,12 @@ impl ResolvedPiiConfig {
         if let Some(value) = cfg.token.clone() {
             self.token = value;
         }
+        if let Some(value) = cfg.pseudonymize {
+            self.pseudonymize = value;
+        }
+        if let Some(value) = cfg.pseudonym_key_file.clone() {
+            self.pseudonym_key_file = Some(value);
+        }
     }
 }
 
@@ -1076,6 +1321,22 @@ impl ResolvedStatsConfig {
     }
 }
 
+impl ResolvedOpenApiConfig {
+    fn apply(&mut self, cfg: &OpenApiConfig) {
+        if let Some(value) = cfg.path_param_threshold {
+            self.path_param_threshold = value;
+        }
+    }
+}
+
+impl ResolvedOtelConfig {
+    fn apply(&mut self, cfg: &OtelConfig) {
+        if let Some(value) = cfg.path_param_threshold {
+            self.path_param_threshold = value;
+        }
+    }
+}
+
 pub fn load_config() -> Result<Config> {
     let mut config = Config::default();
     let paths = config_search_paths()?;
@@ -1113,6 +1374,8 @@ fn merge_config(base: &mut Config, other: Config) {
         FtsRebuildConfig::merge,
     );
     merge_section(&mut base.stats, other.stats, StatsConfig::merge);
+    merge_section(&mut base.openapi, other.openapi, OpenApiConfig::merge);
+    merge_section(&mut base.otel, other.otel, OtelConfig::merge);
     merge_plugins(&mut base.plugins, other.plugins);
 }
 
@@ -1150,6 +1413,9 @@ impl ImportConfig {
         merge_opt(&mut self.url_regex, other.url_regex);
         merge_opt(&mut self.from, other.from);
         merge_opt(&mut self.to, other.to);
+        merge_opt(&mut self.redact, other.redact);
+        merge_opt(&mut self.redact_policy, other.redact_policy);
+        merge_opt(&mut self.redact_pii, other.redact_pii);
     }
 }
 
@@ -1164,6 +1430,9 @@ impl CdpConfig {
         merge_opt(&mut self.max_body_size, other.max_body_size);
         merge_opt(&mut self.text_only, other.text_only);
         merge_opt(&mut self.duration, other.duration);
+        merge_opt(&mut self.redact, other.redact);
+        merge_opt(&mut self.redact_policy, other.redact_policy);
+        merge_opt(&mut self.redact_pii, other.redact_pii);
     }
 }
 
@@ -1204,8 +1473,14 @@ impl RedactConfig {
         merge_opt(&mut self.cookie, other.cookie);
         merge_opt(&mut self.query_param, other.query_param);
         merge_opt(&mut self.body_regex, other.body_regex);
+        merge_opt(&mut self.body_path, other.body_path);
+        merge_opt(&mut self.body_field, other.body_field);
         merge_opt(&mut self.match_mode, other.match_mode);
         merge_opt(&mut self.token, other.token);
+        merge_opt(&mut self.pseudonymize, other.pseudonymize);
+        merge_opt(&mut self.pseudonym_key_file, other.pseudonym_key_file);
+        merge_opt(&mut self.policy, other.policy);
+        merge_opt(&mut self.verify, other.verify);
     }
 }
 
@@ -1221,11 +1496,18 @@ impl PiiConfig {
         merge_opt(&mut self.no_phone, other.no_phone);
         merge_opt(&mut self.no_ssn, other.no_ssn);
         merge_opt(&mut self.no_credit_card, other.no_credit_card);
+        merge_opt(&mut self.no_jwt, other.no_jwt);
+        merge_opt(&mut self.no_api_keys, other.no_api_keys);
+        merge_opt(&mut self.no_private_keys, other.no_private_keys);
+        merge_opt(&mut self.entropy, other.entropy);
+        merge_opt(&mut self.min_entropy, other.min_entropy);
         merge_opt(&mut self.email_regex, other.email_regex);
         merge_opt(&mut self.phone_regex, other.phone_regex);
         merge_opt(&mut self.ssn_regex, other.ssn_regex);
         merge_opt(&mut self.credit_card_regex, other.credit_card_regex);
         merge_opt(&mut self.token, other.token);
+        merge_opt(&mut self.pseudonymize, other.pseudonymize);
+        merge_opt(&mut self.pseudonym_key_file, other.pseudonym_key_file);
     }
 }
 
@@ -1287,6 +1569,18 @@ impl StatsConfig {
     }
 }
 
+impl OpenApiConfig {
+    fn merge(&mut self, other: OpenApiConfig) {
+        merge_opt(&mut self.path_param_threshold, other.path_param_threshold);
+    }
+}
+
+impl OtelConfig {
+    fn merge(&mut self, other: OtelConfig) {
+        merge_opt(&mut self.path_param_threshold, other.path_param_threshold);
+    }
+}
+
 fn merge_opt<T>(base: &mut Option<T>, other: Option<T>) {
     if other.is_some() {
         *base = other;
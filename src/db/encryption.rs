@@ -0,0 +1,378 @@
+//! Keys for encrypted databases and extracted body files.
+//!
+//! With the `encryption` feature, databases are SQLCipher databases keyed with a
+//! passphrase (`PRAGMA key`), so they also open in `sqlcipher` and other
+//! SQLCipher-compatible tools. Extracted body files written while a key is set are
+//! sealed with XChaCha20-Poly1305 under a key derived from the same passphrase.
+//!
+//! The sealing key is the passphrase stretched with Argon2id (default parameters:
+//! 19 MiB, 2 passes) over a random 16-byte salt stored in each file header, then
+//! passed through BLAKE3's KDF. A process picks one salt for every file it writes and
+//! caches the derived key per salt, so Argon2 runs once per run rather than per file,
+//! and no precomputed dictionary applies to more than one run's files. Every file also
+//! gets a random 24-byte nonce, and the header (including the salt) is authenticated
+//! as associated data.
+//!
+//! Files are named by a keyed hash of the body hash, so a guessed body can't be
+//! confirmed from a file name without the passphrase. The same body has to map to the
+//! same name in every run, so the name key is stretched over a fixed,
+//! application-specific salt; the database itself is protected by SQLCipher's own
+//! per-database salt.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::error::{HarliteError, Result};
+
+/// Environment variable consulted when neither `--db-key-file` nor `--db-key-prompt` is given.
+pub const DB_KEY_ENV: &str = "HARLITE_DB_KEY";
+
+/// Header of an encrypted extracted body file: a 7-byte tag, a format version and
+/// the Argon2 salt of the sealing key.
+const BODY_TAG: &[u8; 7] = b"HLENC\0\0";
+const BODY_VERSION: u8 = 3;
+const BODY_SALT_LEN: usize = 16;
+const BODY_HEADER_LEN: usize = BODY_TAG.len() + 1 + BODY_SALT_LEN;
+const BODY_NONCE_LEN: usize = 24;
+const BODY_NAME_SALT: &[u8] = b"harlite extracted body key v2";
+const BODY_ENC_CONTEXT: &str = "harlite extracted body encryption v3";
+const BODY_NAME_CONTEXT: &str = "harlite extracted body file names v2";
+
+static DATABASE_KEY: OnceLock<Option<DatabaseKey>> = OnceLock::new();
+
+/// A database passphrase plus the keys derived from it for extracted body files.
+pub struct DatabaseKey {
+    passphrase: String,
+    /// Salt of the files this process seals.
+    seal_salt: [u8; BODY_SALT_LEN],
+    /// Sealing keys by salt, so Argon2 runs once per salt.
+    body_enc: Mutex<HashMap<[u8; BODY_SALT_LEN], [u8; 32]>>,
+    body_name: [u8; 32],
+}
+
+impl DatabaseKey {
+    pub fn new(passphrase: String) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(HarliteError::InvalidArgs(
+                "Database key must not be empty".to_string(),
+            ));
+        }
+        let mut seal_salt = [0u8; BODY_SALT_LEN];
+        OsRng.fill_bytes(&mut seal_salt);
+        Ok(Self {
+            body_name: blake3::derive_key(
+                BODY_NAME_CONTEXT,
+                &stretch(&passphrase, BODY_NAME_SALT)?,
+            ),
+            seal_salt,
+            body_enc: Mutex::new(HashMap::new()),
+            passphrase,
+        })
+    }
+
+    fn cipher(&self, salt: &[u8; BODY_SALT_LEN]) -> Result<XChaCha20Poly1305> {
+        let mut keys = self
+            .body_enc
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let key = match keys.get(salt) {
+            Some(key) => *key,
+            None => {
+                let key = blake3::derive_key(BODY_ENC_CONTEXT, &stretch(&self.passphrase, salt)?);
+                keys.insert(*salt, key);
+                key
+            }
+        };
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+
+    fn seal_body(&self, content: &[u8]) -> Result<Vec<u8>> {
+        let header = body_header(&self.seal_salt);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = self
+            .cipher(&self.seal_salt)?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: content,
+                    aad: &header,
+                },
+            )
+            .map_err(|_| HarliteError::InvalidArgs("Failed to encrypt body file".to_string()))?;
+        let mut out = Vec::with_capacity(header.len() + nonce.len() + sealed.len());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    /// Decrypt a sealed body; `None` when the key is wrong or the file was altered.
+    fn open_body(&self, sealed: &[u8]) -> Result<Option<Vec<u8>>> {
+        if sealed.len() < BODY_HEADER_LEN + BODY_NONCE_LEN
+            || sealed[..BODY_TAG.len()] != BODY_TAG[..]
+            || sealed[BODY_TAG.len()] != BODY_VERSION
+        {
+            return Ok(None);
+        }
+        let (header, rest) = sealed.split_at(BODY_HEADER_LEN);
+        let salt: [u8; BODY_SALT_LEN] = header[BODY_TAG.len() + 1..]
+            .try_into()
+            .expect("header holds a full salt");
+        let (nonce, ciphertext) = rest.split_at(BODY_NONCE_LEN);
+        Ok(self
+            .cipher(&salt)?
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .ok())
+    }
+
+    fn body_file_name(&self, hash: &str) -> String {
+        blake3::keyed_hash(&self.body_name, hash.as_bytes())
+            .to_hex()
+            .to_string()
+    }
+
+    /// Load the key from `key_file`, an interactive prompt or `HARLITE_DB_KEY`, in
+    /// that order. Returns `None` when no source is given.
+    pub fn from_key_source(key_file: Option<&Path>, prompt: bool) -> Result<Option<Self>> {
+        let passphrase = if let Some(path) = key_file {
+            let contents = fs::read_to_string(path).map_err(|err| {
+                HarliteError::InvalidArgs(format!(
+                    "Failed to read database key {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            contents.trim_end_matches(['\r', '\n']).to_string()
+        } else if prompt {
+            prompt_passphrase()?
+        } else {
+            match std::env::var(DB_KEY_ENV) {
+                Ok(value) if !value.is_empty() => value,
+                _ => return Ok(None),
+            }
+        };
+        Self::new(passphrase).map(Some)
+    }
+}
+
+/// Set the key used by every database and extracted body file opened afterwards.
+///
+/// Only the first call has an effect.
+pub fn set_database_key(key: Option<DatabaseKey>) -> Result<()> {
+    #[cfg(not(feature = "encryption"))]
+    if key.is_some() {
+        return Err(HarliteError::InvalidArgs(
+            "Database encryption requires the 'encryption' feature".to_string(),
+        ));
+    }
+    let _ = DATABASE_KEY.set(key);
+    Ok(())
+}
+
+fn database_key() -> Option<&'static DatabaseKey> {
+    DATABASE_KEY.get().and_then(Option::as_ref)
+}
+
+/// Open (or create) a database, applying the configured key.
+pub fn open_database<P: AsRef<Path>>(path: P) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    apply_key(&conn)?;
+    Ok(conn)
+}
+
+/// [`open_database`] with explicit open flags.
+pub fn open_database_with_flags<P: AsRef<Path>>(path: P, flags: OpenFlags) -> Result<Connection> {
+    let conn = Connection::open_with_flags(path, flags)?;
+    apply_key(&conn)?;
+    Ok(conn)
+}
+
+fn apply_key(conn: &Connection) -> Result<()> {
+    let Some(key) = database_key() else {
+        return Ok(());
+    };
+    conn.pragma_update(None, "key", &key.passphrase)?;
+    // SQLCipher only checks the key when the first page is read.
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|_| {
+            HarliteError::InvalidArgs(
+                "Failed to open database: wrong key, or the database is not encrypted".to_string(),
+            )
+        })
+}
+
+/// Contents to write for an extracted body file: encrypted when a key is set.
+pub fn seal_external_body(content: &[u8]) -> Result<Cow<'_, [u8]>> {
+    match database_key() {
+        Some(key) => key.seal_body(content).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(content)),
+    }
+}
+
+/// File name for the extracted body with blob hash `hash`: the hash itself, or a
+/// keyed hash of it when a key is set.
+pub fn external_body_name(hash: &str) -> Cow<'_, str> {
+    match database_key() {
+        Some(key) => Cow::Owned(key.body_file_name(hash)),
+        None => Cow::Borrowed(hash),
+    }
+}
+
+/// Read an extracted body file, decrypting it if it was written with a key.
+pub fn read_external_blob(path: &Path) -> Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if !bytes.starts_with(BODY_TAG) {
+        return Ok(bytes);
+    }
+    if bytes.get(BODY_TAG.len()) != Some(&BODY_VERSION) {
+        return Err(HarliteError::InvalidArgs(format!(
+            "Encrypted body file {} uses an unsupported format; re-import it",
+            path.display()
+        )));
+    }
+    let Some(key) = database_key() else {
+        return Err(HarliteError::InvalidArgs(format!(
+            "Extracted body file {} is encrypted; provide the database key",
+            path.display()
+        )));
+    };
+    key.open_body(&bytes)?.ok_or_else(|| {
+        HarliteError::InvalidArgs(format!(
+            "Failed to decrypt body file {}: wrong key or corrupted file",
+            path.display()
+        ))
+    })
+}
+
+fn body_header(salt: &[u8; BODY_SALT_LEN]) -> [u8; BODY_HEADER_LEN] {
+    let mut header = [0u8; BODY_HEADER_LEN];
+    header[..BODY_TAG.len()].copy_from_slice(BODY_TAG);
+    header[BODY_TAG.len()] = BODY_VERSION;
+    header[BODY_TAG.len() + 1..].copy_from_slice(salt);
+    header
+}
+
+/// Stretch `passphrase` into 32 bytes with Argon2id.
+fn stretch(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut stretched = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut stretched)
+        .map_err(|err| {
+            HarliteError::InvalidArgs(format!("Failed to derive body file key: {err}"))
+        })?;
+    Ok(stretched)
+}
+
+fn prompt_passphrase() -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        return Err(HarliteError::InvalidArgs(
+            "--db-key-prompt requires an interactive terminal".to_string(),
+        ));
+    }
+    eprint!("Database key: ");
+    std::io::stderr().flush()?;
+    let echo_disabled = set_terminal_echo(false);
+    let mut line = String::new();
+    let read = std::io::stdin().lock().read_line(&mut line);
+    if echo_disabled {
+        set_terminal_echo(true);
+        eprintln!();
+    }
+    read?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(unix)]
+fn set_terminal_echo(enabled: bool) -> bool {
+    std::process::Command::new("stty")
+        .arg(if enabled { "echo" } else { "-echo" })
+        .stdin(std::process::Stdio::inherit())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn set_terminal_echo(_enabled: bool) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_bodies_round_trip_and_detect_tampering() {
+        let key = DatabaseKey::new("correct horse".to_string()).expect("key");
+        let body = b"{\"token\":\"secret\"}".repeat(10);
+
+        let sealed = key.seal_body(&body).expect("seal");
+        assert!(sealed.starts_with(&body_header(&key.seal_salt)));
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(
+            key.open_body(&sealed).expect("open").as_deref(),
+            Some(body.as_slice())
+        );
+        // Random nonces: sealing twice gives different files.
+        assert_ne!(key.seal_body(&body).expect("seal"), sealed);
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(key.open_body(&tampered).expect("open").is_none());
+
+        // The salt is authenticated along with the rest of the header.
+        let mut resalted = sealed.clone();
+        resalted[BODY_TAG.len() + 1] ^= 1;
+        assert!(key.open_body(&resalted).expect("open").is_none());
+
+        let other = DatabaseKey::new("battery staple".to_string()).expect("key");
+        assert!(other.open_body(&sealed).expect("open").is_none());
+    }
+
+    #[test]
+    fn each_key_seals_under_its_own_salt_and_opens_any() {
+        let first = DatabaseKey::new("correct horse".to_string()).expect("key");
+        let second = DatabaseKey::new("correct horse".to_string()).expect("key");
+        assert_ne!(first.seal_salt, second.seal_salt);
+
+        let sealed = first.seal_body(b"body").expect("seal");
+        assert_eq!(
+            second.open_body(&sealed).expect("open").as_deref(),
+            Some(&b"body"[..])
+        );
+        // One Argon2 run per salt: the second key now knows both.
+        second.seal_body(b"body").expect("seal");
+        second.open_body(&sealed).expect("open");
+        assert_eq!(second.body_enc.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn body_file_names_do_not_reveal_the_body_hash() {
+        let key = DatabaseKey::new("correct horse".to_string()).expect("key");
+        let other = DatabaseKey::new("battery staple".to_string()).expect("key");
+        let hash = blake3::hash(b"guessable body").to_hex().to_string();
+
+        let name = key.body_file_name(&hash);
+        assert_eq!(name.len(), hash.len());
+        assert_ne!(name, hash);
+        assert_eq!(name, key.body_file_name(&hash));
+        assert_ne!(name, other.body_file_name(&hash));
+    }
+}
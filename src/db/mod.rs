@@ -1,7 +1,9 @@
+mod encryption;
 mod reader;
 mod schema;
 mod writer;
 
+pub use encryption::*;
 pub use reader::*;
 pub use schema::*;
 pub use writer::*;
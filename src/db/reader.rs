@@ -4,8 +4,9 @@ use std::collections::HashSet;
 
 use crate::error::Result;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct EntryRow {
+    pub id: i64,
     pub import_id: i64,
     pub page_id: Option<String>,
     pub started_at: Option<String>,
@@ -69,6 +70,10 @@ pub struct EntryRow {
     pub graphql_operation_name: Option<String>,
     #[allow(dead_code)]
     pub graphql_top_level_fields: Option<String>,
+    #[allow(dead_code)]
+    pub graphql_query_hash: Option<String>,
+    #[allow(dead_code)]
+    pub graphql_variables_hash: Option<String>,
 }
 
 const ENTRY_COLUMNS: &[&str] = &[
@@ -127,6 +132,9 @@ const ENTRY_COLUMNS: &[&str] = &[
     "graphql_operation_type",
     "graphql_operation_name",
     "graphql_top_level_fields",
+    "graphql_query_hash",
+    "graphql_variables_hash",
+    "id",
 ];
 
 #[derive(Debug, Default, Clone)]
@@ -290,6 +298,7 @@ pub fn load_entries(conn: &Connection, query: &EntryQuery) -> Result<Vec<EntryRo
     let mut stmt = conn.prepare(&sql)?;
     let rows = stmt.query_map(params_from_iter(params.iter()), |row| {
         Ok(EntryRow {
+            id: row.get(57)?,
             import_id: row.get(0)?,
             page_id: row.get(1)?,
             started_at: row.get(2)?,
@@ -345,6 +354,8 @@ pub fn load_entries(conn: &Connection, query: &EntryQuery) -> Result<Vec<EntryRo
             graphql_operation_type: row.get(52)?,
             graphql_operation_name: row.get(53)?,
             graphql_top_level_fields: row.get(54)?,
+            graphql_query_hash: row.get(55)?,
+            graphql_variables_hash: row.get(56)?,
         })
     })?;
 
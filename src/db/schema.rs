@@ -106,7 +106,12 @@ CREATE TABLE IF NOT EXISTS entries (
     -- GraphQL metadata
     graphql_operation_type TEXT,
     graphql_operation_name TEXT,
-    graphql_top_level_fields TEXT
+    graphql_top_level_fields TEXT,
+    graphql_query_hash TEXT,
+    graphql_variables_hash TEXT REFERENCES blobs(hash),
+
+    -- Replay provenance (set by `harlite replay --record`)
+    replay_of_entry_id INTEGER
 );
 
 -- Indexes
@@ -123,6 +128,8 @@ CREATE INDEX IF NOT EXISTS idx_entries_parent_request_id ON entries(parent_reque
 CREATE INDEX IF NOT EXISTS idx_entries_redirect_url ON entries(redirect_url);
 CREATE INDEX IF NOT EXISTS idx_entries_graphql_type ON entries(graphql_operation_type);
 CREATE INDEX IF NOT EXISTS idx_entries_graphql_name ON entries(graphql_operation_name);
+CREATE INDEX IF NOT EXISTS idx_entries_graphql_query_hash ON entries(graphql_query_hash);
+CREATE INDEX IF NOT EXISTS idx_entries_replay_of ON entries(replay_of_entry_id);
 
 -- GraphQL top-level fields
 CREATE TABLE IF NOT EXISTS graphql_fields (
@@ -132,6 +139,41 @@ CREATE TABLE IF NOT EXISTS graphql_fields (
 CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_fields_entry_field ON graphql_fields(entry_id, field);
 CREATE INDEX IF NOT EXISTS idx_graphql_fields_field ON graphql_fields(field);
 CREATE INDEX IF NOT EXISTS idx_graphql_fields_entry ON graphql_fields(entry_id);
+
+-- GraphQL nested field paths (dotted, e.g. viewer.repositories.name)
+CREATE TABLE IF NOT EXISTS graphql_field_paths (
+    entry_id INTEGER REFERENCES entries(id),
+    path TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_field_paths_entry_path ON graphql_field_paths(entry_id, path);
+CREATE INDEX IF NOT EXISTS idx_graphql_field_paths_path ON graphql_field_paths(path);
+
+-- Requests that `harlite serve` could not match (recorded with --record-misses)
+CREATE TABLE IF NOT EXISTS serve_misses (
+    id INTEGER PRIMARY KEY,
+    recorded_at TEXT NOT NULL,
+    method TEXT,
+    url TEXT,
+    host TEXT,
+    path TEXT,
+    query_string TEXT,
+    http_version TEXT,
+    request_headers TEXT,
+    request_body_hash TEXT REFERENCES blobs(hash),
+    request_body_size INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_serve_misses_url ON serve_misses(url);
+
+-- Redaction runs applied to this database (written by `harlite redact`)
+CREATE TABLE IF NOT EXISTS redactions (
+    id INTEGER PRIMARY KEY,
+    applied_at TEXT NOT NULL,
+    command TEXT NOT NULL,
+    policy_name TEXT,
+    policy_version INTEGER,
+    policy_hash TEXT,
+    values_redacted INTEGER NOT NULL
+);
 "#;
 
 const SCHEMA_FTS: &str = r#"
@@ -244,7 +286,12 @@ CREATE TABLE IF NOT EXISTS entries (
     -- GraphQL metadata
     graphql_operation_type TEXT,
     graphql_operation_name TEXT,
-    graphql_top_level_fields TEXT
+    graphql_top_level_fields TEXT,
+    graphql_query_hash TEXT,
+    graphql_variables_hash TEXT REFERENCES blobs(hash),
+
+    -- Replay provenance (set by `harlite replay --record`)
+    replay_of_entry_id INTEGER
 );
 
 -- Indexes
@@ -261,6 +308,8 @@ CREATE INDEX IF NOT EXISTS idx_entries_parent_request_id ON entries(parent_reque
 CREATE INDEX IF NOT EXISTS idx_entries_redirect_url ON entries(redirect_url);
 CREATE INDEX IF NOT EXISTS idx_entries_graphql_type ON entries(graphql_operation_type);
 CREATE INDEX IF NOT EXISTS idx_entries_graphql_name ON entries(graphql_operation_name);
+CREATE INDEX IF NOT EXISTS idx_entries_graphql_query_hash ON entries(graphql_query_hash);
+CREATE INDEX IF NOT EXISTS idx_entries_replay_of ON entries(replay_of_entry_id);
 
 -- GraphQL top-level fields
 CREATE TABLE IF NOT EXISTS graphql_fields (
@@ -271,6 +320,41 @@ CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_fields_entry_field ON graphql_fiel
 CREATE INDEX IF NOT EXISTS idx_graphql_fields_field ON graphql_fields(field);
 CREATE INDEX IF NOT EXISTS idx_graphql_fields_entry ON graphql_fields(entry_id);
 
+-- GraphQL nested field paths (dotted, e.g. viewer.repositories.name)
+CREATE TABLE IF NOT EXISTS graphql_field_paths (
+    entry_id INTEGER REFERENCES entries(id),
+    path TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_field_paths_entry_path ON graphql_field_paths(entry_id, path);
+CREATE INDEX IF NOT EXISTS idx_graphql_field_paths_path ON graphql_field_paths(path);
+
+-- Requests that `harlite serve` could not match (recorded with --record-misses)
+CREATE TABLE IF NOT EXISTS serve_misses (
+    id INTEGER PRIMARY KEY,
+    recorded_at TEXT NOT NULL,
+    method TEXT,
+    url TEXT,
+    host TEXT,
+    path TEXT,
+    query_string TEXT,
+    http_version TEXT,
+    request_headers TEXT,
+    request_body_hash TEXT REFERENCES blobs(hash),
+    request_body_size INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_serve_misses_url ON serve_misses(url);
+
+-- Redaction runs applied to this database (written by `harlite redact`)
+CREATE TABLE IF NOT EXISTS redactions (
+    id INTEGER PRIMARY KEY,
+    applied_at TEXT NOT NULL,
+    command TEXT NOT NULL,
+    policy_name TEXT,
+    policy_version INTEGER,
+    policy_hash TEXT,
+    values_redacted INTEGER NOT NULL
+);
+
 -- Full-text search over response bodies (text-only, deduped by blob hash)
 CREATE VIRTUAL TABLE IF NOT EXISTS response_body_fts
 USING fts5(hash UNINDEXED, body, tokenize = 'unicode61');
@@ -399,6 +483,18 @@ pub fn ensure_schema_upgrades(conn: &Connection) -> Result<()> {
             [],
         )?;
     }
+    if !table_has_column(conn, "entries", "graphql_query_hash")? {
+        conn.execute("ALTER TABLE entries ADD COLUMN graphql_query_hash TEXT", [])?;
+    }
+    if !table_has_column(conn, "entries", "graphql_variables_hash")? {
+        conn.execute(
+            "ALTER TABLE entries ADD COLUMN graphql_variables_hash TEXT REFERENCES blobs(hash)",
+            [],
+        )?;
+    }
+    if !table_has_column(conn, "entries", "replay_of_entry_id")? {
+        conn.execute("ALTER TABLE entries ADD COLUMN replay_of_entry_id INTEGER", [])?;
+    }
     if !table_has_column(conn, "entries", "entry_hash")? {
         conn.execute("ALTER TABLE entries ADD COLUMN entry_hash TEXT", [])?;
     }
@@ -496,12 +592,32 @@ pub fn ensure_schema_upgrades(conn: &Connection) -> Result<()> {
         "CREATE INDEX IF NOT EXISTS idx_entries_graphql_name ON entries(graphql_operation_name)",
         [],
     )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entries_graphql_query_hash ON entries(graphql_query_hash)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entries_replay_of ON entries(replay_of_entry_id)",
+        [],
+    )?;
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS graphql_fields (entry_id INTEGER REFERENCES entries(id), field TEXT NOT NULL);
          CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_fields_entry_field ON graphql_fields(entry_id, field);
          CREATE INDEX IF NOT EXISTS idx_graphql_fields_field ON graphql_fields(field);
          CREATE INDEX IF NOT EXISTS idx_graphql_fields_entry ON graphql_fields(entry_id);",
     )?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS graphql_field_paths (entry_id INTEGER REFERENCES entries(id), path TEXT NOT NULL);
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_graphql_field_paths_entry_path ON graphql_field_paths(entry_id, path);
+         CREATE INDEX IF NOT EXISTS idx_graphql_field_paths_path ON graphql_field_paths(path);",
+    )?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS serve_misses (id INTEGER PRIMARY KEY, recorded_at TEXT NOT NULL, method TEXT, url TEXT, host TEXT, path TEXT, query_string TEXT, http_version TEXT, request_headers TEXT, request_body_hash TEXT REFERENCES blobs(hash), request_body_size INTEGER);
+         CREATE INDEX IF NOT EXISTS idx_serve_misses_url ON serve_misses(url);",
+    )?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS redactions (id INTEGER PRIMARY KEY, applied_at TEXT NOT NULL, command TEXT NOT NULL, policy_name TEXT, policy_version INTEGER, policy_hash TEXT, values_redacted INTEGER NOT NULL);",
+    )?;
 
     Ok(())
 }
@@ -541,6 +657,7 @@ mod tests {
         assert!(tables.contains(&"pages".to_string()));
         assert!(tables.contains(&"entries".to_string()));
         assert!(tables.contains(&"response_body_fts".to_string()));
+        assert!(tables.contains(&"serve_misses".to_string()));
     }
 
     #[test]
@@ -552,6 +669,13 @@ mod tests {
         assert_eq!(normalized_on_disk, normalized_runtime);
     }
 
+    #[test]
+    fn core_schema_has_every_index() {
+        for line in super::SCHEMA.lines().filter(|l| l.starts_with("CREATE INDEX")) {
+            assert!(super::SCHEMA_CORE.contains(line), "missing from SCHEMA_CORE: {line}");
+        }
+    }
+
     #[test]
     fn upgrades_legacy_schema() {
         let conn = Connection::open_in_memory().expect("in-memory db");
@@ -636,6 +760,9 @@ mod tests {
         assert!(table_has_column(&conn, "entries", "graphql_operation_type").unwrap());
         assert!(table_has_column(&conn, "entries", "graphql_operation_name").unwrap());
         assert!(table_has_column(&conn, "entries", "graphql_top_level_fields").unwrap());
+        assert!(table_has_column(&conn, "entries", "graphql_query_hash").unwrap());
+        assert!(table_has_column(&conn, "entries", "graphql_variables_hash").unwrap());
+        assert!(table_has_column(&conn, "entries", "replay_of_entry_id").unwrap());
         assert!(table_has_column(&conn, "entries", "entry_hash").unwrap());
         assert!(table_has_column(&conn, "entries", "request_id").unwrap());
         assert!(table_has_column(&conn, "entries", "parent_request_id").unwrap());
@@ -649,6 +776,8 @@ mod tests {
         assert!(table_has_column(&conn, "entries", "tls_cert_subject").unwrap());
         assert!(table_has_column(&conn, "entries", "tls_cert_issuer").unwrap());
         assert!(table_has_column(&conn, "entries", "tls_cert_expiry").unwrap());
+        assert!(table_has_column(&conn, "serve_misses", "request_body_hash").unwrap());
+        assert!(table_has_column(&conn, "redactions", "policy_hash").unwrap());
 
         let fts_exists: i64 = conn
             .query_row(
@@ -11,6 +11,8 @@ use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
+use super::encryption::{external_body_name, seal_external_body};
+
 /// Summary statistics for an import operation.
 pub struct ImportStats {
     pub entries_imported: usize,
@@ -90,6 +92,8 @@ pub struct EntryRelations {
     pub initiator_line: Option<i64>,
     pub initiator_column: Option<i64>,
     pub redirect_url: Option<String>,
+    /// Entry id this entry was replayed from (`harlite replay --record`).
+    pub replay_of_entry_id: Option<i64>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -238,16 +242,22 @@ pub fn store_blob(
     Ok((hash, inserted > 0))
 }
 
+/// Headers are stored as a JSON object keyed by lowercase name. Repeated `Set-Cookie` headers
+/// cannot be comma-joined, so they are folded with newlines (as browsers do) to keep every raw value.
 fn headers_to_json(headers: &[Header]) -> String {
-    let map: serde_json::Map<String, serde_json::Value> = headers
-        .iter()
-        .map(|h| {
-            (
-                h.name.to_lowercase(),
-                serde_json::Value::String(h.value.clone()),
-            )
-        })
-        .collect();
+    let mut map = serde_json::Map::new();
+    for h in headers {
+        let name = h.name.to_lowercase();
+        match map.get_mut(&name) {
+            Some(serde_json::Value::String(existing)) if name == "set-cookie" => {
+                existing.push('\n');
+                existing.push_str(&h.value);
+            }
+            _ => {
+                map.insert(name, serde_json::Value::String(h.value.clone()));
+            }
+        }
+    }
     serde_json::to_string(&map).unwrap_or_else(|_| "{}".to_string())
 }
 
@@ -689,6 +699,20 @@ fn decode_body(content: &crate::har::Content) -> Option<Vec<u8>> {
     }
 }
 
+/// Request body from `postData.text`. HAR has no encoding field for request bodies; harlite
+/// writes binary ones (replay captures, serve misses) base64-encoded with an `encoding` extension.
+fn decode_post_data(post_data: &crate::har::PostData) -> Option<Vec<u8>> {
+    let text = post_data.text.as_ref()?;
+
+    match post_data.extensions.get("encoding").and_then(|v| v.as_str()) {
+        Some("base64") => {
+            use base64::{engine::general_purpose::STANDARD, Engine};
+            STANDARD.decode(text).ok()
+        }
+        _ => Some(text.as_bytes().to_vec()),
+    }
+}
+
 fn is_urlencoded_mime_type(mime: &str) -> bool {
     let media_type = mime.split(';').next().unwrap_or(mime).trim();
     media_type.eq_ignore_ascii_case("application/x-www-form-urlencoded")
@@ -748,7 +772,7 @@ fn read_to_end_limited<R: Read>(mut r: R, max: Option<usize>) -> std::io::Result
 }
 
 #[cfg(feature = "compression")]
-fn decompress_body(
+pub(crate) fn decompress_body(
     body: &[u8],
     content_encoding: &str,
     max_output: Option<usize>,
@@ -783,7 +807,7 @@ fn decompress_body(
 }
 
 #[cfg(not(feature = "compression"))]
-fn decompress_body(
+pub(crate) fn decompress_body(
     _body: &[u8],
     _content_encoding: &str,
     _max_output: Option<usize>,
@@ -791,20 +815,50 @@ fn decompress_body(
     None
 }
 
+/// Where the extracted body with blob hash `hash` lives; with a database key set the
+/// file name (and its shard directories) come from a keyed hash instead.
 fn blob_path(root: &Path, hash: &str, shard_depth: u8) -> PathBuf {
+    let name = external_body_name(hash);
     let mut out = root.to_path_buf();
     let depth = shard_depth as usize;
     for i in 0..depth {
         let start = i * 2;
         let end = start + 2;
-        if hash.len() >= end {
-            out.push(&hash[start..end]);
+        if name.len() >= end {
+            out.push(&name[start..end]);
         }
     }
-    out.push(hash);
+    out.push(name.as_ref());
     out
 }
 
+/// Write `content` as an extracted body file next to `existing`, another extracted
+/// body file, with the same root directory and shard depth.
+///
+/// Returns `None` when `existing` is not an absolute path laid out by `--extract-bodies`.
+pub fn write_sibling_blob(
+    existing: &Path,
+    existing_hash: &str,
+    content: &[u8],
+) -> Result<Option<PathBuf>> {
+    if !existing.is_absolute() {
+        return Ok(None);
+    }
+    let hash = blake3::hash(content).to_hex().to_string();
+    let max_depth = existing_hash.len() / 2;
+    for depth in (0..=max_depth).rev() {
+        let Some(root) = existing.ancestors().nth(depth + 1) else {
+            continue;
+        };
+        if blob_path(root, existing_hash, depth as u8) == existing {
+            let path = blob_path(root, &hash, depth as u8);
+            write_blob_if_missing(&path, content)?;
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
 fn write_blob_if_missing(path: &Path, content: &[u8]) -> Result<()> {
     if path.exists() {
         return Ok(());
@@ -819,7 +873,7 @@ fn write_blob_if_missing(path: &Path, content: &[u8]) -> Result<()> {
     {
         Ok(mut file) => {
             use std::io::Write;
-            file.write_all(content)?;
+            file.write_all(&seal_external_body(content)?)?;
             Ok(())
         }
         Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
@@ -866,7 +920,7 @@ fn maybe_index_response_body_fts(
     Ok(())
 }
 
-const DEFAULT_MAX_DECOMPRESSED_BYTES: usize = 50 * 1024 * 1024;
+pub(crate) const DEFAULT_MAX_DECOMPRESSED_BYTES: usize = 50 * 1024 * 1024;
 
 /// Insert an entry into the database and optionally store bodies.
 pub fn insert_entry(
@@ -1010,8 +1064,8 @@ pub fn insert_entry_with_hash(
         }
 
         if let Some(post_data) = &entry.request.post_data {
-            if let Some(text) = &post_data.text {
-                let body = text.as_bytes();
+            if let Some(body) = decode_post_data(post_data) {
+                let body = body.as_slice();
                 let size_ok = options.max_body_size.is_none_or(|max| body.len() <= max);
                 let mime = post_data.mime_type.as_deref();
                 let type_ok = !options.text_only || is_text_mime_type(mime);
@@ -1049,6 +1103,25 @@ pub fn insert_entry_with_hash(
             serde_json::to_string(&info.top_level_fields).ok()
         }
     });
+    let graphql_query_hash = graphql_info
+        .as_ref()
+        .and_then(|info| info.query_hash.clone());
+    let mut graphql_variables_hash = None;
+    if options.store_bodies {
+        if let Some(info) = graphql_info.as_ref() {
+            // The query hash is the blob hash of the normalized document, so storing it
+            // makes `graphql_query_hash` resolvable through `blobs`.
+            if let Some(query) = info.normalized_query.as_deref() {
+                store_request_blob(conn, query.as_bytes(), Some("application/graphql"), options)?;
+            }
+            if let Some(variables) = info.variables.as_ref() {
+                let json = serde_json::to_vec(variables)?;
+                let (hash, _) =
+                    store_request_blob(conn, &json, Some("application/json"), options)?;
+                graphql_variables_hash = Some(hash);
+            }
+        }
+    }
 
     let insert_sql = if ignore_duplicates {
         "INSERT OR IGNORE INTO entries (
@@ -1060,14 +1133,15 @@ pub fn insert_entry_with_hash(
             is_redirect, server_ip, connection_id, request_id, parent_request_id, initiator_type, initiator_url, initiator_line, initiator_column, redirect_url,
             tls_version, tls_cipher_suite, tls_cert_subject, tls_cert_issuer, tls_cert_expiry, entry_hash,
             entry_extensions, request_extensions, response_extensions, content_extensions, timings_extensions, post_data_extensions,
-            graphql_operation_type, graphql_operation_name, graphql_top_level_fields
+            graphql_operation_type, graphql_operation_name, graphql_top_level_fields, graphql_query_hash,
+            graphql_variables_hash, replay_of_entry_id
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
             ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
             ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
             ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40,
             ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50,
-            ?51, ?52, ?53, ?54, ?55
+            ?51, ?52, ?53, ?54, ?55, ?56, ?57, ?58
         )"
     } else {
         "INSERT INTO entries (
@@ -1079,14 +1153,15 @@ pub fn insert_entry_with_hash(
             is_redirect, server_ip, connection_id, request_id, parent_request_id, initiator_type, initiator_url, initiator_line, initiator_column, redirect_url,
             tls_version, tls_cipher_suite, tls_cert_subject, tls_cert_issuer, tls_cert_expiry, entry_hash,
             entry_extensions, request_extensions, response_extensions, content_extensions, timings_extensions, post_data_extensions,
-            graphql_operation_type, graphql_operation_name, graphql_top_level_fields
+            graphql_operation_type, graphql_operation_name, graphql_top_level_fields, graphql_query_hash,
+            graphql_variables_hash, replay_of_entry_id
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
             ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
             ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
             ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40,
             ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50,
-            ?51, ?52, ?53, ?54, ?55
+            ?51, ?52, ?53, ?54, ?55, ?56, ?57, ?58
         )"
     };
 
@@ -1148,13 +1223,16 @@ pub fn insert_entry_with_hash(
             graphql_operation_type,
             graphql_operation_name,
             graphql_top_level_fields,
+            graphql_query_hash,
+            graphql_variables_hash,
+            relations.replay_of_entry_id,
         ],
     )?;
 
     if inserted > 0 {
         if let Some(info) = graphql_info {
+            let entry_id = conn.last_insert_rowid();
             if !info.top_level_fields.is_empty() {
-                let entry_id = conn.last_insert_rowid();
                 let mut stmt = conn.prepare_cached(
                     "INSERT OR IGNORE INTO graphql_fields (entry_id, field) VALUES (?1, ?2)",
                 )?;
@@ -1162,6 +1240,14 @@ pub fn insert_entry_with_hash(
                     stmt.execute(params![entry_id, field])?;
                 }
             }
+            if !info.field_paths.is_empty() {
+                let mut stmt = conn.prepare_cached(
+                    "INSERT OR IGNORE INTO graphql_field_paths (entry_id, path) VALUES (?1, ?2)",
+                )?;
+                for path in info.field_paths {
+                    stmt.execute(params![entry_id, path])?;
+                }
+            }
         }
     }
 
@@ -1211,11 +1297,93 @@ fn store_response_blob(
 
 #[cfg(test)]
 mod tests {
-    use super::{insert_entry, EntryRelations, InsertEntryOptions};
+    use super::{headers_to_json, insert_entry, EntryRelations, InsertEntryOptions};
     use crate::db::create_schema;
-    use crate::har::Har;
+    use crate::har::{Har, Header};
     use rusqlite::{params, Connection};
 
+    #[test]
+    fn base64_post_data_is_stored_as_bytes() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_schema(&conn).expect("schema created");
+
+        let json = r#"
+        {
+          "log": {
+            "entries": [
+              {
+                "startedDateTime": "2024-01-15T10:30:00.000Z",
+                "time": 1.0,
+                "request": {
+                  "method": "POST",
+                  "url": "https://example.com/upload",
+                  "httpVersion": "HTTP/1.1",
+                  "headers": [],
+                  "postData": {
+                    "mimeType": "application/octet-stream",
+                    "text": "//4A",
+                    "encoding": "base64"
+                  }
+                },
+                "response": {
+                  "status": 204,
+                  "statusText": "No Content",
+                  "httpVersion": "HTTP/1.1",
+                  "headers": [],
+                  "content": {"size": 0}
+                }
+              }
+            ]
+          }
+        }
+        "#;
+        let har: Har = serde_json::from_str(json).expect("parse har");
+        conn.execute(
+            "INSERT INTO imports (id, source_file, imported_at, entry_count) VALUES (1, 'test.har', '2024-01-01T00:00:00Z', 0)",
+            [],
+        )
+        .expect("insert import");
+        let options = InsertEntryOptions {
+            store_bodies: true,
+            ..Default::default()
+        };
+        insert_entry(
+            &conn,
+            1,
+            &har.log.entries[0],
+            &options,
+            &EntryRelations::default(),
+        )
+        .expect("insert entry");
+
+        let (content, size): (Vec<u8>, i64) = conn
+            .query_row(
+                "SELECT b.content, b.size FROM entries e JOIN blobs b ON b.hash = e.request_body_hash",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .expect("request blob");
+        assert_eq!(content, vec![0xff, 0xfe, 0x00]);
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn repeated_set_cookie_headers_are_folded() {
+        let header = |name: &str, value: &str| Header {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        let json = headers_to_json(&[
+            header("Set-Cookie", "a=1; SameSite=Lax"),
+            header("Vary", "Accept"),
+            header("set-cookie", "b=2; Max-Age=60"),
+            header("Vary", "Origin"),
+        ]);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["set-cookie"], "a=1; SameSite=Lax\nb=2; Max-Age=60");
+        assert_eq!(value["vary"], "Origin");
+    }
+
     #[test]
     fn inserts_entry_and_blob() {
         let conn = Connection::open_in_memory().expect("in-memory db");
@@ -1381,6 +1549,100 @@ mod tests {
         assert_eq!(gql_fields, vec!["viewer".to_string()]);
     }
 
+    #[cfg(feature = "graphql")]
+    #[test]
+    fn stores_graphql_document_variables_and_field_paths() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        create_schema(&conn).expect("schema created");
+
+        let json = serde_json::json!({
+            "log": {
+                "entries": [
+                    {
+                        "startedDateTime": "2024-01-15T10:30:00.000Z",
+                        "time": 12.0,
+                        "request": {
+                            "method": "POST",
+                            "url": "https://example.com/graphql",
+                            "httpVersion": "HTTP/1.1",
+                            "headers": [{"name": "Content-Type", "value": "application/json"}],
+                            "cookies": [],
+                            "postData": {
+                                "mimeType": "application/json",
+                                "text": "{\"query\":\"query GetUser($id: ID!) { user(id: $id) { login } }\",\"variables\":{\"id\":\"42\"}}"
+                            }
+                        },
+                        "response": {
+                            "status": 200,
+                            "statusText": "OK",
+                            "httpVersion": "HTTP/1.1",
+                            "headers": [],
+                            "content": {
+                                "size": 0,
+                                "mimeType": "application/json"
+                            }
+                        }
+                    }
+                ]
+            }
+        });
+
+        let har: Har = serde_json::from_value(json).expect("parse har");
+        conn.execute(
+            "INSERT INTO imports (id, source_file, imported_at, entry_count) VALUES (?1, ?2, ?3, ?4)",
+            params![1i64, "test.har", "2024-01-01T00:00:00Z", 0],
+        )
+        .expect("insert import");
+
+        let options = InsertEntryOptions {
+            store_bodies: true,
+            ..Default::default()
+        };
+        insert_entry(
+            &conn,
+            1,
+            &har.log.entries[0],
+            &options,
+            &EntryRelations::default(),
+        )
+        .expect("insert entry");
+
+        let (query_hash, variables_hash): (String, String) = conn
+            .query_row(
+                "SELECT graphql_query_hash, graphql_variables_hash FROM entries",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .expect("load graphql hashes");
+
+        let query: Vec<u8> = conn
+            .query_row(
+                "SELECT content FROM blobs WHERE hash = ?1",
+                params![query_hash],
+                |r| r.get(0),
+            )
+            .expect("query blob");
+        assert!(String::from_utf8_lossy(&query).contains("user(id: $id)"));
+
+        let variables: Vec<u8> = conn
+            .query_row(
+                "SELECT content FROM blobs WHERE hash = ?1",
+                params![variables_hash],
+                |r| r.get(0),
+            )
+            .expect("variables blob");
+        assert_eq!(variables, br#"{"id":"42"}"#.to_vec());
+
+        let paths: Vec<String> = conn
+            .prepare("SELECT path FROM graphql_field_paths ORDER BY path")
+            .expect("prepare")
+            .query_map([], |r| r.get(0))
+            .expect("query")
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(paths, vec!["user".to_string(), "user.login".to_string()]);
+    }
+
     #[test]
     fn inserts_params_only_request_body() {
         let conn = Connection::open_in_memory().expect("in-memory db");
@@ -8,17 +8,29 @@ use url::Url;
 
 use crate::har::{Entry, Header, PostData, QueryParam};
 
+/// Deepest selection nesting recorded in `field_paths`.
+#[cfg(feature = "graphql")]
+const MAX_FIELD_PATH_DEPTH: usize = 16;
+
 #[derive(Debug, Clone, Default)]
 pub struct GraphQLInfo {
     pub operation_type: Option<String>,
     pub operation_name: Option<String>,
     pub top_level_fields: Vec<String>,
+    /// Canonical form of the query document (re-printed when parseable, whitespace-collapsed otherwise).
+    pub normalized_query: Option<String>,
+    /// BLAKE3 hash of `normalized_query`.
+    pub query_hash: Option<String>,
+    pub variables: Option<Value>,
+    /// Dotted paths of every selected field, e.g. `viewer.repositories.name`.
+    pub field_paths: Vec<String>,
 }
 
 #[derive(Default)]
 struct GraphQLPayload {
     query: Option<String>,
     operation_name: Option<String>,
+    variables: Option<Value>,
     detected: bool,
 }
 
@@ -58,22 +70,32 @@ pub fn extract_graphql_info(entry: &Entry) -> Option<GraphQLInfo> {
     }
 
     let mut info = GraphQLInfo {
-        operation_type: None,
         operation_name: payload.operation_name.clone(),
-        top_level_fields: Vec::new(),
+        variables: payload.variables.take(),
+        ..GraphQLInfo::default()
     };
 
-    if let Some(query) = payload.query.as_deref() {
+    if let Some(query) = payload.query.as_deref().filter(|q| !q.trim().is_empty()) {
+        let mut normalized = None;
         if let Some(parsed) = parse_graphql_query(query, payload.operation_name.as_deref()) {
             info.operation_type = parsed.operation_type;
             info.operation_name = parsed.operation_name.or(info.operation_name);
             info.top_level_fields = parsed.top_level_fields;
+            info.field_paths = parsed.field_paths;
+            normalized = parsed.normalized_query;
         }
+        let normalized = normalized.unwrap_or_else(|| collapse_whitespace(query));
+        info.query_hash = Some(blake3::hash(normalized.as_bytes()).to_hex().to_string());
+        info.normalized_query = Some(normalized);
     }
 
     Some(info)
 }
 
+fn collapse_whitespace(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 fn apply_query_params(params: &[QueryParam], payload: &mut GraphQLPayload) {
     for param in params {
         apply_param(&param.name, &param.value, payload);
@@ -126,6 +148,13 @@ fn apply_param(name: &str, value: &str, payload: &mut GraphQLPayload) {
             }
             payload.detected = true;
         }
+        "variables" => {
+            if let Ok(value) = serde_json::from_str::<Value>(value) {
+                if !value.is_null() {
+                    payload.variables = Some(value);
+                }
+            }
+        }
         "extensions" => {
             if let Ok(value) = serde_json::from_str::<Value>(value) {
                 apply_graphql_json(&value, payload);
@@ -171,6 +200,9 @@ fn apply_graphql_json(value: &Value, payload: &mut GraphQLPayload) {
             payload.operation_name = Some(name.to_string());
         }
     }
+    if let Some(variables) = value.get("variables").filter(|v| !v.is_null()) {
+        payload.variables = Some(variables.clone());
+    }
     if value.get("extensions").and_then(|ext| ext.get("persistedQuery")).is_some() {
         payload.detected = true;
     }
@@ -182,6 +214,7 @@ fn apply_graphql_json(value: &Value, payload: &mut GraphQLPayload) {
 #[cfg(feature = "graphql")]
 fn parse_graphql_query(query: &str, operation_name: Option<&str>) -> Option<GraphQLInfo> {
     let document = parse_query::<String>(query).ok()?;
+    let normalized = document.to_string().trim_end().to_string();
 
     let mut fragments: HashMap<String, SelectionSet<'_, String>> = HashMap::new();
     let mut operations: Vec<OperationDefinition<'_, String>> = Vec::new();
@@ -216,11 +249,24 @@ fn parse_graphql_query(query: &str, operation_name: Option<&str>) -> Option<Grap
     let op_type = operation_type(selected).to_string();
     let op_name = op_name(selected).cloned();
     let fields = collect_top_level_fields(op_selection_set(selected), &fragments);
+    let mut field_paths = Vec::new();
+    collect_field_paths(
+        op_selection_set(selected),
+        &fragments,
+        "",
+        0,
+        &mut Vec::new(),
+        &mut field_paths,
+        &mut HashSet::new(),
+    );
 
     Some(GraphQLInfo {
         operation_type: Some(op_type),
         operation_name: op_name,
         top_level_fields: fields,
+        normalized_query: Some(normalized),
+        field_paths,
+        ..GraphQLInfo::default()
     })
 }
 
@@ -295,6 +341,76 @@ fn collect_fields<'a>(
     }
 }
 
+/// Walk a selection set and record the dotted path of each field, expanding fragments
+/// (with a guard against recursive spreads).
+#[cfg(feature = "graphql")]
+fn collect_field_paths<'a>(
+    selection_set: &'a SelectionSet<'a, String>,
+    fragments: &'a HashMap<String, SelectionSet<'a, String>>,
+    prefix: &str,
+    depth: usize,
+    active_fragments: &mut Vec<&'a str>,
+    out: &mut Vec<String>,
+    seen: &mut HashSet<String>,
+) {
+    if depth >= MAX_FIELD_PATH_DEPTH {
+        return;
+    }
+    for selection in &selection_set.items {
+        match selection {
+            Selection::Field(field) => {
+                let path = if prefix.is_empty() {
+                    field.name.clone()
+                } else {
+                    format!("{prefix}.{}", field.name)
+                };
+                if seen.insert(path.clone()) {
+                    out.push(path.clone());
+                }
+                collect_field_paths(
+                    &field.selection_set,
+                    fragments,
+                    &path,
+                    depth + 1,
+                    active_fragments,
+                    out,
+                    seen,
+                );
+            }
+            Selection::InlineFragment(fragment) => {
+                collect_field_paths(
+                    &fragment.selection_set,
+                    fragments,
+                    prefix,
+                    depth,
+                    active_fragments,
+                    out,
+                    seen,
+                );
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.fragment_name.as_str();
+                if active_fragments.contains(&name) {
+                    continue;
+                }
+                if let Some(fragment) = fragments.get(name) {
+                    active_fragments.push(name);
+                    collect_field_paths(
+                        fragment,
+                        fragments,
+                        prefix,
+                        depth,
+                        active_fragments,
+                        out,
+                        seen,
+                    );
+                    active_fragments.pop();
+                }
+            }
+        }
+    }
+}
+
 fn is_graphql_content_type(mime: Option<&str>) -> bool {
     mime.is_some_and(|m| m.to_ascii_lowercase().contains("graphql"))
 }
@@ -476,4 +592,67 @@ mod tests {
         assert_eq!(info.operation_name.as_deref(), Some("Foo"));
         assert_eq!(info.top_level_fields, vec!["viewer".to_string()]);
     }
+
+    #[test]
+    fn records_field_paths_variables_and_stable_query_hash() {
+        let entry_for = |query: &str| {
+            let body = serde_json::to_string(&json!({
+                "query": query,
+                "variables": {"login": "octocat"}
+            }))
+            .expect("body");
+            load_entry(json!({
+                "log": {
+                    "version": "1.2",
+                    "entries": [
+                        {
+                            "startedDateTime": "2024-01-15T10:30:00.000Z",
+                            "time": 10.0,
+                            "request": {
+                                "method": "POST",
+                                "url": "https://example.com/graphql",
+                                "httpVersion": "HTTP/1.1",
+                                "headers": [{"name": "Content-Type", "value": "application/json"}],
+                                "cookies": [],
+                                "postData": {"mimeType": "application/json", "text": body},
+                                "headersSize": 0,
+                                "bodySize": 0
+                            },
+                            "response": {
+                                "status": 200,
+                                "statusText": "OK",
+                                "httpVersion": "HTTP/1.1",
+                                "headers": [],
+                                "content": {"size": 0, "mimeType": "application/json"},
+                                "headersSize": 0,
+                                "bodySize": 0
+                            }
+                        }
+                    ]
+                }
+            }))
+        };
+
+        let compact = extract_graphql_info(&entry_for(
+            "query User($login: String!) { user(login: $login) { name ...Repos } } fragment Repos on User { repositories { name } }",
+        ))
+        .expect("graphql info");
+        let spaced = extract_graphql_info(&entry_for(
+            "query User($login: String!) {\n  user(login: $login) {\n    name\n    ...Repos\n  }\n}\n\nfragment Repos on User {\n  repositories { name }\n}",
+        ))
+        .expect("graphql info");
+
+        assert_eq!(
+            compact.field_paths,
+            vec![
+                "user".to_string(),
+                "user.name".to_string(),
+                "user.repositories".to_string(),
+                "user.repositories.name".to_string(),
+            ]
+        );
+        assert_eq!(compact.variables, Some(json!({"login": "octocat"})));
+        assert!(compact.query_hash.is_some());
+        assert_eq!(compact.query_hash, spaced.query_hash);
+    }
 }
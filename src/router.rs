@@ -1,17 +1,23 @@
 #[cfg(feature = "completions")]
 use clap::CommandFactory;
 
+use std::path::PathBuf;
+
 use crate::cli::{Cli, Commands};
 use crate::commands::{
-    run_analyze, run_diff, run_export, run_export_data, run_fts_rebuild, run_import, run_imports,
-    run_info, run_merge, run_openapi, run_pii, run_prune, run_query, run_redact, run_schema,
-    run_report, run_search, run_stats, run_waterfall, AnalyzeOptions, DiffOptions, EntryFilterOptions,
-    ExportDataOptions, ExportOptions, ImportOptions, InfoOptions, MergeOptions, OpenApiOptions,
-    PiiOptions, QueryOptions, RedactOptions, ReportOptions, StatsOptions, WaterfallFormat,
-    WaterfallGroupBy, WaterfallOptions,
+    run_analyze, run_codegen, run_conform, run_diff, run_export, run_export_collection,
+    run_export_data, run_export_loadtest, run_fts_rebuild, run_import, run_imports, run_info,
+    run_merge, run_openapi, run_pii, run_prune, run_query, run_redact, run_report, run_schema,
+    run_search, run_stats, run_types, run_waterfall, AnalyzeOptions, CodegenOptions,
+    CollectionOptions, ConformOptions, DiffOptions, EntryFilterOptions, ExportDataOptions,
+    ExportOptions, ImportOptions, InfoOptions, LoadTestOptions, MergeOptions, OpenApiOptions,
+    PiiOptions, QueryOptions, RedactOptions, ReportOptions, StatsOptions, TypesOptions,
+    WaterfallFormat, WaterfallGroupBy, WaterfallOptions,
 };
 #[cfg(feature = "cdp")]
 use crate::commands::{run_cdp, CdpOptions};
+#[cfg(feature = "graphql")]
+use crate::commands::{run_graphql, GraphqlOptions};
 #[cfg(feature = "otel")]
 use crate::commands::{run_otel, OtelExportOptions};
 #[cfg(feature = "repl")]
@@ -22,7 +28,10 @@ use crate::commands::{run_replay, ReplayOptions};
 use crate::commands::{run_serve, ServeOptions};
 #[cfg(feature = "watch")]
 use crate::commands::{run_watch, WatchOptions};
-use crate::config::{load_config, render_config, ResolvedConfig};
+use crate::config::{
+    load_config, render_config, ResolvedConfig, ResolvedPiiConfig, ResolvedRedactConfig,
+};
+use crate::db::{set_database_key, DatabaseKey};
 use crate::error::Result;
 use crate::plugins::resolve_plugins;
 use crate::size;
@@ -30,6 +39,10 @@ use crate::size;
 pub fn run(cli: Cli) -> Result<()> {
     let config = load_config()?;
     let resolved = ResolvedConfig::from_config(&config);
+    set_database_key(DatabaseKey::from_key_source(
+        cli.db_key_file.as_deref(),
+        cli.db_key_prompt.unwrap_or(false),
+    )?)?;
 
     match cli.command {
         Commands::Import {
@@ -54,6 +67,9 @@ pub fn run(cli: Cli) -> Result<()> {
             url_regex,
             from,
             to,
+            redact,
+            redact_policy,
+            redact_pii,
             plugin,
             disable_plugin,
         } => {
@@ -95,6 +111,14 @@ pub fn run(cli: Cli) -> Result<()> {
                 url_regex: url_regex.unwrap_or_else(|| defaults.url_regex.clone()),
                 from: from.or_else(|| defaults.from.clone()),
                 to: to.or_else(|| defaults.to.clone()),
+                redact: inline_redact_options(
+                    &resolved.redact,
+                    redact.unwrap_or(defaults.redact),
+                    redact_policy.or_else(|| defaults.redact_policy.clone()),
+                ),
+                redact_pii: redact_pii
+                    .unwrap_or(defaults.redact_pii)
+                    .then(|| inline_pii_options(&resolved.pii)),
                 plugins,
             };
             run_import(&files, &options).map(|_| ())
@@ -111,6 +135,9 @@ pub fn run(cli: Cli) -> Result<()> {
             max_body_size,
             text_only,
             duration,
+            redact,
+            redact_policy,
+            redact_pii,
         } => {
             let defaults = &resolved.cdp;
             let max_body_size = size::parse_size_bytes_usize(
@@ -126,6 +153,14 @@ pub fn run(cli: Cli) -> Result<()> {
                 max_body_size,
                 text_only: text_only.unwrap_or(defaults.text_only),
                 duration_secs: duration.or(defaults.duration),
+                redact: inline_redact_options(
+                    &resolved.redact,
+                    redact.unwrap_or(defaults.redact),
+                    redact_policy.or_else(|| defaults.redact_policy.clone()),
+                ),
+                redact_pii: redact_pii
+                    .unwrap_or(defaults.redact_pii)
+                    .then(|| inline_pii_options(&resolved.pii)),
             };
             run_cdp(&options)
         }
@@ -159,6 +194,9 @@ pub fn run(cli: Cli) -> Result<()> {
             url_regex,
             from,
             to,
+            redact,
+            redact_policy,
+            redact_pii,
             plugin,
             disable_plugin,
         } => {
@@ -201,6 +239,14 @@ pub fn run(cli: Cli) -> Result<()> {
                 url_regex: url_regex.unwrap_or_else(|| defaults.url_regex.clone()),
                 from: from.or_else(|| defaults.from.clone()),
                 to: to.or_else(|| defaults.to.clone()),
+                redact: inline_redact_options(
+                    &resolved.redact,
+                    redact.unwrap_or(defaults.redact),
+                    redact_policy.or_else(|| defaults.redact_policy.clone()),
+                ),
+                redact_pii: redact_pii
+                    .unwrap_or(defaults.redact_pii)
+                    .then(|| inline_pii_options(&resolved.pii)),
                 plugins,
             };
 
@@ -398,13 +444,16 @@ pub fn run(cli: Cli) -> Result<()> {
         Commands::Otel {
             database,
             format,
+            signal,
             output,
             endpoint,
             service_name,
             resource_attr,
             no_phases,
+            no_page_spans,
             sample_rate,
             max_spans,
+            path_param_threshold,
             url,
             url_contains,
             url_regex,
@@ -442,13 +491,17 @@ pub fn run(cli: Cli) -> Result<()> {
             };
             let options = OtelExportOptions {
                 format,
+                signal,
                 output,
                 endpoint,
                 service_name,
                 resource_attr: resource_attr.unwrap_or_default(),
                 include_phases: !no_phases,
+                page_spans: !no_page_spans,
                 sample_rate,
                 max_spans,
+                path_param_threshold: path_param_threshold
+                    .unwrap_or(resolved.otel.path_param_threshold),
                 filters,
             };
             run_otel(database, &options)
@@ -461,6 +514,8 @@ pub fn run(cli: Cli) -> Result<()> {
             version,
             sample_bodies,
             sample_body_max_size,
+            literal_paths,
+            path_param_threshold,
             allow_external_paths,
             external_path_root,
             url,
@@ -504,6 +559,9 @@ pub fn run(cli: Cli) -> Result<()> {
                 version,
                 sample_bodies,
                 sample_body_max_size,
+                literal_paths: literal_paths.unwrap_or(false),
+                path_param_threshold: path_param_threshold
+                    .unwrap_or(resolved.openapi.path_param_threshold),
                 allow_external_paths: allow_external_paths.unwrap_or(false),
                 external_path_root,
                 filters,
@@ -511,6 +569,342 @@ pub fn run(cli: Cli) -> Result<()> {
             run_openapi(database, &options)
         }
 
+        Commands::Conform {
+            database,
+            spec,
+            output,
+            format,
+            ignore_header,
+            fail_on_violation,
+            allow_external_paths,
+            external_path_root,
+            url,
+            url_contains,
+            url_regex,
+            host,
+            method,
+            status,
+            mime,
+            ext,
+            source,
+            source_contains,
+            from,
+            to,
+            min_request_size,
+            max_request_size,
+            min_response_size,
+            max_response_size,
+        } => {
+            let filters = EntryFilterOptions {
+                url: url.unwrap_or_default(),
+                url_contains: url_contains.unwrap_or_default(),
+                url_regex: url_regex.unwrap_or_default(),
+                host: host.unwrap_or_default(),
+                method: method.unwrap_or_default(),
+                status: status.unwrap_or_default(),
+                mime_contains: mime.unwrap_or_default(),
+                ext: ext.unwrap_or_default(),
+                source: source.unwrap_or_default(),
+                source_contains: source_contains.unwrap_or_default(),
+                from,
+                to,
+                min_request_size,
+                max_request_size,
+                min_response_size,
+                max_response_size,
+            };
+            let options = ConformOptions {
+                spec,
+                output,
+                format,
+                ignore_headers: ignore_header.unwrap_or_default(),
+                fail_on_violation: fail_on_violation.unwrap_or(false),
+                allow_external_paths: allow_external_paths.unwrap_or(false),
+                external_path_root,
+                filters,
+            };
+            run_conform(database, &options)
+        }
+
+        Commands::Types {
+            database,
+            output,
+            format,
+            sample_bodies,
+            sample_body_max_size,
+            literal_paths,
+            path_param_threshold,
+            allow_external_paths,
+            external_path_root,
+            url,
+            url_contains,
+            url_regex,
+            host,
+            method,
+            status,
+            mime,
+            ext,
+            source,
+            source_contains,
+            from,
+            to,
+            min_request_size,
+            max_request_size,
+            min_response_size,
+            max_response_size,
+        } => {
+            let filters = EntryFilterOptions {
+                url: url.unwrap_or_default(),
+                url_contains: url_contains.unwrap_or_default(),
+                url_regex: url_regex.unwrap_or_default(),
+                host: host.unwrap_or_default(),
+                method: method.unwrap_or_default(),
+                status: status.unwrap_or_default(),
+                mime_contains: mime.unwrap_or_default(),
+                ext: ext.unwrap_or_default(),
+                source: source.unwrap_or_default(),
+                source_contains: source_contains.unwrap_or_default(),
+                from,
+                to,
+                min_request_size,
+                max_request_size,
+                min_response_size,
+                max_response_size,
+            };
+            let options = TypesOptions {
+                output,
+                format,
+                sample_bodies,
+                sample_body_max_size,
+                literal_paths: literal_paths.unwrap_or(false),
+                path_param_threshold: path_param_threshold
+                    .unwrap_or(resolved.openapi.path_param_threshold),
+                allow_external_paths: allow_external_paths.unwrap_or(false),
+                external_path_root,
+                filters,
+            };
+            run_types(database, &options)
+        }
+
+        #[cfg(feature = "graphql")]
+        Commands::Graphql {
+            database,
+            output,
+            format,
+            allow_external_paths,
+            external_path_root,
+            url,
+            url_contains,
+            url_regex,
+            host,
+            method,
+            status,
+            mime,
+            ext,
+            source,
+            source_contains,
+            from,
+            to,
+            min_request_size,
+            max_request_size,
+            min_response_size,
+            max_response_size,
+        } => {
+            let filters = EntryFilterOptions {
+                url: url.unwrap_or_default(),
+                url_contains: url_contains.unwrap_or_default(),
+                url_regex: url_regex.unwrap_or_default(),
+                host: host.unwrap_or_default(),
+                method: method.unwrap_or_default(),
+                status: status.unwrap_or_default(),
+                mime_contains: mime.unwrap_or_default(),
+                ext: ext.unwrap_or_default(),
+                source: source.unwrap_or_default(),
+                source_contains: source_contains.unwrap_or_default(),
+                from,
+                to,
+                min_request_size,
+                max_request_size,
+                min_response_size,
+                max_response_size,
+            };
+            let options = GraphqlOptions {
+                output,
+                format,
+                allow_external_paths: allow_external_paths.unwrap_or(false),
+                external_path_root,
+                filters,
+            };
+            run_graphql(database, &options)
+        }
+
+        Commands::ExportCollection {
+            database,
+            output,
+            format,
+            name,
+            environment_output,
+            max_examples,
+            allow_external_paths,
+            external_path_root,
+            url,
+            url_contains,
+            url_regex,
+            host,
+            method,
+            status,
+            mime,
+            ext,
+            source,
+            source_contains,
+            from,
+            to,
+            min_request_size,
+            max_request_size,
+            min_response_size,
+            max_response_size,
+        } => {
+            let filters = EntryFilterOptions {
+                url: url.unwrap_or_default(),
+                url_contains: url_contains.unwrap_or_default(),
+                url_regex: url_regex.unwrap_or_default(),
+                host: host.unwrap_or_default(),
+                method: method.unwrap_or_default(),
+                status: status.unwrap_or_default(),
+                mime_contains: mime.unwrap_or_default(),
+                ext: ext.unwrap_or_default(),
+                source: source.unwrap_or_default(),
+                source_contains: source_contains.unwrap_or_default(),
+                from,
+                to,
+                min_request_size,
+                max_request_size,
+                min_response_size,
+                max_response_size,
+            };
+            let options = CollectionOptions {
+                output,
+                format,
+                name,
+                environment_output,
+                max_examples,
+                allow_external_paths: allow_external_paths.unwrap_or(false),
+                external_path_root,
+                filters,
+            };
+            run_export_collection(database, &options)
+        }
+
+        Commands::Codegen {
+            database,
+            output,
+            format,
+            drop_sensitive,
+            allow_external_paths,
+            external_path_root,
+            url,
+            url_contains,
+            url_regex,
+            host,
+            method,
+            status,
+            mime,
+            ext,
+            source,
+            source_contains,
+            from,
+            to,
+            min_request_size,
+            max_request_size,
+            min_response_size,
+            max_response_size,
+        } => {
+            let filters = EntryFilterOptions {
+                url: url.unwrap_or_default(),
+                url_contains: url_contains.unwrap_or_default(),
+                url_regex: url_regex.unwrap_or_default(),
+                host: host.unwrap_or_default(),
+                method: method.unwrap_or_default(),
+                status: status.unwrap_or_default(),
+                mime_contains: mime.unwrap_or_default(),
+                ext: ext.unwrap_or_default(),
+                source: source.unwrap_or_default(),
+                source_contains: source_contains.unwrap_or_default(),
+                from,
+                to,
+                min_request_size,
+                max_request_size,
+                min_response_size,
+                max_response_size,
+            };
+            let options = CodegenOptions {
+                output,
+                format,
+                drop_sensitive: drop_sensitive.unwrap_or(false),
+                allow_external_paths: allow_external_paths.unwrap_or(false),
+                external_path_root,
+                filters,
+            };
+            run_codegen(database, &options)
+        }
+
+        Commands::ExportLoadtest {
+            database,
+            output,
+            format,
+            include_static,
+            max_think_time,
+            drop_sensitive,
+            allow_external_paths,
+            external_path_root,
+            url,
+            url_contains,
+            url_regex,
+            host,
+            method,
+            status,
+            mime,
+            ext,
+            source,
+            source_contains,
+            from,
+            to,
+            min_request_size,
+            max_request_size,
+            min_response_size,
+            max_response_size,
+        } => {
+            let filters = EntryFilterOptions {
+                url: url.unwrap_or_default(),
+                url_contains: url_contains.unwrap_or_default(),
+                url_regex: url_regex.unwrap_or_default(),
+                host: host.unwrap_or_default(),
+                method: method.unwrap_or_default(),
+                status: status.unwrap_or_default(),
+                mime_contains: mime.unwrap_or_default(),
+                ext: ext.unwrap_or_default(),
+                source: source.unwrap_or_default(),
+                source_contains: source_contains.unwrap_or_default(),
+                from,
+                to,
+                min_request_size,
+                max_request_size,
+                min_response_size,
+                max_response_size,
+            };
+            let options = LoadTestOptions {
+                output,
+                format,
+                include_static: include_static.unwrap_or(false),
+                max_think_time,
+                drop_sensitive: drop_sensitive.unwrap_or(false),
+                allow_external_paths: allow_external_paths.unwrap_or(false),
+                external_path_root,
+                filters,
+            };
+            run_export_loadtest(database, &options)
+        }
+
         Commands::Waterfall {
             database,
             output,
@@ -603,8 +997,14 @@ pub fn run(cli: Cli) -> Result<()> {
             cookie,
             query_param,
             body_regex,
+            body_path,
+            body_field,
             match_mode,
             token,
+            pseudonymize,
+            pseudonym_key_file,
+            policy,
+            verify,
             database,
         } => {
             let defaults = &resolved.redact;
@@ -617,8 +1017,15 @@ pub fn run(cli: Cli) -> Result<()> {
                 cookies: cookie.unwrap_or_else(|| defaults.cookie.clone()),
                 query_params: query_param.unwrap_or_else(|| defaults.query_param.clone()),
                 body_regexes: body_regex.unwrap_or_else(|| defaults.body_regex.clone()),
+                body_paths: body_path.unwrap_or_else(|| defaults.body_path.clone()),
+                body_fields: body_field.unwrap_or_else(|| defaults.body_field.clone()),
                 match_mode: match_mode.unwrap_or(defaults.match_mode),
                 token: token.unwrap_or_else(|| defaults.token.clone()),
+                pseudonymize: pseudonymize.unwrap_or(defaults.pseudonymize),
+                pseudonym_key_file: pseudonym_key_file
+                    .or_else(|| defaults.pseudonym_key_file.clone()),
+                policy: policy.or_else(|| defaults.policy.clone()),
+                verify: verify.unwrap_or(defaults.verify),
             };
             run_redact(database, &options)
         }
@@ -634,11 +1041,18 @@ pub fn run(cli: Cli) -> Result<()> {
             no_phone,
             no_ssn,
             no_credit_card,
+            no_jwt,
+            no_api_keys,
+            no_private_keys,
+            entropy,
+            min_entropy,
             email_regex,
             phone_regex,
             ssn_regex,
             credit_card_regex,
             token,
+            pseudonymize,
+            pseudonym_key_file,
             database,
         } => {
             let defaults = &resolved.pii;
@@ -653,12 +1067,20 @@ pub fn run(cli: Cli) -> Result<()> {
                 no_phone: no_phone.unwrap_or(defaults.no_phone),
                 no_ssn: no_ssn.unwrap_or(defaults.no_ssn),
                 no_credit_card: no_credit_card.unwrap_or(defaults.no_credit_card),
+                no_jwt: no_jwt.unwrap_or(defaults.no_jwt),
+                no_api_keys: no_api_keys.unwrap_or(defaults.no_api_keys),
+                no_private_keys: no_private_keys.unwrap_or(defaults.no_private_keys),
+                entropy: entropy.unwrap_or(defaults.entropy),
+                min_entropy: min_entropy.or(defaults.min_entropy),
                 email_regexes: email_regex.unwrap_or_else(|| defaults.email_regex.clone()),
                 phone_regexes: phone_regex.unwrap_or_else(|| defaults.phone_regex.clone()),
                 ssn_regexes: ssn_regex.unwrap_or_else(|| defaults.ssn_regex.clone()),
                 credit_card_regexes: credit_card_regex
                     .unwrap_or_else(|| defaults.credit_card_regex.clone()),
                 token: token.unwrap_or_else(|| defaults.token.clone()),
+                pseudonymize: pseudonymize.unwrap_or(defaults.pseudonymize),
+                pseudonym_key_file: pseudonym_key_file
+                    .or_else(|| defaults.pseudonym_key_file.clone()),
             };
             run_pii(database, &options)
         }
@@ -701,6 +1123,18 @@ pub fn run(cli: Cli) -> Result<()> {
             status,
             override_host,
             override_header,
+            diff_body,
+            ignore_json_path,
+            record,
+            extract,
+            cookie_jar,
+            load,
+            duration,
+            ramp_up,
+            vus,
+            rps,
+            preserve_timing,
+            speed,
         } => {
             let defaults = &resolved.replay;
             let options = ReplayOptions {
@@ -721,6 +1155,19 @@ pub fn run(cli: Cli) -> Result<()> {
                 override_host: override_host.unwrap_or_else(|| defaults.override_host.clone()),
                 override_header: override_header
                     .unwrap_or_else(|| defaults.override_header.clone()),
+                diff_body: diff_body.unwrap_or(defaults.diff_body),
+                ignore_json_path: ignore_json_path
+                    .unwrap_or_else(|| defaults.ignore_json_path.clone()),
+                record: record.or_else(|| defaults.record.clone()),
+                extract: extract.unwrap_or_else(|| defaults.extract.clone()),
+                cookie_jar: cookie_jar.unwrap_or(defaults.cookie_jar),
+                load: load.unwrap_or(defaults.load),
+                duration_secs: duration.unwrap_or(defaults.duration_secs),
+                ramp_up_secs: ramp_up.unwrap_or(defaults.ramp_up_secs),
+                vus: vus.or(defaults.vus),
+                rps: rps.or(defaults.rps),
+                preserve_timing: preserve_timing.unwrap_or(defaults.preserve_timing),
+                speed: speed.unwrap_or(defaults.speed),
             };
             run_replay(input, &options)
         }
@@ -735,6 +1182,10 @@ pub fn run(cli: Cli) -> Result<()> {
             external_path_root,
             tls_cert,
             tls_key,
+            admin,
+            max_unmatched,
+            misses_har,
+            record_misses,
         } => {
             let options = ServeOptions {
                 bind,
@@ -744,6 +1195,10 @@ pub fn run(cli: Cli) -> Result<()> {
                 external_path_root,
                 tls_cert,
                 tls_key,
+                admin: admin.unwrap_or(false),
+                max_unmatched: max_unmatched.unwrap_or(1000),
+                misses_har,
+                record_misses: record_misses.unwrap_or(false),
             };
             run_serve(input, &options)
         }
@@ -839,3 +1294,62 @@ pub fn run(cli: Cli) -> Result<()> {
         }
     }
 }
+
+/// `redact` options for redacting while importing: rules come from `[redact]` config,
+/// and a policy file (from the flag or config) turns redaction on by itself.
+fn inline_redact_options(
+    defaults: &ResolvedRedactConfig,
+    enabled: bool,
+    policy: Option<PathBuf>,
+) -> Option<RedactOptions> {
+    let policy = policy.or_else(|| defaults.policy.clone().filter(|_| enabled));
+    if !enabled && policy.is_none() {
+        return None;
+    }
+    Some(RedactOptions {
+        output: None,
+        force: false,
+        dry_run: false,
+        no_defaults: defaults.no_defaults,
+        headers: defaults.header.clone(),
+        cookies: defaults.cookie.clone(),
+        query_params: defaults.query_param.clone(),
+        body_regexes: defaults.body_regex.clone(),
+        body_paths: defaults.body_path.clone(),
+        body_fields: defaults.body_field.clone(),
+        match_mode: defaults.match_mode,
+        token: defaults.token.clone(),
+        pseudonymize: defaults.pseudonymize,
+        pseudonym_key_file: defaults.pseudonym_key_file.clone(),
+        policy,
+        verify: false,
+    })
+}
+
+/// `pii --redact` options for redacting while importing, from `[pii]` config.
+fn inline_pii_options(defaults: &ResolvedPiiConfig) -> PiiOptions {
+    PiiOptions {
+        format: defaults.format,
+        redact: true,
+        output: None,
+        force: false,
+        dry_run: false,
+        no_defaults: defaults.no_defaults,
+        no_email: defaults.no_email,
+        no_phone: defaults.no_phone,
+        no_ssn: defaults.no_ssn,
+        no_credit_card: defaults.no_credit_card,
+        no_jwt: defaults.no_jwt,
+        no_api_keys: defaults.no_api_keys,
+        no_private_keys: defaults.no_private_keys,
+        entropy: defaults.entropy,
+        min_entropy: defaults.min_entropy,
+        email_regexes: defaults.email_regex.clone(),
+        phone_regexes: defaults.phone_regex.clone(),
+        ssn_regexes: defaults.ssn_regex.clone(),
+        credit_card_regexes: defaults.credit_card_regex.clone(),
+        token: defaults.token.clone(),
+        pseudonymize: defaults.pseudonymize,
+        pseudonym_key_file: defaults.pseudonym_key_file.clone(),
+    }
+}